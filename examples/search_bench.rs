@@ -0,0 +1,39 @@
+//! Searches a set of middlegame positions to a fixed depth and reports
+//! the nodes searched per second.
+//!
+//! ```text
+//! cargo run --release --example search_bench
+//! ```
+
+use std::time::Instant;
+
+use maulstrom::search::SearchOptions;
+use maulstrom::state::BoardState;
+
+const POSITIONS: [&str; 4] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3 c3,f6 - 0",
+];
+
+const DEPTH: u8 = 3;
+
+fn main() {
+    let states: Vec<BoardState> = POSITIONS.iter().map(|fen| BoardState::from_extended_fen(fen).unwrap()).collect();
+    let options = SearchOptions { depth: DEPTH, ..SearchOptions::default() };
+
+    let start = Instant::now();
+    let mut nodes = 0;
+    for state in &states {
+        let result = state.search(&options);
+        println!("{}", result.to_uci_info());
+        nodes += result.nodes;
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "{nodes} nodes at depth {DEPTH} in {elapsed:?}, {:.0} nodes/s",
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+}
@@ -0,0 +1,115 @@
+//! Stable addresses for positions inside a branched game.
+
+use std::{fmt, str::FromStr};
+
+/// The address of a position inside a game and its branches.
+///
+/// An address starts at the root game and follows a path of
+/// `(branch, ply)` steps. Each step goes to `ply` halfmoves into the
+/// line selected by `branch`, where branch 0 continues the current
+/// line and `n > 0` takes the nth variation played from the position
+/// the previous step ended on.
+///
+/// Addresses are formatted as the root game id in hex followed by
+/// the steps, like `00000000075bcd15_0.12_1.3`, which is URL-safe.
+#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+pub struct NodeAddress {
+    /// The game_id of the root game.
+    pub root: u64,
+
+    /// The `(branch, ply)` steps from the start of the root game.
+    pub path: Vec<(usize, usize)>,
+}
+
+impl NodeAddress {
+    /// The address of the start position of the root game.
+    pub fn new(root: u64) -> Self {
+        Self { root, path: Vec::new() }
+    }
+
+    /// The address of a position in the mainline of the root game.
+    pub fn mainline(root: u64, ply: usize) -> Self {
+        Self { root, path: vec![(0, ply)] }
+    }
+
+    /// Extend the address by another step.
+    pub fn with(mut self, branch: usize, ply: usize) -> Self {
+        self.path.push((branch, ply));
+        self
+    }
+
+    /// The address one step up the path, or None if this is the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, path) = self.path.split_last()?;
+        Some(Self { root: self.root, path: path.to_vec() })
+    }
+
+    /// The total number of halfmoves from the start of the root game.
+    pub fn ply(&self) -> usize {
+        self.path.iter().map(|&(_, ply)| ply).sum()
+    }
+}
+
+impl fmt::Display for NodeAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.root)?;
+        for (branch, ply) in &self.path {
+            write!(f, "_{branch}.{ply}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NodeAddressError {
+    /// The root game id is not a hexadecimal u64.
+    InvalidRoot,
+
+    /// The step at this index is not of the form "branch.ply".
+    InvalidStep(usize),
+}
+
+impl FromStr for NodeAddress {
+    type Err = NodeAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('_');
+        let root = parts.next()
+            .filter(|root| !root.is_empty() && root.len() <= 16)
+            .and_then(|root| u64::from_str_radix(root, 16).ok())
+            .ok_or(NodeAddressError::InvalidRoot)?;
+
+        let mut path = Vec::new();
+        for (i, step) in parts.enumerate() {
+            let (branch, ply) = step.split_once('.')
+                .and_then(|(branch, ply)| Some((branch.parse().ok()?, ply.parse().ok()?)))
+                .ok_or(NodeAddressError::InvalidStep(i))?;
+            path.push((branch, ply));
+        }
+
+        Ok(Self { root, path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodeAddress, NodeAddressError};
+
+    #[test]
+    fn address() {
+        let address = NodeAddress::mainline(123456789, 12).with(1, 3);
+        assert_eq!(address.to_string(), "00000000075bcd15_0.12_1.3");
+        assert_eq!("00000000075bcd15_0.12_1.3".parse(), Ok(address.clone()));
+        assert_eq!(address.ply(), 15);
+        assert_eq!(address.parent(), Some(NodeAddress::mainline(123456789, 12)));
+        assert_eq!(NodeAddress::new(7).parent(), None);
+        assert_eq!(NodeAddress::new(7).to_string().parse(), Ok(NodeAddress::new(7)));
+        assert_eq!("ff".parse(), Ok(NodeAddress::new(255)));
+
+        assert_eq!("".parse::<NodeAddress>(), Err(NodeAddressError::InvalidRoot));
+        assert_eq!("10000000000000000".parse::<NodeAddress>(), Err(NodeAddressError::InvalidRoot));
+        assert_eq!("g_0.1".parse::<NodeAddress>(), Err(NodeAddressError::InvalidRoot));
+        assert_eq!("ff_0.1_2".parse::<NodeAddress>(), Err(NodeAddressError::InvalidStep(1)));
+        assert_eq!("ff_0.1_x.2".parse::<NodeAddress>(), Err(NodeAddressError::InvalidStep(1)));
+    }
+}
@@ -0,0 +1,90 @@
+//! Changes made to a game in progress by an arbiter, such as adding
+//! time to a clock or changing the increment.
+//!
+//! Amendments are kept with the halfmove index of the position they
+//! were made at and take effect from that position on, so every replay
+//! of the game, such as `ChessGame::clock_history`, applies them at the
+//! same point.
+
+use crate::{end::EndCondition, settings::ClockSettings, team::Team};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Amendment {
+    /// Add milliseconds to the team's clock, or take them away if negative.
+    AddTime { team: Team, ms: i32 },
+
+    /// Change the bonus per move, in seconds, for every later move.
+    SetBonus(u32),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AmendError {
+    /// The game has ended, so it can no longer be amended.
+    GameEnded(EndCondition),
+
+    /// The game has no clock to amend.
+    Untimed,
+}
+
+impl Amendment {
+    /// Apply the amendment to the clock times and the bonus, all in milliseconds.
+    pub(crate) fn apply(&self, white: &mut i64, black: &mut i64, bonus: &mut i64) {
+        match *self {
+            Amendment::AddTime { team: Team::White, ms } => *white += ms as i64,
+            Amendment::AddTime { team: Team::Black, ms } => *black += ms as i64,
+            Amendment::SetBonus(seconds) => *bonus = seconds as i64 * 1000,
+        }
+    }
+}
+
+/// The bonus per move in milliseconds after every amendment.
+pub fn bonus_ms(clock: ClockSettings, amendments: &[(usize, Amendment)]) -> u32 {
+    amendments
+        .iter()
+        .rev()
+        .find_map(|(_, amendment)| match amendment {
+            Amendment::SetBonus(seconds) => Some(seconds.saturating_mul(1000)),
+            Amendment::AddTime { .. } => None,
+        })
+        .unwrap_or(clock.bonus_ms())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AmendError, Amendment};
+    use crate::{clock::MockClock, game::ChessGame, settings::{ClockSettings, GameSettings}, square::Square, team::Team};
+
+    #[test]
+    fn amendments() {
+        let time = MockClock::new(0);
+        let settings = GameSettings {
+            clock: Some(ClockSettings::starting_now(&time, 60, 0)),
+            ..GameSettings::default()
+        };
+
+        let mut game = ChessGame::init(settings);
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+        time.advance(5_000);
+        game.play_timed(sq("e2"), sq("e4"), None, &time).ok().unwrap();
+
+        assert_eq!(game.amend(Amendment::AddTime { team: Team::Black, ms: 30_000 }), Ok(1));
+        assert_eq!(game.amend(Amendment::SetBonus(2)), Ok(1));
+        assert_eq!(game.cursor.black_time, 90_000);
+
+        time.advance(10_000);
+        game.play_timed(sq("e7"), sq("e5"), None, &time).ok().unwrap();
+        assert_eq!(game.cursor.black_time, 82_000);
+
+        let history = game.clock_history();
+        assert_eq!(history, [(0, 60_000, 60_000), (1, 55_000, 90_000), (2, 55_000, 82_000)]);
+
+        // the amendments replay the same way after a round trip.
+        let decoded = ChessGame::from_bytes(&game.to_bytes()).unwrap();
+        assert_eq!(decoded.amendments, game.amendments);
+        assert_eq!(decoded.clock_history(), history);
+
+        game.abort().ok().unwrap();
+        assert!(matches!(game.amend(Amendment::SetBonus(0)), Err(AmendError::GameEnded(_))));
+        assert_eq!(ChessGame::default().amend(Amendment::SetBonus(0)), Err(AmendError::Untimed));
+    }
+}
@@ -0,0 +1,137 @@
+//! Fast checks of played games for likely blunders, cheap enough to run
+//! on every game as it finishes.
+//!
+//! Only moves that static exchange evaluation finds suspicious are
+//! searched: moves onto a square where the piece can be won, and moves
+//! after which the opponent has a capture that wins material. Those are
+//! searched before and after they were played, with iterative deepening
+//! for half of the time per move each, and flagged if they lose at least
+//! `BLUNDER` centipawns. Blunders that lose to a quiet move, such as a
+//! mating attack, are only found if the search of a suspicious move
+//! sees them.
+
+use std::time::{Duration, Instant};
+
+use crate::{delta::BoardDelta, game::{AnnotateError, ChessGame}, search::{SearchOptions, SearchResult}, state::BoardState};
+
+/// The least number of centipawns a move must lose to be a blunder.
+pub const BLUNDER: i32 = 300;
+
+/// The numeric annotation glyph for a blunder, written as "??".
+pub const BLUNDER_NAG: u8 = 4;
+
+/// The deepest iteration a quick check searches, however much time is left.
+const MAX_DEPTH: u8 = 8;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Blunder {
+    /// The halfmove index of the position after the blunder,
+    /// which is where its annotation goes.
+    pub index: usize,
+
+    /// The move that was played.
+    pub delta: BoardDelta,
+
+    /// The best move found in the position the blunder was played in.
+    pub best: Option<BoardDelta>,
+
+    /// The centipawns the blunder lost compared to the best move.
+    pub loss: i32,
+}
+
+/// Check every move of the game for blunders, spending about `ms_per_move`
+/// milliseconds on each suspicious move. The last iteration of a search is
+/// always finished, so a search can run over its share of the time.
+pub fn quickcheck(game: &ChessGame, ms_per_move: u64) -> Vec<Blunder> {
+    let budget = Duration::from_millis(ms_per_move) / 2;
+    let mut blunders = Vec::new();
+    for ((index, state), &delta) in game.positions().zip(&game.deltas) {
+        let next = state.next(delta);
+        if !suspicious(&state, &next, delta) {
+            continue;
+        }
+
+        let before = timed_search(&state, budget);
+        let after = -timed_search(&next, budget).score;
+        let loss = before.score.saturating_sub(after);
+        if loss >= BLUNDER {
+            blunders.push(Blunder { index: index + 1, delta, best: before.best, loss });
+        }
+    }
+
+    blunders
+}
+
+/// Mark each blunder with "??", and unless the move already has a
+/// comment, name the best move in one. Stops at the first blunder
+/// that is not in the game, or that would take the game past its
+/// annotation limit.
+pub fn annotate(game: &mut ChessGame, blunders: &[Blunder]) -> Result<(), AnnotateError> {
+    for blunder in blunders {
+        let best = blunder.best.zip(game.position_at(blunder.index - 1))
+            .map(|(best, state)| crate::san::to_san(&state, best));
+
+        let annotation = game.annotate(blunder.index)?;
+        if !annotation.nags.contains(&BLUNDER_NAG) {
+            annotation.nags.push(BLUNDER_NAG);
+        }
+
+        if let (None, Some(best)) = (&annotation.comment, best) {
+            annotation.comment = Some(format!("{best} was best"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the moved piece can be won where it landed, or the
+/// opponent has a capture that wins material after the move.
+fn suspicious(state: &BoardState, next: &BoardState, delta: BoardDelta) -> bool {
+    let (src, dst) = (delta.get_src_sq(), delta.get_dst_sq());
+    if crate::see::see(state, src, dst).is_some_and(|gain| gain < 0) {
+        return true
+    }
+
+    next.captures()
+        .into_iter()
+        .any(|(src, dst, _)| crate::see::see(next, src, dst).is_some_and(|gain| gain > 0))
+}
+
+/// Search one iteration deeper at a time until the budget is spent.
+fn timed_search(state: &BoardState, budget: Duration) -> SearchResult {
+    let start = Instant::now();
+    let mut result = state.search(&SearchOptions { depth: 1, ..SearchOptions::default() });
+    for depth in 2..=MAX_DEPTH {
+        if start.elapsed() >= budget {
+            break;
+        }
+
+        result = state.search(&SearchOptions { depth, ..SearchOptions::default() });
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{annotate, quickcheck, BLUNDER, BLUNDER_NAG};
+    use crate::{game::ChessGame, state::BoardState};
+
+    #[test]
+    fn quickcheck_blunders() {
+        // 3. Qxe5+ gives the queen away for a pawn.
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 d1h5 b8c6 h5e5 c6e5").unwrap();
+        let blunders = quickcheck(&game, 20);
+        assert_eq!(blunders.len(), 1);
+        assert_eq!(blunders[0].index, 5);
+        assert_eq!(blunders[0].delta.to_uci(), "h5e5");
+        assert!(blunders[0].loss >= BLUNDER);
+
+        assert!(annotate(&mut game, &blunders).is_ok());
+        assert_eq!(game.annotations[&5].nags, [BLUNDER_NAG]);
+        assert!(game.annotations[&5].comment.as_ref().is_some_and(|comment| comment.ends_with("was best")));
+
+        let quiet = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6").unwrap();
+        assert!(quickcheck(&quiet, 20).is_empty());
+    }
+}
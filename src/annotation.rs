@@ -0,0 +1,213 @@
+//! Comments, numeric annotation glyphs, and board drawings attached to moves.
+
+use crate::square::Square;
+
+/// The annotations of one position in a game, exported in PGN
+/// after the move that reached it.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Annotation {
+    /// Text about the move, exported as a `{comment}`.
+    pub comment: Option<String>,
+
+    /// Numeric annotation glyphs, exported as `$1` and so on. The first
+    /// six are the move suffixes "!", "?", "!!", "??", "!?", and "?!".
+    pub nags: Vec<u8>,
+
+    /// Arrows drawn on the board in this position.
+    pub arrows: Vec<Arrow>,
+
+    /// Squares circled on the board in this position.
+    pub highlights: Vec<Highlight>,
+}
+
+impl Annotation {
+    /// Whether there is no comment, glyph, or drawing.
+    pub fn is_empty(&self) -> bool {
+        self.comment.is_none() && self.nags.is_empty() && self.arrows.is_empty() && self.highlights.is_empty()
+    }
+
+    /// The arrows and highlights as the `[%csl]` and `[%cal]` commands
+    /// that lichess and ChessBase embed in PGN comments, or None if
+    /// there are neither.
+    pub fn drawing_commands(&self) -> Option<String> {
+        let mut commands = String::new();
+        if !self.highlights.is_empty() {
+            let squares: Vec<_> = self.highlights.iter().map(|hl| format!("{}{}", hl.color.to_char(), hl.square)).collect();
+            commands.push_str(&format!("[%csl {}]", squares.join(",")));
+        }
+
+        if !self.arrows.is_empty() {
+            let arrows: Vec<_> = self.arrows.iter().map(|arrow| format!("{}{}{}", arrow.color.to_char(), arrow.from, arrow.to)).collect();
+            commands.push_str(&format!("[%cal {}]", arrows.join(",")));
+        }
+
+        (!commands.is_empty()).then_some(commands)
+    }
+
+    /// Add the arrows and highlights of the `[%csl]` and `[%cal]` commands
+    /// in a PGN comment, returning the rest of the comment. Other
+    /// commands, and drawings that cannot be parsed, are left in the text.
+    pub fn read_drawing_commands(&mut self, comment: &str) -> String {
+        let mut rest = String::new();
+        let mut text = comment;
+        while let Some(start) = text.find("[%") {
+            let Some(len) = text[start..].find(']') else {
+                break
+            };
+
+            let command = &text[start + 2..start + len];
+            let parsed = match command.split_once(char::is_whitespace) {
+                Some(("csl", args)) => parse_list(args, parse_highlight).map(|hls| self.highlights.extend(hls)),
+                Some(("cal", args)) => parse_list(args, parse_arrow).map(|arrows| self.arrows.extend(arrows)),
+                _ => None,
+            };
+
+            rest.push_str(&text[..start]);
+            if parsed.is_none() {
+                rest.push_str(&text[start..=start + len]);
+            }
+            text = &text[start + len + 1..];
+        }
+
+        rest.push_str(text);
+        rest.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+fn parse_list<T>(args: &str, parse: fn(&str) -> Option<T>) -> Option<Vec<T>> {
+    args.split(',').map(|arg| parse(arg.trim())).collect()
+}
+
+/// A highlight like "Rd4".
+fn parse_highlight(arg: &str) -> Option<Highlight> {
+    let color = Brush::from_char(arg.chars().next()?)?;
+    let square = arg.get(1..)?.parse().ok()?;
+    Some(Highlight { square, color })
+}
+
+/// An arrow like "Ge2e4".
+fn parse_arrow(arg: &str) -> Option<Arrow> {
+    let color = Brush::from_char(arg.chars().next()?)?;
+    let from = arg.get(1..3)?.parse().ok()?;
+    let to = arg.get(3..)?.parse().ok()?;
+    Some(Arrow { from, to, color })
+}
+
+/// An arrow drawn from one square to another.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub color: Brush,
+}
+
+/// A circle drawn around a square.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Highlight {
+    pub square: Square,
+    pub color: Brush,
+}
+
+/// The drawing colors offered by most board UIs.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, Default)]
+pub enum Brush {
+    #[default]
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl Brush {
+    pub const fn to_u8(&self) -> u8 {
+        match self {
+            Self::Green => 0,
+            Self::Red => 1,
+            Self::Yellow => 2,
+            Self::Blue => 3,
+        }
+    }
+
+    pub const fn from_u8(u: u8) -> Option<Self> {
+        match u {
+            0 => Some(Self::Green),
+            1 => Some(Self::Red),
+            2 => Some(Self::Yellow),
+            3 => Some(Self::Blue),
+            _ => None,
+        }
+    }
+
+    /// The letter for the color in `[%cal]` and `[%csl]` commands.
+    pub const fn to_char(&self) -> char {
+        match self {
+            Self::Green => 'G',
+            Self::Red => 'R',
+            Self::Yellow => 'Y',
+            Self::Blue => 'B',
+        }
+    }
+
+    pub const fn from_char(c: char) -> Option<Self> {
+        match c {
+            'G' => Some(Self::Green),
+            'R' => Some(Self::Red),
+            'Y' => Some(Self::Yellow),
+            'B' => Some(Self::Blue),
+            _ => None,
+        }
+    }
+}
+
+/// The glyph for a move suffix such as "!?", if it is one of the six
+/// suffixes with a glyph.
+pub fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annotation, Arrow, Brush, Highlight};
+
+    #[test]
+    fn drawings() {
+        for brush in [Brush::Green, Brush::Red, Brush::Yellow, Brush::Blue] {
+            assert_eq!(Brush::from_u8(brush.to_u8()), Some(brush));
+            assert_eq!(Brush::from_char(brush.to_char()), Some(brush));
+        }
+        assert_eq!(Brush::from_u8(4), None);
+        assert_eq!(Brush::from_char('g'), None);
+
+        let mut annotation = Annotation::default();
+        assert!(annotation.is_empty());
+        assert_eq!(annotation.drawing_commands(), None);
+
+        let rest = annotation.read_drawing_commands("Central [%csl Rd4,Ge5] control [%cal Ge2e4,Bd2d4] [%eval 0.25] [%cal Xa1a2]");
+        assert_eq!(rest, "Central control [%eval 0.25] [%cal Xa1a2]");
+        assert!(!annotation.is_empty());
+        assert_eq!(annotation.highlights, [
+            Highlight { square: "d4".parse().unwrap(), color: Brush::Red },
+            Highlight { square: "e5".parse().unwrap(), color: Brush::Green },
+        ]);
+        assert_eq!(annotation.arrows[1], Arrow { from: "d2".parse().unwrap(), to: "d4".parse().unwrap(), color: Brush::Blue });
+        assert_eq!(annotation.drawing_commands().as_deref(), Some("[%csl Rd4,Ge5][%cal Ge2e4,Bd2d4]"));
+
+        // the commands read back into the same drawings.
+        let mut copy = Annotation::default();
+        assert_eq!(copy.read_drawing_commands(&annotation.drawing_commands().unwrap()), "");
+        assert_eq!(copy, annotation);
+
+        let arrow_only = Annotation { arrows: vec![annotation.arrows[0]], ..Annotation::default() };
+        assert!(!arrow_only.is_empty());
+        assert_eq!(arrow_only.drawing_commands().as_deref(), Some("[%cal Ge2e4]"));
+        assert_eq!(Annotation::default().read_drawing_commands("unclosed [%csl Rd4"), "unclosed [%csl Rd4");
+    }
+}
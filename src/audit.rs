@@ -0,0 +1,347 @@
+//! Integrity checks for games received from untrusted sources.
+
+use crate::{delta::{BoardDelta, DeltaError, DeltaField}, deltas::Deltas, end::EndCondition, game::ChessGame, state::BoardState, team::Team, trace::MoveTrace};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AuditError {
+    /// The move played at this halfmove index is not legal,
+    /// or is forbidden by the move filter.
+    IllegalMove(usize),
+
+    /// The move at this halfmove index is legal, but the delta
+    /// does not match the delta that playing the move would produce.
+    InconsistentDelta(usize),
+
+    /// The team's clock is negative after the move at this halfmove index.
+    NegativeClock(usize, Team),
+
+    /// The end condition is not satisfied by the final position.
+    EndMismatch(EndCondition),
+
+    /// The branch metadata does not describe a legal move
+    /// into the start position of this game.
+    InvalidBranch,
+}
+
+/// What `repair` removed from a game.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Repair {
+    /// The moves removed from the end of the game, in order.
+    pub removed: Vec<BoardDelta>,
+
+    /// The number of annotations removed with the moves.
+    pub annotations: usize,
+
+    /// The number of amendments removed with the moves.
+    pub amendments: usize,
+
+    /// The end condition, if it was cleared.
+    pub end: Option<EndCondition>,
+}
+
+impl Repair {
+    /// Whether the game passed its audit as it was, so nothing was removed.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ReplayError {
+    /// The delta at this halfmove index is rejected by `verify_delta`.
+    Delta(usize, DeltaError),
+
+    /// The move at this halfmove index is legal, but the move filter forbids it.
+    Forbidden(usize),
+}
+
+impl ReplayError {
+    /// The halfmove index of the move that failed to replay.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Delta(i, _) | Self::Forbidden(i) => *i,
+        }
+    }
+}
+
+/// Replay every delta from the start position, verifying that each
+/// move is legal, allowed by the move filter, and encoded the way
+/// `play` would encode it. Returns every position of the game, from
+/// the start position to the last.
+pub fn replay(game: &ChessGame) -> Result<Vec<BoardState>, ReplayError> {
+    let mut state = game.start;
+    let mut history = Vec::with_capacity(game.deltas.len() + 1);
+    history.push(state);
+
+    for (i, delta) in game.deltas.iter().enumerate() {
+        let trace = verify_delta(&state, delta).map_err(|error| ReplayError::Delta(i, error))?;
+        let (src, dst) = state.move_squares(delta);
+        if !game.allows(&state, src, dst, &trace) {
+            return Err(ReplayError::Forbidden(i))
+        }
+
+        state = state.next(*delta);
+        history.push(state);
+    }
+
+    Ok(history)
+}
+
+/// Replay every delta from the start position, verifying that
+/// each move is legal and encoded the way `play` would encode it,
+/// that neither clock goes negative, that the end condition is
+/// satisfied by the final position, and that the branch metadata
+/// is consistent with the start position.
+pub fn audit(game: &ChessGame) -> Result<(), AuditError> {
+    check_branch(game)?;
+
+    let history = replay(game).map_err(|error| match error {
+        ReplayError::Delta(i, DeltaError::Illegal) | ReplayError::Forbidden(i) => AuditError::IllegalMove(i),
+        ReplayError::Delta(i, _) => AuditError::InconsistentDelta(i),
+    })?;
+
+    for (index, white, black) in game.clock_history() {
+        if white < 0 {
+            return Err(AuditError::NegativeClock(index, Team::White))
+        }
+
+        if black < 0 {
+            return Err(AuditError::NegativeClock(index, Team::Black))
+        }
+    }
+
+    match game.end {
+        Some(end) if !end_matches(game, end, &history) => Err(AuditError::EndMismatch(end)),
+        _ => Ok(()),
+    }
+}
+
+/// Cut a game that fails its audit back to its last valid position.
+/// Moves from the first illegal or inconsistent move on are removed, as
+/// are moves from the first that leaves a clock negative, along with the
+/// annotations and amendments after the new last position. The end
+/// condition is cleared if moves were removed or the final position does
+/// not satisfy it. The cursor and clocks are then recomputed from the
+/// start position, so the game passes its audit afterwards. A game with
+/// invalid branch metadata cannot be repaired this way.
+pub fn repair(game: &mut ChessGame) -> Result<Repair, AuditError> {
+    check_branch(game)?;
+
+    let mut len = replay(game).map_or_else(|error| error.index(), |_| game.deltas.len());
+    let negative = game.clock_history()
+        .into_iter()
+        .take(len + 1)
+        .find(|&(_, white, black)| white < 0 || black < 0)
+        .map(|(index, ..)| index);
+
+    if let Some(index) = negative {
+        len = index.saturating_sub(1);
+    }
+
+    let mut repair = Repair {
+        removed: game.deltas.split_off(len).to_vec(),
+        annotations: game.annotations.split_off(&(len + 1)).len(),
+        ..Repair::default()
+    };
+
+    // amendments at a negative clock's index are removed with it,
+    // including those at the start position.
+    let kept = game.amendments.partition_point(|&(index, _)| index <= len && negative != Some(index));
+    repair.amendments = game.amendments.split_off(kept).len();
+
+    let history = replay(game).expect("the remaining moves replay");
+    if let Some(end) = game.end && (!repair.removed.is_empty() || !end_matches(game, end, &history)) {
+        repair.end = game.end.take();
+    }
+
+    if !repair.removed.is_empty() {
+        game.draw_offer = None;
+    }
+
+    let index = game.cursor.index.min(len);
+    game.start.invalidate_cache();
+    game.goto_start();
+    game.goto(index);
+    if let Some(&(_, white, black)) = game.clock_history().last() {
+        game.cursor.white_time = white.clamp(0, u32::MAX as i64) as u32;
+        game.cursor.black_time = black.clamp(0, u32::MAX as i64) as u32;
+    }
+
+    Ok(repair)
+}
+
+/// Check that the branch metadata describes a legal move into the
+/// start position of the game.
+fn check_branch(game: &ChessGame) -> Result<(), AuditError> {
+    if let Some(branch) = game.is_branch {
+        let parent = game.start.prev(branch.delta);
+        let (src, dst) = parent.move_squares(&branch.delta);
+        let legal = parent.trace(src, dst).is_some_and(|trace| {
+            parent.delta(src, dst, &trace, branch.delta.get_promote_pc()).is_some()
+        });
+
+        if !legal || branch.parent_id == game.game_id || (branch.src_halfmoves as usize) < branch.src_index {
+            return Err(AuditError::InvalidBranch)
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the end condition is satisfied by the last of the positions.
+fn end_matches(game: &ChessGame, end: EndCondition, history: &[BoardState]) -> bool {
+    let state = *history.last().unwrap();
+    match end {
+            EndCondition::Checkmate => state.is_checkmate(),
+            EndCondition::Stalemate => state.is_stalemate(),
+            EndCondition::FiftyMoveRule => state.halfmoves >= crate::end::FIFTY_MOVES,
+            EndCondition::SeventyFiveMoveRule => state.halfmoves >= crate::end::SEVENTY_FIVE_MOVES,
+            EndCondition::Repetition => history.iter().filter(|pos| pos.is_repetition_of(&state)).count() >= 3,
+            EndCondition::InsufficientMaterial => crate::end::insufficient_material(&state),
+            EndCondition::Timeout |
+            EndCondition::TimeoutVsInsufficientMaterial => game.settings.clock.is_some() && crate::end::timeout(&state) == end,
+            EndCondition::Agreement |
+            EndCondition::WhiteResign |
+            EndCondition::BlackResign |
+            EndCondition::Aborted |
+            EndCondition::Abandoned => true,
+    }
+}
+
+/// Set the check flags and the previous halfmove count of each delta to
+/// those of the delta its move produces, for deltas stored before those
+/// fields were written in full. Stops at the first illegal move, which
+/// `replay` and `audit` then reject.
+pub(crate) fn fill_derived_fields(start: BoardState, deltas: &mut Deltas) {
+    let mut state = start;
+    for i in 0..deltas.len() {
+        let delta = deltas.get_mut(i).unwrap();
+        let (src, dst) = state.move_squares(delta);
+        let Some(expected) = state.trace(src, dst).and_then(|trace| state.delta(src, dst, &trace, delta.get_promote_pc())) else {
+            return
+        };
+
+        delta.set_prev_halfmoves(expected.get_prev_halfmoves());
+
+        if expected.was_check() {
+            delta.set_was_check();
+        }
+
+        if expected.is_check() {
+            delta.set_is_check();
+        }
+
+        state = state.next(expected);
+    }
+}
+
+/// Check that the delta is exactly the delta its move produces in the
+/// position, apart from the time spent, returning the move's trace.
+/// The first field group that differs is reported.
+pub fn verify_delta(state: &BoardState, delta: &BoardDelta) -> Result<MoveTrace, DeltaError> {
+    let (src, dst) = state.move_squares(delta);
+    let trace = state.trace(src, dst).ok_or(DeltaError::Illegal)?;
+    let mut expected = state.delta(src, dst, &trace, delta.get_promote_pc()).ok_or(DeltaError::Illegal)?;
+    expected.set_time(delta.get_time());
+
+    let fields = [
+        (DeltaField::Capture, (expected.get_capture_pc(), expected.get_ep_capture_sq()) == (delta.get_capture_pc(), delta.get_ep_capture_sq())),
+        (DeltaField::Promote, expected.get_promote_pc() == delta.get_promote_pc()),
+        (DeltaField::EnPassant, (expected.get_prev_ep_sq(), expected.is_double_push()) == (delta.get_prev_ep_sq(), delta.is_double_push())),
+        (DeltaField::Castle, (expected.get_castle_side(), expected.get_castle_deltas()) == (delta.get_castle_side(), delta.get_castle_deltas())),
+        (DeltaField::Halfmoves, (expected.get_prev_halfmoves(), expected.is_resets_halfmoves()) == (delta.get_prev_halfmoves(), delta.is_resets_halfmoves())),
+        (
+            DeltaField::Wormhole,
+            (expected.get_wormhole_sq(), expected.is_popped_wormhole(), expected.is_pushed_wormhole(), expected.get_crushed_pc(), expected.is_wormhole_in_1())
+                == (delta.get_wormhole_sq(), delta.is_popped_wormhole(), delta.is_pushed_wormhole(), delta.get_crushed_pc(), delta.is_wormhole_in_1()),
+        ),
+        (DeltaField::Check, (expected.was_check(), expected.is_check()) == (delta.was_check(), delta.is_check())),
+    ];
+
+    if let Some((field, _)) = fields.into_iter().find(|(_, matches)| !matches) {
+        return Err(DeltaError::Field(field))
+    }
+
+    // every field matches, so the rest of the bits differ.
+    if expected != *delta {
+        return Err(DeltaError::UnusedBits)
+    }
+
+    Ok(trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuditError, ReplayError};
+    use crate::{delta::{DeltaError, DeltaField}, end::EndCondition, game::ChessGame, pieces::Piece, state::BoardState, team::Team};
+
+    #[test]
+    fn audit() {
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6").unwrap();
+        assert_eq!(game.audit(), Ok(()));
+
+        // replaying every move flips the side to move and clears stale en passant squares.
+        let last = game.position_at(6).unwrap();
+        assert_eq!(last.turn, Team::White);
+        assert_eq!(last.en_passant, None);
+
+        // white plays e4 again, from a square that is now empty.
+        let mut illegal = game.clone();
+        illegal.deltas[2] = illegal.deltas[0];
+        assert_eq!(illegal.audit(), Err(AuditError::IllegalMove(2)));
+
+        // the move is legal, but the delta claims a capture.
+        let mut inconsistent = game.clone();
+        inconsistent.deltas[4].set_capture_pc(Piece::Knight);
+        assert_eq!(inconsistent.audit(), Err(AuditError::InconsistentDelta(4)));
+
+        let mut mismatch = game.clone();
+        mismatch.end = Some(EndCondition::Checkmate);
+        assert_eq!(mismatch.audit(), Err(AuditError::EndMismatch(EndCondition::Checkmate)));
+        mismatch.end = Some(EndCondition::BlackResign);
+        assert_eq!(mismatch.audit(), Ok(()));
+    }
+
+    #[test]
+    fn validate() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6").unwrap();
+        assert_eq!(game.validate(), Ok(()));
+
+        // only the first bad move is reported.
+        game.deltas[3] = game.deltas[0];
+        assert_eq!(game.validate(), Err(ReplayError::Delta(3, DeltaError::Illegal)));
+        game.deltas[2].set_capture_pc(Piece::Pawn);
+        assert_eq!(game.validate(), Err(ReplayError::Delta(2, DeltaError::Field(DeltaField::Capture))));
+        assert_eq!(game.validate().map_err(|error| error.index()), Err(2));
+    }
+
+    #[test]
+    fn repair() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 f1b5").unwrap();
+        assert!(game.repair().unwrap().is_empty());
+
+        let (e4, c6) = (game.deltas[0], game.deltas[3]);
+        game.deltas[2] = e4;
+        game.annotate(2).unwrap().comment = Some("kept".into());
+        game.annotate(4).unwrap().comment = Some("removed".into());
+        game.end = Some(EndCondition::WhiteResign);
+
+        let repair = game.repair().unwrap();
+        assert_eq!(repair.removed.len(), 3);
+        assert_eq!(repair.removed[1], c6);
+        assert_eq!(repair.annotations, 1);
+        assert_eq!(repair.end, Some(EndCondition::WhiteResign));
+        assert_eq!(game.moves_uci(), "e2e4 e7e5");
+        assert_eq!(game.cursor.index, 2);
+        assert!(game.cursor.state.is_repetition_of(&game.position_at(2).unwrap()));
+        assert!(game.annotations.contains_key(&2));
+        assert_eq!(game.audit(), Ok(()));
+
+        // an end condition the final position does not satisfy is cleared.
+        game.end = Some(EndCondition::Checkmate);
+        let repair = game.repair().unwrap();
+        assert!(repair.removed.is_empty());
+        assert_eq!(repair.end, Some(EndCondition::Checkmate));
+        assert_eq!(game.audit(), Ok(()));
+    }
+}
@@ -0,0 +1,783 @@
+//! Compact binary encoding of whole games.
+//!
+//! # Layout
+//! All integers are little-endian.
+//!
+//! - 4 bytes: the magic "MLST".
+//! - 1 byte: the format version, currently 1.
+//! - 1 byte: flags. Bit 0 is Chess960, bit 1 is a clock, bit 2 is an
+//!   end condition, bit 3 is a branch, bit 4 is metadata, bit 5 is
+//!   annotations, bit 6 is amendments, and bit 7 is a slice.
+//! - 8 bytes each: the game id and the seed.
+//! - 5 bytes: the wormhole settings (spawn mode, max count, start count,
+//!   hole wait time, and hole queue time).
+//! - If there is a clock: the start time in milliseconds since the Unix
+//!   epoch (8 bytes), then the bonus and total seconds (4 bytes each).
+//! - If there is an end condition: its code (1 byte).
+//! - If there is a branch: the parent id (8 bytes), the source index
+//!   (4 bytes), the source halfmoves (2 bytes), and the delta (14 bytes).
+//! - If the game is a slice: the parent id (8 bytes), and the first and
+//!   last halfmove indices in the parent (4 bytes each).
+//! - If there is metadata: 1 byte with a bit set for each known field,
+//!   in the order white, black, white elo, black elo, event, site, date,
+//!   and round. Then each known field, where names are UTF-8 prefixed by
+//!   their length (2 bytes), ratings are 2 bytes, and the date is the
+//!   number of days since 0001-01-01 (4 bytes).
+//! - The start position as extended FEN, prefixed by its length (2 bytes).
+//! - The number of deltas (4 bytes), then 14 bytes per delta.
+//! - If there are annotations: their count (4 bytes), then for each the
+//!   halfmove index (4 bytes), the number of glyphs (1 byte) and the
+//!   glyphs (1 byte each), 1 if there is a comment (1 byte) followed
+//!   by the comment, prefixed by its length (2 bytes), the number of
+//!   arrows (1 byte) and each arrow's from square, to square, and color
+//!   (1 byte each), and the number of highlights (1 byte) and each
+//!   highlight's square and color (1 byte each).
+//! - If there are amendments: their count (4 bytes), then for each the
+//!   halfmove index (4 bytes) and its kind (1 byte). Added time (kind 0)
+//!   is followed by the team (1 byte) and the milliseconds (4 bytes,
+//!   signed), and a new bonus (kind 1) by the seconds (4 bytes).
+//! - The cursor index (4 bytes), the white and black clock times
+//!   (4 bytes each), and whether the clock is ticking (1 byte).
+//! - A CRC-32 of everything before it (4 bytes).
+//!
+//! # Studies
+//! A study is its own container, with each line of each chapter
+//! encoded as a game.
+//!
+//! - 4 bytes: the magic "MLSS".
+//! - 1 byte: the format version, currently 1.
+//! - The study name, prefixed by its length (2 bytes).
+//! - The number of shared tags (2 bytes), then each tag's name and
+//!   value, both prefixed by their length (2 bytes).
+//! - The number of chapters (4 bytes), then for each chapter its title,
+//!   prefixed by its length (2 bytes), the number of lines (4 bytes), and
+//!   each line in the order of `GameTree::games`, prefixed by its length
+//!   in bytes (4 bytes).
+//! - A CRC-32 of everything before it (4 bytes).
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, NaiveDate};
+
+use crate::{amend::Amendment, annotation::{Annotation, Arrow, Brush, Highlight}, audit::AuditError, delta::BoardDelta, deltas::Deltas, end::EndCondition, events::Events, fen::FenError, game::{Branch, ChessGame, Cursor, Slice}, meta::GameMeta, settings::{ClockSettings, GameLimits, GameSettings, WormholeSettings, WormholeSpawnMode}, square::Square, state::BoardState, study::Study, team::Team, tree::GameTree};
+
+pub(crate) const MAGIC: &[u8; 4] = b"MLST";
+pub(crate) const VERSION: u8 = 1;
+
+pub(crate) const STUDY_MAGIC: &[u8; 4] = b"MLSS";
+pub(crate) const STUDY_VERSION: u8 = 1;
+
+const CHESS960: u8 = 1 << 0;
+const HAS_CLOCK: u8 = 1 << 1;
+const HAS_END: u8 = 1 << 2;
+const IS_BRANCH: u8 = 1 << 3;
+const HAS_META: u8 = 1 << 4;
+const HAS_ANNOTATIONS: u8 = 1 << 5;
+const HAS_AMENDMENTS: u8 = 1 << 6;
+const IS_SLICE: u8 = 1 << 7;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BinaryError {
+    /// The data does not start with the magic bytes.
+    Magic,
+
+    /// The data was written by an unsupported version of the format.
+    Version(u8),
+
+    /// The data ended before the game did.
+    Truncated,
+
+    /// The checksum does not match, so the data is corrupt.
+    Checksum,
+
+    /// A field has a value that no game could have.
+    InvalidField,
+
+    /// The start position could not be parsed.
+    Fen(FenError),
+
+    /// The decoded game failed its audit.
+    Audit(AuditError),
+}
+
+/// Encode the game, including its settings, start position, moves, and cursor.
+pub fn to_bytes(game: &ChessGame) -> Vec<u8> {
+    let mut out = Vec::with_capacity(96 + game.deltas.len() * BoardDelta::PACKED_LEN);
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+
+    let mut flags = 0;
+    if game.settings.is_chess960 { flags |= CHESS960 }
+    if game.settings.clock.is_some() { flags |= HAS_CLOCK }
+    if game.end.is_some() { flags |= HAS_END }
+    if game.is_branch.is_some() { flags |= IS_BRANCH }
+    if game.meta.is_some() { flags |= HAS_META }
+    if !game.annotations.is_empty() { flags |= HAS_ANNOTATIONS }
+    if !game.amendments.is_empty() { flags |= HAS_AMENDMENTS }
+    if game.sliced_from.is_some() { flags |= IS_SLICE }
+    out.push(flags);
+
+    out.extend_from_slice(&game.game_id.to_le_bytes());
+    out.extend_from_slice(&game.seed.to_le_bytes());
+
+    let wormhole = game.settings.wormhole;
+    out.extend_from_slice(&[
+        spawn_mode_to_u8(wormhole.spawn_mode),
+        wormhole.max_count,
+        wormhole.start_count,
+        wormhole.hole_wait_time,
+        wormhole.hole_queue_time,
+    ]);
+
+    if let Some(clock) = game.settings.clock {
+        out.extend_from_slice(&clock.start.timestamp_millis().to_le_bytes());
+        out.extend_from_slice(&clock.bonus.to_le_bytes());
+        out.extend_from_slice(&clock.total.to_le_bytes());
+    }
+
+    if let Some(end) = game.end {
+        out.push(end_to_u8(end));
+    }
+
+    if let Some(branch) = game.is_branch {
+        out.extend_from_slice(&branch.parent_id.to_le_bytes());
+        out.extend_from_slice(&(branch.src_index as u32).to_le_bytes());
+        out.extend_from_slice(&branch.src_halfmoves.to_le_bytes());
+        out.extend_from_slice(&branch.delta.pack());
+    }
+
+    if let Some(slice) = game.sliced_from {
+        out.extend_from_slice(&slice.parent_id.to_le_bytes());
+        out.extend_from_slice(&(slice.from as u32).to_le_bytes());
+        out.extend_from_slice(&(slice.to as u32).to_le_bytes());
+    }
+
+    if let Some(meta) = &game.meta {
+        write_meta(&mut out, meta);
+    }
+
+    let fen = game.start.to_extended_fen();
+    out.extend_from_slice(&(fen.len() as u16).to_le_bytes());
+    out.extend_from_slice(fen.as_bytes());
+
+    out.extend_from_slice(&(game.deltas.len() as u32).to_le_bytes());
+    for delta in &game.deltas {
+        out.extend_from_slice(&delta.pack());
+    }
+
+    if !game.annotations.is_empty() {
+        write_annotations(&mut out, &game.annotations);
+    }
+
+    if !game.amendments.is_empty() {
+        write_amendments(&mut out, &game.amendments);
+    }
+
+    out.extend_from_slice(&(game.cursor.index as u32).to_le_bytes());
+    out.extend_from_slice(&game.cursor.white_time.to_le_bytes());
+    out.extend_from_slice(&game.cursor.black_time.to_le_bytes());
+    out.push(game.cursor.clock_is_ticking as u8);
+
+    let checksum = crc32(&out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out
+}
+
+/// Decode a game written by `to_bytes`, verifying the checksum
+/// and auditing the moves.
+pub fn from_bytes(bytes: &[u8]) -> Result<ChessGame, BinaryError> {
+    if bytes.len() < MAGIC.len() + 1 || &bytes[..4] != MAGIC {
+        return Err(BinaryError::Magic)
+    }
+
+    if bytes[4] != VERSION {
+        return Err(BinaryError::Version(bytes[4]))
+    }
+
+    let Some((body, checksum)) = bytes.split_last_chunk::<4>() else {
+        return Err(BinaryError::Truncated)
+    };
+
+    if crc32(body) != u32::from_le_bytes(*checksum) {
+        return Err(BinaryError::Checksum)
+    }
+
+    let mut r = Reader(&body[5..]);
+    let flags = r.u8()?;
+    let game_id = r.u64()?;
+    let seed = r.u64()?;
+
+    let wormhole = WormholeSettings {
+        spawn_mode: spawn_mode_from_u8(r.u8()?).ok_or(BinaryError::InvalidField)?,
+        max_count: r.u8()?,
+        start_count: r.u8()?,
+        hole_wait_time: r.u8()?,
+        hole_queue_time: r.u8()?,
+    };
+
+    let clock = if flags & HAS_CLOCK != 0 {
+        Some(ClockSettings {
+            start: DateTime::from_timestamp_millis(r.i64()?).ok_or(BinaryError::InvalidField)?,
+            bonus: r.u32()?,
+            total: r.u32()?,
+        })
+    } else {
+        None
+    };
+
+    let end = if flags & HAS_END != 0 {
+        Some(end_from_u8(r.u8()?).ok_or(BinaryError::InvalidField)?)
+    } else {
+        None
+    };
+
+    let is_branch = if flags & IS_BRANCH != 0 {
+        Some(Branch {
+            parent_id: r.u64()?,
+            src_index: r.u32()? as usize,
+            src_halfmoves: r.u16()?,
+            delta: read_delta(&mut r)?,
+        })
+    } else {
+        None
+    };
+
+    let sliced_from = if flags & IS_SLICE != 0 {
+        let slice = Slice {
+            parent_id: r.u64()?,
+            from: r.u32()? as usize,
+            to: r.u32()? as usize,
+        };
+
+        if slice.from > slice.to {
+            return Err(BinaryError::InvalidField)
+        }
+
+        Some(slice)
+    } else {
+        None
+    };
+
+    let meta = if flags & HAS_META != 0 {
+        Some(read_meta(&mut r)?)
+    } else {
+        None
+    };
+
+    let fen_len = r.u16()? as usize;
+    let fen = std::str::from_utf8(r.bytes(fen_len)?).map_err(|_| BinaryError::InvalidField)?;
+    let start = BoardState::from_extended_fen(fen).map_err(BinaryError::Fen)?;
+
+    let count = r.u32()? as usize;
+    if r.0.len() < count.saturating_mul(BoardDelta::PACKED_LEN) {
+        return Err(BinaryError::Truncated)
+    }
+
+    let mut deltas = Deltas::new();
+    for _ in 0..count {
+        deltas.push(read_delta(&mut r)?);
+    }
+
+    let annotations = if flags & HAS_ANNOTATIONS != 0 {
+        read_annotations(&mut r, deltas.len())?
+    } else {
+        BTreeMap::new()
+    };
+
+    let amendments = if flags & HAS_AMENDMENTS != 0 {
+        read_amendments(&mut r, deltas.len())?
+    } else {
+        Vec::new()
+    };
+
+    let index = r.u32()? as usize;
+    let white_time = r.u32()?;
+    let black_time = r.u32()?;
+    let clock_is_ticking = r.u8()? != 0;
+
+    if !r.0.is_empty() || index > deltas.len() {
+        return Err(BinaryError::InvalidField)
+    }
+
+    let mut game = ChessGame {
+        start,
+        cursor: Cursor::new(start),
+        deltas,
+        settings: GameSettings {
+            is_chess960: flags & CHESS960 != 0,
+            clock,
+            wormhole,
+            move_filter: None,
+            limits: GameLimits::default(),
+        },
+        game_id,
+        is_branch,
+        seed,
+        end,
+        meta,
+        annotations,
+        draw_offer: None,
+        events: Events::new(),
+        amendments,
+        sliced_from,
+    };
+
+    game.audit().map_err(BinaryError::Audit)?;
+
+    let state = game.deltas.iter().take(index).fold(start, |state, delta| state.next(*delta));
+    game.cursor = Cursor {
+        state,
+        index,
+        white_time,
+        black_time,
+        clock_is_ticking,
+    };
+
+    Ok(game)
+}
+
+/// Read a packed delta.
+fn read_delta(r: &mut Reader) -> Result<BoardDelta, BinaryError> {
+    BoardDelta::unpack(r.take()?).map_err(|_| BinaryError::InvalidField)
+}
+
+/// Encode the study with every line of its chapters.
+pub fn study_to_bytes(study: &Study) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(STUDY_MAGIC);
+    out.push(STUDY_VERSION);
+
+    write_text(&mut out, &study.name);
+    let tags = &study.tags[..study.tags.len().min(u16::MAX as usize)];
+    out.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+    for (name, value) in tags {
+        write_text(&mut out, name);
+        write_text(&mut out, value);
+    }
+
+    out.extend_from_slice(&(study.chapters.len() as u32).to_le_bytes());
+    for chapter in &study.chapters {
+        write_text(&mut out, &chapter.title);
+        out.extend_from_slice(&(chapter.tree.games().len() as u32).to_le_bytes());
+        for game in chapter.tree.games() {
+            let bytes = to_bytes(game);
+            out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            out.extend_from_slice(&bytes);
+        }
+    }
+
+    let crc = crc32(&out);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out
+}
+
+/// Decode a study written by `study_to_bytes`, verifying the checksum,
+/// decoding each line with `from_bytes`, and checking that every branch
+/// starts from a position in its parent.
+pub fn study_from_bytes(bytes: &[u8]) -> Result<Study, BinaryError> {
+    if bytes.len() < STUDY_MAGIC.len() + 1 || &bytes[..4] != STUDY_MAGIC {
+        return Err(BinaryError::Magic)
+    }
+
+    if bytes[4] != STUDY_VERSION {
+        return Err(BinaryError::Version(bytes[4]))
+    }
+
+    let Some((body, checksum)) = bytes.split_last_chunk::<4>() else {
+        return Err(BinaryError::Truncated)
+    };
+
+    if crc32(body) != u32::from_le_bytes(*checksum) {
+        return Err(BinaryError::Checksum)
+    }
+
+    let mut r = Reader(&body[5..]);
+    let mut study = Study::new(r.str()?);
+    for _ in 0..r.u16()? {
+        let name = r.str()?;
+        study.tags.push((name, r.str()?));
+    }
+
+    for _ in 0..r.u32()? {
+        let title = r.str()?;
+        let mut lines = Vec::new();
+        for _ in 0..r.u32()? {
+            let len = r.u32()? as usize;
+            lines.push(from_bytes(r.bytes(len)?)?);
+        }
+
+        if lines.is_empty() {
+            return Err(BinaryError::InvalidField)
+        }
+
+        let mainline = lines.remove(0);
+        let tree = GameTree::from_lines(mainline, lines).map_err(|_| BinaryError::InvalidField)?;
+        study.add_chapter(title, tree);
+    }
+
+    Ok(study)
+}
+
+fn write_meta(out: &mut Vec<u8>, meta: &GameMeta) {
+    let fields = [
+        meta.white.is_some(),
+        meta.black.is_some(),
+        meta.white_elo.is_some(),
+        meta.black_elo.is_some(),
+        meta.event.is_some(),
+        meta.site.is_some(),
+        meta.date.is_some(),
+        meta.round.is_some(),
+    ];
+
+    out.push(fields.iter().enumerate().fold(0, |bits, (i, &known)| bits | (known as u8) << i));
+
+    write_str(out, &meta.white);
+    write_str(out, &meta.black);
+    for elo in [meta.white_elo, meta.black_elo].into_iter().flatten() {
+        out.extend_from_slice(&elo.to_le_bytes());
+    }
+    write_str(out, &meta.event);
+    write_str(out, &meta.site);
+    if let Some(date) = meta.date {
+        out.extend_from_slice(&date.num_days_from_ce().to_le_bytes());
+    }
+    write_str(out, &meta.round);
+}
+
+fn write_str(out: &mut Vec<u8>, s: &Option<String>) {
+    if let Some(s) = s {
+        write_text(out, s);
+    }
+}
+
+/// Text longer than the length prefix allows is cut at a character boundary.
+fn write_text(out: &mut Vec<u8>, s: &str) {
+    let mut len = s.len().min(u16::MAX as usize);
+    while !s.is_char_boundary(len) {
+        len -= 1;
+    }
+
+    out.extend_from_slice(&(len as u16).to_le_bytes());
+    out.extend_from_slice(&s.as_bytes()[..len]);
+}
+
+/// Glyphs, arrows, and highlights past the 255th are dropped.
+fn write_annotations(out: &mut Vec<u8>, annotations: &BTreeMap<usize, Annotation>) {
+    out.extend_from_slice(&(annotations.len() as u32).to_le_bytes());
+    for (&index, annotation) in annotations {
+        out.extend_from_slice(&(index as u32).to_le_bytes());
+
+        let nags = &annotation.nags[..annotation.nags.len().min(u8::MAX as usize)];
+        out.push(nags.len() as u8);
+        out.extend_from_slice(nags);
+
+        out.push(annotation.comment.is_some() as u8);
+        write_str(out, &annotation.comment);
+
+        let arrows = &annotation.arrows[..annotation.arrows.len().min(u8::MAX as usize)];
+        out.push(arrows.len() as u8);
+        for arrow in arrows {
+            out.extend_from_slice(&[arrow.from.to_index() as u8, arrow.to.to_index() as u8, arrow.color.to_u8()]);
+        }
+
+        let highlights = &annotation.highlights[..annotation.highlights.len().min(u8::MAX as usize)];
+        out.push(highlights.len() as u8);
+        for highlight in highlights {
+            out.extend_from_slice(&[highlight.square.to_index() as u8, highlight.color.to_u8()]);
+        }
+    }
+}
+
+fn write_amendments(out: &mut Vec<u8>, amendments: &[(usize, Amendment)]) {
+    out.extend_from_slice(&(amendments.len() as u32).to_le_bytes());
+    for &(index, amendment) in amendments {
+        out.extend_from_slice(&(index as u32).to_le_bytes());
+        match amendment {
+            Amendment::AddTime { team, ms } => {
+                out.extend_from_slice(&[0, (team == Team::Black) as u8]);
+                out.extend_from_slice(&ms.to_le_bytes());
+            }
+            Amendment::SetBonus(seconds) => {
+                out.push(1);
+                out.extend_from_slice(&seconds.to_le_bytes());
+            }
+        }
+    }
+}
+
+fn read_amendments(r: &mut Reader, deltas: usize) -> Result<Vec<(usize, Amendment)>, BinaryError> {
+    let mut amendments: Vec<(usize, Amendment)> = Vec::new();
+    for _ in 0..r.u32()? {
+        let index = r.u32()? as usize;
+        if index > deltas || amendments.last().is_some_and(|&(last, _)| index < last) {
+            return Err(BinaryError::InvalidField)
+        }
+
+        let amendment = match r.u8()? {
+            0 => Amendment::AddTime {
+                team: match r.u8()? {
+                    0 => Team::White,
+                    1 => Team::Black,
+                    _ => return Err(BinaryError::InvalidField),
+                },
+                ms: r.i32()?,
+            },
+            1 => Amendment::SetBonus(r.u32()?),
+            _ => return Err(BinaryError::InvalidField),
+        };
+
+        amendments.push((index, amendment));
+    }
+
+    Ok(amendments)
+}
+
+fn read_annotations(r: &mut Reader, deltas: usize) -> Result<BTreeMap<usize, Annotation>, BinaryError> {
+    let mut annotations = BTreeMap::new();
+    for _ in 0..r.u32()? {
+        let index = r.u32()? as usize;
+        if index > deltas {
+            return Err(BinaryError::InvalidField)
+        }
+
+        let count = r.u8()? as usize;
+        let nags = r.bytes(count)?.to_vec();
+        let comment = if r.u8()? != 0 {
+            let len = r.u16()? as usize;
+            Some(std::str::from_utf8(r.bytes(len)?).map_err(|_| BinaryError::InvalidField)?.to_string())
+        } else {
+            None
+        };
+
+        let mut annotation = Annotation { comment, nags, ..Annotation::default() };
+        let square = |sq: u8| Square::try_from_index(sq as usize).ok_or(BinaryError::InvalidField);
+        let color = |color: u8| Brush::from_u8(color).ok_or(BinaryError::InvalidField);
+        for _ in 0..r.u8()? {
+            let [from, to, brush] = r.take::<3>()?;
+            annotation.arrows.push(Arrow {
+                from: square(from)?,
+                to: square(to)?,
+                color: color(brush)?,
+            });
+        }
+
+        for _ in 0..r.u8()? {
+            let [sq, brush] = r.take::<2>()?;
+            annotation.highlights.push(Highlight { square: square(sq)?, color: color(brush)? });
+        }
+
+        annotations.insert(index, annotation);
+    }
+
+    Ok(annotations)
+}
+
+fn read_meta(r: &mut Reader) -> Result<GameMeta, BinaryError> {
+    let fields = r.u8()?;
+    let known = |i: u8| fields & (1 << i) != 0;
+
+    let read_str = |r: &mut Reader, i: u8| -> Result<Option<String>, BinaryError> {
+        if !known(i) {
+            return Ok(None)
+        }
+
+        let len = r.u16()? as usize;
+        let s = std::str::from_utf8(r.bytes(len)?).map_err(|_| BinaryError::InvalidField)?;
+        Ok(Some(s.to_string()))
+    };
+
+    Ok(GameMeta {
+        white: read_str(r, 0)?,
+        black: read_str(r, 1)?,
+        white_elo: if known(2) { Some(r.u16()?) } else { None },
+        black_elo: if known(3) { Some(r.u16()?) } else { None },
+        event: read_str(r, 4)?,
+        site: read_str(r, 5)?,
+        date: if known(6) {
+            Some(NaiveDate::from_num_days_from_ce_opt(r.i32()?).ok_or(BinaryError::InvalidField)?)
+        } else {
+            None
+        },
+        round: read_str(r, 7)?,
+    })
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], BinaryError> {
+        if self.0.len() < n {
+            return Err(BinaryError::Truncated)
+        }
+
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], BinaryError> {
+        let mut out = [0; N];
+        out.copy_from_slice(self.bytes(N)?);
+        Ok(out)
+    }
+
+    /// A UTF-8 string prefixed by its length (2 bytes).
+    fn str(&mut self) -> Result<String, BinaryError> {
+        let len = self.u16()? as usize;
+        let s = std::str::from_utf8(self.bytes(len)?).map_err(|_| BinaryError::InvalidField)?;
+        Ok(s.to_string())
+    }
+
+    fn u8(&mut self) -> Result<u8, BinaryError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, BinaryError> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, BinaryError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn i32(&mut self) -> Result<i32, BinaryError> {
+        self.take().map(i32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, BinaryError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Result<i64, BinaryError> {
+        self.take().map(i64::from_le_bytes)
+    }
+}
+
+fn spawn_mode_to_u8(mode: WormholeSpawnMode) -> u8 {
+    match mode {
+        WormholeSpawnMode::Manual => 0,
+        WormholeSpawnMode::Random => 1,
+        WormholeSpawnMode::Mirror => 2,
+    }
+}
+
+fn spawn_mode_from_u8(u: u8) -> Option<WormholeSpawnMode> {
+    Some(match u {
+        0 => WormholeSpawnMode::Manual,
+        1 => WormholeSpawnMode::Random,
+        2 => WormholeSpawnMode::Mirror,
+        _ => return None,
+    })
+}
+
+fn end_to_u8(end: EndCondition) -> u8 {
+    match end {
+        EndCondition::Checkmate => 0,
+        EndCondition::FiftyMoveRule => 1,
+        EndCondition::Stalemate => 2,
+        EndCondition::Repetition => 3,
+        EndCondition::Agreement => 4,
+        EndCondition::WhiteResign => 5,
+        EndCondition::BlackResign => 6,
+        EndCondition::InsufficientMaterial => 7,
+        EndCondition::Aborted => 8,
+        EndCondition::Timeout => 9,
+        EndCondition::TimeoutVsInsufficientMaterial => 10,
+        EndCondition::Abandoned => 11,
+        EndCondition::SeventyFiveMoveRule => 12,
+    }
+}
+
+fn end_from_u8(u: u8) -> Option<EndCondition> {
+    Some(match u {
+        0 => EndCondition::Checkmate,
+        1 => EndCondition::FiftyMoveRule,
+        2 => EndCondition::Stalemate,
+        3 => EndCondition::Repetition,
+        4 => EndCondition::Agreement,
+        5 => EndCondition::WhiteResign,
+        6 => EndCondition::BlackResign,
+        7 => EndCondition::InsufficientMaterial,
+        8 => EndCondition::Aborted,
+        9 => EndCondition::Timeout,
+        10 => EndCondition::TimeoutVsInsufficientMaterial,
+        11 => EndCondition::Abandoned,
+        12 => EndCondition::SeventyFiveMoveRule,
+        _ => return None,
+    })
+}
+
+/// CRC-32 (IEEE), computed bitwise since games are small.
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{BinaryError, VERSION};
+    use crate::{annotation::{Arrow, Brush, Highlight}, end::EndCondition, game::{AnnotateError, ChessGame}, meta::GameMeta, square::Square, state::BoardState};
+
+    #[test]
+    fn round_trip() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "f2f3 e7e5 g2g4 d8h4").unwrap();
+        game.end = Some(EndCondition::Checkmate);
+        game.cursor.white_time = 1234;
+        game.meta = Some(GameMeta {
+            black: Some("Ørjan".to_string()),
+            black_elo: Some(1850),
+            date: NaiveDate::from_ymd_opt(2023, 11, 4),
+            round: Some("2".to_string()),
+            ..GameMeta::default()
+        });
+        game.annotate(0).unwrap().comment = Some("A short one.".to_string());
+        game.annotate(3).unwrap().nags.extend([4, 18]);
+        game.annotate(4).unwrap().comment = Some(String::new());
+        game.annotate(4).unwrap().arrows.push(Arrow { from: "h4".parse().unwrap(), to: "e1".parse().unwrap(), color: Brush::Red });
+        game.annotate(4).unwrap().highlights.push(Highlight { square: Square::from_index(4), color: Brush::Blue });
+
+        let bytes = game.to_bytes();
+        let decoded = ChessGame::from_bytes(&bytes).unwrap();
+        assert!(decoded.deltas.last().unwrap().is_check());
+        assert_eq!(decoded.deltas, game.deltas);
+        assert_eq!(decoded.game_id, game.game_id);
+        assert_eq!(decoded.end, game.end);
+        assert_eq!(decoded.cursor.index, game.cursor.index);
+        assert_eq!(decoded.cursor.white_time, 1234);
+        assert_eq!(decoded.meta, game.meta);
+        assert_eq!(decoded.annotations, game.annotations);
+        assert!(decoded.cursor.state.is_repetition_of(&game.cursor.state));
+        assert_eq!(decoded.to_bytes(), bytes);
+
+        // positions past the end cannot be annotated, so every game can be read back.
+        let mut short = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5").unwrap();
+        assert_eq!(short.annotate(5).err(), Some(AnnotateError::Index));
+        assert!(short.annotate(2).is_ok_and(|annotation| annotation.nags.is_empty()));
+        assert_eq!(ChessGame::from_bytes(&short.to_bytes()).unwrap().annotations, short.annotations);
+    }
+
+    #[test]
+    fn corruption_is_detected() {
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5").unwrap();
+        let mut bytes = game.to_bytes();
+
+        assert_eq!(ChessGame::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(BinaryError::Checksum));
+
+        let last = bytes.len() - 10;
+        bytes[last] ^= 1;
+        assert_eq!(ChessGame::from_bytes(&bytes).err(), Some(BinaryError::Checksum));
+
+        bytes[4] = VERSION + 1;
+        assert_eq!(ChessGame::from_bytes(&bytes).err(), Some(BinaryError::Version(VERSION + 1)));
+        assert_eq!(ChessGame::from_bytes(b"PGN?").err(), Some(BinaryError::Magic));
+    }
+}
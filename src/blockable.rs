@@ -1,4 +1,4 @@
-use crate::{board::BitBoard, cached::*, ray::*, pieces::Piece, square::Square, state::BoardState, team::Team};
+use crate::{board::BitBoard, square::Square, state::BoardState};
 
 /// Compute the squares a piece could move to to maintain a pin, block check, or capture a checking piece.
 /// This can be used to filter out moves. If there is no check or pins, then the returned mask with be u64::MAX.
@@ -13,9 +13,9 @@ pub fn blockable(sq: Square, state: &BoardState) -> BitBoard {
         // squares occupied by enemy pieces.
         let enemy = state.pieces.on_team(!state.turn).transmit(wormholes);
 
-        let enemy_diag = (state.pieces.bishops | state.pieces.queens) & enemy;
-        let enemy_ortho = (state.pieces.rooks | state.pieces.queens) & enemy;
-        let enemy_knights = state.pieces.knights & enemy;
+        let enemy_diag = state.pieces.diagonal_sliders() & enemy;
+        let enemy_ortho = state.pieces.orthogonal_sliders() & enemy;
+        let enemy_knights = state.pieces.leapers() & enemy;
         let enemy_pawns = state.pieces.pawns & enemy;
         let enemy_kings = state.pieces.kings & enemy;
 
@@ -63,7 +63,7 @@ pub fn blockable(sq: Square, state: &BoardState) -> BitBoard {
             }
 
             let ortho = king.rook_moves(enemy_ortho);
-            for enemy_sq in ortho & enemy_diag {
+            for enemy_sq in ortho & enemy_ortho {
                 let btw = king.between(enemy_sq);
                 let cnt = ((btw & !enemy_ortho) & occupied).count();
                 if cnt == 0 || (cnt == 1 && btw.has(sq)) {
@@ -80,7 +80,7 @@ pub fn blockable(sq: Square, state: &BoardState) -> BitBoard {
                             for out_sq in wormholes {
                                 if let Some(btw2) = ray.cast_if_hit(out_sq, enemy_diag) {
                                     let path = btw1 | btw2;
-                                    let cnt2 = ((path &! enemy_diag) & occupied).count();
+                                    let cnt2 = ((path & !enemy_diag) & occupied).count();
                                     if cnt2 == 0 || (cnt2 == 1 && path.has(sq)) {
                                         blockable &= path.transmit(wormholes);
                                     }
@@ -4,6 +4,7 @@
 use std::fmt::{self, Write};
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
 
+use crate::geometry::{FILES, RANKS, SQUARES};
 use crate::square::Rank;
 use crate::team::Team;
 
@@ -53,7 +54,7 @@ impl BitBoard {
 
     /// Mask all bits before the index, exclusive.
     pub const fn before(i: usize) -> Self {
-        Self(!0u64 >> (64 - i))
+        Self(!0u64 >> (SQUARES - i))
     }
 
     /// Mask all bits after the index, exclusive.
@@ -67,7 +68,7 @@ impl BitBoard {
     }
 
     pub const fn without(self, sq: Square) -> Self {
-        Self(self.0 &! sq.to_mask())
+        Self(self.0 & !sq.to_mask())
     }
 
     pub const fn is_set(&self, sq: Square) -> bool {
@@ -134,6 +135,16 @@ impl BitBoard {
         }
     }
 
+    /// Mirror the board vertically, so the first rank becomes the eighth.
+    pub const fn flip_vertical(&self) -> Self {
+        Self(self.0.swap_bytes())
+    }
+
+    /// Mirror the board horizontally, so the a-file becomes the h-file.
+    pub const fn flip_horizontal(&self) -> Self {
+        Self(self.0.reverse_bits().swap_bytes())
+    }
+
     pub const fn pawn_captures(&self, team: Team) -> BitBoard {
         let pr = self.0 & !0x8080808080808080;
         let pl = self.0 & !0x0101010101010101;
@@ -252,9 +263,9 @@ impl<T: Into<BitBoard>> BitXorAssign<T> for BitBoard {
 impl fmt::Debug for BitBoard {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("BitBoard(\n")?;
-        for x in (0..8).rev() {
+        for x in (0..RANKS).rev() {
             f.write_char('\t')?;
-            for y in 0..8 {
+            for y in 0..FILES {
                 if self.has((x, y).into()) {
                     f.write_str("o ")?;
                 } else {
@@ -275,7 +286,7 @@ impl Iterator for BitBoardIndices {
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
         let i = self.0.trailing_zeros();
-        if i != 64 {
+        if i != SQUARES as u32 {
             self.0 ^= 1 << i;
             Some(i)
         } else {
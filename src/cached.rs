@@ -1,6 +1,7 @@
+use crate::geometry::SQUARES;
 
 /// Attacked squares for a white pawn on this square.
-pub const WHITE_PAWN_ATTACKS: [u64; 64] = [
+pub const WHITE_PAWN_ATTACKS: [u64; SQUARES] = [
     512, 1280, 2560, 5120, 10240, 20480, 40960, 16384, 131072, 327680, 655360, 1310720, 2621440, 5242880, 
     10485760, 4194304, 33554432, 83886080, 167772160, 335544320, 671088640, 1342177280, 2684354560, 1073741824, 
     8589934592, 21474836480, 42949672960, 85899345920, 171798691840, 343597383680, 687194767360, 274877906944, 
@@ -12,7 +13,7 @@ pub const WHITE_PAWN_ATTACKS: [u64; 64] = [
 
 /// Attacked squares for a black pawn on this square.
 #[rustfmt::skip]
-pub const BLACK_PAWN_ATTACKS: [u64; 64] = [
+pub const BLACK_PAWN_ATTACKS: [u64; SQUARES] = [
     0, 0, 0, 0, 0, 0, 0, 0, 2, 5, 10, 20, 40, 80, 160, 64, 512, 1280, 2560, 5120, 10240, 20480, 40960, 16384, 131072, 
     327680, 655360, 1310720, 2621440, 5242880, 10485760, 4194304, 33554432, 83886080, 167772160, 335544320, 671088640, 
     1342177280, 2684354560, 1073741824, 8589934592, 21474836480, 42949672960, 85899345920, 171798691840, 343597383680, 
@@ -22,7 +23,7 @@ pub const BLACK_PAWN_ATTACKS: [u64; 64] = [
 ];
 
 #[rustfmt::skip]
-pub const KING_MOVES: [u64; 64] = [
+pub const KING_MOVES: [u64; SQUARES] = [
     770, 1797, 3594, 7188, 14376, 28752, 57504, 49216, 197123, 460039, 920078, 1840156, 3680312, 7360624, 
     14721248, 12599488, 50463488, 117769984, 235539968, 471079936, 942159872, 1884319744, 3768639488, 3225468928, 
     12918652928, 30149115904, 60298231808, 120596463616, 241192927232, 482385854464, 964771708928, 825720045568, 
@@ -35,7 +36,7 @@ pub const KING_MOVES: [u64; 64] = [
 ];
 
 #[rustfmt::skip]
-pub const KNIGHT_MOVES: [u64; 64] = [
+pub const KNIGHT_MOVES: [u64; SQUARES] = [
     132096, 329728, 659712, 1319424, 2638848, 5277696, 10489856, 4202496, 33816580, 84410376, 168886289, 
     337772578, 675545156, 1351090312, 2685403152, 1075839008, 8657044482, 21609056261, 43234889994, 86469779988, 
     172939559976, 345879119952, 687463207072, 275414786112, 2216203387392, 5531918402816, 11068131838464, 22136263676928, 
@@ -49,7 +50,7 @@ pub const KNIGHT_MOVES: [u64; 64] = [
 ];
 
 #[rustfmt::skip]
-pub const RIGHT_DIAGONAL: [u64; 64] = [
+pub const RIGHT_DIAGONAL: [u64; SQUARES] = [
     9241421688590303745, 36099303471055874, 141012904183812, 550831656968, 2151686160, 
     8405024, 32832, 128, 4620710844295151872, 9241421688590303745, 36099303471055874, 
     141012904183812, 550831656968, 2151686160, 8405024, 32832, 2310355422147575808, 
@@ -68,7 +69,7 @@ pub const RIGHT_DIAGONAL: [u64; 64] = [
 ];
 
 #[rustfmt::skip]
-pub const LEFT_DIAGONAL: [u64; 64] = [
+pub const LEFT_DIAGONAL: [u64; SQUARES] = [
     1, 258, 66052, 16909320, 4328785936, 1108169199648, 283691315109952, 72624976668147840, 
     258, 66052, 16909320, 4328785936, 1108169199648, 283691315109952, 72624976668147840, 
     145249953336295424, 66052, 16909320, 4328785936, 1108169199648, 283691315109952, 
@@ -84,7 +85,7 @@ pub const LEFT_DIAGONAL: [u64; 64] = [
 ];
 
 #[rustfmt::skip]
-pub const RANK: [u64; 64] = [
+pub const RANK: [u64; SQUARES] = [
     255, 255, 255, 255, 255, 255, 255, 255, 65280, 65280, 65280, 65280, 65280, 65280, 65280, 65280, 
     16711680, 16711680, 16711680, 16711680, 16711680, 16711680, 16711680, 16711680, 4278190080, 
     4278190080, 4278190080, 4278190080, 4278190080, 4278190080, 4278190080, 4278190080, 1095216660480, 
@@ -97,7 +98,7 @@ pub const RANK: [u64; 64] = [
 ];
 
 #[rustfmt::skip]
-pub const FILE: [u64; 64] = [
+pub const FILE: [u64; SQUARES] = [
     72340172838076673, 144680345676153346, 289360691352306692, 578721382704613384, 1157442765409226768, 
     2314885530818453536, 4629771061636907072, 9259542123273814144, 72340172838076673, 144680345676153346, 
     289360691352306692, 578721382704613384, 1157442765409226768, 2314885530818453536, 4629771061636907072, 
@@ -115,7 +116,7 @@ pub const FILE: [u64; 64] = [
 
 /// All squares in the +rank direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_POS_ZERO_INCLUSIVE: [u64; 64] = [
+pub const RAY_POS_ZERO_INCLUSIVE: [u64; SQUARES] = [
     72340172838076673, 144680345676153346, 289360691352306692, 578721382704613384, 1157442765409226768, 
     2314885530818453536, 4629771061636907072, 9259542123273814144, 72340172838076672, 144680345676153344, 
     289360691352306688, 578721382704613376, 1157442765409226752, 2314885530818453504, 4629771061636907008, 
@@ -133,7 +134,7 @@ pub const RAY_POS_ZERO_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the +rank direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_POS_ZERO_EXCLUSIVE: [u64; 64] = [
+pub const RAY_POS_ZERO_EXCLUSIVE: [u64; SQUARES] = [
     72340172838076672, 144680345676153344, 289360691352306688, 578721382704613376, 1157442765409226752, 
     2314885530818453504, 4629771061636907008, 9259542123273814016, 72340172838076416, 144680345676152832, 
     289360691352305664, 578721382704611328, 1157442765409222656, 2314885530818445312, 4629771061636890624, 
@@ -150,7 +151,7 @@ pub const RAY_POS_ZERO_EXCLUSIVE: [u64; 64] = [
 
 /// All squares in the -rank direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_NEG_ZERO_INCLUSIVE: [u64; 64] = [
+pub const RAY_NEG_ZERO_INCLUSIVE: [u64; SQUARES] = [
     1, 2, 4, 8, 16, 32, 64, 128, 257, 514, 1028, 2056, 4112, 8224, 16448, 32896, 65793, 131586, 263172, 
     526344, 1052688, 2105376, 4210752, 8421504, 16843009, 33686018, 67372036, 134744072, 269488144, 538976288, 
     1077952576, 2155905152, 4311810305, 8623620610, 17247241220, 34494482440, 68988964880, 137977929760, 
@@ -163,7 +164,7 @@ pub const RAY_NEG_ZERO_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the -rank direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_NEG_ZERO_EXCLUSIVE: [u64; 64] = [
+pub const RAY_NEG_ZERO_EXCLUSIVE: [u64; SQUARES] = [
     0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 4, 8, 16, 32, 64, 128, 257, 514, 1028, 2056, 4112, 8224, 16448, 32896, 65793, 
     131586, 263172, 526344, 1052688, 2105376, 4210752, 8421504, 16843009, 33686018, 67372036, 134744072, 269488144, 
     538976288, 1077952576, 2155905152, 4311810305, 8623620610, 17247241220, 34494482440, 68988964880, 137977929760, 
@@ -174,7 +175,7 @@ pub const RAY_NEG_ZERO_EXCLUSIVE: [u64; 64] = [
 
 /// All squares in the +file direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_ZERO_POS_INCLUSIVE: [u64; 64] = [
+pub const RAY_ZERO_POS_INCLUSIVE: [u64; SQUARES] = [
     255, 254, 252, 248, 240, 224, 192, 128, 65280, 65024, 64512, 63488, 61440, 57344, 49152, 32768, 16711680, 
     16646144, 16515072, 16252928, 15728640, 14680064, 12582912, 8388608, 4278190080, 4261412864, 4227858432, 
     4160749568, 4026531840, 3758096384, 3221225472, 2147483648, 1095216660480, 1090921693184, 1082331758592, 
@@ -187,7 +188,7 @@ pub const RAY_ZERO_POS_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the +file direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_ZERO_POS_EXCLUSIVE: [u64; 64] = [
+pub const RAY_ZERO_POS_EXCLUSIVE: [u64; SQUARES] = [
     254, 252, 248, 240, 224, 192, 128, 0, 65024, 64512, 63488, 61440, 57344, 49152, 32768, 0, 16646144, 16515072, 
     16252928, 15728640, 14680064, 12582912, 8388608, 0, 4261412864, 4227858432, 4160749568, 4026531840, 3758096384, 
     3221225472, 2147483648, 0, 1090921693184, 1082331758592, 1065151889408, 1030792151040, 962072674304, 824633720832, 
@@ -199,7 +200,7 @@ pub const RAY_ZERO_POS_EXCLUSIVE: [u64; 64] = [
 
 /// All squares in the -file direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_ZERO_NEG_INCLUSIVE: [u64; 64] = [
+pub const RAY_ZERO_NEG_INCLUSIVE: [u64; SQUARES] = [
     1, 3, 7, 15, 31, 63, 127, 255, 256, 768, 1792, 3840, 7936, 16128, 32512, 65280, 65536, 196608, 458752, 983040, 
     2031616, 4128768, 8323072, 16711680, 16777216, 50331648, 117440512, 251658240, 520093696, 1056964608, 2130706432, 
     4278190080, 4294967296, 12884901888, 30064771072, 64424509440, 133143986176, 270582939648, 545460846592, 
@@ -211,7 +212,7 @@ pub const RAY_ZERO_NEG_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the -file direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_ZERO_NEG_EXCLUSIVE: [u64; 64] = [
+pub const RAY_ZERO_NEG_EXCLUSIVE: [u64; SQUARES] = [
     0, 1, 3, 7, 15, 31, 63, 127, 0, 256, 768, 1792, 3840, 7936, 16128, 32512, 0, 65536, 196608, 458752, 983040, 2031616, 
     4128768, 8323072, 0, 16777216, 50331648, 117440512, 251658240, 520093696, 1056964608, 2130706432, 0, 4294967296, 
     12884901888, 30064771072, 64424509440, 133143986176, 270582939648, 545460846592, 0, 1099511627776, 3298534883328, 
@@ -222,7 +223,7 @@ pub const RAY_ZERO_NEG_EXCLUSIVE: [u64; 64] = [
 
 /// All squares in the +rank,+file direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_POS_POS_INCLUSIVE: [u64; 64] = [
+pub const RAY_POS_POS_INCLUSIVE: [u64; SQUARES] = [
     9241421688590303745, 36099303471055874, 141012904183812, 550831656968, 2151686160, 8405024, 32832, 128, 4620710844295151872, 
     9241421688590303744, 36099303471055872, 141012904183808, 550831656960, 2151686144, 8404992, 32768, 2310355422147575808, 
     4620710844295151616, 9241421688590303232, 36099303471054848, 141012904181760, 550831652864, 2151677952, 8388608, 
@@ -237,7 +238,7 @@ pub const RAY_POS_POS_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the +rank,+file direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_POS_POS_EXCLUSIVE: [u64; 64] = [
+pub const RAY_POS_POS_EXCLUSIVE: [u64; SQUARES] = [
     9241421688590303744, 36099303471055872, 141012904183808, 550831656960, 2151686144, 8404992, 32768, 0, 4620710844295151616, 
     9241421688590303232, 36099303471054848, 141012904181760, 550831652864, 2151677952, 8388608, 0, 2310355422147510272, 
     4620710844295020544, 9241421688590041088, 36099303470530560, 141012903133184, 550829555712, 2147483648, 0, 1155177711056977920, 
@@ -250,7 +251,7 @@ pub const RAY_POS_POS_EXCLUSIVE: [u64; 64] = [
 
 /// All squares in the -rank,-file direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_NEG_NEG_INCLUSIVE: [u64; 64] = [
+pub const RAY_NEG_NEG_INCLUSIVE: [u64; SQUARES] = [
     1, 2, 4, 8, 16, 32, 64, 128, 256, 513, 1026, 2052, 4104, 8208, 16416, 32832, 65536, 131328, 262657, 525314, 1050628, 
     2101256, 4202512, 8405024, 16777216, 33619968, 67240192, 134480385, 268960770, 537921540, 1075843080, 2151686160, 4294967296, 
     8606711808, 17213489152, 34426978560, 68853957121, 137707914242, 275415828484, 550831656968, 1099511627776, 2203318222848, 
@@ -262,7 +263,7 @@ pub const RAY_NEG_NEG_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the -rank,-file direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_NEG_NEG_EXCLUSIVE: [u64; 64] = [
+pub const RAY_NEG_NEG_EXCLUSIVE: [u64; SQUARES] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 4, 8, 16, 32, 64, 0, 256, 513, 1026, 2052, 4104, 8208, 16416, 0, 65536, 131328, 262657, 525314, 
     1050628, 2101256, 4202512, 0, 16777216, 33619968, 67240192, 134480385, 268960770, 537921540, 1075843080, 0, 4294967296, 8606711808, 
     17213489152, 34426978560, 68853957121, 137707914242, 275415828484, 0, 1099511627776, 2203318222848, 4406653222912, 8813306511360, 
@@ -272,7 +273,7 @@ pub const RAY_NEG_NEG_EXCLUSIVE: [u64; 64] = [
 
 /// All squares in the +rank,-file direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_POS_NEG_INCLUSIVE: [u64; 64] = [
+pub const RAY_POS_NEG_INCLUSIVE: [u64; SQUARES] = [
     1, 258, 66052, 16909320, 4328785936, 1108169199648, 283691315109952, 72624976668147840, 256, 66048, 16909312, 4328785920, 
     1108169199616, 283691315109888, 72624976668147712, 145249953336295424, 65536, 16908288, 4328783872, 1108169195520, 283691315101696, 
     72624976668131328, 145249953336262656, 290499906672525312, 16777216, 4328521728, 1108168671232, 283691314053120, 72624976666034176, 
@@ -285,7 +286,7 @@ pub const RAY_POS_NEG_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the +rank,-file direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_POS_NEG_EXCLUSIVE: [u64; 64] = [
+pub const RAY_POS_NEG_EXCLUSIVE: [u64; SQUARES] = [
     0, 256, 66048, 16909312, 4328785920, 1108169199616, 283691315109888, 72624976668147712, 0, 65536, 16908288, 4328783872, 1108169195520, 
     283691315101696, 72624976668131328, 145249953336262656, 0, 16777216, 4328521728, 1108168671232, 283691314053120, 72624976666034176, 145249953332068352, 
     290499906664136704, 0, 4294967296, 1108101562368, 283691179835392, 72624976397598720, 145249952795197440, 290499905590394880, 580999811180789760, 0, 
@@ -296,7 +297,7 @@ pub const RAY_POS_NEG_EXCLUSIVE: [u64; 64] = [
 
 /// All squares in the -rank,+file direction, inclusively.
 #[rustfmt::skip]
-pub const RAY_NEG_POS_INCLUSIVE: [u64; 64] = [
+pub const RAY_NEG_POS_INCLUSIVE: [u64; SQUARES] = [
     1, 2, 4, 8, 16, 32, 64, 128, 258, 516, 1032, 2064, 4128, 8256, 16512, 32768, 66052, 132104, 264208, 528416, 1056832, 2113664, 4227072, 
     8388608, 16909320, 33818640, 67637280, 135274560, 270549120, 541097984, 1082130432, 2147483648, 4328785936, 8657571872, 17315143744, 
     34630287488, 69260574720, 138521083904, 277025390592, 549755813888, 1108169199648, 2216338399296, 4432676798592, 8865353596928, 17730707128320, 
@@ -307,7 +308,7 @@ pub const RAY_NEG_POS_INCLUSIVE: [u64; 64] = [
 
 /// All squares in the -rank,+file direction, exclusively.
 #[rustfmt::skip]
-pub const RAY_NEG_POS_EXCLUSIVE: [u64; 64] = [
+pub const RAY_NEG_POS_EXCLUSIVE: [u64; SQUARES] = [
     0, 0, 0, 0, 0, 0, 0, 0, 2, 4, 8, 16, 32, 64, 128, 0, 516, 1032, 2064, 4128, 8256, 16512, 32768, 0, 132104, 264208, 528416, 1056832, 
     2113664, 4227072, 8388608, 0, 33818640, 67637280, 135274560, 270549120, 541097984, 1082130432, 2147483648, 0, 8657571872, 17315143744, 
     34630287488, 69260574720, 138521083904, 277025390592, 549755813888, 0, 2216338399296, 4432676798592, 8865353596928, 17730707128320, 
@@ -317,7 +318,7 @@ pub const RAY_NEG_POS_EXCLUSIVE: [u64; 64] = [
 
 /// Squares between two squares, either orthogonally or diagonally, exclusively.
 #[rustfmt::skip]
-pub const BETWEEN_EXCLUSIVE: [[u64; 64]; 64] = [
+pub const BETWEEN_EXCLUSIVE: [[u64; SQUARES]; SQUARES] = [
     [0, 0, 2, 6, 14, 30, 62, 126, 0, 0, 0, 0, 0, 0, 0, 0, 256, 0, 512, 0, 0, 0, 0, 0, 65792, 0, 0, 262656, 0, 0, 0, 0, 16843008, 0, 0, 0, 134480384, 0, 0, 0, 
     4311810304, 0, 0, 0, 0, 68853957120, 0, 0, 1103823438080, 0, 0, 0, 0, 0, 35253226045952, 0, 282578800148736, 0, 0, 0, 0, 0, 0, 18049651735527936], 
     [0, 0, 0, 4, 12, 28, 60, 124, 0, 0, 0, 0, 0, 0, 0, 0, 0, 512, 0, 1024, 0, 0, 0, 0, 0, 131584, 0, 0, 525312, 0, 0, 0, 0, 33686016, 0, 0, 0, 268960768, 0, 0, 
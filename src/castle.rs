@@ -1,4 +1,4 @@
-use crate::{board::BitBoard, cached::BETWEEN_EXCLUSIVE, square::{File, Rank, Square}, team::Team};
+use crate::{board::BitBoard, cached::BETWEEN_EXCLUSIVE, square::{File, Square}, team::Team};
 
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -53,6 +53,14 @@ impl CastleRights {
         }
     }
 
+    /// Swap the rights of white and black.
+    pub const fn flipped(&self) -> Self {
+        Self {
+            rights: ((self.rights & 0b0011) << 2) | ((self.rights >> 2) & 0b0011),
+            settings: self.settings,
+        }
+    }
+
     /// Set the rook start file for this side.
     pub fn set_rook(&mut self, side: Castle, file: u8) {
         match side {
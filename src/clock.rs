@@ -0,0 +1,144 @@
+//! Sources of the current time for game clocks.
+//!
+//! The clock subsystem never reads the time directly. It asks a
+//! `TimeSource`, so games can be driven by the system clock, by the
+//! browser's monotonic timer, or by a mock in tests.
+
+use std::cell::Cell;
+
+use chrono::{DateTime, Utc};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_arch = "wasm32")]
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub trait TimeSource {
+    /// The current time, in milliseconds since the Unix epoch.
+    fn now_ms(&self) -> i64;
+
+    /// The current time in UTC.
+    fn now(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(self.now_ms()).unwrap_or_default()
+    }
+}
+
+/// The wall clock. On wasm32 this is `Date.now()`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl TimeSource for SystemClock {
+    fn now_ms(&self) -> i64 {
+        wall_ms()
+    }
+}
+
+/// A clock that never goes backwards, anchored to the wall clock
+/// at the time it was created. On wasm32 this is `performance.now()`,
+/// which is also available in web workers.
+///
+/// Prefer this for live games, since adjusting the system time
+/// while a game is in progress will not add or remove time.
+#[derive(Copy, Clone, Debug)]
+pub struct MonotonicClock {
+    origin_ms: i64,
+    origin: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self {
+            origin_ms: wall_ms(),
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeSource for MonotonicClock {
+    fn now_ms(&self) -> i64 {
+        let elapsed = self.origin.elapsed().as_millis();
+        self.origin_ms.saturating_add(i64::try_from(elapsed).unwrap_or(i64::MAX))
+    }
+}
+
+/// A clock that only moves when told to, for deterministic tests.
+#[derive(Clone, Debug, Default)]
+pub struct MockClock {
+    ms: Cell<i64>,
+}
+
+impl MockClock {
+    /// A mock clock reading `ms` milliseconds since the Unix epoch.
+    pub fn new(ms: i64) -> Self {
+        Self { ms: Cell::new(ms) }
+    }
+
+    pub fn set(&self, ms: i64) {
+        self.ms.set(ms);
+    }
+
+    pub fn advance(&self, ms: i64) {
+        self.ms.set(self.ms.get().saturating_add(ms));
+    }
+}
+
+impl TimeSource for MockClock {
+    fn now_ms(&self) -> i64 {
+        self.ms.get()
+    }
+}
+
+fn wall_ms() -> i64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(since) => i64::try_from(since.as_millis()).unwrap_or(i64::MAX),
+        Err(before) => -i64::try_from(before.duration().as_millis()).unwrap_or(i64::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MockClock, MonotonicClock, TimeSource};
+    use crate::{game::ChessGame, settings::{ClockSettings, GameSettings}, square::Square, team::Team};
+
+    #[test]
+    fn monotonic_never_goes_backwards() {
+        let clock = MonotonicClock::new();
+        let a = clock.now_ms();
+        let b = clock.now_ms();
+        assert!(b >= a);
+    }
+
+    #[test]
+    fn mock_clock_drives_play() {
+        let time = MockClock::new(1_000_000);
+        let settings = GameSettings {
+            clock: Some(ClockSettings::starting_now(&time, 60, 2)),
+            ..GameSettings::default()
+        };
+
+        let mut game = ChessGame::init(settings);
+        time.advance(3_000);
+        assert_eq!(game.remaining_ms(&time), Some((57_000, 60_000)));
+
+        assert!(game.play_timed(Square::from((1, 4)), Square::from((3, 4)), None, &time).is_ok());
+        time.advance(10_500);
+        assert!(game.play_timed(Square::from((6, 4)), Square::from((4, 4)), None, &time).is_ok());
+
+        assert_eq!(game.deltas[0].get_time(), 3_000);
+        assert_eq!(game.deltas[1].get_time(), 10_500);
+        assert_eq!(game.cursor.white_time, 59_000);
+        assert_eq!(game.cursor.black_time, 51_500);
+
+        time.advance(60_000);
+        assert_eq!(game.remaining_ms(&time), Some((-1_000, 51_500)));
+        assert_eq!(game.cursor.state.turn, Team::White);
+        assert!(game.audit().is_ok());
+    }
+}
@@ -0,0 +1,243 @@
+//! Short, URL-safe codes for sharing positions.
+//!
+//! A code is unpadded URL-safe base64 of the following bytes:
+//!
+//! - 1 byte: the format version, currently 1.
+//! - 1 byte: flags. Bit 0 is black to move, bit 1 is an en passant
+//!   square, bit 2 is a pending hole, bit 3 is a hole opening in one
+//!   move, and bit 4 is wormholes on the board.
+//! - 2 bytes: castle rights in bits 0..=3, then the king, short rook,
+//!   and long rook files in 3 bits each.
+//! - 8 bytes: the occupied squares.
+//! - 1 nibble per occupied square, in square order: the piece code,
+//!   with bit 3 set for black.
+//! - If flagged: the en passant square (1 byte), the pending hole
+//!   (1 byte), and the wormholes (8 bytes).
+//!
+//! The start position is 28 bytes, or 38 characters. Move counters are
+//! not included, so decoded positions start at move 1.
+
+use crate::{board::BitBoard, castle::{Castle, CastleRights}, holes::HoleExits, pieces::{Piece, Pieces}, square::Square, state::BoardState, team::Team};
+
+const VERSION: u8 = 1;
+
+const BLACK_TO_MOVE: u8 = 1 << 0;
+const HAS_EN_PASSANT: u8 = 1 << 1;
+const HAS_NEXT_HOLE: u8 = 1 << 2;
+const HOLE_IN_1: u8 = 1 << 3;
+const HAS_WORMHOLES: u8 = 1 << 4;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum CodeError {
+    /// The code contains a character that is not URL-safe base64.
+    Base64,
+
+    /// The code was written by an unsupported version of the format.
+    Version(u8),
+
+    /// The code ended before the position did.
+    Truncated,
+
+    /// A field has a value that no position could have.
+    InvalidField,
+}
+
+/// Encode the position as a share code.
+pub fn to_code(state: &BoardState) -> String {
+    let mut flags = 0;
+    if state.turn == Team::Black { flags |= BLACK_TO_MOVE }
+    if state.en_passant.is_some() { flags |= HAS_EN_PASSANT }
+    if state.next_hole.is_some() { flags |= HAS_NEXT_HOLE }
+    if state.hole_in_1 { flags |= HOLE_IN_1 }
+    if !state.wormholes.is_empty() { flags |= HAS_WORMHOLES }
+
+    let settings = state.castle.settings;
+    let castle = (state.castle.rights as u16 & 0b1111)
+        | (settings.king_file.to_u8() as u16) << 4
+        | (settings.short_file.to_u8() as u16) << 7
+        | (settings.long_file.to_u8() as u16) << 10;
+
+    let occupied = state.pieces.occupied();
+    let mut bytes = vec![VERSION, flags];
+    bytes.extend_from_slice(&castle.to_le_bytes());
+    bytes.extend_from_slice(&occupied.0.to_le_bytes());
+
+    let nibbles: Vec<u8> = occupied.into_iter().map(|sq| {
+        let pc = state.pieces.piece_at(sq).map_or(Piece::NONE, |pc| pc.to_u8());
+        let team = if state.pieces.black.has(sq) { 0b1000 } else { 0 };
+        pc | team
+    }).collect();
+
+    for pair in nibbles.chunks(2) {
+        bytes.push(pair[0] | pair.get(1).map_or(0, |hi| hi << 4));
+    }
+
+    if let Some(sq) = state.en_passant {
+        bytes.push(sq.to_index() as u8);
+    }
+
+    if let Some(sq) = state.next_hole {
+        bytes.push(sq.to_index() as u8);
+    }
+
+    if !state.wormholes.is_empty() {
+        bytes.extend_from_slice(&state.wormholes.0.to_le_bytes());
+    }
+
+    encode_base64(&bytes)
+}
+
+/// Decode a share code written by `to_code`.
+pub fn from_code(code: &str) -> Result<BoardState, CodeError> {
+    let bytes = decode_base64(code.trim()).ok_or(CodeError::Base64)?;
+    let mut bytes = bytes.as_slice();
+    let mut take = |n: usize| -> Result<&[u8], CodeError> {
+        let (head, tail) = bytes.split_at_checked(n).ok_or(CodeError::Truncated)?;
+        bytes = tail;
+        Ok(head)
+    };
+
+    let version = take(1)?[0];
+    if version != VERSION {
+        return Err(CodeError::Version(version))
+    }
+
+    let flags = take(1)?[0];
+    if flags >> 5 != 0 {
+        return Err(CodeError::InvalidField)
+    }
+
+    let castle = u16::from_le_bytes([take(1)?[0], take(1)?[0]]);
+    if castle >> 13 != 0 {
+        return Err(CodeError::InvalidField)
+    }
+
+    let mut rights = CastleRights { rights: (castle & 0b1111) as u8, ..CastleRights::default() };
+    rights.set_king(((castle >> 4) & 0b111) as u8);
+    rights.set_rook(Castle::Short, ((castle >> 7) & 0b111) as u8);
+    rights.set_rook(Castle::Long, ((castle >> 10) & 0b111) as u8);
+
+    let occupied = BitBoard(read_u64(take(8)?));
+    let packed = take(occupied.count().div_ceil(2))?;
+
+    let mut pieces = Pieces::empty();
+    for (i, sq) in occupied.into_iter().enumerate() {
+        let nibble = (packed[i / 2] >> ((i & 1) * 4)) & 0xF;
+        let pc = Piece::from_u8(nibble & 0b111).ok_or(CodeError::InvalidField)?;
+        let team = if nibble & 0b1000 != 0 { Team::Black } else { Team::White };
+        pieces.insert(sq, pc, team, BitBoard::new());
+    }
+
+    if occupied.count() % 2 == 1 && packed[packed.len() - 1] >> 4 != 0 {
+        return Err(CodeError::InvalidField)
+    }
+
+    let mut square = |flag: u8| -> Result<Option<Square>, CodeError> {
+        if flags & flag == 0 {
+            return Ok(None)
+        }
+
+        Square::try_from_index(take(1)?[0] as usize).map(Some).ok_or(CodeError::InvalidField)
+    };
+
+    let en_passant = square(HAS_EN_PASSANT)?;
+    let next_hole = square(HAS_NEXT_HOLE)?;
+    let wormholes = if flags & HAS_WORMHOLES != 0 {
+        BitBoard(read_u64(take(8)?))
+    } else {
+        BitBoard::new()
+    };
+
+    if !bytes.is_empty() || (flags & HOLE_IN_1 != 0 && next_hole.is_none()) || (flags & HAS_WORMHOLES != 0 && wormholes.is_empty()) {
+        return Err(CodeError::InvalidField)
+    }
+
+    let mut state = BoardState {
+        en_passant,
+        next_hole,
+        hole_in_1: flags & HOLE_IN_1 != 0,
+        wormholes,
+        pieces,
+        castle: rights,
+        turn: if flags & BLACK_TO_MOVE != 0 { Team::Black } else { Team::White },
+        exits: HoleExits::new(wormholes),
+        ..BoardState::default()
+    };
+
+    state.is_check = state.king_attacked();
+    Ok(state)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(bytes);
+    u64::from_le_bytes(buf)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+    out
+}
+
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 4 == 1 {
+        return None
+    }
+
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let v = ALPHABET.iter().position(|&a| a == c)? as u32;
+            n |= v << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodeError;
+    use crate::state::BoardState;
+
+    #[test]
+    fn round_trip() {
+        let start = BoardState::default();
+        let code = start.to_code();
+        assert_eq!(code.len(), 38);
+        assert!(BoardState::from_code(&code).unwrap().is_repetition_of(&start));
+
+        for fen in [
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "qnnrbbkr/pppppppp/8/8/8/8/PPPPPPPP/QNNRBBKR b Hd - 0 1",
+            "4k3/8/8/3p4/8/8/8/4K3 w - - 0 1 d4,e5 c3 1",
+        ] {
+            let state = BoardState::from_extended_fen(fen).unwrap();
+            let decoded = BoardState::from_code(&state.to_code()).unwrap();
+            assert!(decoded.is_repetition_of(&state), "{fen}");
+            assert_eq!(decoded.to_extended_fen(), state.to_extended_fen().replace(" 0 2 ", " 0 1 "));
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(BoardState::from_code("not a code!").err(), Some(CodeError::Base64));
+        assert_eq!(BoardState::from_code("Ag").err(), Some(CodeError::Version(2)));
+
+        let code = BoardState::default().to_code();
+        assert_eq!(BoardState::from_code(&code[..20]).err(), Some(CodeError::Truncated));
+    }
+}
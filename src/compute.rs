@@ -1,4 +1,4 @@
-use crate::{board::BitBoard, cached::*, castle::{can_castle, Castle}, ray::*, pieces::Piece, square::Square, state::BoardState, team::Team};
+use crate::{board::BitBoard, castle::{can_castle, Castle}, pieces::Piece, square::Square, state::BoardState};
 
 pub fn compute(state: &BoardState, sq: Square, defense: Option<BitBoard>) -> BitBoard {
     let wormholes = state.wormholes;
@@ -6,7 +6,6 @@ pub fn compute(state: &BoardState, sq: Square, defense: Option<BitBoard>) -> Bit
     let occupied = state.pieces.occupied().transmit(wormholes);
     let turn = state.turn;
     let friendly = state.pieces.on_team(turn);
-    let holes_are_occ = occupied.intersects(wormholes);
 
     if let Some(pc) = state.pieces.piece_at_or_on_hole(sq, wormholes) {
         match pc {
@@ -14,9 +13,7 @@ pub fn compute(state: &BoardState, sq: Square, defense: Option<BitBoard>) -> Bit
                 let defense = defense.unwrap_or_else(|| crate::defense::defense(state));
             
                 if wormholes.has(sq) {
-                    for out_sq in wormholes {
-                        moves |= out_sq.king_moves() & !wormholes;
-                    }
+                    moves |= state.hole_exits(wormholes).king;
                 } else {
                     moves |= sq.king_moves();
                 }
@@ -32,53 +29,24 @@ pub fn compute(state: &BoardState, sq: Square, defense: Option<BitBoard>) -> Bit
                 }
             },
             Piece::Queen => {
-                if wormholes.has(sq) {
-                    for out_sq in wormholes {
-                        moves |= out_sq.bishop_moves(occupied) | out_sq.rook_moves(occupied);
-                    }
-                    moves &= !wormholes;
-                } else {
-                    moves = sq.bishop_moves(occupied) | sq.rook_moves(occupied);
-                    if !holes_are_occ {
-                        for in_sq in (moves & !occupied) & wormholes {
-                            if let Some(ray) = sq.ray(in_sq) {
-                                for out_sq in wormholes {
-                                    moves |= ray.cast(out_sq, occupied);
-                                }
-                            }
-                        }
-                    }
-                }
-                moves &= (!friendly) | crate::blockable::blockable(sq, state);
+                moves |= diagonal(sq, occupied, wormholes) | orthogonal(sq, occupied, wormholes);
+                moves &= !friendly & crate::blockable::blockable(sq, state);
             },
             Piece::Bishop => {
-                if wormholes.has(sq) {
-                    for out_sq in wormholes {
-                        moves |= out_sq.bishop_moves(occupied) & !wormholes;                    
-                    }
-                } else {
-                    moves |= sq.bishop_moves(occupied);
-                    if !holes_are_occ {
-                        for in_sq in (moves & !occupied) & wormholes {
-                            if let Some(ray) = sq.diag_ray(in_sq) {
-                                for out_sq in wormholes {
-                                    moves |= ray.cast(out_sq, occupied);
-                                }
-                            }
-                        }
-                    }
-                }
-                moves &= (!friendly) | crate::blockable::blockable(sq, state);
+                moves |= diagonal(sq, occupied, wormholes);
+                moves &= !friendly & crate::blockable::blockable(sq, state);
             },
             Piece::Knight => {
-                if wormholes.has(sq) {
-                    for out_sq in wormholes {
-                        moves |= out_sq.knight_moves() & !wormholes;
-                    } 
-                } else {
-                    moves |= sq.knight_moves();
-                }
-                moves &= (!friendly) | crate::blockable::blockable(sq, state);
+                moves |= leaps(state, sq);
+                moves &= !friendly & crate::blockable::blockable(sq, state);
+            },
+            Piece::Archbishop => {
+                moves |= diagonal(sq, occupied, wormholes) | leaps(state, sq);
+                moves &= !friendly & crate::blockable::blockable(sq, state);
+            },
+            Piece::Chancellor => {
+                moves |= orthogonal(sq, occupied, wormholes) | leaps(state, sq);
+                moves &= !friendly & crate::blockable::blockable(sq, state);
             },
             Piece::Pawn => {
                 let mut captures = BitBoard(0);
@@ -87,11 +55,11 @@ pub fn compute(state: &BoardState, sq: Square, defense: Option<BitBoard>) -> Bit
 
                 if wormholes.has(sq) {
                     let is_pawn_rank = pawn_rank.intersects(wormholes);
+                    captures |= state.hole_exits(wormholes).pawn(turn);
                     for out_sq in wormholes {
-                        captures |= out_sq.pawn_captures(turn);
                         if let Some(one) = out_sq.next(delta) && !occupied.has(one) {
                             moves |= one;
-                            if let Some(two) = one.next(delta) && is_pawn_rank {
+                            if let Some(two) = one.next(delta) && is_pawn_rank && !occupied.has(two) {
                                 moves |= two;
                             }
                         }
@@ -122,25 +90,10 @@ pub fn compute(state: &BoardState, sq: Square, defense: Option<BitBoard>) -> Bit
                 moves |= (captures & (ep_tx | enemy)) & crate::blockable::blockable(sq, state);
             },
             Piece::Rook => {
-                if wormholes.has(sq) {
-                    for out_sq in wormholes {
-                        moves |= out_sq.rook_moves(occupied) & !wormholes;
-                    }
-                } else {
-                    moves |= sq.rook_moves(occupied);
-                    if !holes_are_occ {
-                        for in_sq in (moves & !occupied) & wormholes {
-                            if let Some(ray) = sq.ortho_ray(in_sq) {
-                                for out_sq in wormholes {
-                                    moves |= ray.cast(out_sq, occupied);
-                                }
-                            }
-                        }
-                    }
-                }
+                moves |= orthogonal(sq, occupied, wormholes);
 
                 let blockable = crate::blockable::blockable(sq, state);
-                moves &= (!friendly) | blockable;
+                moves &= !friendly & blockable;
 
                 if blockable == BitBoard(!0) {
                     for side in [Castle::Short, Castle::Long] {
@@ -159,4 +112,58 @@ pub fn compute(state: &BoardState, sq: Square, defense: Option<BitBoard>) -> Bit
     }
 
     moves.transmit(wormholes)
-}
\ No newline at end of file
+}
+/// The squares a diagonal slider on `sq` reaches, including through
+/// wormholes, before friendly pieces and pins are masked out.
+fn diagonal(sq: Square, occupied: BitBoard, wormholes: BitBoard) -> BitBoard {
+    let mut moves = BitBoard(0);
+    if wormholes.has(sq) {
+        for out_sq in wormholes {
+            moves |= out_sq.bishop_moves(occupied) & !wormholes;
+        }
+    } else {
+        moves |= sq.bishop_moves(occupied);
+        if !occupied.intersects(wormholes) {
+            for in_sq in (moves & !occupied) & wormholes {
+                if let Some(ray) = sq.diag_ray(in_sq) {
+                    for out_sq in wormholes {
+                        moves |= ray.cast(out_sq, occupied);
+                    }
+                }
+            }
+        }
+    }
+    moves
+}
+
+/// The squares an orthogonal slider on `sq` reaches, including through
+/// wormholes, before friendly pieces and pins are masked out.
+fn orthogonal(sq: Square, occupied: BitBoard, wormholes: BitBoard) -> BitBoard {
+    let mut moves = BitBoard(0);
+    if wormholes.has(sq) {
+        for out_sq in wormholes {
+            moves |= out_sq.rook_moves(occupied) & !wormholes;
+        }
+    } else {
+        moves |= sq.rook_moves(occupied);
+        if !occupied.intersects(wormholes) {
+            for in_sq in (moves & !occupied) & wormholes {
+                if let Some(ray) = sq.ortho_ray(in_sq) {
+                    for out_sq in wormholes {
+                        moves |= ray.cast(out_sq, occupied);
+                    }
+                }
+            }
+        }
+    }
+    moves
+}
+
+/// The squares a knight on `sq` reaches, from any wormhole exit if it is on one.
+fn leaps(state: &BoardState, sq: Square) -> BitBoard {
+    if state.wormholes.has(sq) {
+        state.hole_exits(state.wormholes).knight
+    } else {
+        sq.knight_moves()
+    }
+}
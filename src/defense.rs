@@ -1,4 +1,4 @@
-use crate::{board::BitBoard, state::BoardState};
+use crate::{board::BitBoard, square::Square, state::BoardState};
 
 
 /// Get a mask of squares defended by the opponent.
@@ -6,87 +6,102 @@ pub fn defense(state: &BoardState) -> BitBoard {
     let mut defense = BitBoard(0);
 
     if let Some(king) = state.checkable_king() {
-        // The wormholes that will be available to the opponent NEXT turn.
+        for_each_attack(state, king, |_, attacks| defense |= attacks);
+        defense = defense.transmit(state.next_wormholes());
+    }
+
+    defense
+}
+
+/// Get a mask of the opponent's pieces that attack the king
+/// of the team to move, including through wormholes.
+pub fn checkers(state: &BoardState) -> BitBoard {
+    let mut checkers = BitBoard(0);
+
+    if let Some(king) = state.checkable_king() {
         let wormholes = state.next_wormholes();
-        // squares occupied by anything.
-        let occupied = state.pieces.occupied().without(king).transmit(wormholes);
-        // squares occupied by enemy pieces.
-        let enemy = state.pieces.on_team(!state.turn).transmit(wormholes);
-
-        let enemy_diag = (state.pieces.bishops | state.pieces.queens) & enemy;
-        let enemy_ortho = (state.pieces.rooks | state.pieces.queens) & enemy;
-        let enemy_knights = state.pieces.knights & enemy;
-        let enemy_pawns = state.pieces.pawns & enemy;
-        let enemy_kings = state.pieces.kings & enemy;
-
-        // enemy diagonal sliding pieces on wormholes
-        if enemy_diag.intersects(wormholes) {
-            for out_sq in wormholes {
-                defense |= out_sq.bishop_moves(occupied) & !wormholes;
+        for_each_attack(state, king, |enemy_sq, attacks| {
+            if attacks.transmit(wormholes).has(king) {
+                checkers.set(enemy_sq);
             }
-        }
+        });
+    }
 
-        // enemy diagonal sliding pieces not on wormholes
-        for enemy_sq in enemy_diag & !wormholes {
-            let moves = enemy_sq.bishop_moves(occupied);
-            defense |= moves;
-            for in_sq in moves & wormholes & !occupied {
+    checkers
+}
+
+/// Call `f` with the square of each enemy piece and the squares it attacks,
+/// looking through the king so it cannot step back along a sliding attack.
+fn for_each_attack(state: &BoardState, king: Square, mut f: impl FnMut(Square, BitBoard)) {
+    // The wormholes that will be available to the opponent NEXT turn.
+    let wormholes = state.next_wormholes();
+    // squares occupied by anything.
+    let occupied = state.pieces.occupied().without(king).transmit(wormholes);
+    // squares occupied by enemy pieces.
+    let enemy = state.pieces.on_team(!state.turn).transmit(wormholes);
+
+    let enemy_diag = state.pieces.diagonal_sliders() & enemy;
+    let enemy_ortho = state.pieces.orthogonal_sliders() & enemy;
+    let enemy_knights = state.pieces.leapers() & enemy;
+    let enemy_pawns = state.pieces.pawns & enemy;
+    let enemy_kings = state.pieces.kings & enemy;
+    let exits = state.hole_exits(wormholes);
+
+    for enemy_sq in enemy_diag {
+        let mut attacks = BitBoard(0);
+        if wormholes.has(enemy_sq) {
+            // enemy diagonal sliding pieces on wormholes
+            for out_sq in wormholes {
+                attacks |= out_sq.bishop_moves(occupied) & !wormholes;
+            }
+        } else {
+            attacks = enemy_sq.bishop_moves(occupied);
+            for in_sq in attacks & wormholes & !occupied {
                 if let Some(ray) = enemy_sq.diag_ray(in_sq) {
                     for out_sq in wormholes {
-                        defense |= ray.cast(out_sq, occupied);
+                        attacks |= ray.cast(out_sq, occupied);
                     }
                 }
             }
         }
 
-        // enemy orthogonal sliding attacks on wormholes
-        if enemy_ortho.intersects(wormholes) {
+        f(enemy_sq, attacks);
+    }
+
+    for enemy_sq in enemy_ortho {
+        let mut attacks = BitBoard(0);
+        if wormholes.has(enemy_sq) {
+            // enemy orthogonal sliding pieces on wormholes
             for out_sq in wormholes {
-                defense |= out_sq.rook_moves(occupied) & !wormholes;
+                attacks |= out_sq.rook_moves(occupied) & !wormholes;
             }
-        }
-
-        // enemy orthogonal sliding pieces not on wormholes.
-        for enemy_sq in enemy_ortho & !wormholes {
-            let moves = enemy_sq.rook_moves(occupied);
-            defense |= moves;
-            for in_sq in moves & wormholes & !occupied {
-                if let Some(ray) = enemy_sq.diag_ray(in_sq) {
+        } else {
+            attacks = enemy_sq.rook_moves(occupied);
+            for in_sq in attacks & wormholes & !occupied {
+                if let Some(ray) = enemy_sq.ortho_ray(in_sq) {
                     for out_sq in wormholes {
-                        defense |= ray.cast(out_sq, occupied);
+                        attacks |= ray.cast(out_sq, occupied);
                     }
                 }
             }
         }
 
-        if enemy_knights.intersects(wormholes) {
-            for out_sq in wormholes {
-                defense |= out_sq.knight_moves() & !wormholes;
-            }
-        }
-
-        for enemy_sq in enemy_knights & !wormholes {
-            defense |= enemy_sq.knight_moves();
-        }
-
-        if enemy_kings.intersects(wormholes) {
-            for out_sq in wormholes {
-                defense |= out_sq.king_moves() & !wormholes;
-            }
-        }
-
-        for enemy_sq in enemy_kings & !wormholes {
-            defense |= enemy_sq.king_moves();
-        }
-
-        if enemy_pawns.intersects(wormholes) {
-            defense |= (enemy_pawns & wormholes).pawn_captures(!state.turn) & !wormholes;
-        }
+        f(enemy_sq, attacks);
+    }
 
-        defense |= (enemy_pawns & !wormholes).pawn_captures(!state.turn);
+    for enemy_sq in enemy_knights {
+        f(enemy_sq, if wormholes.has(enemy_sq) { exits.knight } else { enemy_sq.knight_moves() });
+    }
 
-        defense = defense.transmit(wormholes);
+    for enemy_sq in enemy_kings {
+        f(enemy_sq, if wormholes.has(enemy_sq) { exits.king } else { enemy_sq.king_moves() });
     }
 
-    defense
-}
\ No newline at end of file
+    for enemy_sq in enemy_pawns {
+        f(enemy_sq, if wormholes.has(enemy_sq) {
+            exits.pawn(!state.turn)
+        } else {
+            BitBoard::from(enemy_sq).pawn_captures(!state.turn)
+        });
+    }
+}
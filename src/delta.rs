@@ -1,5 +1,5 @@
 use std::fmt;
-use crate::{castle::Castle, pieces::Piece, square::Square};
+use crate::{castle::Castle, pieces::Piece, square::{File, Square}};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
 pub struct BoardDelta {
@@ -8,20 +8,19 @@ pub struct BoardDelta {
 
     /// Squares that had changes on the board.
     /// # Layout
-    ///  - bits 0..=6: Source Square
-    ///  - bits 7..=12: Destination Square
-    ///  - bits 13..=18: Wormhole Square
-    ///  - bits 19..=24: Square of pawn captured en-passant
+    ///  - bits 0..=5: Source Square
+    ///  - bits 6..=11: Destination Square
+    ///  - bits 12..=17: Square of pawn captured en-passant
+    ///  - bits 18..=23: Wormhole Square
+    ///  - bit 24: unused
     ///  - bits 25..=30: Previous En Passant Square
     ///  - bit 31: Whether the prev ep sq is Some.
     squares: u32,
 
-    /// Relevant pieces and flags.
+    /// Relevant flags.
     /// # Layout
-    ///  - bits 0,1,2: Capture Piece (6=None)
-    ///  - bits 3,4,5: Promote Piece (6=None)
-    ///  - bits 6,7,8: Piece Crushed by Wormhole Spawning (6=None)
-    ///  - bits 9..=15: Previous Halfmove Count
+    ///  - bits 0..=8: unused
+    ///  - bits 9..=15: Previous Halfmove Count, low 7 bits
     ///  - bit 16: IS_DOUBLE_PUSH (allows ep)
     ///  - bit 17: HALFMOVES_RESET
     ///  - bit 18: WORMHOLE_POPPED (wormhole on queue spawned)
@@ -36,7 +35,67 @@ pub struct BoardDelta {
     ///  - bit 27: WORMHOLE_IN_1 (wormhole will be popped next turn)
     ///  - bit 28: WAS_CHECK (whether the king was in check in the position the move was played in)
     ///  - bit 29: IS_CHECK (whether the king is in check in the resulting position)
+    ///  - bit 30: Previous Halfmove Count, high bit
     data: u32,
+
+    /// Piece codes, 4 bits each.
+    /// # Layout
+    ///  - bits 0..=3: Capture Piece (15=None)
+    ///  - bits 4..=7: Promote Piece (15=None)
+    ///  - bits 8..=11: Piece Crushed by Wormhole Spawning (15=None)
+    ///  - bits 12..=15: unused
+    pieces: u16,
+}
+
+/// Why a packed delta could not be decoded.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DeltaError {
+    /// Bits that no field uses are set.
+    UnusedBits,
+
+    /// A piece field holds a code that is not a piece,
+    /// or a piece that cannot be captured or promoted to.
+    Piece,
+
+    /// The source and destination squares are the same.
+    Squares,
+
+    /// The move is marked as both a short and a long castle.
+    Castle,
+
+    /// The move is not legal in the position, or needs a promotion
+    /// piece that the delta does not have.
+    Illegal,
+
+    /// The move is legal, but this field does not match the delta
+    /// that the move produces in the position.
+    Field(DeltaField),
+}
+
+/// A group of fields in a delta, as reported by `DeltaError::Field`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DeltaField {
+    /// The captured piece, and the square of a pawn taken en passant.
+    Capture,
+
+    /// The piece a pawn promotes to.
+    Promote,
+
+    /// The en passant square before the move, and whether the move is a double push.
+    EnPassant,
+
+    /// The castle side, and the castling rights the move takes away.
+    Castle,
+
+    /// The halfmove clock before the move, and whether the move resets it.
+    Halfmoves,
+
+    /// The wormhole square, whether it was spawned or queued, the
+    /// piece it crushed, and whether one spawns next turn.
+    Wormhole,
+
+    /// Whether the king was in check before the move and is after it.
+    Check,
 }
 
 impl Default for BoardDelta {
@@ -44,41 +103,53 @@ impl Default for BoardDelta {
         Self {
             time: 0,
             squares: 0,
-            data: 0x1FF
+            data: 0,
+            // capture, promote, and crushed pieces are all Piece::NONE.
+            pieces: 0xFFF,
         }
     }
 }
 
 impl BoardDelta {
+    /// The number of milliseconds the move took to be played.
+    pub fn get_time(&self) -> u32 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, ms: u32) {
+        self.time = ms;
+    }
+
     pub fn get_capture_pc(&self) -> Option<Piece> {
-        Piece::from_u8((self.data & 0b111) as u8)
+        Piece::from_u8((self.pieces & 0xF) as u8)
     }
 
     pub fn set_capture_pc(&mut self, pc: Piece) {
-        self.data &= !0b111;
-        self.data |= pc.to_u8() as u32;
+        self.pieces &= !0xF;
+        self.pieces |= pc.to_u8() as u16;
     }
     
+    /// Whether both deltas move the same piece between the same
+    /// squares with the same promotion, ignoring the time spent.
+    pub fn is_same_move(&self, other: &Self) -> bool {
+        self.get_src_sq() == other.get_src_sq()
+            && self.get_dst_sq() == other.get_dst_sq()
+            && self.get_promote_pc() == other.get_promote_pc()
+    }
+
     pub fn get_promote_pc(&self) -> Option<Piece> {
-        Piece::from_u8(((self.data >> 3) & 0b111) as u8)
+        Piece::from_u8(((self.pieces >> 4) & 0xF) as u8)
     }
 
     pub fn set_promote_pc(&mut self, pc: Piece) {
-        let bits = match pc {
-            Piece::Bishop => 0,
-            Piece::Knight => 1,
-            Piece::Queen => 2,
-            Piece::Rook => 3,
-            _ => 6,
-        };
-
-        self.data &= !0x38;
-        self.data |= bits << 3;
+        let bits = if pc.is_promotable() { pc.to_u8() } else { Piece::NONE } as u16;
+        self.pieces &= !(0xF << 4);
+        self.pieces |= bits << 4;
     }
 
     pub fn get_ep_capture_sq(&self) -> Option<Square> {
         if self.data & (1 << 24) != 0 {
-            Some(Square::from_index(((self.squares >> 19) & 0x3F) as usize))
+            Some(Square::from_index(((self.squares >> 12) & 0x3F) as usize))
         } else {
             None
         }
@@ -86,8 +157,8 @@ impl BoardDelta {
 
     pub fn set_ep_capture_sq(&mut self, sq: Square) {
         self.data |= 1 << 24;
-        self.squares &= !(0x3F << 19);
-        self.squares |= (sq.to_index() as u32) << 19;
+        self.squares &= !(0x3F << 12);
+        self.squares |= (sq.to_index() as u32) << 12;
     }
 
     pub fn is_double_push(&self) -> bool {
@@ -116,7 +187,7 @@ impl BoardDelta {
     }
 
     pub fn set_castle_deltas(&mut self, prev: u8, next: u8) {
-        self.data &= !0b1111 << 20;
+        self.data &= !(0b1111 << 20);
         self.data |= ((prev ^ next) as u32) << 20;
     }
 
@@ -162,12 +233,13 @@ impl BoardDelta {
     }
 
     pub fn get_prev_halfmoves(&self) -> u8 {
-        ((self.data >> 9) & 0x3F) as u8
+        (((self.data >> 9) & 0x7F) | ((self.data >> 23) & 0x80)) as u8
     }
 
     pub fn set_prev_halfmoves(&mut self, halfmoves: u8) {
-        self.data &= !(0x3F << 9);
-        self.data |= (halfmoves as u32) << 9;
+        self.data &= !(0x7F << 9 | 1 << 30);
+        self.data |= (halfmoves as u32 & 0x7F) << 9;
+        self.data |= (halfmoves as u32 & 0x80) << 23;
     }
 
     pub fn is_popped_wormhole(&self) -> bool {
@@ -187,12 +259,12 @@ impl BoardDelta {
     }
 
     pub fn get_crushed_pc(&self) -> Option<Piece> {
-        Piece::from_u8(((self.data >> 6) & 0b111) as u8)
+        Piece::from_u8(((self.pieces >> 8) & 0xF) as u8)
     }
 
     pub fn set_crushed_pc(&mut self, pc: Piece) {
-        self.data &= !(0b1111 << 6);
-        self.data |= (pc.to_u8() as u32) << 6;
+        self.pieces &= !(0xF << 8);
+        self.pieces |= (pc.to_u8() as u16) << 8;
     }
 
     pub fn is_resets_halfmoves(&self) -> bool {
@@ -226,11 +298,89 @@ impl BoardDelta {
     pub fn set_is_check(&mut self) {
         self.data |= 1 << 29
     }
+
+    /// The number of bytes in a packed delta.
+    pub const PACKED_LEN: usize = 14;
+
+    /// Encode the delta as `PACKED_LEN` bytes for storage or transfer.
+    /// The layout is opaque; decode it with `unpack`.
+    pub fn pack(&self) -> [u8; Self::PACKED_LEN] {
+        let mut bytes = [0; Self::PACKED_LEN];
+        bytes[0..4].copy_from_slice(&self.time.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.squares.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.data.to_le_bytes());
+        bytes[12..14].copy_from_slice(&self.pieces.to_le_bytes());
+        bytes
+    }
+
+    /// Decode a delta written by `pack`, rejecting bytes that no move
+    /// could have produced. A valid delta may still be illegal in a
+    /// given position; use `ChessGame::audit` to check moves in context.
+    pub fn unpack(bytes: [u8; Self::PACKED_LEN]) -> Result<Self, DeltaError> {
+        let delta = Self::unpack_unchecked(bytes);
+
+        if delta.data >> 31 != 0 || delta.data & 0x1FF != 0 || delta.squares & (1 << 24) != 0 || delta.pieces >> 12 != 0 {
+            return Err(DeltaError::UnusedBits)
+        }
+
+        for code in [delta.pieces & 0xF, (delta.pieces >> 4) & 0xF, (delta.pieces >> 8) & 0xF] {
+            if code as u8 != Piece::NONE && Piece::from_u8(code as u8).is_none() {
+                return Err(DeltaError::Piece)
+            }
+        }
+
+        if delta.get_capture_pc() == Some(Piece::King) {
+            return Err(DeltaError::Piece)
+        }
+
+        if delta.get_promote_pc().is_some_and(|pc| !pc.is_promotable()) {
+            return Err(DeltaError::Piece)
+        }
+
+        if delta.get_src_sq() == delta.get_dst_sq() {
+            return Err(DeltaError::Squares)
+        }
+
+        if delta.data & (0b11 << 25) == 0b11 << 25 {
+            return Err(DeltaError::Castle)
+        }
+
+        Ok(delta)
+    }
+
+    /// The inverse of `pack`, without checking that the fields are valid.
+    pub(crate) fn unpack_unchecked(bytes: [u8; Self::PACKED_LEN]) -> Self {
+        let [t0, t1, t2, t3, s0, s1, s2, s3, d0, d1, d2, d3, p0, p1] = bytes;
+        Self {
+            time: u32::from_le_bytes([t0, t1, t2, t3]),
+            squares: u32::from_le_bytes([s0, s1, s2, s3]),
+            data: u32::from_le_bytes([d0, d1, d2, d3]),
+            pieces: u16::from_le_bytes([p0, p1]),
+        }
+    }
+
+    /// The move in coordinate notation, such as "e2e4" or "e7e8q".
+    /// Castling is written as the king's move, such as "e1g1".
+    pub fn to_uci(&self) -> String {
+        let src = self.get_src_sq();
+        let dst = match self.get_castle_side() {
+            Some(Castle::Short) => Square::new(src.rank(), File::G),
+            Some(Castle::Long) => Square::new(src.rank(), File::C),
+            None => self.get_dst_sq(),
+        };
+
+        let mut uci = format!("{src}{dst}");
+        if let Some(pc) = self.get_promote_pc() {
+            uci.push(pc.to_char_lower());
+        }
+        uci
+    }
 }
 
 impl fmt::Debug for BoardDelta {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("BoardDelta")
+            .field("time", &self.get_time())
             .field("src_sq", &self.get_src_sq())
             .field("dst_sq", &self.get_dst_sq())
             .field("capture_pc", &self.get_capture_pc())
@@ -249,4 +399,65 @@ impl fmt::Debug for BoardDelta {
             .field("wormhole_sq", &self.get_wormhole_sq())
             .finish()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{BoardDelta, DeltaError, DeltaField};
+    use crate::{game::ChessGame, pieces::Piece, state::BoardState};
+
+    #[test]
+    fn pack_round_trip() {
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 d7d5 e4d5 d8d5 b1c3").unwrap();
+        for delta in &game.deltas {
+            assert_eq!(BoardDelta::unpack(delta.pack()), Ok(*delta));
+        }
+
+        let mut bytes = game.deltas[2].pack();
+        bytes[11] |= 0x80;
+        assert_eq!(BoardDelta::unpack(bytes), Err(DeltaError::UnusedBits));
+
+        // the old 3-bit piece codes and the top of the piece fields are unused.
+        for (at, bit) in [(8, 0x01), (13, 0x10)] {
+            let mut bytes = game.deltas[2].pack();
+            bytes[at] |= bit;
+            assert_eq!(BoardDelta::unpack(bytes), Err(DeltaError::UnusedBits));
+        }
+
+        let mut bytes = game.deltas[2].pack();
+        bytes[12] = bytes[12] & !0xF | 8;
+        assert_eq!(BoardDelta::unpack(bytes), Err(DeltaError::Piece));
+
+        assert_eq!(BoardDelta::unpack(BoardDelta::default().pack()), Err(DeltaError::Squares));
+
+        // halfmove counts past 127 keep their high bit.
+        let mut delta = game.deltas[4];
+        for halfmoves in [63, 64, 127, 149, 255] {
+            delta.set_prev_halfmoves(halfmoves);
+            assert_eq!(BoardDelta::unpack(delta.pack()).map(|delta| delta.get_prev_halfmoves()), Ok(halfmoves));
+        }
+        assert!(!delta.is_double_push());
+    }
+
+    #[test]
+    fn verify() {
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 d7d5 e4d5").unwrap();
+        let (_, state) = game.positions().nth(2).unwrap();
+        let capture = game.deltas[2];
+        assert!(state.verify_delta(&capture).is_ok_and(|trace| trace.captures.is_some()));
+
+        let tampered = |tamper: fn(&mut BoardDelta)| {
+            let mut delta = capture;
+            tamper(&mut delta);
+            state.verify_delta(&delta)
+        };
+
+        assert_eq!(tampered(|delta| delta.set_capture_pc(Piece::Queen)).err(), Some(DeltaError::Field(DeltaField::Capture)));
+        assert_eq!(tampered(|delta| delta.set_prev_halfmoves(9)).err(), Some(DeltaError::Field(DeltaField::Halfmoves)));
+        assert_eq!(tampered(|delta| delta.set_castle_deltas(0, 0b1111)).err(), Some(DeltaError::Field(DeltaField::Castle)));
+        assert_eq!(tampered(|delta| delta.set_pushed_wormhole()).err(), Some(DeltaError::Field(DeltaField::Wormhole)));
+        assert_eq!(tampered(|delta| delta.set_time(5_000)).err(), None);
+
+        // the pawn on d7 has already moved.
+        assert_eq!(state.verify_delta(&game.deltas[1]).err(), Some(DeltaError::Illegal));
+    }
+}
@@ -0,0 +1,259 @@
+//! The move list of a game, shared between clones.
+//!
+//! Deltas are stored in full chunks of `CHUNK` moves behind an `Arc`, and
+//! an owned tail holds the moves since the last full chunk. Cloning the
+//! list copies the chunk pointers and the short tail, so cloning a long
+//! game, such as to explore a variation, does not copy its moves. A chunk
+//! is only copied when a clone changes a move in it.
+
+use std::{fmt, ops::{Index, IndexMut}, sync::Arc};
+
+use crate::delta::BoardDelta;
+
+/// The number of moves in each shared chunk.
+const CHUNK: usize = 64;
+
+#[derive(Clone, Default)]
+pub struct Deltas {
+    chunks: Vec<Arc<[BoardDelta]>>,
+    tail: Vec<BoardDelta>,
+}
+
+impl Deltas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len() * CHUNK + self.tail.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty() && self.tail.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&BoardDelta> {
+        match self.chunks.get(index / CHUNK) {
+            Some(chunk) => chunk.get(index % CHUNK),
+            None => self.tail.get(index - self.chunks.len() * CHUNK),
+        }
+    }
+
+    /// The delta at the index, copying its chunk first if a clone shares it.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut BoardDelta> {
+        let shared = self.chunks.len() * CHUNK;
+        match self.chunks.get_mut(index / CHUNK) {
+            Some(chunk) => Arc::make_mut(chunk).get_mut(index % CHUNK),
+            None => self.tail.get_mut(index - shared),
+        }
+    }
+
+    pub fn first(&self) -> Option<&BoardDelta> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<&BoardDelta> {
+        self.len().checked_sub(1).and_then(|i| self.get(i))
+    }
+
+    pub fn push(&mut self, delta: BoardDelta) {
+        self.tail.push(delta);
+        if self.tail.len() == CHUNK {
+            self.chunks.push(Arc::from(std::mem::take(&mut self.tail)));
+        }
+    }
+
+    /// Keep the first `len` deltas, doing nothing if there are not more.
+    pub fn truncate(&mut self, len: usize) {
+        if len >= self.len() {
+            return
+        }
+
+        let full = len / CHUNK;
+        if full < self.chunks.len() {
+            self.tail = self.chunks[full][..len % CHUNK].to_vec();
+            self.chunks.truncate(full);
+        } else {
+            self.tail.truncate(len - full * CHUNK);
+        }
+    }
+
+    /// Remove the deltas from the index on, returning them.
+    pub fn split_off(&mut self, at: usize) -> Deltas {
+        let rest = self.iter().skip(at).copied().collect();
+        self.truncate(at);
+        rest
+    }
+
+    /// Move every delta of `other` to the end of this list.
+    pub fn append(&mut self, other: &mut Deltas) {
+        self.extend(std::mem::take(other).iter().copied());
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { deltas: self, front: 0, back: self.len() }
+    }
+
+    pub fn to_vec(&self) -> Vec<BoardDelta> {
+        self.iter().copied().collect()
+    }
+
+    /// The heap memory held by the list, counting shared chunks in full.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let delta = std::mem::size_of::<BoardDelta>();
+        self.chunks.capacity() * std::mem::size_of::<Arc<[BoardDelta]>>()
+            + self.chunks.len() * CHUNK * delta
+            + self.tail.capacity() * delta
+    }
+}
+
+impl Index<usize> for Deltas {
+    type Output = BoardDelta;
+
+    fn index(&self, index: usize) -> &BoardDelta {
+        let len = self.len();
+        self.get(index).unwrap_or_else(|| panic!("delta index {index} out of range for {len} deltas"))
+    }
+}
+
+impl IndexMut<usize> for Deltas {
+    fn index_mut(&mut self, index: usize) -> &mut BoardDelta {
+        let len = self.len();
+        self.get_mut(index).unwrap_or_else(|| panic!("delta index {index} out of range for {len} deltas"))
+    }
+}
+
+impl Extend<BoardDelta> for Deltas {
+    fn extend<I: IntoIterator<Item = BoardDelta>>(&mut self, iter: I) {
+        for delta in iter {
+            self.push(delta);
+        }
+    }
+}
+
+impl FromIterator<BoardDelta> for Deltas {
+    fn from_iter<I: IntoIterator<Item = BoardDelta>>(iter: I) -> Self {
+        let mut deltas = Self::new();
+        deltas.extend(iter);
+        deltas
+    }
+}
+
+impl From<Vec<BoardDelta>> for Deltas {
+    fn from(deltas: Vec<BoardDelta>) -> Self {
+        deltas.into_iter().collect()
+    }
+}
+
+impl PartialEq for Deltas {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for Deltas {}
+
+impl fmt::Debug for Deltas {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a Deltas {
+    type Item = &'a BoardDelta;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// The deltas of a list in order, from either end.
+#[derive(Clone)]
+pub struct Iter<'a> {
+    deltas: &'a Deltas,
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a BoardDelta;
+
+    fn next(&mut self) -> Option<&'a BoardDelta> {
+        if self.front == self.back {
+            return None
+        }
+
+        self.front += 1;
+        self.deltas.get(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<&'a BoardDelta> {
+        self.front = (self.front + n).min(self.back);
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None
+        }
+
+        self.back -= 1;
+        self.deltas.get(self.back)
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{Deltas, CHUNK};
+    use crate::{game::ChessGame, settings::GameSettings};
+
+    #[test]
+    fn shared() {
+        let game = crate::sim::random_game(GameSettings::default(), 3);
+        let moves = game.deltas.to_vec();
+        let mut deltas = Deltas::from(moves.clone());
+        assert!(deltas.len() > CHUNK);
+        assert!(deltas.iter().eq(moves.iter()));
+        assert!(deltas.iter().rev().eq(moves.iter().rev()));
+        assert_eq!(deltas.iter().nth(CHUNK + 1), moves.get(CHUNK + 1));
+
+        // clones share chunks until one of them changes a move.
+        let mut clone = deltas.clone();
+        assert!(Arc::ptr_eq(&clone.chunks[0], &deltas.chunks[0]));
+        clone[1] = moves[0];
+        assert!(!Arc::ptr_eq(&clone.chunks[0], &deltas.chunks[0]));
+        assert_eq!(deltas[1], moves[1]);
+
+        let rest = deltas.split_off(CHUNK - 1);
+        assert_eq!(deltas.len(), CHUNK - 1);
+        assert_eq!(rest.first(), moves.get(CHUNK - 1));
+        deltas.append(&mut rest.clone());
+        assert_eq!(deltas, Deltas::from(moves.clone()));
+
+        deltas.truncate(CHUNK);
+        assert_eq!((deltas.chunks.len(), deltas.tail.len()), (1, 0));
+        assert_eq!(deltas.last(), moves.get(CHUNK - 1));
+
+        // lengths past the end keep every delta, even a chunk or more past it.
+        let mut short = Deltas::from(moves[..10].to_vec());
+        for len in [10, 11, CHUNK + 6, 2 * CHUNK] {
+            short.truncate(len);
+            assert_eq!(short.len(), 10);
+            assert!(short.split_off(len).is_empty());
+        }
+        assert!(short.iter().eq(moves[..10].iter()));
+        assert!(ChessGame::default().deltas.is_empty());
+    }
+}
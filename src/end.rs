@@ -1,11 +1,204 @@
+use crate::{board::BitBoard, state::BoardState, team::Team};
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+/// The squares of the same color as a1.
+const DARK_SQUARES: BitBoard = BitBoard(0xAA55_AA55_AA55_AA55);
+
+/// The halfmoves without a capture or pawn move after which a draw can be claimed.
+pub const FIFTY_MOVES: u8 = 100;
+
+/// The halfmoves without a capture or pawn move after which the game is drawn.
+pub const SEVENTY_FIVE_MOVES: u8 = 150;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum EndCondition {
     Checkmate,
+
+    /// A draw claimed after 50 moves by each team without a capture
+    /// or pawn move, see `ChessGame::claim_fifty_moves`.
     FiftyMoveRule,
     Stalemate,
     Repetition,
     Agreement,
     WhiteResign,
     BlackResign,
-}
\ No newline at end of file
+    InsufficientMaterial,
+
+    /// The game was called off and has no result.
+    Aborted,
+
+    /// The side to move ran out of time, and the other team wins.
+    Timeout,
+
+    /// The side to move ran out of time, but the other team cannot
+    /// checkmate, so the game is drawn.
+    TimeoutVsInsufficientMaterial,
+
+    /// The side to move left the game, and the other team wins.
+    Abandoned,
+
+    /// 75 moves by each team without a capture or pawn move, which
+    /// draws the game without a claim.
+    SeventyFiveMoveRule,
+}
+
+impl EndCondition {
+    /// The result of ending the game this way with `to_move` to move in
+    /// the final position, None for an aborted game.
+    pub fn result(&self, to_move: Team) -> Option<GameResult> {
+        Some(match self {
+            Self::Checkmate |
+            Self::Timeout |
+            Self::Abandoned => GameResult::win(!to_move),
+            Self::WhiteResign => GameResult::BlackWins,
+            Self::BlackResign => GameResult::WhiteWins,
+            Self::FiftyMoveRule |
+            Self::SeventyFiveMoveRule |
+            Self::Stalemate |
+            Self::Repetition |
+            Self::Agreement |
+            Self::InsufficientMaterial |
+            Self::TimeoutVsInsufficientMaterial => GameResult::Draw,
+            Self::Aborted => return None,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl GameResult {
+    pub const fn win(team: Team) -> Self {
+        match team {
+            Team::White => Self::WhiteWins,
+            Team::Black => Self::BlackWins,
+        }
+    }
+
+    /// The team that won, None for a draw.
+    pub const fn winner(&self) -> Option<Team> {
+        match self {
+            Self::WhiteWins => Some(Team::White),
+            Self::BlackWins => Some(Team::Black),
+            Self::Draw => None,
+        }
+    }
+
+    /// The result as written in the "Result" tag of a PGN.
+    pub const fn to_pgn_result(&self) -> &'static str {
+        match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+        }
+    }
+}
+
+/// The end condition reached in the position, if any. `earlier` is the
+/// positions before it in the game, most recent first; only those since
+/// the last capture or pawn move are looked at for repetitions.
+pub fn detect(state: &BoardState, earlier: impl IntoIterator<Item = BoardState>) -> Option<EndCondition> {
+    if !state.has_legal_moves() {
+        return Some(if state.king_attacked() {
+            EndCondition::Checkmate
+        } else {
+            EndCondition::Stalemate
+        })
+    }
+
+    if state.halfmoves >= SEVENTY_FIVE_MOVES {
+        return Some(EndCondition::SeventyFiveMoveRule)
+    }
+
+    if insufficient_material(state) {
+        return Some(EndCondition::InsufficientMaterial)
+    }
+
+    let repeats = earlier
+        .into_iter()
+        .take(state.halfmoves as usize)
+        .filter(|pos| pos.is_repetition_of(state))
+        .count();
+
+    (repeats >= 2).then_some(EndCondition::Repetition)
+}
+
+/// Whether neither team can ever checkmate: besides the kings there is
+/// no major or fairy piece and at most one minor piece, or only bishops that all stand on squares
+/// of one color. A wormhole can carry a bishop to the other
+/// color, so the bishops only count as one color when there are none.
+pub fn insufficient_material(state: &BoardState) -> bool {
+    let pieces = &state.pieces;
+    if !(pieces.queens | pieces.rooks | pieces.pawns | pieces.archbishops | pieces.chancellors).is_empty() {
+        return false
+    }
+
+    let minors = pieces.bishops | pieces.knights;
+    if minors.count() <= 1 {
+        return true
+    }
+
+    let no_wormholes = state.wormholes.is_empty() && state.next_hole.is_none();
+    let one_color = (pieces.bishops & DARK_SQUARES).is_empty() || (pieces.bishops & !DARK_SQUARES).is_empty();
+    no_wormholes && pieces.knights.is_empty() && one_color
+}
+
+/// Whether the team has the material to checkmate with the other team's
+/// help. A lone king cannot, and against a lone king, neither can the
+/// minor pieces that leave the position without enough material.
+pub fn can_mate(state: &BoardState, team: Team) -> bool {
+    let pieces = &state.pieces;
+    let own = pieces.on_team(team) & !pieces.kings;
+    if own.is_empty() {
+        return false
+    }
+
+    // the other team's pieces can block its own king in.
+    let others = pieces.on_team(!team) & !pieces.kings;
+    !others.is_empty() || !insufficient_material(state)
+}
+
+/// The end condition for the side to move running out of time.
+pub fn timeout(state: &BoardState) -> EndCondition {
+    if can_mate(state, !state.turn) {
+        EndCondition::Timeout
+    } else {
+        EndCondition::TimeoutVsInsufficientMaterial
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EndCondition, GameResult};
+    use crate::{game::ChessGame, state::BoardState, team::Team};
+
+    #[test]
+    fn results() {
+        for team in [Team::White, Team::Black] {
+            assert_eq!(GameResult::win(team).winner(), Some(team));
+            for condition in [EndCondition::Checkmate, EndCondition::Timeout, EndCondition::Abandoned] {
+                assert_eq!(condition.result(team), Some(GameResult::win(!team)));
+            }
+
+            assert_eq!(EndCondition::WhiteResign.result(team), Some(GameResult::BlackWins));
+            assert_eq!(EndCondition::BlackResign.result(team), Some(GameResult::WhiteWins));
+            assert_eq!(EndCondition::Stalemate.result(team), Some(GameResult::Draw));
+            assert_eq!(EndCondition::TimeoutVsInsufficientMaterial.result(team), Some(GameResult::Draw));
+            assert_eq!(EndCondition::Aborted.result(team), None);
+        }
+
+        assert_eq!(GameResult::Draw.winner(), None);
+        let pgn: Vec<_> = [GameResult::WhiteWins, GameResult::BlackWins, GameResult::Draw].iter().map(GameResult::to_pgn_result).collect();
+        assert_eq!(pgn, ["1-0", "0-1", "1/2-1/2"]);
+
+        // the loser of a checkmate is found from the team that started.
+        let black_first = BoardState::from_fen("6k1/5ppp/8/8/8/8/rr6/6K1 b - - 0 1").unwrap();
+        let game = ChessGame::from_uci_moves(black_first, "a2a1").unwrap();
+        assert_eq!((game.end, game.result()), (Some(EndCondition::Checkmate), Some(GameResult::BlackWins)));
+        let game = ChessGame::from_uci_moves(black_first, "a2a3 g1f1").unwrap();
+        assert_eq!((game.end, game.result()), (None, None));
+    }
+}
@@ -0,0 +1,298 @@
+//! Endgame knowledge that the static evaluation cannot see.
+//!
+//! A passed pawn racing a lone king is decided by the rule of the square:
+//! the pawn promotes unless the king can reach the promotion square in
+//! time. Kings can take wormholes, so the king's distance is the shortest
+//! king path through the holes rather than the distance on the board.
+//!
+//! With only kings and pawns left, a king on a key square of its pawn
+//! can escort it to promotion, and the opposition decides which king
+//! gives way. Key squares do not account for wormholes.
+
+use crate::{board::BitBoard, eval::Score, pieces::Piece, square::Square, state::BoardState, team::Team};
+
+/// The endgame bonus for the team with the first unstoppable pawn.
+pub const UNSTOPPABLE: Score = Score(0, 600);
+
+/// The endgame bonus for a king on a key square of its pawn.
+pub const KEY_SQUARE: Score = Score(0, 80);
+
+/// The endgame bonus for the team with the opposition.
+pub const OPPOSITION: Score = Score(0, 20);
+
+/// The endgame bonus for each point of king tropism.
+pub const TROPISM: Score = Score(0, 2);
+
+/// A passed pawn against the enemy king, where the enemy has nothing
+/// besides the king and pawns to stop it with.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PawnRace {
+    pub team: Team,
+    pub pawn: Square,
+
+    /// The square the pawn promotes on.
+    pub promotion: Square,
+
+    /// The moves the pawn needs to promote, with a double push as one.
+    pub pawn_moves: u8,
+
+    /// The fewest moves the enemy king needs to reach the promotion square,
+    /// None if it cannot get there.
+    pub king_moves: Option<u8>,
+
+    /// Whether the king is too slow, and nothing stands in the pawn's way.
+    pub unstoppable: bool,
+}
+
+impl PawnRace {
+    /// The halfmoves until the pawn promotes, from the position it was found in.
+    pub fn halfmoves(&self, turn: Team) -> u8 {
+        self.pawn_moves * 2 - (turn == self.team) as u8
+    }
+}
+
+/// Every passed pawn whose enemy only has a king and pawns, by team and square.
+/// Pawns on wormholes are left out, since they can promote from any hole.
+pub fn pawn_races(state: &BoardState) -> Vec<PawnRace> {
+    let pieces = &state.pieces;
+    let mut races = Vec::new();
+    for team in [Team::White, Team::Black] {
+        let enemy = pieces.on_team(!team);
+        let Some(king) = (pieces.kings & enemy).first() else {
+            continue
+        };
+
+        if !(enemy & !(pieces.kings | pieces.pawns)).is_empty() {
+            continue
+        }
+
+        let promotion_rank = (!team).back_rank_u8();
+        for pawn in pieces.get(Piece::Pawn, team) & !state.wormholes {
+            let ahead = front_span(pawn, team);
+            if ahead.intersects(pieces.pawns & enemy) {
+                continue
+            }
+
+            let Some(promotion) = Square::try_new(promotion_rank, pawn.file_u8()) else {
+                continue
+            };
+
+            let distance = promotion.rank_u8().abs_diff(pawn.rank_u8());
+            let pawn_moves = distance - (pawn.rank_u8() == team.pawn_rank_u8()) as u8;
+            let king_moves = crate::path::distance(king, promotion, state.wormholes, |sq| sq.king_moves());
+
+            // the defender gets one more move if it moves first.
+            let tempo = (state.turn != team) as u8;
+            let blocked = ahead.intersects(pieces.occupied() & BitBoard::new().with_file_u8(pawn.file_u8()));
+            let unstoppable = !blocked && king_moves.is_none_or(|moves| moves > pawn_moves + tempo);
+
+            races.push(PawnRace { team, pawn, promotion, pawn_moves, king_moves, unstoppable });
+        }
+    }
+
+    races
+}
+
+/// The bonus for the team whose unstoppable pawn promotes first, for
+/// white minus black.
+pub fn race_score(state: &BoardState) -> Score {
+    let races = pawn_races(state);
+    let first = |team: Team| races
+        .iter()
+        .filter(|race| race.team == team && race.unstoppable)
+        .map(|race| race.halfmoves(state.turn))
+        .min();
+
+    match (first(Team::White), first(Team::Black)) {
+        (Some(white), Some(black)) if white < black => UNSTOPPABLE,
+        (Some(_), Some(_)) => -UNSTOPPABLE,
+        (Some(_), None) => UNSTOPPABLE,
+        (None, Some(_)) => -UNSTOPPABLE,
+        (None, None) => Score::ZERO,
+    }
+}
+
+/// Whether the kings stand on the same rank, file, or diagonal with an
+/// odd number of squares between them, so the team that is not to move
+/// has the opposition.
+pub fn has_opposition(state: &BoardState) -> bool {
+    let kings = state.pieces.kings;
+    let (Some(white), Some(black)) = ((kings & state.pieces.white).first(), (kings & state.pieces.black).first()) else {
+        return false
+    };
+
+    let ranks = white.rank_u8().abs_diff(black.rank_u8());
+    let files = white.file_u8().abs_diff(black.file_u8());
+    let aligned = ranks == 0 || files == 0 || ranks == files;
+    aligned && ranks.max(files).is_multiple_of(2)
+}
+
+/// The squares that the team's king can stand on to promote the pawn
+/// against a lone king, whoever is to move.
+pub fn key_squares(pawn: Square, team: Team) -> BitBoard {
+    let dir = team.pawn_dir();
+    let relative = match team {
+        Team::White => pawn.rank_u8(),
+        Team::Black => 7 - pawn.rank_u8(),
+    };
+
+    let mut squares = BitBoard::new();
+    let mut add = |rank: i8, file: i8| {
+        if let Some(sq) = pawn.next((rank * dir, file)) {
+            squares.set(sq);
+        }
+    };
+
+    // a rook pawn only promotes if the king gets in front of it on the next file.
+    if pawn.file_u8() == 0 || pawn.file_u8() == 7 {
+        let file = if pawn.file_u8() == 0 { 1 } else { -1 };
+        let to_promotion = 7 - relative as i8;
+        add(to_promotion, file);
+        add(to_promotion - 1, file);
+        return squares
+    }
+
+    let ahead = match relative {
+        0..=3 => 2..=2,
+        4 | 5 => 1..=2,
+        _ => 0..=1,
+    };
+
+    for rank in ahead {
+        for file in -1..=1 {
+            add(rank, file);
+        }
+    }
+
+    squares.without(pawn)
+}
+
+/// How close the team's pieces besides the king are to the enemy king,
+/// as the sum of 7 minus each piece's king distance, through wormholes.
+pub fn king_tropism(state: &BoardState, team: Team) -> u32 {
+    let pieces = &state.pieces;
+    let Some(king) = (pieces.kings & pieces.on_team(!team)).first() else {
+        return 0
+    };
+
+    let distances = crate::path::distances(king, state.wormholes, |sq| sq.king_moves());
+    (pieces.on_team(team) & !pieces.kings)
+        .into_iter()
+        .filter_map(|sq| distances[sq.to_index()])
+        .map(|distance| 7u32.saturating_sub(distance as u32))
+        .sum()
+}
+
+/// The king terms, for white minus black: tropism, and with only kings
+/// and pawns left, key squares and the opposition.
+pub fn king_score(state: &BoardState) -> Score {
+    let pieces = &state.pieces;
+    let pawn_ending = (pieces.occupied() & !(pieces.kings | pieces.pawns)).is_empty();
+
+    let mut score = Score::ZERO;
+    for team in [Team::White, Team::Black] {
+        let mut term = TROPISM * king_tropism(state, team).min(i16::MAX as u32) as i16;
+        let king = pieces.kings & pieces.on_team(team);
+        let escorts = pieces.get(Piece::Pawn, team).into_iter().any(|pawn| key_squares(pawn, team).intersects(king));
+        if pawn_ending && escorts {
+            term += KEY_SQUARE;
+        }
+
+        score += match team {
+            Team::White => term,
+            Team::Black => -term,
+        };
+    }
+
+    if pawn_ending && has_opposition(state) {
+        score += match state.turn {
+            Team::White => -OPPOSITION,
+            Team::Black => OPPOSITION,
+        };
+    }
+
+    score
+}
+
+/// Every endgame term, for white minus black.
+pub fn score(state: &BoardState) -> Score {
+    race_score(state) + king_score(state)
+}
+
+/// The squares in front of the pawn on its file and the files beside it.
+fn front_span(pawn: Square, team: Team) -> BitBoard {
+    let mut span = BitBoard::new();
+    let mut rank = pawn.rank_u8() as i8 + team.pawn_dir();
+    while (0..8).contains(&rank) {
+        span.set_rank_u8(rank as u8);
+        rank += team.pawn_dir();
+    }
+
+    span & adjacent_files(pawn)
+}
+
+/// The pawn's file and the files beside it.
+fn adjacent_files(pawn: Square) -> BitBoard {
+    let file = pawn.file_u8();
+    let mut files = BitBoard::new();
+    for f in file.saturating_sub(1)..=(file + 1).min(7) {
+        files.set_file_u8(f);
+    }
+
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{has_opposition, key_squares, king_tropism, pawn_races, race_score, UNSTOPPABLE};
+    use crate::{board::BitBoard, eval::Score, square::Square, state::BoardState, team::Team};
+
+    #[test]
+    fn races() {
+        // the king is outside the square of the pawn with white to move.
+        let state = BoardState::from_fen("8/8/7k/8/8/8/1P6/K7 w - - 0 1").unwrap();
+        let races = pawn_races(&state);
+        assert_eq!(races.len(), 1);
+        assert_eq!((races[0].pawn_moves, races[0].king_moves), (5, Some(6)));
+        assert!(races[0].unstoppable);
+        assert_eq!(race_score(&state), UNSTOPPABLE);
+
+        // with black to move, the king gets one step closer in time.
+        let state = BoardState::from_fen("8/8/6k1/8/8/8/1P6/K7 b - - 0 1").unwrap();
+        assert!(!pawn_races(&state)[0].unstoppable);
+        assert_eq!(race_score(&state), Score::ZERO);
+
+        // a wormhole next to the promotion square brings the king back in time.
+        let holes = BoardState::from_extended_fen("8/8/7k/8/8/8/1P6/K7 w - - 0 1 h5,c7 - 0").unwrap();
+        assert_eq!(pawn_races(&holes)[0].king_moves, Some(2));
+        assert!(!pawn_races(&holes)[0].unstoppable);
+
+        // the first pawn to promote wins the race, and a knight stops races.
+        let both = BoardState::from_fen("8/6p1/8/8/1P5k/K7/8/8 b - - 0 1").unwrap();
+        assert!(pawn_races(&both).iter().all(|race| race.unstoppable));
+        assert_eq!(race_score(&both), UNSTOPPABLE);
+        assert!(pawn_races(&BoardState::from_fen("8/8/7k/5n2/8/8/1P6/K7 w - - 0 1").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn kings() {
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+        let squares = |list: &[&str]| list.iter().fold(BitBoard::new(), |board, s| board.with(sq(s)));
+
+        assert!(has_opposition(&BoardState::from_fen("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1").unwrap()));
+        assert!(has_opposition(&BoardState::from_fen("7k/8/8/8/8/8/1K6/8 w - - 0 1").unwrap()));
+        assert!(!has_opposition(&BoardState::from_fen("4k3/8/8/4K3/8/8/8/8 w - - 0 1").unwrap()));
+
+        assert_eq!(key_squares(sq("e2"), Team::White), squares(&["d4", "e4", "f4"]));
+        assert_eq!(key_squares(sq("e5"), Team::White), squares(&["d6", "e6", "f6", "d7", "e7", "f7"]));
+        assert_eq!(key_squares(sq("d7"), Team::Black), squares(&["c5", "d5", "e5"]));
+        assert_eq!(key_squares(sq("e7"), Team::White), squares(&["d7", "f7", "d8", "e8", "f8"]));
+        assert_eq!(key_squares(sq("h3"), Team::White), squares(&["g7", "g8"]));
+        assert_eq!(key_squares(sq("a6"), Team::Black), squares(&["b1", "b2"]));
+
+        // the knight is three king moves from h8, the rook is on the next square.
+        let state = BoardState::from_fen("7k/6R1/8/5N2/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(king_tropism(&state, Team::White), 4 + 6);
+        assert_eq!(king_tropism(&state, Team::Black), 0);
+    }
+}
@@ -0,0 +1,335 @@
+//! Static evaluation with tapered middlegame and endgame scores.
+//!
+//! Every term is a `Score` pair. The pairs are summed, and the total is
+//! blended by the phase of the game, so a term can matter more in one
+//! phase than the other without a jump when the phase changes.
+//!
+//! Material and piece-square terms only depend on which piece is on
+//! which square, so an `Accumulator` can keep their sum up to date as
+//! moves are made and unmade, only looking at the squares that changed.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::{board::BitBoard, delta::BoardDelta, phase::GamePhase, pieces::Piece, square::Square, state::BoardState, team::Team};
+
+/// A middlegame and endgame score, in centipawns.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+pub struct Score(pub i16, pub i16);
+
+impl Score {
+    pub const ZERO: Self = Self(0, 0);
+
+    pub const fn mg(&self) -> i16 {
+        self.0
+    }
+
+    pub const fn eg(&self) -> i16 {
+        self.1
+    }
+
+    /// Interpolate between the middlegame and endgame score by the phase.
+    pub fn taper(&self, phase: GamePhase) -> i32 {
+        phase.blend(self.0 as i32, self.1 as i32)
+    }
+}
+
+impl Add for Score {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0), self.1.saturating_add(rhs.1))
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Score {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0), self.1.saturating_sub(rhs.1))
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Score {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg(), self.1.saturating_neg())
+    }
+}
+
+impl Mul<i16> for Score {
+    type Output = Self;
+
+    fn mul(self, rhs: i16) -> Self {
+        Self(self.0.saturating_mul(rhs), self.1.saturating_mul(rhs))
+    }
+}
+
+/// The material value of each piece. Minor pieces are worth a little
+/// less in the endgame, and rooks and pawns a little more. The fairy
+/// pieces follow their parts: the archbishop loses value in the endgame
+/// like a bishop and knight, and the chancellor keeps it like a rook.
+pub const fn material(piece: Piece) -> Score {
+    match piece {
+        Piece::Pawn => Score(82, 94),
+        Piece::Knight => Score(337, 281),
+        Piece::Bishop => Score(365, 297),
+        Piece::Rook => Score(477, 512),
+        Piece::Queen => Score(1025, 936),
+        Piece::Archbishop => Score(945, 860),
+        Piece::Chancellor => Score(980, 945),
+        Piece::King => Score::ZERO,
+    }
+}
+
+/// The bonus for a piece of the team standing on the square.
+/// Minor pieces, queens and fairy pieces prefer the center, pawns and
+/// rooks prefer to advance, and the king hides in the middlegame but
+/// centralizes in the endgame.
+pub fn piece_square(piece: Piece, team: Team, sq: Square) -> Score {
+    let rank = match team {
+        Team::White => sq.rank_u8(),
+        Team::Black => 7 - sq.rank_u8(),
+    } as i16;
+
+    let file = sq.file_u8() as i16;
+    let edge = |n: i16| if n < 4 { 3 - n } else { n - 4 };
+    // 0 on the four center squares, up to 6 in the corners.
+    let center = edge(rank) + edge(file);
+
+    match piece {
+        Piece::Pawn => Score(rank * 6 - edge(file) * 2, rank * 12),
+        Piece::Knight => Score(-center * 8, -center * 6),
+        Piece::Bishop => Score(-center * 4, -center * 4),
+        Piece::Rook => Score(if rank == 6 { 20 } else { 0 }, 0),
+        Piece::Queen => Score(-center * 2, -center * 4),
+        Piece::Archbishop => Score(-center * 6, -center * 5),
+        Piece::Chancellor => Score(-center * 3, -center * 3),
+        Piece::King => Score(-(if rank < 4 { rank } else { 4 }) * 15, -center * 8),
+    }
+}
+
+/// The material and piece-square score of a piece, positive for white.
+fn piece_score(piece: Piece, team: Team, sq: Square) -> Score {
+    let score = material(piece) + piece_square(piece, team, sq);
+    match team {
+        Team::White => score,
+        Team::Black => -score,
+    }
+}
+
+/// The score of the pieces on the given squares, not counting wormholes.
+fn squares_score(state: &BoardState, squares: BitBoard) -> Score {
+    let mut score = Score::ZERO;
+    for (team, piece, board) in state.pieces.as_array() {
+        for sq in board & squares & !state.wormholes {
+            score += piece_score(piece, team, sq);
+        }
+    }
+
+    score
+}
+
+/// The score of the piece standing on the wormholes, if any. It occupies
+/// every hole, but is counted once, on the first.
+fn hole_score(state: &BoardState) -> Score {
+    let mut score = Score::ZERO;
+    for (team, piece, board) in state.pieces.as_array() {
+        if let Some(sq) = (board & state.wormholes).first() {
+            score += piece_score(piece, team, sq);
+        }
+    }
+
+    score
+}
+
+/// The sum of every term, for white minus black.
+pub fn score(state: &BoardState) -> Score {
+    squares_score(state, BitBoard(!0)) + hole_score(state)
+}
+
+/// The evaluation in centipawns, from the view of the team to move.
+pub fn evaluate(state: &BoardState) -> i32 {
+    from_score(state, score(state))
+}
+
+/// Add the terms that need the whole position, then taper.
+fn from_score(state: &BoardState, score: Score) -> i32 {
+    let eval = (score + crate::endgame::score(state)).taper(state.phase());
+    match state.turn {
+        Team::White => eval,
+        Team::Black => -eval,
+    }
+}
+
+/// Relative ranks 3 to 6, which are the same for both teams.
+const MIDDLE_RANKS: BitBoard = BitBoard(0x0000_FFFF_FFFF_0000);
+
+/// The squares the team's pawns attack now. A pawn on a wormhole attacks
+/// from every hole, and attacking a hole attacks all of them.
+fn pawn_attacks(state: &BoardState, team: Team) -> BitBoard {
+    let pawns = state.pieces.get(Piece::Pawn, team);
+    let mut attacks = (pawns & !state.wormholes).pawn_captures(team);
+    if pawns.intersects(state.wormholes) {
+        attacks |= state.hole_exits(state.wormholes).pawn(team);
+    }
+
+    attacks.transmit(state.wormholes)
+}
+
+/// The squares the team's pawns attack now or could attack after
+/// advancing, assuming nothing blocks them.
+fn pawn_attack_span(state: &BoardState, team: Team) -> BitBoard {
+    let mut span = pawn_attacks(state, team);
+    for _ in 0..6 {
+        span |= match team {
+            Team::White => BitBoard(span.0 << 8),
+            Team::Black => BitBoard(span.0 >> 8),
+        };
+    }
+
+    span.transmit(state.wormholes)
+}
+
+/// The squares on relative ranks 3 to 6 that the team's pawns can never
+/// attack again, where an enemy piece cannot be chased away by a pawn.
+pub fn weak_squares(state: &BoardState, team: Team) -> BitBoard {
+    MIDDLE_RANKS & !pawn_attack_span(state, team)
+}
+
+/// The squares on relative ranks 4 to 6 that the team's pawns support
+/// and the enemy's pawns can never attack, where a piece of the team
+/// can settle.
+pub fn outposts(state: &BoardState, team: Team) -> BitBoard {
+    let ranks = match team {
+        Team::White => BitBoard(0x0000_FFFF_FF00_0000),
+        Team::Black => BitBoard(0x0000_00FF_FFFF_0000),
+    };
+
+    ranks & weak_squares(state, !team) & pawn_attacks(state, team)
+}
+
+/// The material and piece-square sum of a position, updated as moves
+/// are made and unmade instead of being recomputed from every piece.
+/// Debug builds check every update against a full recomputation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct Accumulator {
+    pub score: Score,
+}
+
+impl Accumulator {
+    pub fn new(state: &BoardState) -> Self {
+        Self { score: score(state) }
+    }
+
+    /// Play the delta in the position and return the next position.
+    pub fn make(&mut self, state: &BoardState, delta: BoardDelta) -> BoardState {
+        let next = state.next(delta);
+        self.update(state, &next);
+        next
+    }
+
+    /// Undo the delta that led to the position and return the previous position.
+    pub fn unmake(&mut self, state: &BoardState, delta: BoardDelta) -> BoardState {
+        let prev = state.prev(delta);
+        self.update(state, &prev);
+        prev
+    }
+
+    /// The evaluation of the position the accumulator is tracking,
+    /// in centipawns from the view of the team to move.
+    pub fn evaluate(&self, state: &BoardState) -> i32 {
+        from_score(state, self.score)
+    }
+
+    fn update(&mut self, from: &BoardState, to: &BoardState) {
+        let mut changed = from.wormholes ^ to.wormholes;
+        for ((_, _, a), (_, _, b)) in from.pieces.as_array().into_iter().zip(to.pieces.as_array()) {
+            changed |= a ^ b;
+        }
+
+        self.score = self.score
+            - squares_score(from, changed) - hole_score(from)
+            + squares_score(to, changed) + hole_score(to);
+
+        debug_assert_eq!(self.score, score(to), "incremental score diverged from {}", to.to_extended_fen());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{material, outposts, weak_squares, Accumulator, Score};
+    use crate::{phase::{GamePhase, Stage}, pieces::Piece, settings::{GameSettings, WormholeSettings}, state::BoardState, team::Team};
+
+    #[test]
+    fn incremental_matches_full() {
+        let game = crate::sim::random_game(GameSettings {
+            wormhole: WormholeSettings { start_count: 4, max_count: 4, ..WormholeSettings::default() },
+            ..GameSettings::default()
+        }, 5);
+
+        let mut acc = Accumulator::new(&game.start);
+        let mut state = game.start;
+        for delta in &game.deltas {
+            state = acc.make(&state, *delta);
+            assert_eq!(acc.evaluate(&state), state.evaluate());
+        }
+
+        for delta in game.deltas.iter().rev() {
+            state = acc.unmake(&state, *delta);
+        }
+
+        assert_eq!(acc, Accumulator::new(&game.start));
+    }
+
+    #[test]
+    fn tapered() {
+        let score = Score(100, 300) + Score(20, -20) * 2 - Score(40, 20);
+        assert_eq!(score, Score(100, 240));
+        assert_eq!(score.taper(GamePhase { stage: Stage::Middlegame, taper: 128 }), 170);
+
+        assert_eq!(BoardState::default().evaluate(), 0);
+
+        // black to move, a rook down in the endgame.
+        let state = BoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1").unwrap();
+        assert!(state.evaluate() < -450);
+
+        // the fairy pieces are worth more than a rook and less than a queen with a pawn.
+        for piece in [Piece::Archbishop, Piece::Chancellor] {
+            for phase in [0, 256] {
+                let taper = |score: Score| score.taper(GamePhase { stage: Stage::Middlegame, taper: phase });
+                assert!(taper(material(piece)) > taper(material(Piece::Rook)));
+                assert!(taper(material(piece)) < taper(material(Piece::Queen) + material(Piece::Pawn)));
+            }
+        }
+    }
+
+    #[test]
+    fn outpost_squares() {
+        let state = BoardState::from_fen("4k3/p4p2/8/8/2P1P3/8/8/4K3 w - - 0 1").unwrap();
+        let sq = |s: &str| s.parse().unwrap();
+
+        // black's pawns can never reach the c, d, f and h files.
+        let weak = weak_squares(&state, Team::Black);
+        assert_eq!(weak.count(), 20);
+        assert!(weak.has(sq("d5")) && !weak.has(sq("e6")));
+        assert_eq!(outposts(&state, Team::White), sq("d5") | sq("f5"));
+        assert!(outposts(&state, Team::Black).is_empty());
+
+        // the a-pawn attacks d5 through the wormhole it could reach on b6.
+        let holes = BoardState::from_extended_fen("4k3/p4p2/8/8/2P1P3/8/8/4K3 w - - 0 1 b6,d5 - 0").unwrap();
+        assert_eq!(outposts(&holes, Team::White), sq("f5").into());
+    }
+}
@@ -0,0 +1,166 @@
+//! Callbacks that a game runs as it is played, so a GUI or sound system
+//! can react to moves without polling the deltas.
+//!
+//! Move callbacks run after a new move is added to the game, once the
+//! cursor is on the position after it, in the order: move, capture,
+//! check, wormhole spawn, end. Replaying a move that is already in the
+//! game and creating a branch run no callbacks. End callbacks also run
+//! when the game ends without a move, by resignation, abort, or draw
+//! agreement. Callbacks are shared between clones of a game and the
+//! branches created from it, and are not saved.
+
+use std::sync::Arc;
+
+use crate::{delta::BoardDelta, end::EndCondition, pieces::Piece, square::Square, state::BoardState, trace::MoveTrace};
+
+/// A move that was just added to a game.
+pub struct MoveEvent<'a> {
+    pub game_id: u64,
+
+    /// The halfmove index of the position after the move.
+    pub index: usize,
+
+    pub delta: BoardDelta,
+    pub trace: &'a MoveTrace,
+
+    /// The position after the move.
+    pub state: &'a BoardState,
+}
+
+type MoveHook = Arc<dyn Fn(&MoveEvent) + Send + Sync>;
+type PieceHook = Arc<dyn Fn(&MoveEvent, Piece) + Send + Sync>;
+type SquareHook = Arc<dyn Fn(&MoveEvent, Square) + Send + Sync>;
+
+/// The callbacks registered on a game.
+#[derive(Clone, Default)]
+pub struct Events {
+    moves: Vec<MoveHook>,
+    captures: Vec<PieceHook>,
+    checks: Vec<MoveHook>,
+    spawns: Vec<SquareHook>,
+    ends: Vec<Arc<dyn Fn(EndCondition) + Send + Sync>>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run the callback after every new move.
+    pub fn on_move(&mut self, hook: impl Fn(&MoveEvent) + Send + Sync + 'static) {
+        self.moves.push(Arc::new(hook));
+    }
+
+    /// Run the callback after a move that captures, with the captured piece.
+    pub fn on_capture(&mut self, hook: impl Fn(&MoveEvent, Piece) + Send + Sync + 'static) {
+        self.captures.push(Arc::new(hook));
+    }
+
+    /// Run the callback after a move that leaves the other king in check.
+    pub fn on_check(&mut self, hook: impl Fn(&MoveEvent) + Send + Sync + 'static) {
+        self.checks.push(Arc::new(hook));
+    }
+
+    /// Run the callback after a move that spawns a wormhole, with its square.
+    pub fn on_wormhole_spawn(&mut self, hook: impl Fn(&MoveEvent, Square) + Send + Sync + 'static) {
+        self.spawns.push(Arc::new(hook));
+    }
+
+    /// Run the callback when the game ends.
+    pub fn on_end(&mut self, hook: impl Fn(EndCondition) + Send + Sync + 'static) {
+        self.ends.push(Arc::new(hook));
+    }
+
+    /// Remove every callback.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The heap memory held by the lists of callbacks, not by the callbacks.
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let hook = std::mem::size_of::<MoveHook>();
+        (self.moves.capacity() + self.captures.capacity() + self.checks.capacity() + self.spawns.capacity() + self.ends.capacity()) * hook
+    }
+
+    pub(crate) fn moved(&self, event: &MoveEvent, end: Option<EndCondition>) {
+        for hook in &self.moves {
+            hook(event);
+        }
+
+        if let Some(piece) = event.delta.get_capture_pc() {
+            for hook in &self.captures {
+                hook(event, piece);
+            }
+        }
+
+        if !self.checks.is_empty() && event.state.king_attacked() {
+            for hook in &self.checks {
+                hook(event);
+            }
+        }
+
+        if event.delta.is_popped_wormhole() {
+            for hook in &self.spawns {
+                hook(event, event.delta.get_wormhole_sq());
+            }
+        }
+
+        if let Some(end) = end {
+            self.ended(end);
+        }
+    }
+
+    pub(crate) fn ended(&self, end: EndCondition) {
+        for hook in &self.ends {
+            hook(end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{end::EndCondition, game::ChessGame, pieces::Piece, settings::GameSettings, team::Team};
+
+    #[test]
+    fn hooks() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut game = ChessGame::init(GameSettings::default());
+
+        let moves = log.clone();
+        game.events.on_move(move |event| moves.lock().unwrap().push(format!("move {}", event.index)));
+        let captures = log.clone();
+        game.events.on_capture(move |_, piece| captures.lock().unwrap().push(format!("capture {piece:?}")));
+        let checks = log.clone();
+        game.events.on_check(move |event| checks.lock().unwrap().push(format!("check {:?}", event.state.turn)));
+        let ends = log.clone();
+        game.events.on_end(move |end| ends.lock().unwrap().push(format!("end {end:?}")));
+
+        for uci in ["e2e4", "f7f5", "e4f5", "g7g5", "d1h5"] {
+            game.play_uci(uci).ok().unwrap();
+        }
+
+        let expected = [
+            "move 1", "move 2", "move 3",
+            &format!("capture {:?}", Piece::Pawn),
+            "move 4", "move 5",
+            &format!("check {:?}", Team::Black),
+            &format!("end {:?}", EndCondition::Checkmate),
+        ];
+        assert_eq!(*log.lock().unwrap(), expected);
+
+        // replaying moves and branching run nothing, resigning ends the clone.
+        log.lock().unwrap().clear();
+        let mut clone = game.clone();
+        clone.end = None;
+        clone.goto(2);
+        assert!(clone.play_uci("e4f5").ok().unwrap().branch.is_none());
+        assert!(clone.play_uci("g7g6").ok().unwrap().branch.is_some());
+        assert!(log.lock().unwrap().is_empty());
+
+        clone.goto(5);
+        clone.resign(Team::Black).ok().unwrap();
+        assert_eq!(*log.lock().unwrap(), [format!("end {:?}", EndCondition::BlackResign)]);
+    }
+}
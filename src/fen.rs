@@ -0,0 +1,436 @@
+//! Forsyth-Edwards Notation.
+//!
+//! Standard FEN has no way to describe wormholes, so the extended
+//! dialect appends three fields after the fullmove number:
+//!
+//! - the wormhole squares separated by commas, or "-" if there are none.
+//! - the square of the next hole, or "-" if no hole is pending.
+//! - "1" if the next hole opens in one move, "0" otherwise.
+//!
+//! e.g. "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 d4,e5 c3 1".
+//!
+//! Castling is written as X-FEN, which is identical to standard FEN unless a
+//! Chess960 position has a rook between the castling rook and the corner, in
+//! which case the rook's file letter is used instead of K or Q. Shredder-FEN,
+//! which always uses file letters, can be written with `to_shredder_fen`.
+//! Parsing accepts all three.
+
+use std::fmt::Write;
+
+use crate::{board::BitBoard, castle::{Castle, CastleRights}, holes::HoleExits, pieces::{Piece, Pieces}, square::Square, state::BoardState, team::Team};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum FenError {
+    /// The string does not have the six space-separated fields,
+    /// or nine for the extended dialect.
+    FieldCount(usize),
+
+    /// The piece placement field is invalid at this rank,
+    /// where 0 is the first rank in the string (the eighth rank).
+    Placement(usize),
+
+    /// The side to move is not "w" or "b".
+    Turn,
+
+    /// The castling field is not "-" or a combination of "KQkq" and
+    /// rook files, or names a rook that is not on the back rank.
+    Castling,
+
+    /// The en-passant field is not "-" or a square.
+    EnPassant,
+
+    /// The halfmove clock is not a number from 0 to 255.
+    Halfmoves,
+
+    /// The fullmove number is not a number from 1 to 65535.
+    Fullmoves,
+
+    /// The wormhole field is not "-" or a comma-separated list of squares.
+    Wormholes,
+
+    /// The next hole field is not "-" or a square.
+    NextHole,
+
+    /// The hole-in-1 field is not "0" or "1",
+    /// or is "1" without a next hole.
+    HoleIn1,
+}
+
+/// Write the position as a FEN string, using X-FEN castling.
+pub fn to_fen(state: &BoardState) -> String {
+    write_fen(state, false)
+}
+
+/// Write the position as a Shredder-FEN string,
+/// where castling rights are always the rook files.
+pub fn to_shredder_fen(state: &BoardState) -> String {
+    write_fen(state, true)
+}
+
+fn write_fen(state: &BoardState, shredder: bool) -> String {
+    let mut fen = String::new();
+    write_placement(&mut fen, &state.pieces);
+
+    fen.push(' ');
+    fen.push(match state.turn {
+        Team::White => 'w',
+        Team::Black => 'b',
+    });
+
+    fen.push(' ');
+    write_castling(&mut fen, &state.castle, &state.pieces, shredder);
+
+    let _ = match state.en_passant {
+        Some(sq) => write!(fen, " {sq}"),
+        None => write!(fen, " -"),
+    };
+
+    let _ = write!(fen, " {} {}", state.halfmoves, state.fullmoves);
+    fen
+}
+
+/// Write the position as an extended FEN string, including wormhole state.
+pub fn to_extended_fen(state: &BoardState) -> String {
+    let mut fen = to_fen(state);
+
+    fen.push(' ');
+    if state.wormholes == BitBoard::new() {
+        fen.push('-');
+    } else {
+        for (i, sq) in state.wormholes.into_iter().enumerate() {
+            if i > 0 {
+                fen.push(',');
+            }
+            let _ = write!(fen, "{sq}");
+        }
+    }
+
+    let _ = match state.next_hole {
+        Some(sq) => write!(fen, " {sq}"),
+        None => write!(fen, " -"),
+    };
+
+    fen.push(' ');
+    fen.push(if state.hole_in_1 { '1' } else { '0' });
+    fen
+}
+
+/// Parse a FEN string into a position.
+pub fn from_fen(fen: &str) -> Result<BoardState, FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() != 6 {
+        return Err(FenError::FieldCount(fields.len()))
+    }
+
+    parse_fields(&fields)
+}
+
+/// Parse an extended FEN string into a position.
+/// Standard six-field FEN is also accepted, and has no wormholes.
+pub fn from_extended_fen(fen: &str) -> Result<BoardState, FenError> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    match fields.len() {
+        6 => parse_fields(&fields),
+        9 => {
+            let mut state = parse_fields(&fields[..6])?;
+            state.wormholes = parse_wormholes(fields[6])?;
+            state.exits = HoleExits::new(state.wormholes);
+            state.next_hole = match fields[7] {
+                "-" => None,
+                sq => Some(sq.parse().map_err(|_| FenError::NextHole)?),
+            };
+            state.hole_in_1 = match fields[8] {
+                "0" => false,
+                "1" if state.next_hole.is_some() => true,
+                _ => return Err(FenError::HoleIn1),
+            };
+
+            // pieces standing on a wormhole occupy every hole.
+            for sq in state.wormholes {
+                if let Some(pc) = state.pieces.piece_at(sq) {
+                    let team = if state.pieces.white.has(sq) { Team::White } else { Team::Black };
+                    state.pieces.insert(sq, pc, team, state.wormholes);
+                }
+            }
+
+            state.is_check = state.king_attacked();
+            Ok(state)
+        },
+        n => Err(FenError::FieldCount(n)),
+    }
+}
+
+fn parse_fields(fields: &[&str]) -> Result<BoardState, FenError> {
+    let pieces = parse_placement(fields[0])?;
+    let mut state = BoardState {
+        pieces,
+        turn: parse_turn(fields[1])?,
+        castle: parse_castling(fields[2], &pieces)?,
+        en_passant: parse_en_passant(fields[3])?,
+        halfmoves: fields[4].parse().map_err(|_| FenError::Halfmoves)?,
+        fullmoves: fields[5].parse().ok().filter(|&n| n != 0).ok_or(FenError::Fullmoves)?,
+        ..BoardState::default()
+    };
+
+    state.is_check = state.king_attacked();
+    Ok(state)
+}
+
+pub(crate) fn write_placement(fen: &mut String, pieces: &Pieces) {
+    for rank in (0..8).rev() {
+        let mut empty = 0;
+        for file in 0..8 {
+            let sq = Square::from((rank, file));
+            match pieces.piece_at(sq) {
+                Some(pc) => {
+                    if empty > 0 {
+                        let _ = write!(fen, "{empty}");
+                        empty = 0;
+                    }
+
+                    if pieces.white.has(sq) {
+                        fen.push(pc.to_char_upper());
+                    } else {
+                        fen.push(pc.to_char_lower());
+                    }
+                },
+                None => empty += 1,
+            }
+        }
+
+        if empty > 0 {
+            let _ = write!(fen, "{empty}");
+        }
+
+        if rank > 0 {
+            fen.push('/');
+        }
+    }
+}
+
+pub(crate) fn write_castling(fen: &mut String, castle: &CastleRights, pieces: &Pieces, shredder: bool) {
+    let len = fen.len();
+    for (side, team, c) in [
+        (Castle::Short, Team::White, 'K'),
+        (Castle::Long, Team::White, 'Q'),
+        (Castle::Short, Team::Black, 'k'),
+        (Castle::Long, Team::Black, 'q'),
+    ] {
+        if !castle.has(side, team) {
+            continue
+        }
+
+        let rook = castle.rook_start(side, team);
+        let back_rooks = pieces.get(Piece::Rook, team) & BitBoard::from(team.back_rank());
+        let is_outermost = back_rooks.into_iter().all(|sq| match side {
+            Castle::Short => sq.file_u8() <= rook.file_u8(),
+            Castle::Long => sq.file_u8() >= rook.file_u8(),
+        });
+
+        if shredder || !is_outermost {
+            let file = (b'a' + rook.file_u8()) as char;
+            fen.push(if team == Team::White { file.to_ascii_uppercase() } else { file });
+        } else {
+            fen.push(c);
+        }
+    }
+
+    if fen.len() == len {
+        fen.push('-');
+    }
+}
+
+pub(crate) fn parse_placement(field: &str) -> Result<Pieces, FenError> {
+    let mut pieces = Pieces::empty();
+
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::Placement(ranks.len().min(8)))
+    }
+
+    for (i, row) in ranks.into_iter().enumerate() {
+        let rank = 7 - i as u8;
+        let mut file = 0u8;
+        for c in row.chars() {
+            if let Some(n) = c.to_digit(10) {
+                if n == 0 || n > 8 {
+                    return Err(FenError::Placement(i))
+                }
+                file += n as u8;
+            } else {
+                let pc = Piece::from_char(c).ok_or(FenError::Placement(i))?;
+                let team = if c.is_ascii_uppercase() { Team::White } else { Team::Black };
+                if file >= 8 {
+                    return Err(FenError::Placement(i))
+                }
+                pieces.insert(Square::from((rank, file)), pc, team, BitBoard::new());
+                file += 1;
+            }
+
+            if file > 8 {
+                return Err(FenError::Placement(i))
+            }
+        }
+
+        if file != 8 {
+            return Err(FenError::Placement(i))
+        }
+    }
+
+    Ok(pieces)
+}
+
+pub(crate) fn parse_turn(field: &str) -> Result<Team, FenError> {
+    match field {
+        "w" => Ok(Team::White),
+        "b" => Ok(Team::Black),
+        _ => Err(FenError::Turn),
+    }
+}
+
+pub(crate) fn parse_castling(field: &str, pieces: &Pieces) -> Result<CastleRights, FenError> {
+    let mut castle = CastleRights {
+        rights: 0,
+        ..CastleRights::default()
+    };
+    if field == "-" {
+        return Ok(castle)
+    }
+
+    // both teams share one set of castle settings,
+    // so the files they name must agree.
+    let mut king_file = None;
+    let mut short_file = None;
+    let mut long_file = None;
+
+    for c in field.chars() {
+        let team = if c.is_ascii_uppercase() { Team::White } else { Team::Black };
+        let back_rank = BitBoard::from(team.back_rank());
+        let king = (pieces.get(Piece::King, team) & back_rank).first().ok_or(FenError::Castling)?;
+        let rooks = pieces.get(Piece::Rook, team) & back_rank;
+
+        let rook = match c.to_ascii_lowercase() {
+            'k' => rooks.into_iter().filter(|sq| sq.file_u8() > king.file_u8()).last(),
+            'q' => rooks.into_iter().find(|sq| sq.file_u8() < king.file_u8()),
+            file @ 'a'..='h' => Some(Square::from((team.back_rank_u8(), file as u8 - b'a'))).filter(|&sq| rooks.has(sq)),
+            _ => None,
+        }.ok_or(FenError::Castling)?;
+
+        let (side, file) = match rook.file_u8().cmp(&king.file_u8()) {
+            std::cmp::Ordering::Greater => (Castle::Short, &mut short_file),
+            std::cmp::Ordering::Less => (Castle::Long, &mut long_file),
+            std::cmp::Ordering::Equal => return Err(FenError::Castling),
+        };
+
+        if *file.get_or_insert(rook.file_u8()) != rook.file_u8() ||
+            *king_file.get_or_insert(king.file_u8()) != king.file_u8() ||
+            !castle.give(side, team)
+        {
+            return Err(FenError::Castling)
+        }
+    }
+
+    if let Some(file) = king_file {
+        castle.set_king(file);
+    }
+
+    if let Some(file) = short_file {
+        castle.set_rook(Castle::Short, file);
+    }
+
+    if let Some(file) = long_file {
+        castle.set_rook(Castle::Long, file);
+    }
+
+    Ok(castle)
+}
+
+pub(crate) fn parse_wormholes(field: &str) -> Result<BitBoard, FenError> {
+    let mut holes = BitBoard::new();
+    if field == "-" {
+        return Ok(holes)
+    }
+
+    for sq in field.split(',') {
+        holes |= sq.parse::<Square>().map_err(|_| FenError::Wormholes)?;
+    }
+
+    Ok(holes)
+}
+
+pub(crate) fn parse_en_passant(field: &str) -> Result<Option<Square>, FenError> {
+    if field == "-" {
+        Ok(None)
+    } else {
+        field.parse().map(Some).map_err(|_| FenError::EnPassant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FenError;
+    use crate::{castle::Castle, game::ChessGame, pieces::Piece, square::Square, state::BoardState, team::Team};
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(BoardState::default().to_fen(), START);
+        assert!(BoardState::from_fen(START).unwrap().is_repetition_of(&BoardState::default()));
+
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 c7c5 g1f3 d7d6 e1e2").unwrap();
+        let fen = game.cursor.state.to_fen();
+        assert!(fen.starts_with("rnbqkbnr/pp2pppp/3p4/2p5/4P3/5N2/PPPPKPPP/RNBQ1B1R b kq - "));
+        assert_eq!(BoardState::from_fen(&fen).unwrap().to_fen(), fen);
+
+        // archbishops and chancellors are written as A and C.
+        let fairy = "rabqkbcr/pppppppp/8/8/8/8/PPPPPPPP/RABQKBCR w KQkq - 0 1";
+        let state = BoardState::from_fen(fairy).unwrap();
+        assert_eq!(state.pieces.piece_at(Square::from((0, 1))), Some(Piece::Archbishop));
+        assert_eq!(state.pieces.piece_at(Square::from((7, 6))), Some(Piece::Chancellor));
+        assert_eq!(state.to_fen(), fairy);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(BoardState::from_fen("8/8/8/8 w - - 0 1").err(), Some(FenError::Placement(4)));
+        assert_eq!(BoardState::from_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").err(), Some(FenError::Placement(2)));
+        assert_eq!(BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1").err(), Some(FenError::Turn));
+        assert_eq!(BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1").err(), Some(FenError::EnPassant));
+        assert_eq!(BoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").err(), Some(FenError::FieldCount(4)));
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // rooks on b1 and g1, with an extra rook on h1 outside the short rook.
+        let fen = "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w Gkq - 0 1";
+        let state = BoardState::from_fen(fen).unwrap();
+        assert_eq!(state.castle.rook_start(Castle::Short, Team::White), Square::from((0, 6)));
+        assert_eq!(state.castle.rook_start(Castle::Long, Team::Black), Square::from((7, 1)));
+        assert_eq!(state.to_fen(), fen);
+        assert_eq!(state.to_shredder_fen(), "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w Ggb - 0 1");
+        assert!(BoardState::from_fen(&state.to_shredder_fen()).unwrap().is_repetition_of(&state));
+
+        assert_eq!(BoardState::default().to_shredder_fen(), START.replace("KQkq", "HAha"));
+
+        // the teams name different short rooks.
+        assert_eq!(BoardState::from_fen("1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1RR w Hkq - 0 1").err(), Some(FenError::Castling));
+    }
+
+    #[test]
+    fn extended_round_trip() {
+        assert_eq!(BoardState::default().to_extended_fen(), format!("{START} - - 0"));
+        assert!(BoardState::from_extended_fen(START).unwrap().is_repetition_of(&BoardState::default()));
+
+        let fen = format!("{START} d4,e5 c3 1");
+        let state = BoardState::from_extended_fen(&fen).unwrap();
+        assert_eq!(state.wormholes.count(), 2);
+        assert_eq!(state.next_hole, Some(Square::from((2, 2))));
+        assert!(state.hole_in_1);
+        assert_eq!(state.to_extended_fen(), fen);
+
+        assert_eq!(BoardState::from_extended_fen(&format!("{START} d9 - 0")).err(), Some(FenError::Wormholes));
+        assert_eq!(BoardState::from_extended_fen(&format!("{START} - - 1")).err(), Some(FenError::HoleIn1));
+        assert_eq!(BoardState::from_fen(&fen).err(), Some(FenError::FieldCount(9)));
+    }
+}
@@ -0,0 +1,54 @@
+//! Open and semi-open files.
+//!
+//! Files are returned as boards with every square of each file set, so
+//! they can be intersected with the pieces directly.
+
+use crate::{board::BitBoard, pieces::Pieces, team::Team};
+
+/// The whole of every file with a square in the board.
+fn fill_files(board: BitBoard) -> BitBoard {
+    let mut files = BitBoard::new();
+    for file in 0..8 {
+        if board.intersects(BitBoard::new().with_file_u8(file)) {
+            files.set_file_u8(file);
+        }
+    }
+
+    files
+}
+
+/// The files without any pawns.
+pub fn open_files(pieces: &Pieces) -> BitBoard {
+    !fill_files(pieces.pawns)
+}
+
+/// The files where the team has no pawns but the other team does.
+pub fn semi_open_for(pieces: &Pieces, team: Team) -> BitBoard {
+    let own = pieces.pawns & pieces.on_team(team);
+    let enemy = pieces.pawns & pieces.on_team(!team);
+    fill_files(enemy) & !fill_files(own)
+}
+
+/// The team's rooks that stand on open files.
+pub fn rook_on_open_file(pieces: &Pieces, team: Team) -> BitBoard {
+    pieces.rooks & pieces.on_team(team) & open_files(pieces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open_files, rook_on_open_file, semi_open_for};
+    use crate::{board::BitBoard, square::Square, state::BoardState, team::Team};
+
+    #[test]
+    fn files() {
+        let state = BoardState::from_fen("2r1r1k1/pp3ppp/8/3p4/8/8/PP3PPP/2R2RK1 w - - 0 1").unwrap();
+        let file = |f: u8| BitBoard::new().with_file_u8(f);
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+
+        assert_eq!(open_files(&state.pieces), file(2) | file(4));
+        assert_eq!(semi_open_for(&state.pieces, Team::White), file(3));
+        assert!(semi_open_for(&state.pieces, Team::Black).is_empty());
+        assert_eq!(rook_on_open_file(&state.pieces, Team::White), sq("c1").into());
+        assert_eq!(rook_on_open_file(&state.pieces, Team::Black), sq("c8") | sq("e8"));
+    }
+}
@@ -0,0 +1,91 @@
+//! Generation of forcing moves: checks, captures, and threats.
+
+use crate::{pieces::Piece, square::Square, state::BoardState, trace::MoveTrace};
+
+#[derive(Copy, Clone)]
+pub struct ForcingMove {
+    pub src: Square,
+    pub dst: Square,
+    pub trace: MoveTrace,
+
+    /// The move puts the enemy king in check.
+    pub is_check: bool,
+
+    /// The piece the move captures, if any.
+    pub captures: Option<Piece>,
+
+    /// After the move, the moved piece attacks an enemy piece
+    /// that is undefended or worth more than it.
+    pub is_threat: bool,
+}
+
+/// Every legal move for the team to move that gives check,
+/// captures, or creates a threat, in the order they were generated.
+/// Moves that require promotion are classified as promoting to a queen.
+pub fn forcing_moves(state: &BoardState) -> Vec<ForcingMove> {
+    let mut forcing = Vec::new();
+    for (src, dst, trace) in state.legal_moves() {
+        let Some(delta) = state.delta(src, dst, &trace, Some(Piece::Queen)) else {
+            continue
+        };
+
+        let next = state.next(delta);
+        let captures = trace.captures;
+        let is_check = next.king_attacked();
+        let is_threat = creates_threat(&next, dst);
+
+        if is_check || captures.is_some() || is_threat {
+            forcing.push(ForcingMove { src, dst, trace, is_check, captures, is_threat });
+        }
+    }
+
+    forcing
+}
+
+/// Whether the piece that just moved to `dst` attacks an enemy piece,
+/// other than the king, that is undefended or worth more than it.
+fn creates_threat(next: &BoardState, dst: Square) -> bool {
+    let mut null = *next;
+    null.turn = !next.turn;
+    null.en_passant = None;
+
+    let Some(mover) = null.pieces.piece_at_or_on_hole(dst, null.wormholes) else {
+        return false
+    };
+
+    // squares the enemy defends.
+    let defended = crate::defense::defense(&null);
+    let enemy = null.pieces.on_team(next.turn);
+    let targets = crate::compute::compute(&null, dst, Some(defended)) & enemy;
+
+    targets.into_iter().any(|sq| {
+        next.pieces.piece_at(sq).is_some_and(|pc| {
+            pc != Piece::King && (!defended.has(sq) || pc.value() > mover.value())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{pieces::Piece, square::Square, state::BoardState};
+
+    #[test]
+    fn classifies_checks_captures_and_threats() {
+        let state = BoardState::from_fen("4k3/8/8/3p4/8/2N5/8/R3K3 w - - 0 1").unwrap();
+        let sq = |rank, file| Square::from((rank, file));
+        let forcing = state.forcing_moves();
+
+        let check = forcing.iter().find(|m| m.src == sq(0, 0) && m.dst == sq(7, 0)).expect("Ra8+");
+        assert!(check.is_check && check.captures.is_none());
+
+        let capture = forcing.iter().find(|m| m.src == sq(2, 2) && m.dst == sq(4, 3)).expect("Nxd5");
+        assert_eq!(capture.captures, Some(Piece::Pawn));
+
+        // the rook on a5 attacks the undefended pawn on d5.
+        let threat = forcing.iter().find(|m| m.src == sq(0, 0) && m.dst == sq(4, 0)).expect("Ra5");
+        assert!(threat.is_threat && !threat.is_check);
+
+        // a quiet king move is not forcing.
+        assert!(!forcing.iter().any(|m| m.src == sq(0, 4) && m.dst == sq(1, 4)));
+    }
+}
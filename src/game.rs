@@ -1,6 +1,6 @@
-use chrono::Duration;
+use std::{collections::BTreeMap, io, path::Path};
 
-use crate::{board::BitBoard, castle::{Castle, CastleRights}, delta::BoardDelta, end::EndCondition, pieces::Piece, rng::WyRand, settings::GameSettings, square::Square, state::BoardState, trace::MoveTrace};
+use crate::{amend::{AmendError, Amendment}, annotation::Annotation, audit::{AuditError, Repair, ReplayError}, board::BitBoard, binary::BinaryError, clock::{MockClock, SystemClock, TimeSource}, delta::{BoardDelta, DeltaError}, deltas::Deltas, end::{EndCondition, GameResult}, events::{Events, MoveEvent}, memory::MemoryFootprint, meta::GameMeta, persist::LoadError, pgn::PgnError, pieces::Piece, rng::WyRand, settings::{GameLimits, GameSettings, LimitError}, square::Square, state::BoardState, team::Team, trace::MoveTrace, tree::TreeError, uci::UciError};
 
 #[derive(Clone)]
 pub struct ChessGame {
@@ -11,7 +11,7 @@ pub struct ChessGame {
     pub cursor: Cursor,
 
     /// The changes that occur at each move in the game.
-    pub deltas: Vec<BoardDelta>,
+    pub deltas: Deltas,
 
     /// Settings that control wormhole and clock behavior.
     pub settings: GameSettings,
@@ -37,41 +37,151 @@ pub struct ChessGame {
     /// Whether the last position in the game is checkmate,
     /// stalemate, or any other end condition.
     pub end: Option<EndCondition>,
+
+    /// Player names, ratings, and event details, if known.
+    pub meta: Option<GameMeta>,
+
+    /// Comments and glyphs by the halfmove index of the position they
+    /// annotate, so the annotation of a move is at the index after it.
+    /// Index 0 holds a comment about the game before the first move.
+    pub annotations: BTreeMap<usize, Annotation>,
+
+    /// The team whose draw offer is waiting for an answer. Offers
+    /// expire when the next move is played, and are not saved.
+    pub draw_offer: Option<Team>,
+
+    /// Callbacks run as the game is played. Clones and branches
+    /// share the callbacks, and they are not saved.
+    pub events: Events,
+
+    /// Arbiter changes, such as added time, by the halfmove index of
+    /// the position they were made at, in the order they were made.
+    pub amendments: Vec<(usize, Amendment)>,
+
+    /// The game and range of positions this game was cut from by
+    /// `slice`, if any.
+    pub sliced_from: Option<Slice>,
 }
 
 impl ChessGame {
     pub fn init(settings: GameSettings) -> Self {
-        let seed = crate::rng::entropy();
+        Self::init_seeded(settings, crate::rng::entropy())
+    }
+
+    /// Create a game whose random choices, such as the Chess960
+    /// position, are derived from the seed instead of system entropy.
+    pub fn init_seeded(settings: GameSettings, seed: u64) -> Self {
         let mut rng = WyRand { seed };
 
-        let start = if settings.is_chess960 {
+        let mut start = if settings.is_chess960 {
             crate::init::init_chess960(&mut rng)
         } else {
             BoardState::default()
         };
 
+        crate::init::place_wormholes(&mut start, &settings.wormhole, &mut rng);
+
         let cursor = if let Some(clock) = settings.clock {
             Cursor {
                 state: start,
                 index: 0,
-                white_time: clock.total,
-                black_time: clock.total,
+                white_time: clock.total_ms(),
+                black_time: clock.total_ms(),
                 clock_is_ticking: true,
             }
         } else {
             Cursor::new(start)
         };
 
+        let game_id = crate::rng::entropy();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            game_id,
+            chess960 = settings.is_chess960,
+            clock_ms = settings.clock.map(|clock| clock.total_ms()),
+            "game created"
+        );
+
         Self {
             start,
             cursor,
-            deltas: Vec::new(),
+            deltas: Deltas::new(),
             settings,
-            game_id: crate::rng::entropy(),
+            game_id,
             is_branch: None,
             seed,
             end: None,
+            meta: None,
+            annotations: BTreeMap::new(),
+            draw_offer: None,
+            events: Events::new(),
+            amendments: Vec::new(),
+            sliced_from: None,
+        }
+    }
+
+    /// Start a game with the settings and play the moves, returning the
+    /// index of the first move that cannot be played. Like `from_uci_moves`,
+    /// only an end condition reached by the last move is kept.
+    pub fn from_moves(settings: GameSettings, moves: &[(Square, Square, Option<Piece>)]) -> Result<Self, usize> {
+        let mut game = Self::init(settings);
+        for (i, &(src, dst, promote)) in moves.iter().enumerate() {
+            game.end = None;
+            game.play(src, dst, promote).map_err(|_| i)?;
+        }
+
+        Ok(game)
+    }
+
+    /// Build a game from a start position and a whitespace-separated
+    /// list of moves in coordinate notation, such as "e2e4 e7e5 g1f3".
+    pub fn from_uci_moves(start: BoardState, moves: &str) -> Result<Self, UciError> {
+        crate::uci::from_uci_moves(start, moves)
+    }
+
+    /// Build a game from a UCI position command, such as
+    /// "position startpos moves e2e4 e7e5" or "position fen <fen> moves ...".
+    pub fn from_uci_position(command: &str) -> Result<Self, UciError> {
+        crate::uci::from_uci_position(command)
+    }
+
+    /// The moves of the game in coordinate notation, separated by
+    /// spaces, as sent to engines in `position startpos moves ...`.
+    pub fn moves_uci(&self) -> String {
+        self.deltas.iter().map(|delta| delta.to_uci()).collect::<Vec<_>>().join(" ")
+    }
+
+    /// Play a move in coordinate notation, such as "e2e4" or "e7e8q".
+    /// Returns `PlayError::InvalidMove` if the notation is malformed.
+    pub fn play_uci(&mut self, uci: &str) -> Result<PlaySuccess, PlayError> {
+        let (src, dst, promote) = crate::uci::parse_move(uci).ok_or(PlayError::InvalidMove)?;
+        self.play(src, dst, promote)
+    }
+
+    /// Play a move in Standard Algebraic Notation, such as "Nxe5" or "e8=Q",
+    /// resolved against the cursor position. Returns `PlayError::InvalidMove`
+    /// if the notation does not match exactly one legal move.
+    pub fn play_san(&mut self, san: &str) -> Result<PlaySuccess, PlayError> {
+        let (src, dst, promote) = crate::san::parse_san(&self.cursor.state, san).ok_or(PlayError::InvalidMove)?;
+        self.play(src, dst, promote)
+    }
+
+    /// Play a move received as a delta, such as an opponent's move sent
+    /// over the network. The delta must be exactly the delta that playing
+    /// its move at the cursor produces, except for the time spent, which
+    /// is taken from the delta instead of being measured.
+    pub fn play_delta(&mut self, delta: BoardDelta) -> Result<PlaySuccess, PlayError> {
+        match self.cursor.state.verify_delta(&delta) {
+            Ok(_) => {},
+            Err(DeltaError::Illegal) => return Err(PlayError::InvalidMove),
+            Err(_) => return Err(PlayError::InconsistentDelta),
         }
+
+        let (src, dst) = self.cursor.state.move_squares(&delta);
+        let promote = delta.get_promote_pc();
+        let played_at = self.last_move_ms().unwrap_or_default() + delta.get_time() as i64;
+        self.play_timed(src, dst, promote, &MockClock::new(played_at))
     }
 
     pub fn cursor(&self) -> &Cursor {
@@ -82,6 +192,27 @@ impl ChessGame {
         self.cursor.index == self.deltas.len()
     }
 
+    /// The squares the piece on `sq` can move to in the cursor position,
+    /// excluding moves forbidden by the move filter. Use this for highlighting.
+    pub fn valid_moves(&self, sq: Square) -> BitBoard {
+        let state = &self.cursor.state;
+        let mut moves = state.valid_moves(sq);
+        if self.settings.move_filter.is_some() {
+            for dst in moves {
+                if !state.trace(sq, dst).is_some_and(|trace| self.allows(state, sq, dst, &trace)) {
+                    moves.clear(dst);
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// Whether the move filter allows a legal move. Always true without a filter.
+    pub fn allows(&self, state: &BoardState, src: Square, dst: Square, trace: &MoveTrace) -> bool {
+        self.settings.move_filter.is_none_or(|filter| filter(state, src, dst, trace))
+    }
+
     /// Get the delta for the move that was played in the cursor position.
     /// If this is the last move in the game, None is returned.
     pub fn get_next_delta(&self) -> Option<BoardDelta> {
@@ -97,6 +228,171 @@ impl ChessGame {
         }
     }
 
+    /// The annotation of the position at the halfmove index, created
+    /// empty if there is none. Refuses indices past the last position,
+    /// and annotating another position once the game has as many
+    /// annotations as its limits allow.
+    pub fn annotate(&mut self, index: usize) -> Result<&mut Annotation, AnnotateError> {
+        if index > self.deltas.len() {
+            return Err(AnnotateError::Index)
+        }
+
+        if !self.annotations.contains_key(&index) {
+            GameLimits::check(self.settings.limits.max_annotations, self.annotations.len(), LimitError::Annotations)
+                .map_err(AnnotateError::Limit)?;
+        }
+
+        Ok(self.annotations.entry(index).or_default())
+    }
+
+    /// Draw the position at the cursor as an SVG diagram from the side
+    /// of the team, with the arrows and highlights annotated there.
+    pub fn to_svg(&self, perspective: Team) -> String {
+        self.cursor.state.to_svg(self.annotations.get(&self.cursor.index), perspective)
+    }
+
+    /// Reconstruct the time remaining on each clock after every halfmove,
+    /// as `(index, white_ms, black_ms)`, where index 0 is the start position.
+    /// 
+    /// Times are derived from the delta times, the clock settings, and
+    /// the amendments, so a clock that ran out is reported as a negative
+    /// value. Branches are assumed to start with full clocks. Returns an
+    /// empty Vec if the game is not timed.
+    pub fn clock_history(&self) -> Vec<(usize, i64, i64)> {
+        let Some(clock) = self.settings.clock else {
+            return Vec::new()
+        };
+
+        let mut bonus = clock.bonus_ms() as i64;
+        let mut white = clock.total_ms() as i64;
+        let mut black = clock.total_ms() as i64;
+        let mut turn = self.start.turn;
+        let mut amendments = self.amendments.iter().peekable();
+
+        let mut history = Vec::with_capacity(self.deltas.len() + 1);
+        for i in 0..=self.deltas.len() {
+            if let Some(delta) = i.checked_sub(1).and_then(|prev| self.deltas.get(prev)) {
+                let spent = delta.get_time() as i64;
+                match turn {
+                    Team::White => white += bonus - spent,
+                    Team::Black => black += bonus - spent,
+                }
+
+                turn = !turn;
+            }
+
+            while let Some((_, amendment)) = amendments.next_if(|(index, _)| *index == i) {
+                amendment.apply(&mut white, &mut black, &mut bonus);
+            }
+
+            history.push((i, white, black));
+        }
+
+        history
+    }
+
+    /// The time the last move was played, or the clock started,
+    /// in milliseconds since the Unix epoch. None if the game is not timed.
+    fn last_move_ms(&self) -> Option<i64> {
+        let clock = self.settings.clock?;
+        let spent: i64 = self.deltas.iter().map(|delta| delta.get_time() as i64).sum();
+        Some(clock.start.timestamp_millis() + spent)
+    }
+
+    /// The time remaining on each clock right now, as `(white_ms, black_ms)`,
+    /// counting the time the side to move has spent since the last move.
+    /// Negative if a clock has run out. None if the game is not timed.
+    pub fn remaining_ms(&self, time: &impl TimeSource) -> Option<(i64, i64)> {
+        let (_, mut white, mut black) = self.clock_history().pop()?;
+        if self.end.is_none() {
+            let thinking = time.now_ms().saturating_sub(self.last_move_ms()?).max(0);
+            let turn = self.deltas.iter().fold(self.start.turn, |turn, _| !turn);
+            match turn {
+                Team::White => white -= thinking,
+                Team::Black => black -= thinking,
+            }
+        }
+
+        Some((white, black))
+    }
+
+    /// Who won the game, or None if it has not ended or was aborted.
+    pub fn result(&self) -> Option<GameResult> {
+        let turn = self.deltas.iter().fold(self.start.turn, |turn, _| !turn);
+        self.end?.result(turn)
+    }
+
+    /// The halfmove index of the position the game ended at, None if it
+    /// has not ended. No moves can follow an end, so it is always the last.
+    pub fn ended_at(&self) -> Option<usize> {
+        self.end.map(|_| self.deltas.len())
+    }
+
+    /// Replay every delta from the start position, reporting the first
+    /// move that is illegal, forbidden, or inconsistently encoded.
+    /// Unlike `audit`, the clocks, end condition, and branch are not checked.
+    pub fn validate(&self) -> Result<(), ReplayError> {
+        crate::audit::replay(self).map(|_| ())
+    }
+
+    /// Verify that every move in the game is legal and consistently
+    /// encoded, that the clocks and end condition agree with the moves,
+    /// and that the branch metadata is valid.
+    pub fn audit(&self) -> Result<(), AuditError> {
+        crate::audit::audit(self)
+    }
+
+    /// Cut the game back to its last valid position if it fails its
+    /// audit, such as after a past bug stored a bad move, reporting what
+    /// was removed. See `audit::repair`.
+    pub fn repair(&mut self) -> Result<Repair, AuditError> {
+        crate::audit::repair(self)
+    }
+
+    /// The bytes held by the game, see `memory`.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        crate::memory::game(self)
+    }
+
+    /// Encode the game in the compact binary format described in `binary`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        crate::binary::to_bytes(self)
+    }
+
+    /// Decode a game from the compact binary format,
+    /// detecting corruption and auditing the moves.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+        crate::binary::from_bytes(bytes)
+    }
+
+    /// Atomically save the game to a file, so it can be resumed with `load`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        crate::persist::save(self, path.as_ref())
+    }
+
+    /// Resume a game saved with `save`, reconciling the clocks with the system clock.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        crate::persist::load(path.as_ref(), &SystemClock)
+    }
+
+    /// Resume a game saved with `save`, reconciling the clocks with the provided time source.
+    pub fn load_with(path: impl AsRef<Path>, time: &impl TimeSource) -> Result<Self, LoadError> {
+        crate::persist::load(path.as_ref(), time)
+    }
+
+    /// Export the game as PGN, with SAN movetext derived from the deltas.
+    pub fn to_pgn(&self) -> String {
+        crate::pgn::to_pgn(self)
+    }
+
+    /// Import the first game in a PGN string, including its variant settings.
+    pub fn from_pgn(pgn: &str) -> Result<Self, PgnError> {
+        crate::pgn::from_pgn(pgn)
+    }
+
+    /// Start a game from the position after `delta` is played at the
+    /// cursor, recording this game as its parent. The branch has ended if
+    /// the move ends the game, judged with the positions before it here.
     pub fn branch(&mut self, delta: BoardDelta) -> ChessGame {
         let next = self.cursor.state.next(delta);
 
@@ -109,7 +405,7 @@ impl ChessGame {
         Self {
             start: next,
             cursor: Cursor::new(next),
-            deltas: Vec::new(),
+            deltas: Deltas::new(),
             settings: self.settings,
             game_id: crate::rng::entropy(),
             is_branch: Some(
@@ -121,121 +417,388 @@ impl ChessGame {
                 }
             ),
             seed: self.seed,
-            end: None, // todo: figure this out
+            end: self.end_after(delta),
+            meta: self.meta.clone(),
+            annotations: BTreeMap::new(),
+            draw_offer: None,
+            events: self.events.clone(),
+            amendments: Vec::new(),
+            sliced_from: None,
+        }
+    }
+
+    /// Cut the positions from `from` to `to`, both halfmove indices, into
+    /// a game of their own that starts at `from`, with its move counters,
+    /// and ends at `to`. The new game is untimed, keeps the annotations in
+    /// the range, and records where it was cut from in `sliced_from`.
+    /// An end condition is kept if the range reaches the end of the game
+    /// and the condition does not depend on the positions or clocks before
+    /// `from`. Returns None if the range is empty or past the end.
+    pub fn slice(&self, from: usize, to: usize) -> Option<ChessGame> {
+        if from > to || to > self.deltas.len() {
+            return None
         }
+
+        let start = self.position_at(from)?;
+        let end = self.end.filter(|end| to == self.deltas.len() && !matches!(
+            end,
+            EndCondition::Repetition |
+            EndCondition::Timeout |
+            EndCondition::TimeoutVsInsufficientMaterial
+        ));
+
+        Some(Self {
+            start,
+            cursor: Cursor::new(start),
+            deltas: self.deltas.iter().skip(from).take(to - from).copied().collect(),
+            settings: GameSettings { clock: None, ..self.settings },
+            game_id: crate::rng::entropy(),
+            is_branch: None,
+            seed: self.seed,
+            end,
+            meta: self.meta.clone(),
+            annotations: self.annotations.range(from..=to).map(|(i, a)| (i - from, a.clone())).collect(),
+            draw_offer: None,
+            events: Events::new(),
+            amendments: Vec::new(),
+            sliced_from: Some(Slice { parent_id: self.game_id, from, to }),
+        })
+    }
+
+    /// Swap a branch of this game with the continuation it branched from.
+    /// Afterwards this game plays the branch's moves from its `src_index`,
+    /// and the branch holds the moves this game played there instead.
+    /// End conditions are swapped with the moves. The branch's cursor moves
+    /// to its start, and this game's cursor moves back to the branch point
+    /// if it was past it.
+    pub fn promote_branch(&mut self, branch: &mut ChessGame) -> Result<(), TreeError> {
+        let info = branch.is_branch.ok_or(TreeError::NotABranch)?;
+        if info.parent_id != self.game_id {
+            return Err(TreeError::UnknownParent)
+        }
+
+        let fork = self.positions()
+            .nth(info.src_index)
+            .map(|(_, state)| state)
+            .filter(|state| state.next(info.delta).is_repetition_of(&branch.start))
+            .ok_or(TreeError::StartMismatch)?;
+
+        let continuation = *self.deltas.get(info.src_index).ok_or(TreeError::NoContinuation)?;
+        let tail = self.deltas.split_off(info.src_index + 1);
+        self.deltas.truncate(info.src_index);
+        self.deltas.push(info.delta);
+        self.deltas.append(&mut branch.deltas);
+
+        branch.deltas = tail;
+        branch.start = fork.next(continuation);
+        branch.is_branch = Some(Branch { delta: continuation, ..info });
+        std::mem::swap(&mut self.end, &mut branch.end);
+
+        // annotations move with the positions they annotate.
+        let moved = self.annotations.split_off(&(info.src_index + 1));
+        self.annotations.extend(std::mem::take(&mut branch.annotations).into_iter().map(|(i, a)| (i + info.src_index + 1, a)));
+        branch.annotations = moved.into_iter().map(|(i, a)| (i - info.src_index - 1, a)).collect();
+
+        // so do amendments.
+        let split = self.amendments.partition_point(|&(i, _)| i <= info.src_index);
+        let moved = self.amendments.split_off(split);
+        self.amendments.extend(std::mem::take(&mut branch.amendments).into_iter().map(|(i, a)| (i + info.src_index + 1, a)));
+        branch.amendments = moved.into_iter().map(|(i, a)| (i - info.src_index - 1, a)).collect();
+
+        branch.goto_start();
+        if self.cursor.index > info.src_index {
+            self.goto_start();
+            self.goto(info.src_index);
+        }
+
+        Ok(())
     }
 
+    /// Play a move, timing it with the system clock. A new move that
+    /// ends the game sets `end`, after which no more moves can be played.
     pub fn play(
         &mut self, 
         src: Square, 
         dst: Square, 
         promote: Option<Piece>
+    ) -> Result<PlaySuccess, PlayError> {
+        self.play_timed(src, dst, promote, &SystemClock)
+    }
+
+    /// Play a move, timing it with the provided time source.
+    /// The time spent is measured from the previous move, or from
+    /// the start of the clock if this is the first move.
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(game_id = self.game_id, src = src.to_index(), dst = dst.to_index())
+    ))]
+    pub fn play_timed(
+        &mut self, 
+        src: Square, 
+        dst: Square, 
+        promote: Option<Piece>,
+        time: &impl TimeSource,
     ) -> Result<PlaySuccess, PlayError> {
         if self.cursor_is_last()  {
             if let Some(condition) = self.end {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(?condition, "move rejected, game has ended");
                 return Err(PlayError::GameEnded(condition))
             } 
         } 
 
-        if let Some(trace) = self.cursor.state.trace(src, dst) {
-            let mut delta = BoardDelta::default();
-            if trace.requires_promotion {
-                if promote.is_none_or(|pc| matches!(pc, Piece::Pawn | Piece::King)) {
-                    return Err(PlayError::RequiresPromotion)
-                } else {
-                    delta.set_promote_pc(promote.unwrap());
-                }
-            }
-
-            let prev = self.cursor.state;
-            let mut castle = prev.castle;
-
-            delta.set_prev_halfmoves(prev.halfmoves);
-
-            if let Some(side) = trace.is_castle {
-                castle.lose(Castle::Short, prev.turn);
-                castle.lose(Castle::Long, prev.turn);
-                delta.set_src_sq(castle.king_start(prev.turn));
-                delta.set_dst_sq(castle.rook_target(side, prev.turn));
-                delta.set_is_castle(side);
-            } else {
-                delta.set_src_sq(src);
-                delta.set_dst_sq(dst);    
-
-                if !trace.is_king_move {
-                    if let Some(side) = trace.loses_castle {
-                        castle.lose(side, prev.turn);
-                    }
-        
-                    if let Some(side) = trace.takes_castle {
-                        castle.lose(side, !prev.turn);
-                    }
-                }
-
-                if let Some(capture) = trace.captures {
-                    delta.set_capture_pc(capture);
-                }
-    
-                if let Some(ep_sq) = prev.en_passant {
-                    delta.set_prev_ep_sq(ep_sq);
-                }
-    
-                if let Some(_) = trace.allows_en_passant {
-                    delta.set_is_double_push();
-                } else {
-                    if let Some(ep_capture_sq) = trace.is_capture_en_passant {
-                        delta.set_ep_capture_sq(ep_capture_sq);
-                    }
-                }
+        if let Some(mut trace) = self.cursor.state.trace(src, dst) {
+            if !self.allows(&self.cursor.state, src, dst, &trace) {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("move rejected, forbidden by the move filter");
+                return Err(PlayError::Forbidden)
             }
 
-            if trace.is_king_move {
-                castle.lose(Castle::Long, prev.turn);
-                castle.lose(Castle::Short, prev.turn);
-            } 
+            let Some(mut delta) = self.cursor.state.delta(src, dst, &trace, promote) else {
+                return Err(PlayError::RequiresPromotion)
+            };
 
-            delta.set_castle_deltas(prev.castle.rights, castle.rights);
-            delta.set_prev_halfmoves(prev.halfmoves);
+            trace.gives_check = delta.is_check();
+            trace.gives_checkmate = delta.is_check() && !self.cursor.state.next(delta).has_legal_moves();
 
             // if the cursor is not last, the move must either be 
             // equal to the existing move (advancement) or create
             // a branch if different. 
-            if !self.cursor_is_last() {
-                if self.get_next_delta().is_some_and(|del| {
-                    del.get_src_sq() == delta.get_src_sq() && 
-                    del.get_dst_sq() == delta.get_dst_sq()
-                }) {
+            let mut appended = true;
+            if let Some(next) = self.get_next_delta() {
+                if !next.is_same_move(&delta) {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(index = self.cursor.index, "branch created");
+                    let branch = self.branch(delta);
                     return Ok(
                         PlaySuccess {
-                            branch: Some(self.branch(delta)),
+                            end: branch.end,
+                            branch: Some(branch),
                             delta,
                             trace,
                         }
                     )
-                } 
+                }
+
+                delta = next;
+                appended = false;
             } else {
-                self.deltas.push(delta);
+                GameLimits::check(self.settings.limits.max_plies, self.deltas.len(), LimitError::Plies).map_err(PlayError::Limit)?;
+
+                if let (Some(clock), Some(last)) = (self.settings.clock, self.last_move_ms()) {
+                    let spent = time.now_ms().saturating_sub(last).clamp(0, u32::MAX as i64) as u32;
+                    delta.set_time(spent);
+
+                    let remaining = match self.cursor.state.turn {
+                        Team::White => &mut self.cursor.white_time,
+                        Team::Black => &mut self.cursor.black_time,
+                    };
+
+                    // a move made after the flag fell is not played.
+                    if spent > *remaining {
+                        let condition = crate::end::timeout(&self.cursor.state);
+                        self.finish(condition)?;
+                        return Err(PlayError::GameEnded(condition))
+                    }
+
+                    let bonus = crate::amend::bonus_ms(clock, &self.amendments);
+                    *remaining = remaining.saturating_sub(spent).saturating_add(bonus);
+                }
 
-                // todo: tick the clock
+                self.end = self.end_after(delta);
+                self.draw_offer = None;
+                self.deltas.push(delta);
             }
 
             self.cursor.index += 1;
             self.cursor.state = self.cursor.state.next(delta);
 
-            // todo: check for end condition
+            #[cfg(feature = "tracing")]
+            tracing::trace!(
+                index = self.cursor.index,
+                white_ms = self.cursor.white_time,
+                black_ms = self.cursor.black_time,
+                ?delta,
+                "move applied"
+            );
+
+            if appended {
+                let event = MoveEvent {
+                    game_id: self.game_id,
+                    index: self.cursor.index,
+                    delta,
+                    trace: &trace,
+                    state: &self.cursor.state,
+                };
+                self.events.moved(&event, self.end);
+            }
 
             Ok(
                 PlaySuccess {
                     branch: None,
                     delta,
-                    trace
+                    trace,
+                    end: if self.cursor_is_last() { self.end } else { None },
                 }
             )
         } else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!("move rejected, invalid move");
             Err(PlayError::InvalidMove)
         }
     }
 
+    /// Offer a draw on behalf of the team. If the other team has already
+    /// offered one, the offer is accepted instead.
+    pub fn offer_draw(&mut self, team: Team) -> Result<(), DrawError> {
+        if let Some(condition) = self.end {
+            return Err(DrawError::GameEnded(condition))
+        }
+
+        if self.draw_offer == Some(!team) {
+            return self.accept_draw()
+        }
+
+        self.draw_offer = Some(team);
+        Ok(())
+    }
+
+    /// Accept the pending draw offer, ending the game by agreement.
+    pub fn accept_draw(&mut self) -> Result<(), DrawError> {
+        if let Some(condition) = self.end {
+            return Err(DrawError::GameEnded(condition))
+        }
+
+        self.draw_offer.take().ok_or(DrawError::NoOffer)?;
+        self.end = Some(EndCondition::Agreement);
+        self.events.ended(EndCondition::Agreement);
+        Ok(())
+    }
+
+    /// Decline the pending draw offer, returning the team that made it.
+    pub fn decline_draw(&mut self) -> Result<Team, DrawError> {
+        self.draw_offer.take().ok_or(DrawError::NoOffer)
+    }
+
+    /// Whether the side to move in the last position can claim a draw
+    /// by the fifty-move rule.
+    pub fn can_claim_fifty_moves(&self) -> bool {
+        self.end.is_none() && self.position_at(self.deltas.len()).is_some_and(|state| state.halfmoves >= crate::end::FIFTY_MOVES)
+    }
+
+    /// Claim a draw by the fifty-move rule in the last position. After 75
+    /// moves the game is drawn without a claim, see `SeventyFiveMoveRule`.
+    pub fn claim_fifty_moves(&mut self) -> Result<(), DrawError> {
+        if let Some(condition) = self.end {
+            return Err(DrawError::GameEnded(condition))
+        }
+
+        if !self.can_claim_fifty_moves() {
+            return Err(DrawError::NotClaimable)
+        }
+
+        let _ = self.finish(EndCondition::FiftyMoveRule);
+        Ok(())
+    }
+
+    /// Resign on behalf of the team, returning the halfmove index
+    /// the game ended at.
+    pub fn resign(&mut self, team: Team) -> Result<usize, PlayError> {
+        self.finish(match team {
+            Team::White => EndCondition::WhiteResign,
+            Team::Black => EndCondition::BlackResign,
+        })
+    }
+
+    /// Call off the game without a result, returning the halfmove index
+    /// the game ended at.
+    pub fn abort(&mut self) -> Result<usize, PlayError> {
+        self.finish(EndCondition::Aborted)
+    }
+
+    /// Record a change by the arbiter at the last position, returning its
+    /// halfmove index. Added time is given to the clocks at the cursor if
+    /// the cursor is on the last position.
+    pub fn amend(&mut self, amendment: Amendment) -> Result<usize, AmendError> {
+        if let Some(condition) = self.end {
+            return Err(AmendError::GameEnded(condition))
+        }
+
+        if self.settings.clock.is_none() {
+            return Err(AmendError::Untimed)
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(game_id = self.game_id, ?amendment, "game amended");
+        if let (Amendment::AddTime { team, ms }, true) = (amendment, self.cursor_is_last()) {
+            let remaining = match team {
+                Team::White => &mut self.cursor.white_time,
+                Team::Black => &mut self.cursor.black_time,
+            };
+
+            *remaining = remaining.saturating_add_signed(ms);
+        }
+
+        let index = self.deltas.len();
+        self.amendments.push((index, amendment));
+        Ok(index)
+    }
+
+    /// End the game because the side to move left it, returning the
+    /// halfmove index the game ended at.
+    pub fn abandon(&mut self) -> Result<usize, PlayError> {
+        self.finish(EndCondition::Abandoned)
+    }
+
+    /// End the game on time if the clock of the side to move has run out,
+    /// returning the end condition. None if the game is not timed, has
+    /// already ended, or there is time left.
+    pub fn check_flag(&mut self, time: &impl TimeSource) -> Option<EndCondition> {
+        if self.end.is_some() {
+            return None
+        }
+
+        let (white, black) = self.remaining_ms(time)?;
+        let state = self.position_at(self.deltas.len())?;
+        let remaining = match state.turn {
+            Team::White => white,
+            Team::Black => black,
+        };
+
+        if remaining >= 0 {
+            return None
+        }
+
+        let condition = crate::end::timeout(&state);
+        self.finish(condition).ok()?;
+        Some(condition)
+    }
+
+    fn finish(&mut self, condition: EndCondition) -> Result<usize, PlayError> {
+        if let Some(end) = self.end {
+            return Err(PlayError::GameEnded(end))
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(game_id = self.game_id, ?condition, "game ended");
+        self.end = Some(condition);
+        self.draw_offer = None;
+        self.events.ended(condition);
+        Ok(self.deltas.len())
+    }
+
+    /// The end condition reached by playing the delta at the cursor.
+    fn end_after(&self, delta: BoardDelta) -> Option<EndCondition> {
+        let state = self.cursor.state;
+        let earlier = self.deltas.iter().take(self.cursor.index).rev().scan(state, |pos, delta| {
+            *pos = pos.prev(*delta);
+            Some(*pos)
+        });
+
+        crate::end::detect(&state.next(delta), std::iter::once(state).chain(earlier))
+    }
+
     pub fn next(&mut self) -> Option<&Cursor> {
         self.get_next_delta().map(|delta| {
             self.cursor.state = self.cursor.state.next(delta);
@@ -245,7 +808,7 @@ impl ChessGame {
     }
 
     pub fn prev(&mut self) -> Option<&Cursor> {
-        if !self.cursor.index == 0 {
+        if self.cursor.index != 0 {
             self.get_prev_delta().map(|delta| {
                 self.cursor.state = self.cursor.state.prev(delta);
                 self.cursor.index -= 1;
@@ -255,6 +818,63 @@ impl ChessGame {
             None
         }
     }
+
+    /// Move the cursor to the position at the halfmove index, applying deltas
+    /// from the cursor or from the start, whichever is closer. Returns None
+    /// if the index is past the end of the game.
+    pub fn goto(&mut self, index: usize) -> Option<&Cursor> {
+        self.cursor.state = self.position_at(index)?;
+        self.cursor.index = index;
+        Some(&self.cursor)
+    }
+
+    /// The position at the halfmove index, applying deltas from the cursor
+    /// or from the start, whichever is closer, without moving the cursor.
+    /// Returns None if the index is past the end of the game.
+    pub fn position_at(&self, index: usize) -> Option<BoardState> {
+        if index > self.deltas.len() {
+            return None
+        }
+
+        let from = nearest([(0, self.start), (self.cursor.index, self.cursor.state)], index);
+        Some(walk(&self.deltas, from, index))
+    }
+
+    /// Move the cursor to the start position.
+    pub fn goto_start(&mut self) -> &Cursor {
+        self.cursor.state = self.start;
+        self.cursor.index = 0;
+        &self.cursor
+    }
+
+    /// Move the cursor to the last position.
+    pub fn goto_end(&mut self) -> &Cursor {
+        let _ = self.goto(self.deltas.len());
+        &self.cursor
+    }
+
+    /// Every position in the game with its halfmove index, from the start
+    /// position to the last, without moving the cursor.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, BoardState)> + '_ {
+        let after = self.deltas.iter().enumerate().scan(self.start, |state, (i, delta)| {
+            *state = state.next(*delta);
+            Some((i + 1, *state))
+        });
+
+        std::iter::once((0, self.start)).chain(after)
+    }
+
+    /// Every move in the game with the position it was played in and its
+    /// trace, which holds the route taken through any wormholes. Traces
+    /// are rebuilt by tracing each delta again, so iteration stops early
+    /// at a delta that is not legal in its position.
+    pub fn moves(&self) -> impl Iterator<Item = (BoardState, BoardDelta, MoveTrace)> + '_ {
+        self.positions().zip(&self.deltas).map_while(|((_, state), delta)| {
+            let (src, dst) = state.move_squares(delta);
+            let trace = state.trace(src, dst)?;
+            Some((state, *delta, trace))
+        })
+    }
 }
 
 impl Default for ChessGame {
@@ -262,12 +882,18 @@ impl Default for ChessGame {
         Self {
             start: BoardState::default(),
             cursor: Cursor::default(),
-            deltas: Vec::new(),
+            deltas: Deltas::new(),
             settings: GameSettings::default(),
             is_branch: None,
             game_id: 0,
             seed: 0,
             end: None,
+            meta: None,
+            annotations: BTreeMap::new(),
+            draw_offer: None,
+            events: Events::new(),
+            amendments: Vec::new(),
+            sliced_from: None,
         }
     }
 }
@@ -287,6 +913,22 @@ pub struct PlaySuccess {
     /// Use this to determine which wormholes to travel through,
     /// if any, when animating moves.
     pub trace: MoveTrace,
+
+    /// The end condition the move reached, which is also set as the
+    /// `end` of the game, or of the branch if one was created.
+    pub end: Option<EndCondition>,
+}
+
+impl PlaySuccess {
+    /// Whether the move put the opponent in check, for a "+" or a check sound.
+    pub fn gives_check(&self) -> bool {
+        self.trace.gives_check
+    }
+
+    /// Whether the move checkmated the opponent, for a "#".
+    pub fn gives_checkmate(&self) -> bool {
+        self.trace.gives_checkmate
+    }
 }
 
 pub enum PlayError {
@@ -298,9 +940,96 @@ pub enum PlayError {
     /// The move is illegal and cannot occur.
     InvalidMove,
 
+    /// The move is legal, but the move filter in the settings forbids it.
+    Forbidden,
+
     /// The move cannot be performed unless the
     /// "promote" argument is set to a valid piece.
     RequiresPromotion,
+
+    /// The delta's move is legal, but its other fields do not match
+    /// the delta that the move produces at the cursor.
+    InconsistentDelta,
+
+    /// The move would grow the game past a limit in the settings.
+    Limit(LimitError),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AnnotateError {
+    /// The halfmove index is past the last position of the game.
+    Index,
+
+    /// The game already has as many annotated positions as its limits allow.
+    Limit(LimitError),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DrawError {
+    /// The game has already ended.
+    GameEnded(EndCondition),
+
+    /// No draw has been offered.
+    NoOffer,
+
+    /// The last position does not allow the draw to be claimed.
+    NotClaimable,
+}
+
+/// Every Nth position of a game, so any position of a long game can be
+/// found by applying at most N / 2 deltas. The checkpoints describe the
+/// deltas they were built from, so rebuild them if earlier moves change;
+/// moves played after building are still reached from the last checkpoint.
+#[derive(Clone)]
+pub struct Checkpoints {
+    interval: usize,
+    states: Vec<BoardState>,
+}
+
+impl Checkpoints {
+    /// Record every `interval`th position of the game, treating 0 as 1.
+    pub fn new(game: &ChessGame, interval: usize) -> Self {
+        let interval = interval.max(1);
+        let states = game.positions().step_by(interval).map(|(_, state)| state).collect();
+        Self { interval, states }
+    }
+
+    /// The position at the halfmove index in the game, from the nearest
+    /// checkpoint or the game's cursor. Returns None if the index is past
+    /// the end of the game.
+    pub fn position_at(&self, game: &ChessGame, index: usize) -> Option<BoardState> {
+        if index > game.deltas.len() {
+            return None
+        }
+
+        let below = (index / self.interval).min(self.states.len().saturating_sub(1));
+        let mut candidates = vec![(game.cursor.index, game.cursor.state)];
+        candidates.extend(self.states.get(below).map(|state| (below * self.interval, *state)));
+        candidates.extend(self.states.get(below + 1).map(|state| ((below + 1) * self.interval, *state)));
+
+        let from = nearest(candidates, index);
+        Some(walk(&game.deltas, from, index))
+    }
+}
+
+/// The known position closest to the halfmove index.
+fn nearest(known: impl IntoIterator<Item = (usize, BoardState)>, index: usize) -> (usize, BoardState) {
+    known.into_iter().min_by_key(|(at, _)| at.abs_diff(index)).unwrap()
+}
+
+/// Apply deltas forward or backward from a known position to the halfmove index.
+fn walk(deltas: &Deltas, (mut at, mut state): (usize, BoardState), index: usize) -> BoardState {
+    while at < index {
+        state = state.next(deltas[at]);
+        at += 1;
+    }
+
+    while at > index {
+        at -= 1;
+        state = state.prev(deltas[at]);
+    }
+
+    state
 }
 
 #[derive(Copy, Clone)]
@@ -357,3 +1086,473 @@ pub struct Branch {
     /// The halfmove index of the source position in the parent.
     pub src_halfmoves: u16,
 }
+
+/// Where a game made by `ChessGame::slice` was cut from.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct Slice {
+    /// The ID of the game that was sliced.
+    pub parent_id: u64,
+
+    /// The halfmove index in the parent of the first position.
+    pub from: usize,
+
+    /// The halfmove index in the parent of the last position.
+    pub to: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AnnotateError, Checkpoints, ChessGame, DrawError, PlayError, Slice};
+    use crate::{audit::ReplayError, clock::MockClock, end::{EndCondition, GameResult}, pieces::Piece, settings::{ClockSettings, GameSettings}, square::Square, state::BoardState, team::Team, trace::MoveTrace};
+
+    fn no_early_queen(state: &BoardState, src: Square, _: Square, _: &MoveTrace) -> bool {
+        state.fullmoves >= 10 || state.pieces.piece_at(src) != Some(Piece::Queen)
+    }
+
+    #[test]
+    fn goto() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6").unwrap();
+        let positions: Vec<BoardState> = (0..=6).map(|i| game.goto(i).unwrap().state).collect();
+        assert!(game.goto(7).is_none());
+
+        for i in [5, 1, 6, 0, 3, 4, 2] {
+            assert!(game.goto(i).unwrap().state.is_repetition_of(&positions[i]));
+        }
+
+        game.goto(3);
+        assert_eq!(game.positions().count(), 7);
+        for (i, state) in game.positions() {
+            assert!(state.is_repetition_of(&positions[i]));
+        }
+        assert_eq!(game.cursor.index, 3);
+
+        assert_eq!(game.goto_end().index, 6);
+        assert!(game.prev().is_some());
+        assert_eq!(game.goto_start().index, 0);
+        assert!(game.cursor.state.is_repetition_of(&game.start));
+    }
+
+    #[test]
+    fn moves() {
+        // the rook enters the a2 wormhole and leaves through c4 to take on c7.
+        let start = BoardState::from_extended_fen("4k3/2n5/8/8/8/8/8/R3K3 w - - 0 1 a2,c4 - 0").unwrap();
+        let game = ChessGame::from_uci_moves(start, "a1c7 e8d8").unwrap();
+
+        let moves: Vec<_> = game.moves().collect();
+        assert_eq!(moves.len(), 2);
+        assert!(moves[0].0.is_repetition_of(&start));
+        assert_eq!(moves[0].1.to_uci(), "a1c7");
+        assert_eq!(moves[0].2.route, Some(("a2".parse().unwrap(), "c4".parse().unwrap())));
+        assert_eq!(moves[0].2.captures, Some(Piece::Knight));
+        assert!(moves[1].2.route.is_none());
+    }
+
+    #[test]
+    fn move_filter() {
+        let mut game = ChessGame::init_seeded(GameSettings {
+            move_filter: Some(no_early_queen),
+            ..GameSettings::default()
+        }, 0);
+
+        assert!(game.play_uci("e2e4").is_ok());
+        assert!(game.play_uci("e7e5").is_ok());
+
+        let queen: Square = "d1".parse().unwrap();
+        let h5: Square = "h5".parse().unwrap();
+        assert!(game.cursor.state.valid_moves(queen).has(h5));
+        assert!(game.valid_moves(queen).is_empty());
+        assert!(matches!(game.play_uci("d1h5"), Err(PlayError::Forbidden)));
+
+        // a forbidden move smuggled into the deltas fails the audit.
+        let state = game.cursor.state;
+        let trace = state.trace(queen, h5).unwrap();
+        game.deltas.push(state.delta(queen, h5, &trace, None).unwrap());
+        assert!(game.audit().is_err());
+        assert_eq!(game.validate(), Err(ReplayError::Forbidden(2)));
+    }
+
+    #[test]
+    fn play_delta() {
+        let time = MockClock::new(1_700_000_000_000);
+        let settings = GameSettings {
+            clock: Some(ClockSettings::starting_now(&time, 60, 0)),
+            ..GameSettings::default()
+        };
+        let mut local = ChessGame::init_seeded(settings, 0);
+        let mut remote = ChessGame::init_seeded(settings, 0);
+
+        time.advance(3_000);
+        let delta = local.play_timed("e2".parse().unwrap(), "e4".parse().unwrap(), None, &time).ok().unwrap().delta;
+        assert!(remote.play_delta(delta).is_ok());
+        assert_eq!(remote.deltas, local.deltas);
+        assert_eq!(remote.cursor.white_time, 57_000);
+
+        // a capture that did not happen, and a move from the wrong side.
+        let (e7, e5) = ("e7".parse().unwrap(), "e5".parse().unwrap());
+        let state = remote.cursor.state;
+        let reply = state.delta(e7, e5, &state.trace(e7, e5).unwrap(), None).unwrap();
+        let mut forged = reply;
+        forged.set_capture_pc(Piece::Pawn);
+        assert!(matches!(remote.play_delta(forged), Err(PlayError::InconsistentDelta)));
+        assert!(matches!(remote.play_delta(delta), Err(PlayError::InvalidMove)));
+        assert!(remote.play_delta(reply).is_ok());
+    }
+
+    #[test]
+    fn end_detection() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "f2f3 e7e5 g2g4").unwrap();
+        assert_eq!(game.play_uci("d8h4").ok().and_then(|success| success.end), Some(EndCondition::Checkmate));
+        assert_eq!(game.end, Some(EndCondition::Checkmate));
+        assert_eq!(game.result(), Some(GameResult::BlackWins));
+        assert!(matches!(game.play_uci("e1f2"), Err(PlayError::GameEnded(EndCondition::Checkmate))));
+
+        // a different last move from the position before mate is a branch that has not ended.
+        let mate = *game.deltas.last().unwrap();
+        game.prev();
+        let branch = game.play_uci("d8e7").ok().and_then(|success| success.branch).unwrap();
+        assert_eq!(branch.end, None);
+        assert_eq!(game.branch(mate).end, Some(EndCondition::Checkmate));
+
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1").unwrap();
+        assert_eq!(game.end, None);
+        assert_eq!(game.play_uci("f6g8").ok().and_then(|success| success.end), Some(EndCondition::Repetition));
+
+        // the knight takes the bishop, leaving a knight against a lone king.
+        let mut game = ChessGame::from_uci_moves(BoardState::from_fen("8/8/4k3/8/8/8/2n5/B6K b - - 0 1").unwrap(), "c2e3 h1g1").unwrap();
+        assert_eq!(game.end, None);
+        assert!(game.play_uci("e3c2").ok().is_some_and(|success| success.end.is_none()));
+        assert!(game.play_uci("g1h1").is_ok());
+        assert_eq!(game.play_uci("c2a1").ok().and_then(|success| success.end), Some(EndCondition::InsufficientMaterial));
+        assert_eq!(game.result().map(|result| result.to_pgn_result()), Some("1/2-1/2"));
+    }
+
+    #[test]
+    fn draw_offers() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4").unwrap();
+        assert_eq!(game.accept_draw(), Err(DrawError::NoOffer));
+        assert!(game.offer_draw(Team::Black).is_ok());
+        assert_eq!(game.decline_draw(), Ok(Team::Black));
+
+        // a move expires the offer.
+        assert!(game.offer_draw(Team::Black).is_ok());
+        assert!(game.play_uci("e7e5").is_ok());
+        assert_eq!(game.draw_offer, None);
+
+        // offering back accepts the other team's offer.
+        assert!(game.offer_draw(Team::White).is_ok());
+        assert!(game.offer_draw(Team::Black).is_ok());
+        assert_eq!(game.end, Some(EndCondition::Agreement));
+        assert_eq!(game.result(), Some(GameResult::Draw));
+        assert_eq!(game.offer_draw(Team::White), Err(DrawError::GameEnded(EndCondition::Agreement)));
+    }
+
+    #[test]
+    fn resign_and_abort() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3").unwrap();
+        game.goto(1);
+        assert_eq!(game.resign(Team::Black).ok(), Some(3));
+        assert_eq!(game.ended_at(), Some(3));
+        assert_eq!(game.result(), Some(GameResult::WhiteWins));
+        assert!(matches!(game.abort(), Err(PlayError::GameEnded(EndCondition::BlackResign))));
+
+        // moves can still be replayed, but not added.
+        assert!(game.play_uci("e7e5").is_ok());
+        assert!(game.play_uci("g1f3").is_ok());
+        assert!(matches!(game.play_uci("b8c6"), Err(PlayError::GameEnded(EndCondition::BlackResign))));
+
+        let mut game = ChessGame::default();
+        assert_eq!(game.abort().ok(), Some(0));
+        assert_eq!(game.result(), None);
+        assert_eq!(ChessGame::from_bytes(&game.to_bytes()).ok().and_then(|game| game.end), Some(EndCondition::Aborted));
+
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4").unwrap();
+        assert_eq!(game.abandon().ok(), Some(1));
+        assert_eq!(game.result(), Some(GameResult::WhiteWins));
+    }
+
+    #[test]
+    fn slice() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 f3g5").unwrap();
+        game.annotate(4).unwrap().comment = Some("the Italian".into());
+        game.annotate(6).unwrap().comment = Some("the Two Knights".into());
+        game.resign(Team::Black).ok().unwrap();
+
+        let slice = game.slice(4, 7).unwrap();
+        assert!(slice.start.is_repetition_of(&game.position_at(4).unwrap()));
+        assert_eq!((slice.start.fullmoves, slice.deltas.len()), (3, 3));
+        assert_eq!(slice.moves_uci(), "f1c4 g8f6 f3g5");
+        assert_eq!(slice.annotations.keys().copied().collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(slice.end, Some(EndCondition::BlackResign));
+        assert_eq!(slice.sliced_from, Some(Slice { parent_id: game.game_id, from: 4, to: 7 }));
+        assert!(slice.audit().is_ok());
+
+        let decoded = ChessGame::from_bytes(&slice.to_bytes()).ok().unwrap();
+        assert_eq!(decoded.sliced_from, slice.sliced_from);
+
+        assert_eq!(game.slice(2, 3).unwrap().end, None);
+        assert!(game.slice(3, 3).unwrap().deltas.is_empty());
+        assert!(game.slice(4, 3).is_none() && game.slice(0, 8).is_none());
+    }
+
+    #[test]
+    fn move_rules() {
+        let start = BoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 98 80").unwrap();
+        let mut game = ChessGame::from_uci_moves(start, "a1a2").unwrap();
+        assert!(!game.can_claim_fifty_moves());
+        assert_eq!(game.claim_fifty_moves(), Err(DrawError::NotClaimable));
+
+        game.play_uci("e8d8").ok().unwrap();
+        assert!(game.can_claim_fifty_moves());
+        assert_eq!(game.end, None);
+        game.goto(0);
+        assert_eq!(game.claim_fifty_moves(), Ok(()));
+        assert_eq!((game.end, game.result()), (Some(EndCondition::FiftyMoveRule), Some(GameResult::Draw)));
+        assert!(game.audit().is_ok());
+
+        // after 75 moves the game ends on its own, and the count survives undoing moves.
+        let start = BoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 148 80").unwrap();
+        let mut game = ChessGame::from_uci_moves(start, "a1a2").unwrap();
+        let success = game.play_uci("e8d8").ok().unwrap();
+        assert_eq!(success.end, Some(EndCondition::SeventyFiveMoveRule));
+        assert!(game.audit().is_ok());
+        assert_eq!(game.cursor.state.prev(game.deltas[1]).halfmoves, 149);
+        assert!(matches!(game.claim_fifty_moves(), Err(DrawError::GameEnded(EndCondition::SeventyFiveMoveRule))));
+    }
+
+    #[test]
+    fn timeouts() {
+        let time = MockClock::new(0);
+        let settings = GameSettings {
+            clock: Some(ClockSettings::starting_now(&time, 60, 0)),
+            ..GameSettings::default()
+        };
+
+        let mut game = ChessGame::init(settings);
+        time.advance(5_000);
+        game.play_timed("e2".parse().unwrap(), "e4".parse().unwrap(), None, &time).ok().unwrap();
+        time.advance(60_000);
+        assert_eq!(game.check_flag(&time), None);
+
+        // black's flag falls, and a move made afterwards is refused.
+        time.advance(1);
+        let mut late = game.clone();
+        assert_eq!(game.check_flag(&time), Some(EndCondition::Timeout));
+        assert_eq!(game.result(), Some(GameResult::WhiteWins));
+        assert!(game.audit().is_ok());
+        assert!(matches!(
+            late.play_timed("e7".parse().unwrap(), "e5".parse().unwrap(), None, &time),
+            Err(PlayError::GameEnded(EndCondition::Timeout))
+        ));
+        assert_eq!((late.deltas.len(), late.end), (1, Some(EndCondition::Timeout)));
+
+        // a lone king cannot win on time.
+        let state = BoardState::from_fen("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").unwrap();
+        assert_eq!(crate::end::timeout(&state), EndCondition::TimeoutVsInsufficientMaterial);
+        assert!(crate::end::can_mate(&state, Team::White));
+        let state = BoardState::from_fen("4k3/8/8/8/8/8/8/4KN2 b - - 0 1").unwrap();
+        assert_eq!(crate::end::timeout(&state), EndCondition::TimeoutVsInsufficientMaterial);
+        let state = BoardState::from_fen("4k3/4p3/8/8/8/8/8/4KN2 b - - 0 1").unwrap();
+        assert_eq!(crate::end::timeout(&state), EndCondition::Timeout);
+    }
+
+    #[test]
+    fn clock_history() {
+        assert!(ChessGame::default().clock_history().is_empty());
+
+        let time = MockClock::new(0);
+        let settings = GameSettings {
+            clock: Some(ClockSettings::starting_now(&time, 60, 2)),
+            ..GameSettings::default()
+        };
+
+        let mut game = ChessGame::init(settings);
+        for (ms, src, dst) in [(5_000, "e2", "e4"), (3_000, "e7", "e5"), (10_000, "g1", "f3")] {
+            time.advance(ms);
+            game.play_timed(src.parse().unwrap(), dst.parse().unwrap(), None, &time).ok().unwrap();
+        }
+
+        // each move adds the two second bonus to the clock of the team that moved.
+        assert_eq!(game.clock_history(), vec![
+            (0, 60_000, 60_000),
+            (1, 57_000, 60_000),
+            (2, 57_000, 59_000),
+            (3, 49_000, 59_000),
+        ]);
+    }
+
+    #[test]
+    fn from_moves() {
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+        let mut moves = vec![(sq("e2"), sq("e4"), None), (sq("e7"), sq("e5"), None), (sq("d1"), sq("h5"), None)];
+        let game = ChessGame::from_moves(GameSettings::default(), &moves).ok().unwrap();
+        assert_eq!(game.moves_uci(), "e2e4 e7e5 d1h5");
+
+        moves.push((sq("e8"), sq("e6"), None));
+        assert_eq!(ChessGame::from_moves(GameSettings::default(), &moves).err(), Some(3));
+
+        let filtered = GameSettings { move_filter: Some(no_early_queen), ..GameSettings::default() };
+        assert_eq!(ChessGame::from_moves(filtered, &moves).err(), Some(2));
+    }
+
+    #[test]
+    fn position_at() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6").unwrap();
+        let positions: Vec<BoardState> = game.positions().map(|(_, state)| state).collect();
+        game.goto(3);
+
+        let checkpoints = Checkpoints::new(&game, 3);
+        for (i, state) in positions.iter().enumerate() {
+            assert!(game.position_at(i).unwrap().is_repetition_of(state));
+            assert!(checkpoints.position_at(&game, i).unwrap().is_repetition_of(state));
+        }
+
+        assert_eq!(game.cursor.index, 3);
+        assert!(game.position_at(9).is_none());
+        assert!(checkpoints.position_at(&game, 9).is_none());
+
+        // moves played after the checkpoints were built are still found.
+        game.goto_end();
+        assert!(game.play_uci("e1g1").is_ok());
+        assert!(checkpoints.position_at(&game, 9).unwrap().is_repetition_of(&game.cursor.state));
+    }
+
+    #[test]
+    fn limits() {
+        use crate::settings::{GameLimits, GameSettings, LimitError};
+
+        let limits = GameLimits { max_plies: Some(2), max_annotations: Some(1), ..GameLimits::default() };
+        let mut game = ChessGame::init(GameSettings { limits, ..GameSettings::default() });
+        assert!(game.play_uci("e2e4").is_ok());
+        assert!(game.play_uci("e7e5").is_ok());
+        assert!(matches!(game.play_uci("g1f3"), Err(PlayError::Limit(LimitError::Plies))));
+        assert_eq!(game.deltas.len(), 2);
+
+        // replaying a move that is already in the game does not grow it.
+        game.goto(1);
+        assert!(game.play_uci("e7e5").is_ok());
+
+        assert!(game.annotate(1).is_ok());
+        assert!(game.annotate(1).is_ok());
+        assert_eq!(game.annotate(2).err(), Some(AnnotateError::Limit(LimitError::Annotations)));
+    }
+
+    #[test]
+    fn check_flags() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 d1h5 b8c6 f1c4 g8f6").unwrap();
+        let check = game.play_uci("h5f7").ok().unwrap();
+        assert!(check.gives_check() && check.gives_checkmate());
+        assert!(check.delta.is_check() && !check.delta.was_check());
+        assert_eq!(check.end, Some(EndCondition::Checkmate));
+        assert!(game.cursor.state.is_check);
+
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 f7f6").unwrap();
+        assert!(!game.play_uci("d2d4").ok().unwrap().gives_check());
+        assert!(game.play_uci("a7a6").is_ok());
+        let check = game.play_uci("d1h5").ok().unwrap();
+        assert!(check.gives_check() && !check.gives_checkmate());
+        assert!(game.play_uci("g7g6").ok().unwrap().delta.was_check());
+        assert_eq!(game.position_at(4).unwrap().to_san(check.delta), "Qh5+");
+    }
+
+    #[test]
+    fn positions() {
+        let empty = ChessGame::from_uci_moves(crate::positions::wormhole_start(), "").unwrap();
+        let only: Vec<(usize, BoardState)> = empty.positions().collect();
+        assert_eq!(only.len(), 1);
+        assert_eq!(only[0].0, 0);
+        assert!(only[0].1.is_repetition_of(&crate::positions::wormhole_start()));
+
+        let mut game = ChessGame::from_uci_moves(crate::positions::wormhole_start(), "h2h4 a7a6 h4h5 g7g5 h5g6 h7g6").unwrap();
+        game.goto(2);
+        let mut count = 0;
+        for (i, state) in game.positions() {
+            assert_eq!(i, count);
+            assert!(state.is_repetition_of(&game.position_at(i).unwrap()));
+            assert_eq!(state.wormholes.count(), 4);
+            assert_eq!(state.turn, if i % 2 == 0 { Team::White } else { Team::Black });
+            count += 1;
+        }
+
+        assert_eq!(count, 7);
+        assert_eq!(game.cursor.index, 2);
+        assert!(game.positions().nth(4).unwrap().1.en_passant.is_some());
+        assert!(game.positions().last().unwrap().1.is_repetition_of(&game.goto_end().state));
+    }
+
+    #[test]
+    fn moves_uci() {
+        assert_eq!(ChessGame::from_uci_moves(BoardState::default(), "").unwrap().moves_uci(), "");
+
+        // castling is written as the king's move, however it was entered.
+        let mut game = ChessGame::from_uci_moves(crate::positions::kiwipete(), "").unwrap();
+        assert!(game.play_uci("e1h1").is_ok());
+        assert!(game.play_san("O-O-O").is_ok());
+        assert!(game.play_uci("d5e6").is_ok());
+        assert!(game.play_uci("b4b3").is_ok());
+        assert!(game.play_uci("e6f7").is_ok());
+        assert!(game.play_uci("b3a2").is_ok());
+        assert!(game.play_uci("f7f8n").is_ok());
+        let moves = "e1g1 e8c8 d5e6 b4b3 e6f7 b3a2 f7f8n";
+        assert_eq!(game.moves_uci(), moves);
+
+        // every move is listed wherever the cursor is, and the list replays the game.
+        game.goto(2);
+        assert_eq!(game.moves_uci(), moves);
+        let replayed = ChessGame::from_uci_moves(crate::positions::kiwipete(), &game.moves_uci()).unwrap();
+        assert!(replayed.cursor.state.is_repetition_of(&game.position_at(7).unwrap()));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing() {
+        use std::{fmt::Debug, sync::{Arc, Mutex}};
+        use tracing::{field::{Field, Visit}, span, Event, Metadata, Subscriber};
+
+        #[derive(Default)]
+        struct Message(String);
+
+        impl Visit for Message {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{value:?}");
+                }
+            }
+        }
+
+        /// Collects the message of every event.
+        #[derive(Clone, Default)]
+        struct Messages(Arc<Mutex<Vec<String>>>);
+
+        impl Subscriber for Messages {
+            fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+            fn new_span(&self, _: &span::Attributes<'_>) -> span::Id { span::Id::from_u64(1) }
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+            fn enter(&self, _: &span::Id) {}
+            fn exit(&self, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut message = Message::default();
+                event.record(&mut message);
+                self.0.lock().unwrap().push(message.0);
+            }
+        }
+
+        let messages = Messages::default();
+        tracing::subscriber::with_default(messages.clone(), || {
+            let mut game = ChessGame::init(GameSettings::default());
+            assert!(game.play_uci("e2e4").is_ok());
+            assert!(game.play_uci("e7e4").is_err());
+            assert!(game.resign(Team::Black).is_ok());
+            assert!(game.play_uci("e7e5").is_err());
+        });
+
+        assert_eq!(*messages.0.lock().unwrap(), [
+            "game created",
+            "move applied",
+            "move rejected, invalid move",
+            "game ended",
+            "move rejected, game has ended",
+        ]);
+    }
+}
@@ -0,0 +1,39 @@
+//! Board dimensions.
+//!
+//! Only the standard 8x8 board is supported. A BitBoard is a u64 with
+//! one bit per square, and the cached and magic tables are built for
+//! that layout, so changing these constants does not give a 10x8 board;
+//! that would take a wider BitBoard and tables for each board size.
+//! The constants only name what the tables and lookups count, instead
+//! of repeating the literals.
+
+/// The number of files (columns) on the board.
+pub const FILES: u8 = 8;
+
+/// The number of ranks (rows) on the board.
+pub const RANKS: u8 = 8;
+
+/// The number of squares on the board.
+pub const SQUARES: usize = FILES as usize * RANKS as usize;
+
+#[cfg(test)]
+mod tests {
+    use super::{FILES, RANKS, SQUARES};
+    use crate::{board::BitBoard, square::Square};
+
+    #[test]
+    fn squares_cover_the_board() {
+        // every square is one bit of a BitBoard.
+        assert_eq!(BitBoard(!0).count(), SQUARES);
+        assert_eq!(Square::try_from_index(SQUARES), None);
+
+        for i in 0..SQUARES {
+            let sq = Square::from_index(i);
+            assert_eq!(Square::try_new(sq.rank_u8(), sq.file_u8()), Some(sq));
+        }
+
+        assert_eq!(Square::try_new(RANKS, 0), None);
+        assert_eq!(Square::try_new(0, FILES), None);
+        assert_eq!(Square::from_index(SQUARES - 1).to_string(), "h8");
+    }
+}
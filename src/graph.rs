@@ -0,0 +1,121 @@
+//! Exports of the variation tree's shape, for rendering tree diagrams.
+//!
+//! Every position in the tree is a node, named by its line and its
+//! halfmove index in the line as `line:index`. Each node is labeled
+//! with the SAN of the move that reached it and the static evaluation
+//! from white's view. The start of the mainline has no move.
+
+use std::fmt::Write;
+
+use crate::{delta::BoardDelta, search::ScoreKind, state::BoardState, team::Team, tree::GameTree};
+
+/// Write the tree in the Graphviz DOT language. Moves along a line are
+/// solid edges, and the first move of a variation is a dashed edge.
+pub fn to_dot(tree: &GameTree) -> String {
+    let walk = Walk::new(tree);
+    let mut dot = String::from("digraph tree {\n    node [shape=box];\n");
+    walk.dot(0, 0, &mut dot);
+    dot.push_str("}\n");
+    dot
+}
+
+/// Write the tree as nested JSON objects, starting with the start
+/// position of the mainline. Each node has its `line`, `index`, `san`
+/// (null at the root), `eval`, `fen`, and `children`. The continuation
+/// along the same line comes first among the children.
+pub fn to_json_tree(tree: &GameTree) -> String {
+    let walk = Walk::new(tree);
+    let mut json = String::new();
+    walk.json(0, 0, &mut json);
+    json
+}
+
+struct Walk<'a> {
+    tree: &'a GameTree,
+
+    /// Every position of every line, by line and then index.
+    positions: Vec<Vec<BoardState>>,
+}
+
+impl<'a> Walk<'a> {
+    fn new(tree: &'a GameTree) -> Self {
+        let positions = tree.games()
+            .iter()
+            .map(|game| game.positions().map(|(_, state)| state).collect())
+            .collect();
+
+        Self { tree, positions }
+    }
+
+    /// The continuation along the line, then the variations from the node.
+    fn children(&self, line: usize, index: usize) -> Vec<(usize, usize)> {
+        let mut children = Vec::new();
+        if index < self.tree.games()[line].deltas.len() {
+            children.push((line, index + 1));
+        }
+
+        children.extend(self.tree.variations(line, index).into_iter().map(|branch| (branch, 0)));
+        children
+    }
+
+    /// The position and delta of the move that reached the node.
+    fn incoming(&self, line: usize, index: usize) -> Option<(BoardState, BoardDelta)> {
+        if index > 0 {
+            return Some((self.positions[line][index - 1], self.tree.games()[line].deltas[index - 1]))
+        }
+
+        let info = self.tree.games()[line].is_branch?;
+        let parent = self.tree.parent(line)?;
+        Some((self.positions[parent][info.src_index], info.delta))
+    }
+
+    /// The SAN of the move that reached the node, with its move number.
+    fn san(&self, line: usize, index: usize) -> Option<String> {
+        let (state, delta) = self.incoming(line, index)?;
+        let dots = match state.turn {
+            Team::White => ".",
+            Team::Black => "...",
+        };
+
+        Some(format!("{}{dots} {}", state.fullmoves, state.to_san(delta)))
+    }
+
+    fn eval(&self, line: usize, index: usize) -> ScoreKind {
+        let state = &self.positions[line][index];
+        let eval = ScoreKind::Centipawns(state.evaluate());
+        match state.turn {
+            Team::White => eval,
+            Team::Black => -eval,
+        }
+    }
+
+    fn dot(&self, line: usize, index: usize, dot: &mut String) {
+        let label = self.san(line, index).unwrap_or_else(|| "start".to_string());
+        let _ = writeln!(dot, "    \"{line}:{index}\" [label=\"{label}\\n{}\"];", self.eval(line, index));
+
+        for (child_line, child_index) in self.children(line, index) {
+            let style = if child_line == line { "" } else { " [style=dashed]" };
+            let _ = writeln!(dot, "    \"{line}:{index}\" -> \"{child_line}:{child_index}\"{style};");
+            self.dot(child_line, child_index, dot);
+        }
+    }
+
+    fn json(&self, line: usize, index: usize, json: &mut String) {
+        let san = self.san(line, index).map_or("null".to_string(), |san| format!("\"{san}\""));
+        let _ = write!(
+            json,
+            "{{\"line\":{line},\"index\":{index},\"san\":{san},\"eval\":\"{}\",\"fen\":\"{}\",\"children\":[",
+            self.eval(line, index),
+            self.positions[line][index].to_extended_fen(),
+        );
+
+        for (i, (child_line, child_index)) in self.children(line, index).into_iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            self.json(child_line, child_index, json);
+        }
+
+        json.push_str("]}");
+    }
+}
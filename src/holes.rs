@@ -0,0 +1,161 @@
+//! Precomputed attacks out of wormholes.
+//!
+//! With the `cache-stats` feature, lookups of the cache kept by
+//! BoardState are counted per thread, see `cache_stats`.
+
+#[cfg(feature = "cache-stats")]
+use std::cell::Cell;
+
+use crate::{board::BitBoard, team::Team};
+
+#[cfg(feature = "cache-stats")]
+thread_local! {
+    static STATS: Cell<CacheStats> = const { Cell::new(CacheStats { hits: 0, misses: 0 }) };
+}
+
+/// The squares reachable from any wormhole by each stepping piece,
+/// excluding the wormholes themselves.
+///
+/// These only depend on where the wormholes are, so BoardState keeps
+/// one for its current wormholes and rebuilds it when a hole spawns.
+/// Sliding pieces depend on occupancy and are not included.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct HoleExits {
+    /// The wormholes these exits were computed for.
+    pub wormholes: BitBoard,
+
+    pub king: BitBoard,
+    pub knight: BitBoard,
+    pub white_pawn: BitBoard,
+    pub black_pawn: BitBoard,
+}
+
+impl HoleExits {
+    pub fn new(wormholes: BitBoard) -> Self {
+        let mut exits = Self {
+            wormholes,
+            ..Self::default()
+        };
+
+        for out_sq in wormholes {
+            exits.king |= out_sq.king_moves();
+            exits.knight |= out_sq.knight_moves();
+        }
+
+        exits.king &= !wormholes;
+        exits.knight &= !wormholes;
+        exits.white_pawn = wormholes.pawn_captures(Team::White) & !wormholes;
+        exits.black_pawn = wormholes.pawn_captures(Team::Black) & !wormholes;
+        exits
+    }
+
+    /// Squares a pawn of this team attacks from any wormhole.
+    pub fn pawn(&self, team: Team) -> BitBoard {
+        match team {
+            Team::White => self.white_pawn,
+            Team::Black => self.black_pawn,
+        }
+    }
+}
+
+/// How often `BoardState::hole_exits` found its cache built for the
+/// wormholes asked for. Counted per thread, so the numbers of a server's
+/// worker threads do not contend with each other. Only counted with
+/// the `cache-stats` feature.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// The share of lookups that hit, or 1 if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 1.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+/// The cache statistics of this thread since it started or was reset.
+#[cfg(feature = "cache-stats")]
+pub fn cache_stats() -> CacheStats {
+    STATS.with(Cell::get)
+}
+
+/// Reset the cache statistics of this thread, returning what they were.
+#[cfg(feature = "cache-stats")]
+pub fn reset_cache_stats() -> CacheStats {
+    STATS.with(|stats| stats.replace(CacheStats::default()))
+}
+
+#[cfg(feature = "cache-stats")]
+pub(crate) fn record(hit: bool) {
+    STATS.with(|stats| {
+        let mut counts = stats.get();
+        if hit {
+            counts.hits += 1;
+        } else {
+            counts.misses += 1;
+        }
+
+        stats.set(counts);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HoleExits;
+    use crate::{board::BitBoard, square::Square, state::BoardState, team::Team};
+
+    #[test]
+    fn matches_per_hole_union() {
+        let wormholes = BitBoard::from(Square::from((3, 3))) | Square::from((5, 6)) | Square::from((0, 0));
+        let exits = HoleExits::new(wormholes);
+
+        let mut king = BitBoard::new();
+        let mut knight = BitBoard::new();
+        let mut pawn = BitBoard::new();
+        for out_sq in wormholes {
+            king |= out_sq.king_moves() & !wormholes;
+            knight |= out_sq.knight_moves() & !wormholes;
+            pawn |= out_sq.pawn_captures(Team::Black) & !wormholes;
+        }
+
+        assert_eq!(exits.king, king);
+        assert_eq!(exits.knight, knight);
+        assert_eq!(exits.pawn(Team::Black), pawn);
+    }
+
+    #[test]
+    fn cache() {
+        let mut state = BoardState::from_extended_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 a2,c4 - 0").unwrap();
+        assert_eq!(state.hole_exits(state.wormholes), HoleExits::new(state.wormholes));
+
+        // a stale cache is not used, and is rebuilt when invalidated.
+        state.wormholes.set(Square::from((5, 5)));
+        assert_eq!(state.hole_exits(state.wormholes), HoleExits::new(state.wormholes));
+        state.invalidate_cache();
+        assert_eq!(state.hole_exits(state.wormholes), HoleExits::new(state.wormholes));
+    }
+
+    #[test]
+    #[cfg(feature = "cache-stats")]
+    fn cache_stats() {
+        use super::CacheStats;
+
+        let mut state = BoardState::from_extended_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 a2,c4 - 0").unwrap();
+        super::reset_cache_stats();
+        state.hole_exits(state.wormholes);
+        assert_eq!(super::cache_stats(), CacheStats { hits: 1, misses: 0 });
+
+        // a stale cache misses until it is rebuilt.
+        state.wormholes.set(Square::from((5, 5)));
+        state.hole_exits(state.wormholes);
+        state.invalidate_cache();
+        state.hole_exits(state.wormholes);
+        assert_eq!(super::reset_cache_stats(), CacheStats { hits: 2, misses: 1 });
+        assert_eq!(super::cache_stats().hit_rate(), 1.0);
+    }
+}
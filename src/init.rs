@@ -1,28 +1,19 @@
 
-use crate::{board::BitBoard, castle::{Castle, CastleRights}, pieces::{Piece, Pieces}, rng::WyRand, state::BoardState, team::Team};
+use crate::{board::BitBoard, castle::{Castle, CastleRights}, pieces::{Piece, Pieces}, holes::HoleExits, rng::WyRand, settings::{WormholeSettings, WormholeSpawnMode}, square::Square, state::BoardState, team::Team};
 
 pub fn init_chess960(rng: &mut WyRand) -> BoardState {
     let mut indices = [0, 1, 2, 3, 4, 5, 6, 7];
     rng.shuffle(&mut indices);
 
     // bishops must be on opposite color squares.
-    for i in 0..7 {
-        if indices[5] & 1 == indices[6] & 1 {
-            indices.swap(i, 5)
-        } else {
-            break;
+    if indices[6] & 1 == indices[7] & 1 {
+        if let Some(i) = (0..6).find(|&i| indices[i] & 1 != indices[6] & 1) {
+            indices.swap(i, 7);
         }
     }
 
-    // king must be to the right of the long rook.
-    if indices[0] > indices[1] {
-        indices.swap(0, 1);
-    }
-
-    // king must be to the left of the short rook.
-    if indices[1] > indices[2] {
-        indices.swap(1, 2);
-    }
+    // king must be between the long and short rooks.
+    indices[0..3].sort_unstable();
 
     let mut pieces = Pieces::just_pawns();
     pieces.setup_from_file(Piece::Rook, indices[0]);
@@ -50,5 +41,96 @@ pub fn init_chess960(rng: &mut WyRand) -> BoardState {
         pieces,
         castle,
         turn: Team::White,
+        exits: HoleExits::default(),
+    }
+}
+
+/// Place the wormholes that exist at the start of the game on empty
+/// squares of the middle four ranks. Random mode scatters them, Mirror
+/// mode places them in pairs reflected across the middle of the board,
+/// rounding an odd count down to whole pairs, and Manual mode leaves the
+/// board for the players to fill.
+pub fn place_wormholes(state: &mut BoardState, settings: &WormholeSettings, rng: &mut WyRand) {
+    let lower = BitBoard::new().with_rank_u8(2).with_rank_u8(3);
+    let mut free = (lower | lower.flip_vertical()) & !state.pieces.occupied() & !state.wormholes;
+
+    let count = match settings.spawn_mode {
+        WormholeSpawnMode::Mirror => settings.start_count & !1,
+        _ => settings.start_count,
+    };
+
+    let mut placed = 0;
+    while placed < count && !free.is_empty() {
+        let candidates: Vec<Square> = match settings.spawn_mode {
+            WormholeSpawnMode::Manual => return,
+            WormholeSpawnMode::Random => free.into_iter().collect(),
+            WormholeSpawnMode::Mirror => (free & lower & free.flip_vertical()).into_iter().collect(),
+        };
+
+        if candidates.is_empty() {
+            break;
+        }
+
+        let sq = candidates[rng.range(0..candidates.len()) as usize];
+        let mut holes = BitBoard::from(sq);
+        if settings.spawn_mode == WormholeSpawnMode::Mirror {
+            holes |= holes.flip_vertical();
+        }
+
+        state.wormholes |= holes;
+        free &= !holes;
+        placed += holes.count() as u8;
+    }
+
+    state.exits = HoleExits::new(state.wormholes);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{castle::Castle, game::ChessGame, pieces::Piece, rng::WyRand, settings::{GameSettings, WormholeSettings, WormholeSpawnMode}, state::BoardState, team::Team};
+
+    #[test]
+    fn chess960_positions_are_valid() {
+        let mut rng = WyRand { seed: 960 };
+        for _ in 0..500 {
+            let state = super::init_chess960(&mut rng);
+            let bishops = state.pieces.get(Piece::Bishop, Team::White);
+            assert_eq!(bishops.into_iter().map(|sq| sq.file_u8() & 1).sum::<u8>(), 1);
+
+            let king = state.castle.king_start(Team::White).file_u8();
+            assert!(state.castle.rook_start(Castle::Long, Team::White).file_u8() < king);
+            assert!(state.castle.rook_start(Castle::Short, Team::White).file_u8() > king);
+            assert!(BoardState::from_fen(&state.to_fen()).is_ok());
+        }
+    }
+
+    #[test]
+    fn starting_wormholes() {
+        let mut rng = WyRand { seed: 63 };
+        for (spawn_mode, start_count, expected) in [
+            (WormholeSpawnMode::Random, 3, 3),
+            (WormholeSpawnMode::Mirror, 4, 4),
+            (WormholeSpawnMode::Mirror, 3, 2),
+            (WormholeSpawnMode::Manual, 4, 0),
+        ] {
+            let settings = WormholeSettings { spawn_mode, start_count, ..WormholeSettings::default() };
+            let mut state = BoardState::default();
+            super::place_wormholes(&mut state, &settings, &mut rng);
+
+            assert_eq!(state.wormholes.count(), expected, "{spawn_mode:?}");
+            assert!(!state.wormholes.intersects(state.pieces.occupied()));
+            if spawn_mode == WormholeSpawnMode::Mirror {
+                assert_eq!(state.wormholes, state.wormholes.flip_vertical());
+            }
+        }
+
+        // games start with the holes, and the same seed places the same holes.
+        let settings = GameSettings {
+            wormhole: WormholeSettings { start_count: 2, ..WormholeSettings::default() },
+            ..GameSettings::default()
+        };
+        let game = ChessGame::init_seeded(settings, 5);
+        assert_eq!(game.start.wormholes.count(), 2);
+        assert_eq!(ChessGame::init_seeded(settings, 5).start.wormholes, game.start.wormholes);
     }
-}
\ No newline at end of file
+}
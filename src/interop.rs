@@ -0,0 +1,147 @@
+//! Conversions to and from the `shakmaty` crate, behind the `interop` feature.
+//!
+//! Squares, teams, and pieces convert both ways. shakmaty has no
+//! wormholes or fairy pieces, so only positions without wormholes,
+//! without a hole about to spawn, and without archbishops or
+//! chancellors convert to a `shakmaty::Chess`. Positions are passed
+//! through FEN, so Chess960 castling rights are kept.
+
+use shakmaty::{CastlingMode, Chess, Color, EnPassantMode, Role, fen::Fen};
+
+use crate::{fen::FenError, pieces::Piece, square::Square, state::BoardState, team::Team};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum InteropError {
+    /// The position has wormholes, which shakmaty cannot represent.
+    Wormholes,
+
+    /// The piece, or a piece in the position, is an archbishop or a
+    /// chancellor, which shakmaty cannot represent.
+    FairyPiece,
+
+    /// shakmaty rejected the position.
+    Position,
+
+    /// The position from shakmaty could not be read.
+    Fen(FenError),
+}
+
+impl From<Square> for shakmaty::Square {
+    fn from(sq: Square) -> Self {
+        shakmaty::Square::new(sq.to_index() as u32)
+    }
+}
+
+impl From<shakmaty::Square> for Square {
+    fn from(sq: shakmaty::Square) -> Self {
+        Square::from_index(sq.to_usize())
+    }
+}
+
+impl From<Team> for Color {
+    fn from(team: Team) -> Self {
+        match team {
+            Team::White => Color::White,
+            Team::Black => Color::Black,
+        }
+    }
+}
+
+impl From<Color> for Team {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::White => Team::White,
+            Color::Black => Team::Black,
+        }
+    }
+}
+
+impl TryFrom<Piece> for Role {
+    type Error = InteropError;
+
+    fn try_from(piece: Piece) -> Result<Self, Self::Error> {
+        Ok(match piece {
+            Piece::Bishop => Role::Bishop,
+            Piece::Knight => Role::Knight,
+            Piece::Queen => Role::Queen,
+            Piece::King => Role::King,
+            Piece::Rook => Role::Rook,
+            Piece::Pawn => Role::Pawn,
+            Piece::Archbishop | Piece::Chancellor => return Err(InteropError::FairyPiece),
+        })
+    }
+}
+
+impl From<Role> for Piece {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Bishop => Piece::Bishop,
+            Role::Knight => Piece::Knight,
+            Role::Queen => Piece::Queen,
+            Role::King => Piece::King,
+            Role::Rook => Piece::Rook,
+            Role::Pawn => Piece::Pawn,
+        }
+    }
+}
+
+impl TryFrom<&BoardState> for Chess {
+    type Error = InteropError;
+
+    fn try_from(state: &BoardState) -> Result<Self, Self::Error> {
+        if !state.wormholes.is_empty() || state.next_hole.is_some() {
+            return Err(InteropError::Wormholes)
+        }
+
+        if !(state.pieces.archbishops | state.pieces.chancellors).is_empty() {
+            return Err(InteropError::FairyPiece)
+        }
+
+        let fen: Fen = state.to_shredder_fen().parse().map_err(|_| InteropError::Position)?;
+        let mode = CastlingMode::detect(fen.as_setup());
+        fen.into_position(mode).map_err(|_| InteropError::Position)
+    }
+}
+
+impl TryFrom<&Chess> for BoardState {
+    type Error = InteropError;
+
+    fn try_from(pos: &Chess) -> Result<Self, Self::Error> {
+        let fen = Fen::from_position(pos, EnPassantMode::Legal).to_string();
+        BoardState::from_fen(&fen).map_err(InteropError::Fen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shakmaty::{Chess, Color, Position, Role};
+
+    use super::InteropError;
+    use crate::{pieces::Piece, square::Square, state::BoardState, team::Team};
+
+    #[test]
+    fn convert() {
+        let e4: Square = "e4".parse().unwrap();
+        assert_eq!(shakmaty::Square::from(e4), shakmaty::Square::E4);
+        assert_eq!(Square::from(shakmaty::Square::H8), "h8".parse().unwrap());
+        assert_eq!(Color::from(Team::Black), Color::Black);
+        assert_eq!(Role::try_from(Piece::Knight).map(Piece::from), Ok(Piece::Knight));
+        assert_eq!(Role::try_from(Piece::Chancellor), Err(InteropError::FairyPiece));
+
+        for fen in [
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3",
+            "bqnbrkrn/pppppppp/8/8/8/8/PPPPPPPP/BQNBRKRN w GEge - 0 1",
+        ] {
+            let state = BoardState::from_fen(fen).unwrap();
+            let pos = Chess::try_from(&state).unwrap();
+            assert_eq!(pos.legal_moves().len(), state.legal_moves().len());
+            assert!(BoardState::try_from(&pos).unwrap().is_repetition_of(&state));
+        }
+
+        let holes = BoardState::from_extended_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 a2,c4 - 0").unwrap();
+        assert_eq!(Chess::try_from(&holes).err(), Some(InteropError::Wormholes));
+
+        let fairy = BoardState::from_fen("4k3/8/8/8/8/8/8/3AK3 w - - 0 1").unwrap();
+        assert_eq!(Chess::try_from(&fairy).err(), Some(InteropError::FairyPiece));
+    }
+}
@@ -1,5 +1,6 @@
 
 pub mod slide_table;
+pub mod geometry;
 pub mod blockable;
 pub mod settings;
 pub mod defense;
@@ -12,6 +13,7 @@ pub mod board;
 pub mod state;
 pub mod trace;
 pub mod delta;
+pub mod deltas;
 pub mod magic;
 pub mod team;
 pub mod init;
@@ -19,6 +21,50 @@ pub mod game;
 pub mod ray;
 pub mod end;
 pub mod rng;
+pub mod stats;
+pub mod audit;
+pub mod uci;
+pub mod address;
+pub mod threat;
+pub mod see;
+pub mod symmetry;
+pub mod fen;
+pub mod pgn;
+pub mod holes;
+pub mod san;
+pub mod binary;
+pub mod clock;
+pub mod persist;
+pub mod sim;
+pub mod code;
+pub mod forcing;
+pub mod meta;
+pub mod annotation;
+pub mod phase;
+pub mod eval;
+pub mod tree;
+pub mod search;
+pub mod puzzle;
+pub mod tablebase;
+pub mod registry;
+pub mod graph;
+pub mod study;
+pub mod svg;
+pub mod srs;
+pub mod training;
+pub mod path;
+pub mod files;
+pub mod endgame;
+pub mod events;
+pub mod amend;
+pub mod analysis;
+pub mod memory;
+pub mod migrate;
+pub mod positions;
+pub mod session;
+
+#[cfg(feature = "interop")]
+pub mod interop;
 
 pub mod prelude {
     pub use crate::{
@@ -27,9 +73,11 @@ pub mod prelude {
         castle::{CastleRights, Castle, CastleSettings},
         pieces::{Piece, Pieces},
         square::Square,
-        settings::{GameSettings, ClockSettings, WormholeSettings, WormholeSpawnMode},
+        clock::{TimeSource, SystemClock, MonotonicClock},
+        settings::{GameSettings, ClockSettings, MoveFilter, WormholeSettings, WormholeSpawnMode},
         trace::MoveTrace,
         end::EndCondition,
+        meta::GameMeta,
         team::Team,
     };
 }
\ No newline at end of file
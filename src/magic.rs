@@ -1,4 +1,26 @@
+//! Black magic bitboards for sliding piece moves.
+//!
+//! # Table Layout
+//! Each square has a `BlackMagicEntry` for rooks and one for bishops.
+//! The index of the moves for a square with some occupancy is
+//!
+//! ```text
+//! offset + (((occupied | negate) * magic) >> (64 - width))
+//! ```
+//!
+//! where `negate` is the complement of the relevant blockers (the squares
+//! the piece could move to, excluding the board edge), and `width` is
+//! `ROOK_INDEX_WIDTH` (12) or `BISHOP_INDEX_WIDTH` (9). Setting every
+//! irrelevant bit is what makes these "black" magics: it lets the built-in
+//! rook and bishop entries share overlapping ranges of `SLIDING_MOVES`,
+//! which is why the offsets are not multiples of the table size.
+//!
+//! Tables generated by `find_magics` do not overlap. Each square gets its own
+//! range of `1 << width` moves, which is larger but much easier to find.
+
 use crate::board::BitBoard;
+use crate::geometry::SQUARES;
+use crate::rng::WyRand;
 use crate::square::Square;
 
 pub const fn get_rook_moves(sq: Square, occupied: BitBoard) -> BitBoard {
@@ -10,11 +32,11 @@ pub const fn get_bishop_moves(sq: Square, occupied: BitBoard) -> BitBoard {
 }
 
 const fn get_rook_move_index(sq: Square, occupied: BitBoard) -> usize {
-    magic_index(ROOK_MAGICS, ROOK_INDEX_WIDTH, sq, occupied) as usize
+    magic_index(ROOK_MAGICS, ROOK_INDEX_WIDTH, sq, occupied)
 }
 
 const fn get_bishop_move_index(sq: Square, occupied: BitBoard) -> usize {
-    magic_index(BISHOP_MAGICS, BISHOP_INDEX_WIDTH, sq, occupied) as usize
+    magic_index(BISHOP_MAGICS, BISHOP_INDEX_WIDTH, sq, occupied)
 }
 
 const fn magic_index(magics: &[BlackMagicEntry], index_width: usize, sq: Square, occupied: BitBoard) -> usize {
@@ -24,10 +46,16 @@ const fn magic_index(magics: &[BlackMagicEntry], index_width: usize, sq: Square,
     magic.offset as usize + (hash >> (64 - index_width)) as usize
 }
 
-struct BlackMagicEntry {
-    negate: u64,
-    magic: u64,
-    offset: u32,
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct BlackMagicEntry {
+    /// The complement of the relevant blockers for the square.
+    pub negate: u64,
+
+    /// The multiplier that hashes the occupancy into an index.
+    pub magic: u64,
+
+    /// The start of this square's range in the moves table.
+    pub offset: u32,
 }
 
 const EMPTY: BlackMagicEntry = BlackMagicEntry { negate: 0, magic: 0, offset: 0 };
@@ -35,7 +63,7 @@ const EMPTY: BlackMagicEntry = BlackMagicEntry { negate: 0, magic: 0, offset: 0
 macro_rules! gen_entries {
     ($relevant_blockers:ident, $raw_magics:expr) => {{
         let raw_magics = $raw_magics;
-        let mut magics = [EMPTY; 64];
+        let mut magics = [EMPTY; SQUARES];
         let mut i = 0;
         while i < raw_magics.len() {
             let square = Square::from_index(i);
@@ -56,9 +84,9 @@ const fn get_rook_relevant_blockers(square: Square) -> BitBoard {
     BitBoard((rank_moves | file_moves) & (!square.to_mask()))
 }
 
-const ROOK_INDEX_WIDTH: usize = 12;
+pub const ROOK_INDEX_WIDTH: usize = 12;
 
-const ROOK_MAGICS: &[BlackMagicEntry; 64] = &gen_entries!(
+const ROOK_MAGICS: &[BlackMagicEntry; SQUARES] = &gen_entries!(
     get_rook_relevant_blockers,
     [
         (0x80280013FF84FFFF, 10890), (0x5FFBFEFDFEF67FFF, 50579), (0xFFEFFAFFEFFDFFFF, 62020),
@@ -90,7 +118,7 @@ const fn get_bishop_relevant_blockers(square: Square) -> BitBoard {
     const EDGES: u64 = 18411139144890810879;
     let mut rays = BitBoard::EMPTY.0;
     let mut i = 0;
-    while i < 64 {
+    while i < SQUARES {
         let target = Square::from_index(i);
         let rd = (square.rank_u8() as i8 - target.rank_u8() as i8).abs();
         let fd = (square.file_u8() as i8 - target.file_u8() as i8).abs();
@@ -102,9 +130,9 @@ const fn get_bishop_relevant_blockers(square: Square) -> BitBoard {
     BitBoard(rays & !EDGES)
 }
 
-const BISHOP_INDEX_WIDTH: usize = 9;
+pub const BISHOP_INDEX_WIDTH: usize = 9;
 
-const BISHOP_MAGICS: &[BlackMagicEntry; 64] = &gen_entries!(
+const BISHOP_MAGICS: &[BlackMagicEntry; SQUARES] = &gen_entries!(
     get_bishop_relevant_blockers,
     [
         (0xA7020080601803D8, 60984), (0x13802040400801F1, 66046), (0x0A0080181001F60C, 32910),
@@ -131,3 +159,163 @@ const BISHOP_MAGICS: &[BlackMagicEntry; 64] = &gen_entries!(
         (0x100000C05F582008, 11140)
     ]
 );
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MagicError {
+    /// The rook moves from this square with this occupancy are wrong.
+    Rook(Square, BitBoard),
+
+    /// The bishop moves from this square with this occupancy are wrong.
+    Bishop(Square, BitBoard),
+}
+
+/// A set of magic entries and the moves table they index into,
+/// generated at runtime by `find_magics`.
+#[derive(Clone)]
+pub struct MagicTables {
+    pub rook: [BlackMagicEntry; SQUARES],
+    pub bishop: [BlackMagicEntry; SQUARES],
+    pub moves: Vec<u64>,
+}
+
+impl MagicTables {
+    pub fn rook_moves(&self, sq: Square, occupied: BitBoard) -> BitBoard {
+        BitBoard(self.moves[magic_index(&self.rook, ROOK_INDEX_WIDTH, sq, occupied)])
+    }
+
+    pub fn bishop_moves(&self, sq: Square, occupied: BitBoard) -> BitBoard {
+        BitBoard(self.moves[magic_index(&self.bishop, BISHOP_INDEX_WIDTH, sq, occupied)])
+    }
+
+    /// Check every relevant occupancy of every square against a slow ray walk.
+    pub fn verify(&self) -> Result<(), MagicError> {
+        verify_with(|sq, occ| self.rook_moves(sq, occ), |sq, occ| self.bishop_moves(sq, occ))
+    }
+}
+
+/// Check the built-in tables used by `get_rook_moves` and `get_bishop_moves`
+/// against a slow ray walk, for every relevant occupancy of every square.
+pub fn verify_tables() -> Result<(), MagicError> {
+    verify_with(get_rook_moves, get_bishop_moves)
+}
+
+/// Search for new rook and bishop magics with the given rng,
+/// returning them along with a freshly built moves table.
+pub fn find_magics(rng: &mut WyRand) -> MagicTables {
+    let mut moves = Vec::with_capacity(SQUARES * ((1 << ROOK_INDEX_WIDTH) + (1 << BISHOP_INDEX_WIDTH)));
+    let mut rook = [EMPTY; SQUARES];
+    let mut bishop = [EMPTY; SQUARES];
+
+    for (i, entry) in rook.iter_mut().enumerate() {
+        let sq = Square::from_index(i);
+        *entry = find_magic(rng, &mut moves, sq, get_rook_relevant_blockers(sq), ROOK_INDEX_WIDTH, &ROOK_DIRS);
+    }
+
+    for (i, entry) in bishop.iter_mut().enumerate() {
+        let sq = Square::from_index(i);
+        *entry = find_magic(rng, &mut moves, sq, get_bishop_relevant_blockers(sq), BISHOP_INDEX_WIDTH, &BISHOP_DIRS);
+    }
+
+    MagicTables { rook, bishop, moves }
+}
+
+const ROOK_DIRS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// Find a magic for one square, appending its range to the moves table.
+fn find_magic(
+    rng: &mut WyRand,
+    moves: &mut Vec<u64>,
+    sq: Square,
+    relevant: BitBoard,
+    width: usize,
+    dirs: &[(i8, i8); 4],
+) -> BlackMagicEntry {
+    let negate = !relevant.0;
+    let subsets: Vec<(u64, u64)> = subsets(relevant)
+        .map(|occ| (occ, slow_moves(sq, BitBoard(occ), dirs).0))
+        .collect();
+
+    let mut table = vec![None; 1 << width];
+    loop {
+        // sparse candidates hash much better.
+        let magic = rng.next() & rng.next() & rng.next();
+        table.fill(None);
+
+        let found = subsets.iter().all(|&(occ, mv)| {
+            let i = ((occ | negate).wrapping_mul(magic) >> (64 - width)) as usize;
+            *table[i].get_or_insert(mv) == mv
+        });
+
+        if found {
+            let offset = moves.len() as u32;
+            moves.extend(table.iter().map(|mv| mv.unwrap_or(0)));
+            return BlackMagicEntry { negate, magic, offset }
+        }
+    }
+}
+
+fn verify_with(
+    rook: impl Fn(Square, BitBoard) -> BitBoard,
+    bishop: impl Fn(Square, BitBoard) -> BitBoard,
+) -> Result<(), MagicError> {
+    for sq in BitBoard(!0) {
+        for occ in subsets(get_rook_relevant_blockers(sq)).map(BitBoard) {
+            if rook(sq, occ) != slow_moves(sq, occ, &ROOK_DIRS) {
+                return Err(MagicError::Rook(sq, occ))
+            }
+        }
+
+        for occ in subsets(get_bishop_relevant_blockers(sq)).map(BitBoard) {
+            if bishop(sq, occ) != slow_moves(sq, occ, &BISHOP_DIRS) {
+                return Err(MagicError::Bishop(sq, occ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Every subset of the mask, using the carry-rippler trick.
+fn subsets(mask: BitBoard) -> impl Iterator<Item = u64> {
+    let mask = mask.0;
+    let mut next = Some(0u64);
+    std::iter::from_fn(move || {
+        let subset = next?;
+        let following = subset.wrapping_sub(mask) & mask;
+        next = (following != 0).then_some(following);
+        Some(subset)
+    })
+}
+
+/// Walk each ray until it leaves the board or hits a blocker.
+fn slow_moves(sq: Square, occupied: BitBoard, dirs: &[(i8, i8); 4]) -> BitBoard {
+    let mut moves = BitBoard::new();
+    for &dir in dirs {
+        let mut curr = sq;
+        while let Some(next) = curr.next(dir) {
+            moves |= next;
+            if occupied.has(next) {
+                break
+            }
+            curr = next;
+        }
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::WyRand;
+
+    #[test]
+    fn built_in_tables() {
+        assert_eq!(super::verify_tables(), Ok(()));
+    }
+
+    #[test]
+    fn regenerated_tables() {
+        let tables = super::find_magics(&mut WyRand { seed: 7 });
+        assert_eq!(tables.verify(), Ok(()));
+    }
+}
@@ -0,0 +1,134 @@
+//! Estimates of the memory held by games, for servers that budget
+//! memory across many games at once.
+//!
+//! A footprint counts the game's own struct and the heap memory it owns,
+//! by capacity rather than length. Allocator overhead is not counted, a
+//! `BTreeMap` is counted as its entries, and event callbacks as the
+//! pointers to them, so the totals are estimates. Delta chunks that are
+//! shared between clones are counted in full by every game holding them.
+
+use std::{collections::BTreeMap, iter::Sum, mem::size_of, ops::{Add, AddAssign}};
+
+use crate::{amend::Amendment, annotation::{Annotation, Arrow, Highlight}, deltas::Deltas, game::ChessGame, holes::HoleExits, meta::GameMeta, state::BoardState, tree::GameTree};
+
+/// The bytes held by a game or tree, by what they are used for.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct MemoryFootprint {
+    /// The move list.
+    pub deltas: usize,
+
+    /// The start and cursor positions, without their caches.
+    pub snapshots: usize,
+
+    /// Comments, glyphs, arrows, and highlights.
+    pub annotations: usize,
+
+    /// The wormhole exits cached in each position.
+    pub caches: usize,
+
+    /// Everything else, such as settings, metadata, amendments, and callbacks.
+    pub other: usize,
+}
+
+impl MemoryFootprint {
+    pub fn total(&self) -> usize {
+        self.deltas + self.snapshots + self.annotations + self.caches + self.other
+    }
+}
+
+impl Add for MemoryFootprint {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            deltas: self.deltas + rhs.deltas,
+            snapshots: self.snapshots + rhs.snapshots,
+            annotations: self.annotations + rhs.annotations,
+            caches: self.caches + rhs.caches,
+            other: self.other + rhs.other,
+        }
+    }
+}
+
+impl AddAssign for MemoryFootprint {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for MemoryFootprint {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+/// The memory held by the game.
+pub fn game(game: &ChessGame) -> MemoryFootprint {
+    let states = 2 * size_of::<BoardState>();
+    let annotations = size_of::<BTreeMap<usize, Annotation>>();
+    let inline = size_of::<ChessGame>() - size_of::<Deltas>() - states - annotations;
+
+    MemoryFootprint {
+        deltas: size_of::<Deltas>() + game.deltas.heap_bytes(),
+        snapshots: states - 2 * size_of::<HoleExits>(),
+        annotations: annotations + game.annotations.values().map(annotation_bytes).sum::<usize>(),
+        caches: 2 * size_of::<HoleExits>(),
+        other: inline
+            + game.meta.as_ref().map_or(0, meta_bytes)
+            + game.amendments.capacity() * size_of::<(usize, Amendment)>()
+            + game.events.heap_bytes(),
+    }
+}
+
+/// The memory held by the tree and every line in it.
+pub fn tree(tree: &GameTree) -> MemoryFootprint {
+    let mut footprint: MemoryFootprint = tree.games().iter().map(game).sum();
+    footprint.other += size_of::<GameTree>();
+    footprint
+}
+
+/// The bytes of an annotation's map entry and the heap memory it owns.
+fn annotation_bytes(annotation: &Annotation) -> usize {
+    size_of::<(usize, Annotation)>()
+        + annotation.comment.as_ref().map_or(0, String::capacity)
+        + annotation.nags.capacity()
+        + annotation.arrows.capacity() * size_of::<Arrow>()
+        + annotation.highlights.capacity() * size_of::<Highlight>()
+}
+
+/// The heap memory owned by the metadata's names.
+fn meta_bytes(meta: &GameMeta) -> usize {
+    [&meta.white, &meta.black, &meta.event, &meta.site, &meta.round]
+        .into_iter()
+        .flatten()
+        .map(String::capacity)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use crate::{game::ChessGame, state::BoardState, tree::GameTree};
+
+    #[test]
+    fn footprint() {
+        let empty = ChessGame::default().memory_footprint();
+        assert_eq!(empty.total(), size_of::<ChessGame>());
+
+        let mut game = crate::sim::random_game(Default::default(), 5);
+        let before = game.memory_footprint();
+        assert!(before.deltas >= game.deltas.len() * 12);
+        game.annotate(3).unwrap().comment = Some("a long comment about the third position".into());
+        let after = game.memory_footprint();
+        assert!(after.annotations >= before.annotations + 40);
+        assert_eq!(after.deltas, before.deltas);
+
+        let mut tree = GameTree::new(ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5").unwrap());
+        let line = tree.games()[0].memory_footprint();
+        tree.prev();
+        tree.play("c7".parse().unwrap(), "c5".parse().unwrap(), None).ok().unwrap();
+        assert_eq!(tree.games().len(), 2);
+        assert!(tree.memory_footprint().total() > line.total() + size_of::<ChessGame>());
+    }
+}
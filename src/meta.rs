@@ -0,0 +1,40 @@
+//! Descriptive information about a game, such as who played it and where.
+
+use chrono::NaiveDate;
+
+/// Game metadata, exported as the matching PGN tags.
+/// Fields that are None are written as "?".
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct GameMeta {
+    /// The name of the white player.
+    pub white: Option<String>,
+
+    /// The name of the black player.
+    pub black: Option<String>,
+
+    /// White's rating, exported as `WhiteElo`.
+    pub white_elo: Option<u16>,
+
+    /// Black's rating, exported as `BlackElo`.
+    pub black_elo: Option<u16>,
+
+    /// The name of the tournament or match.
+    pub event: Option<String>,
+
+    /// Where the game was played.
+    pub site: Option<String>,
+
+    /// The day the game started. If None, the date
+    /// is taken from the clock settings instead.
+    pub date: Option<NaiveDate>,
+
+    /// The round of the event, such as "1" or "3.2".
+    pub round: Option<String>,
+}
+
+impl GameMeta {
+    /// Whether no field is known.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -0,0 +1,223 @@
+//! Upgrading games stored by earlier versions of the crate.
+//!
+//! Use `detect` to find what a stored blob holds without decoding it,
+//! and `upgrade` to rewrite it in the current version of its format.
+//! Version 1 is the only version of the game and study formats so far,
+//! and games kept before there was a format are read with `from_v0`.
+//!
+//! Version 0 is the delta layout from before pieces had stable codes.
+//! There was no game container then, so a game was kept as its start
+//! position, its settings, and its deltas, each as the three words of
+//! the delta (time, squares, and flags) in 12 bytes. `from_v0` converts
+//! those games. The layout differs from the current one in that:
+//!
+//! - The piece codes were 3 bits in the low bits of the flags, with
+//!   King and Rook swapped, and either 6 or 7 for no piece.
+//! - The square of a pawn captured en passant was in bits 19..=24 of
+//!   the squares, over the wormhole square.
+//! - The check flags were never set, and the previous halfmove count
+//!   was only read back up to 63.
+
+use crate::{binary::{BinaryError, MAGIC, STUDY_MAGIC, STUDY_VERSION, VERSION}, delta::{BoardDelta, DeltaError}, game::{ChessGame, Cursor}, pieces::Piece, settings::GameSettings, state::BoardState, study::Study};
+
+/// What a stored blob holds, and the version of the format it is in.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Stored {
+    Game(u8),
+    Study(u8),
+}
+
+impl Stored {
+    /// Whether the blob is already in the current version of its format.
+    pub fn is_current(&self) -> bool {
+        match *self {
+            Self::Game(version) => version == VERSION,
+            Self::Study(version) => version == STUDY_VERSION,
+        }
+    }
+}
+
+/// Read the magic and version of a blob, rejecting versions newer
+/// than this crate can read. The rest of the blob is not checked.
+pub fn detect(bytes: &[u8]) -> Result<Stored, BinaryError> {
+    let (magic, version) = bytes.split_first_chunk::<4>()
+        .and_then(|(magic, rest)| Some((magic, *rest.first()?)))
+        .ok_or(BinaryError::Magic)?;
+
+    let stored = match magic {
+        _ if magic == MAGIC => Stored::Game(version),
+        _ if magic == STUDY_MAGIC => Stored::Study(version),
+        _ => return Err(BinaryError::Magic),
+    };
+
+    match stored {
+        Stored::Game(VERSION) | Stored::Study(STUDY_VERSION) => Ok(stored),
+        Stored::Game(version) | Stored::Study(version) => Err(BinaryError::Version(version)),
+    }
+}
+
+/// Rewrite a stored game or study in the current version of its format.
+/// Every version that `detect` accepts is current, so this decodes the
+/// blob, checking all of it, and returns it unchanged.
+pub fn upgrade(bytes: &[u8]) -> Result<Vec<u8>, BinaryError> {
+    match detect(bytes)? {
+        Stored::Game(_) => Ok(ChessGame::from_bytes(bytes)?.to_bytes()),
+        Stored::Study(_) => Ok(Study::from_bytes(bytes)?.to_bytes()),
+    }
+}
+
+/// Decode a delta in the version 0 layout. The check flags and the
+/// previous halfmove count are left for `from_v0` to fill in by
+/// replaying the game.
+pub fn delta_from_v0(bytes: [u8; 12]) -> Result<BoardDelta, DeltaError> {
+    let mut squares = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    let mut data = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+
+    if data & 1 << 24 != 0 {
+        let ep_capture_sq = (squares >> 19) & 0x3F;
+        squares &= !(0x7F << 18);
+        squares |= ep_capture_sq << 12;
+    }
+
+    let mut pieces = 0;
+    for (i, shift) in [0, 3, 6].into_iter().enumerate() {
+        let code = match (data >> shift) & 0b111 {
+            3 => Piece::Rook.to_u8(),
+            4 => Piece::King.to_u8(),
+            6 | 7 => Piece::NONE,
+            code => code as u8,
+        };
+
+        pieces |= (code as u16) << (i * 4);
+    }
+
+    // the piece codes, and the halfmove count with the bit it overflowed into.
+    data &= !(0x1FF | 0x7F << 9);
+
+    let mut packed = [0; BoardDelta::PACKED_LEN];
+    packed[..4].copy_from_slice(&bytes[..4]);
+    packed[4..8].copy_from_slice(&squares.to_le_bytes());
+    packed[8..12].copy_from_slice(&data.to_le_bytes());
+    packed[12..].copy_from_slice(&pieces.to_le_bytes());
+    BoardDelta::unpack(packed)
+}
+
+/// Rebuild a game stored in version 0 from its start position, its
+/// settings, and its deltas, 12 bytes each. The game gets a new id, the
+/// cursor is put on the last position, and the end condition is set if
+/// that position ends the game. The moves are audited like any decoded
+/// game. Bytes in the current format are rejected, see `detect`.
+pub fn from_v0(start: BoardState, settings: GameSettings, deltas: &[u8]) -> Result<ChessGame, BinaryError> {
+    let (chunks, rest) = deltas.as_chunks::<12>();
+    if !rest.is_empty() {
+        return Err(BinaryError::Truncated)
+    }
+
+    let mut game = ChessGame {
+        start,
+        cursor: Cursor::new(start),
+        settings,
+        game_id: crate::rng::entropy(),
+        ..ChessGame::default()
+    };
+
+    for &chunk in chunks {
+        game.deltas.push(delta_from_v0(chunk).map_err(|_| BinaryError::InvalidField)?);
+    }
+
+    crate::audit::fill_derived_fields(start, &mut game.deltas);
+
+    game.audit().map_err(BinaryError::Audit)?;
+    game.goto_end();
+
+    let mut positions: Vec<BoardState> = game.positions().map(|(_, state)| state).collect();
+    let last = positions.pop().unwrap();
+    game.end = crate::end::detect(&last, positions.into_iter().rev());
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Stored, delta_from_v0, detect, from_v0, upgrade};
+    use crate::{binary::{BinaryError, STUDY_VERSION, VERSION}, end::EndCondition, game::ChessGame, pieces::Piece, state::BoardState, study::Study};
+
+    /// Write the game's deltas in the version 0 layout.
+    fn to_v0(game: &ChessGame) -> Vec<u8> {
+        game.deltas.iter().flat_map(|delta| {
+            let packed = delta.pack();
+            let mut squares = u32::from_le_bytes(packed[4..8].try_into().unwrap());
+            if let Some(ep_capture_sq) = delta.get_ep_capture_sq() {
+                squares &= !(0x3F << 12);
+                squares |= (ep_capture_sq.to_index() as u32) << 19;
+            }
+
+            let mut data = u32::from_le_bytes(packed[8..12].try_into().unwrap());
+            data &= !(0x7F << 9 | 0b111 << 28);
+            data |= (delta.get_prev_halfmoves() as u32) << 9;
+            for (i, pc) in [delta.get_capture_pc(), delta.get_promote_pc(), delta.get_crushed_pc()].into_iter().enumerate() {
+                let code = match pc { Some(Piece::Rook) => 3, Some(Piece::King) => 4, Some(pc) => pc.to_u8() as u32, None => 7 };
+                data |= code << (i * 3);
+            }
+
+            let mut bytes = [0; 12];
+            bytes[..4].copy_from_slice(&packed[..4]);
+            bytes[4..8].copy_from_slice(&squares.to_le_bytes());
+            bytes[8..].copy_from_slice(&data.to_le_bytes());
+            bytes
+        }).collect()
+    }
+
+    #[test]
+    fn version_0() {
+        // a rook is captured by a promotion, with a halfmove count too wide for version 0.
+        let start = BoardState::from_fen("r3k3/1P6/8/8/8/8/8/4K2R w K - 70 1").unwrap();
+        let game = ChessGame::from_uci_moves(start, "b7a8q e8d7 h1h7 d7d6 a8d8 d6e5 h7e7 e5f5").unwrap();
+        let v0 = to_v0(&game);
+        assert_eq!(v0[8] & 0b111, 3);
+        assert_eq!(game.deltas[0].get_prev_halfmoves(), 70);
+        assert!(delta_from_v0(v0[..12].try_into().unwrap()).is_ok_and(|delta| delta.get_capture_pc() == Some(Piece::Rook)));
+
+        let migrated = from_v0(start, game.settings, &v0).unwrap();
+        assert_eq!(migrated.deltas, game.deltas);
+        assert_eq!(migrated.cursor.index, game.deltas.len());
+        assert_eq!(migrated.end, None);
+
+        // read with the current codes, the rook would be a captured king.
+        let mut swapped = v0.clone();
+        swapped[8] = swapped[8] & !0b111 | 4;
+        assert_eq!(from_v0(start, game.settings, &swapped).err(), Some(BinaryError::InvalidField));
+        assert_eq!(from_v0(start, game.settings, &v0[..13]).err(), Some(BinaryError::Truncated));
+
+        // both sides capture en passant.
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 a7a6 e4e5 d7d5 e5d6 b7b5 h2h3 b5b4 c2c4 b4c3").unwrap();
+        assert_eq!(from_v0(BoardState::default(), game.settings, &to_v0(&game)).unwrap().deltas, game.deltas);
+
+        let game = ChessGame::from_uci_moves(BoardState::default(), "f2f3 e7e5 g2g4 d8h4").unwrap();
+        let migrated = from_v0(BoardState::default(), game.settings, &to_v0(&game)).unwrap();
+        assert_eq!(migrated.deltas, game.deltas);
+        assert_eq!(migrated.end, Some(EndCondition::Checkmate));
+
+        // version 0 had no container, so its bytes are not a stored game.
+        assert_eq!(detect(&to_v0(&game)), Err(BinaryError::Magic));
+        assert_eq!(ChessGame::from_bytes(&to_v0(&game)).err(), Some(BinaryError::Magic));
+    }
+
+    #[test]
+    fn migrate() {
+        let mate = ChessGame::from_uci_moves(BoardState::default(), "f2f3 e7e5 g2g4 d8h4").unwrap();
+        let mut bytes = mate.to_bytes();
+        assert_eq!(detect(&bytes), Ok(Stored::Game(VERSION)));
+        assert!(Stored::Game(VERSION).is_current());
+        assert_eq!(upgrade(&bytes).unwrap(), bytes);
+
+        let study = Study::from_pgn("[Event \"Openings\"]\n\n1. e4 (1. d4) e5 *\n").unwrap().to_bytes();
+        assert_eq!(detect(&study), Ok(Stored::Study(STUDY_VERSION)));
+        assert_eq!(upgrade(&study).unwrap(), study);
+
+        bytes[4] = VERSION + 1;
+        assert_eq!(detect(&bytes), Err(BinaryError::Version(VERSION + 1)));
+        bytes[4] = 0;
+        assert_eq!(detect(&bytes), Err(BinaryError::Version(0)));
+        assert_eq!(detect(b"MLS"), Err(BinaryError::Magic));
+    }
+}
@@ -0,0 +1,161 @@
+//! Shortest paths for a piece moving alone across the board.
+//!
+//! The board is searched as a graph with an edge for every move of the
+//! piece, breadth first. With wormholes, a piece that lands on a hole is
+//! on every hole, so the next move may leave from any of them. Paths list
+//! the square of each move, ending on the target and not including the
+//! start, and a move onto a hole lists the hole the piece landed on.
+
+use crate::{board::BitBoard, pieces::Piece, square::Square, team::Team};
+
+/// The squares the piece can move to from `sq` on a board where it can
+/// neither land on nor slide through the blockers. Pawns only push,
+/// twice from their starting rank.
+pub fn piece_moves(piece: Piece, team: Team, sq: Square, blockers: BitBoard) -> BitBoard {
+    let moves = match piece {
+        Piece::King => sq.king_moves(),
+        Piece::Knight => sq.knight_moves(),
+        Piece::Bishop => sq.bishop_moves(blockers),
+        Piece::Rook => sq.rook_moves(blockers),
+        Piece::Queen => sq.bishop_moves(blockers) | sq.rook_moves(blockers),
+        Piece::Archbishop => sq.bishop_moves(blockers) | sq.knight_moves(),
+        Piece::Chancellor => sq.rook_moves(blockers) | sq.knight_moves(),
+        Piece::Pawn => {
+            let mut moves = BitBoard::new();
+            let Some(one) = sq.next((team.pawn_dir(), 0)).filter(|one| !blockers.has(*one)) else {
+                return moves
+            };
+
+            moves.set(one);
+            if sq.rank_u8() == team.pawn_rank_u8() {
+                if let Some(two) = one.next((team.pawn_dir(), 0)).filter(|two| !blockers.has(*two)) {
+                    moves.set(two);
+                }
+            }
+
+            moves
+        },
+    };
+
+    moves & !blockers
+}
+
+/// The fewest moves from `from` to every square, None where it cannot go.
+pub fn distances(from: Square, wormholes: BitBoard, moves: impl Fn(Square) -> BitBoard) -> [Option<u8>; 64] {
+    Search::run(from, wormholes, moves).distance
+}
+
+/// The fewest moves from `from` to `to`, None if it cannot get there.
+pub fn distance(from: Square, to: Square, wormholes: BitBoard, moves: impl Fn(Square) -> BitBoard) -> Option<u8> {
+    distances(from, wormholes, moves)[to.to_index()]
+}
+
+/// A shortest path from `from` to `to`, None if there is none. Empty if
+/// the piece is already there.
+pub fn shortest_path(from: Square, to: Square, wormholes: BitBoard, moves: impl Fn(Square) -> BitBoard) -> Option<Vec<Square>> {
+    let search = Search::run(from, wormholes, moves);
+    search.distance[to.to_index()]?;
+
+    let mut path = Vec::new();
+    let mut sq = to;
+    while search.distance[sq.to_index()] != Some(0) {
+        if wormholes.has(sq) {
+            sq = search.entry.unwrap_or(sq);
+        }
+
+        path.push(sq);
+        sq = search.parent[sq.to_index()]?;
+    }
+
+    path.reverse();
+    Some(path)
+}
+
+struct Search {
+    distance: [Option<u8>; 64],
+    parent: [Option<Square>; 64],
+
+    /// The hole the piece first landed on.
+    entry: Option<Square>,
+}
+
+impl Search {
+    fn run(from: Square, wormholes: BitBoard, moves: impl Fn(Square) -> BitBoard) -> Self {
+        let mut search = Self {
+            distance: [None; 64],
+            parent: [None; 64],
+            entry: None,
+        };
+
+        let mut frontier = BitBoard::from(from);
+        let mut depth = 0u8;
+        while !frontier.is_empty() {
+            // landing on any hole puts the piece on all of them.
+            if !(frontier & wormholes).is_empty() {
+                search.entry = (frontier & wormholes).first();
+                frontier |= wormholes;
+            }
+
+            for sq in frontier {
+                search.distance[sq.to_index()] = Some(depth);
+            }
+
+            let mut next = BitBoard::new();
+            for sq in frontier {
+                for dst in moves(sq) {
+                    if search.distance[dst.to_index()].is_none() && !next.has(dst) {
+                        search.parent[dst.to_index()] = Some(sq);
+                        next.set(dst);
+                    }
+                }
+            }
+
+            frontier = next;
+            depth += 1;
+        }
+
+        search
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{distance, piece_moves, shortest_path};
+    use crate::{board::BitBoard, pieces::Piece, square::Square, team::Team};
+
+    fn sq(s: &str) -> Square {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn knight() {
+        assert_eq!(sq("a1").knight_distance(sq("h8")), 6);
+        assert_eq!(sq("a1").knight_distance(sq("b2")), 4);
+        assert_eq!(sq("e4").knight_distance(sq("e4")), 0);
+        assert!(sq("e4").knight_path(sq("e4")).is_empty());
+
+        let path = sq("a1").knight_path(sq("b2"));
+        assert_eq!(path.len(), 4);
+        assert_eq!(path.last(), Some(&sq("b2")));
+        assert!(path.iter().try_fold(sq("a1"), |at, &next| at.knight_moves().has(next).then_some(next)).is_some());
+    }
+
+    #[test]
+    fn wormholes() {
+        let holes = sq("b2") | sq("g7");
+        let king = |sq: Square| sq.king_moves();
+        assert_eq!(distance(sq("a2"), sq("h7"), BitBoard::new(), king), Some(7));
+        assert_eq!(distance(sq("a2"), sq("h7"), holes, king), Some(2));
+        assert_eq!(shortest_path(sq("a2"), sq("h7"), holes, king), Some(vec![sq("b2"), sq("h7")]));
+        assert_eq!(shortest_path(sq("a2"), sq("g7"), holes, king), Some(vec![sq("b2")]));
+
+        // a rook walled in never gets out.
+        let walls = sq("a2") | sq("b1");
+        let rook = |from: Square| piece_moves(Piece::Rook, Team::White, from, walls);
+        assert_eq!(shortest_path(sq("a1"), sq("h8"), BitBoard::new(), rook), None);
+
+        let pawn = |from: Square| piece_moves(Piece::Pawn, Team::Black, from, BitBoard::new());
+        assert_eq!(distance(sq("d7"), sq("d1"), BitBoard::new(), pawn), Some(5));
+        assert_eq!(distance(sq("d7"), sq("d8"), BitBoard::new(), pawn), None);
+    }
+}
@@ -0,0 +1,87 @@
+//! Saving in-progress games to disk and resuming them.
+//!
+//! Games are stored in the versioned format from `binary`. Writes go to
+//! a temporary file that is renamed over the destination, so a crash
+//! while saving leaves the previous save intact.
+
+use std::{ffi::OsString, fs, io::{self, Write}, path::{Path, PathBuf}};
+
+use crate::{binary::BinaryError, clock::TimeSource, game::ChessGame};
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Io(io::Error),
+
+    /// The file is not a valid saved game.
+    Binary(BinaryError),
+}
+
+/// Atomically write the game to `path`.
+pub fn save(game: &ChessGame, path: &Path) -> io::Result<()> {
+    let tmp = tmp_path(path);
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&game.to_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+
+    result
+}
+
+/// Read a game saved with `save`, reconciling the clocks with the
+/// current time of the source. Time that passed while the game was
+/// saved is charged to the side to move, as if the app had stayed open.
+pub fn load(path: &Path, time: &impl TimeSource) -> Result<ChessGame, LoadError> {
+    let bytes = fs::read(path).map_err(LoadError::Io)?;
+    let mut game = ChessGame::from_bytes(&bytes).map_err(LoadError::Binary)?;
+
+    if game.cursor_is_last() {
+        if let Some((white, black)) = game.remaining_ms(time) {
+            game.cursor.white_time = white.clamp(0, u32::MAX as i64) as u32;
+            game.cursor.black_time = black.clamp(0, u32::MAX as i64) as u32;
+        }
+    }
+
+    Ok(game)
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{clock::MockClock, game::ChessGame, settings::{ClockSettings, GameSettings}, square::Square};
+
+    #[test]
+    fn save_and_resume() {
+        let time = MockClock::new(1_700_000_000_000);
+        let mut game = ChessGame::init(GameSettings {
+            clock: Some(ClockSettings::starting_now(&time, 300, 0)),
+            ..GameSettings::default()
+        });
+
+        time.advance(4_000);
+        assert!(game.play_timed(Square::from((1, 4)), Square::from((3, 4)), None, &time).is_ok());
+
+        let path = std::env::temp_dir().join(format!("maulstrom-{:016x}.mlst", game.game_id));
+        game.save(&path).unwrap();
+        assert!(!super::tmp_path(&path).exists());
+
+        time.advance(20_000);
+        let loaded = ChessGame::load_with(&path, &time).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.deltas, game.deltas);
+        assert_eq!(loaded.cursor.white_time, 296_000);
+        assert_eq!(loaded.cursor.black_time, 280_000);
+    }
+}
@@ -0,0 +1,805 @@
+//! Portable Game Notation export and import.
+//!
+//! Variant games carry their settings in custom tags, so they survive
+//! a round trip: `[Variant "Maulstrom"]` for games with wormholes (or
+//! `"Chess960"` for plain Chess960), followed by `Chess960`,
+//! `WormholeSpawn`, `WormholeMax`, `WormholeStart`, `WormholeWait`,
+//! and `WormholeQueue`. The seed is stored in `Seed` as hex.
+
+use std::{fmt::Write, io::{self, BufRead}};
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::{annotation::Annotation, delta::BoardDelta, end::EndCondition, fen::FenError, game::{ChessGame, Cursor}, meta::GameMeta, settings::{ClockSettings, GameLimits, GameSettings, WormholeSettings, WormholeSpawnMode}, state::BoardState, team::Team, tree::GameTree};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PgnError {
+    /// The tag pair on this line (starting at 0) is malformed.
+    Tag(usize),
+
+    /// The FEN tag could not be parsed.
+    Fen(FenError),
+
+    /// A settings tag has a value that could not be parsed.
+    Setting,
+
+    /// The move at this halfmove index is malformed, ambiguous, or illegal.
+    Move(usize),
+}
+
+#[derive(Debug)]
+pub enum StreamError {
+    /// The reader failed. The stream ends after this error.
+    Io(io::Error),
+
+    /// The game could not be imported. The stream continues with the next game.
+    Pgn(PgnError),
+}
+
+/// Lazily reads the games in a multi-game PGN file, one at a time.
+///
+/// A game ends where the next tag section begins, or at the end of the
+/// input, so only one game is held in memory at once.
+pub struct GameStream<R> {
+    reader: R,
+
+    /// The first tag of the next game, read while looking for
+    /// the end of the previous one.
+    pending: Option<String>,
+
+    done: bool,
+}
+
+impl<R: BufRead> GameStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: None,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> GameStream<R> {
+    /// The text of the next game, or None at the end of the input.
+    pub(crate) fn next_text(&mut self) -> Option<io::Result<String>> {
+        if self.done {
+            return None
+        }
+
+        let mut text = self.pending.take().unwrap_or_default();
+        let mut has_movetext = false;
+        // whether the line starts inside a brace comment, where a "["
+        // at the start of a wrapped line does not begin a tag.
+        let mut in_comment = false;
+        loop {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => {
+                    self.done = true;
+                    break;
+                },
+                Ok(_) => {},
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err))
+                },
+            }
+
+            let trimmed = line.trim();
+            let is_tag = !in_comment && trimmed.starts_with('[');
+            if is_tag && has_movetext {
+                self.pending = Some(line);
+                break;
+            }
+
+            if !is_tag {
+                in_comment = ends_in_comment(trimmed, in_comment);
+                has_movetext |= !trimmed.is_empty();
+            }
+
+            text.push_str(&line);
+        }
+
+        if text.trim().is_empty() {
+            return None
+        }
+
+        Some(Ok(text))
+    }
+}
+
+/// Whether a line of movetext ends inside a brace comment, given whether
+/// it starts in one. Brace comments do not nest, and a ";" outside of one
+/// comments out the rest of the line.
+fn ends_in_comment(line: &str, mut in_comment: bool) -> bool {
+    for c in line.chars() {
+        match c {
+            '}' if in_comment => in_comment = false,
+            '{' if !in_comment => in_comment = true,
+            ';' if !in_comment => break,
+            _ => {},
+        }
+    }
+
+    in_comment
+}
+
+impl<R: BufRead> Iterator for GameStream<R> {
+    type Item = Result<ChessGame, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.next_text()? {
+            Ok(text) => from_pgn(&text).map_err(StreamError::Pgn),
+            Err(err) => Err(StreamError::Io(err)),
+        })
+    }
+}
+
+/// The longest line in the movetext, as recommended by the PGN standard.
+const LINE_WIDTH: usize = 80;
+
+/// Write the game as PGN, with a tag section followed by SAN movetext.
+pub fn to_pgn(game: &ChessGame) -> String {
+    write_pgn(game, None, &[])
+}
+
+/// Write the mainline of the tree as PGN, with every other line as a
+/// variation in parentheses after the move it replaces. Lines that
+/// branch from the final position of their parent replace no move,
+/// so they cannot be written.
+pub fn tree_to_pgn(tree: &GameTree) -> String {
+    write_pgn(tree.mainline(), Some(tree), &[])
+}
+
+/// Write the game as PGN with the extra tags after the standard ones,
+/// and the variations of the tree if the game is its mainline.
+pub(crate) fn write_pgn(game: &ChessGame, tree: Option<&GameTree>, extra: &[(String, String)]) -> String {
+    let mut pgn = String::new();
+    let result = result(game);
+
+    let meta = game.meta.clone().unwrap_or_default();
+    let date = match (meta.date, game.settings.clock) {
+        (Some(date), _) => date.format("%Y.%m.%d").to_string(),
+        (None, Some(clock)) => clock.start.format("%Y.%m.%d").to_string(),
+        (None, None) => "????.??.??".to_string(),
+    };
+
+    let _ = writeln!(pgn, "[Event \"{}\"]", escape(meta.event.as_deref()));
+    let _ = writeln!(pgn, "[Site \"{}\"]", escape(meta.site.as_deref()));
+    let _ = writeln!(pgn, "[Date \"{date}\"]");
+    let _ = writeln!(pgn, "[Round \"{}\"]", escape(meta.round.as_deref()));
+    let _ = writeln!(pgn, "[White \"{}\"]", escape(meta.white.as_deref()));
+    let _ = writeln!(pgn, "[Black \"{}\"]", escape(meta.black.as_deref()));
+    let _ = writeln!(pgn, "[Result \"{result}\"]");
+
+    if let Some(elo) = meta.white_elo {
+        let _ = writeln!(pgn, "[WhiteElo \"{elo}\"]");
+    }
+
+    if let Some(elo) = meta.black_elo {
+        let _ = writeln!(pgn, "[BlackElo \"{elo}\"]");
+    }
+
+    let has_wormholes = has_wormholes(game);
+    if has_wormholes {
+        let wormhole = game.settings.wormhole;
+        let _ = writeln!(pgn, "[Variant \"Maulstrom\"]");
+        let _ = writeln!(pgn, "[Chess960 \"{}\"]", game.settings.is_chess960 as u8);
+        let _ = writeln!(pgn, "[WormholeSpawn \"{}\"]", spawn_mode_name(wormhole.spawn_mode));
+        let _ = writeln!(pgn, "[WormholeMax \"{}\"]", wormhole.max_count);
+        let _ = writeln!(pgn, "[WormholeStart \"{}\"]", wormhole.start_count);
+        let _ = writeln!(pgn, "[WormholeWait \"{}\"]", wormhole.hole_wait_time);
+        let _ = writeln!(pgn, "[WormholeQueue \"{}\"]", wormhole.hole_queue_time);
+    } else if game.settings.is_chess960 {
+        let _ = writeln!(pgn, "[Variant \"Chess960\"]");
+    }
+
+    let _ = writeln!(pgn, "[Seed \"{:016x}\"]", game.seed);
+
+    if let Some(clock) = game.settings.clock {
+        let _ = writeln!(pgn, "[UTCTime \"{}\"]", clock.start.format("%H:%M:%S"));
+        let _ = writeln!(pgn, "[TimeControl \"{}+{}\"]", clock.total, clock.bonus);
+    }
+
+    if !game.start.is_repetition_of(&BoardState::default()) {
+        let fen = if game.start.wormholes.is_empty() && game.start.next_hole.is_none() {
+            game.start.to_fen()
+        } else {
+            game.start.to_extended_fen()
+        };
+
+        let _ = writeln!(pgn, "[SetUp \"1\"]");
+        let _ = writeln!(pgn, "[FEN \"{fen}\"]");
+    }
+
+    for (name, value) in extra {
+        let _ = writeln!(pgn, "[{name} \"{}\"]", escape(Some(value)));
+    }
+
+    pgn.push('\n');
+
+    let mut tokens = Vec::with_capacity(game.deltas.len() * 2 + 1);
+    annotate(&mut tokens, game.annotations.get(&0));
+    push_moves(&mut tokens, game, tree.map(|tree| (tree, 0)), game.start, true);
+    tokens.push(result.to_string());
+
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len > 0 && line_len + 1 + token.len() > LINE_WIDTH {
+            pgn.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            pgn.push(' ');
+            line_len += 1;
+        }
+
+        line_len += token.len();
+        pgn.push_str(&token);
+    }
+
+    pgn.push('\n');
+    pgn
+}
+
+/// Push the moves of the line from the position, each followed by its
+/// annotation and, if the line is in a tree, its variations. Black's
+/// move is numbered again when it does not follow white's directly.
+fn push_moves(tokens: &mut Vec<String>, game: &ChessGame, tree: Option<(&GameTree, usize)>, mut state: BoardState, mut interrupted: bool) {
+    for (i, delta) in game.deltas.iter().enumerate() {
+        push_move(tokens, &state, *delta, interrupted);
+        let before = state;
+        state = state.next(*delta);
+        interrupted = annotate(tokens, game.annotations.get(&(i + 1)));
+
+        if let Some((tree, line)) = tree {
+            for branch in tree.variations(line, i) {
+                push_variation(tokens, tree, branch, &before);
+                interrupted = true;
+            }
+        }
+    }
+}
+
+/// Push the line in parentheses, starting with the move that branches
+/// from the position in its parent.
+fn push_variation(tokens: &mut Vec<String>, tree: &GameTree, line: usize, state: &BoardState) {
+    let game = &tree.games()[line];
+    let Some(info) = game.is_branch else {
+        return
+    };
+
+    let first = tokens.len();
+    push_move(tokens, state, info.delta, true);
+    let interrupted = annotate(tokens, game.annotations.get(&0));
+    push_moves(tokens, game, Some((tree, line)), game.start, interrupted);
+
+    tokens[first].insert(0, '(');
+    tokens.last_mut().unwrap().push(')');
+}
+
+/// Push the move number, if white is moving or the black move must be
+/// numbered again, and the SAN of the move.
+fn push_move(tokens: &mut Vec<String>, state: &BoardState, delta: BoardDelta, numbered: bool) {
+    if state.turn == Team::White {
+        tokens.push(format!("{}.", state.fullmoves));
+    } else if numbered {
+        tokens.push(format!("{}...", state.fullmoves));
+    }
+
+    tokens.push(crate::san::to_san(state, delta));
+}
+
+/// Push the glyphs and comment of an annotation as movetext tokens,
+/// returning whether there were any. Arrows and highlights are written
+/// at the start of the comment as `[%cal]` and `[%csl]` commands. The
+/// comment is split into words so that long comments wrap, and a "}"
+/// would end it early, so it is dropped.
+fn annotate(tokens: &mut Vec<String>, annotation: Option<&Annotation>) -> bool {
+    let Some(annotation) = annotation.filter(|annotation| !annotation.is_empty()) else {
+        return false
+    };
+
+    tokens.extend(annotation.nags.iter().map(|nag| format!("${nag}")));
+    let drawings = annotation.drawing_commands();
+    if annotation.comment.is_some() || drawings.is_some() {
+        let comment = [drawings.as_deref(), annotation.comment.as_deref()].into_iter().flatten().collect::<Vec<_>>().join(" ");
+        let comment = comment.replace('}', "");
+        let mut words: Vec<String> = comment.split_whitespace().map(str::to_string).collect();
+        if words.is_empty() {
+            words.push(String::new());
+        }
+
+        words[0].insert(0, '{');
+        words.last_mut().unwrap().push('}');
+        tokens.extend(words);
+    }
+
+    true
+}
+
+/// The PGN result of the game, "*" if it has not ended.
+fn result(game: &ChessGame) -> &'static str {
+    game.result().map_or("*", |result| result.to_pgn_result())
+}
+
+/// A tag value with quotes and backslashes escaped, or "?" if unknown.
+fn escape(value: Option<&str>) -> String {
+    match value {
+        Some(value) => value.replace('\\', "\\\\").replace('"', "\\\""),
+        None => "?".to_string(),
+    }
+}
+
+fn has_wormholes(game: &ChessGame) -> bool {
+    game.settings.wormhole != WormholeSettings::default() || !game.start.wormholes.is_empty()
+}
+
+fn spawn_mode_name(mode: WormholeSpawnMode) -> &'static str {
+    match mode {
+        WormholeSpawnMode::Manual => "Manual",
+        WormholeSpawnMode::Random => "Random",
+        WormholeSpawnMode::Mirror => "Mirror",
+    }
+}
+
+/// Read the first game in a PGN string, restoring its settings from the tags.
+///
+/// Comments and NAGs are kept as annotations, as are the move suffixes
+/// with a glyph, like "!?", and the arrows and highlights of `[%cal]`
+/// and `[%csl]` commands in comments. Variations are skipped. Move times are not part of
+/// PGN, so every delta has a time of zero. The end condition is inferred
+/// from the final position and the Result tag: a decisive result that is
+/// not checkmate is read as a resignation, and a draw that is not stalemate,
+/// the fifty-move rule, repetition, or insufficient material is read as
+/// an agreement.
+pub fn from_pgn(pgn: &str) -> Result<ChessGame, PgnError> {
+    let (tags, movetext) = split(pgn)?;
+    let tag = |name: &str| tags.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str());
+
+    let start = match tag("FEN") {
+        Some(fen) => BoardState::from_extended_fen(fen).map_err(PgnError::Fen)?,
+        None => BoardState::default(),
+    };
+
+    let settings = GameSettings {
+        is_chess960: match (tag("Variant"), tag("Chess960")) {
+            (_, Some(flag)) => parse_flag(flag)?,
+            (Some(variant), None) => variant.eq_ignore_ascii_case("Chess960"),
+            (None, None) => false,
+        },
+        clock: match tag("TimeControl") {
+            Some(control) if control != "-" && control != "?" => Some(parse_clock(control, tag("Date"), tag("UTCTime"))?),
+            _ => None,
+        },
+        wormhole: WormholeSettings {
+            spawn_mode: match tag("WormholeSpawn") {
+                Some("Manual") => WormholeSpawnMode::Manual,
+                Some("Random") | None => WormholeSpawnMode::Random,
+                Some("Mirror") => WormholeSpawnMode::Mirror,
+                Some(_) => return Err(PgnError::Setting),
+            },
+            max_count: parse_number(tag("WormholeMax"))?,
+            start_count: parse_number(tag("WormholeStart"))?,
+            hole_wait_time: parse_number(tag("WormholeWait"))?,
+            hole_queue_time: parse_number(tag("WormholeQueue"))?,
+        },
+        move_filter: None,
+        limits: GameLimits::default(),
+    };
+
+    let seed = match tag("Seed") {
+        Some(seed) => u64::from_str_radix(seed, 16).map_err(|_| PgnError::Setting)?,
+        None => 0,
+    };
+
+    // "?" and "-" mark an unknown value.
+    let known = |name: &str| tag(name).filter(|value| !matches!(*value, "" | "?" | "-")).map(str::to_string);
+    let meta = GameMeta {
+        white: known("White"),
+        black: known("Black"),
+        white_elo: known("WhiteElo").and_then(|elo| elo.parse().ok()),
+        black_elo: known("BlackElo").and_then(|elo| elo.parse().ok()),
+        event: known("Event"),
+        site: known("Site"),
+        date: tag("Date").and_then(|date| NaiveDate::parse_from_str(date, "%Y.%m.%d").ok()),
+        round: known("Round"),
+    };
+
+    let mut game = ChessGame {
+        start,
+        cursor: Cursor::new(start),
+        game_id: crate::rng::entropy(),
+        seed,
+        meta: (!meta.is_empty()).then_some(meta),
+        ..ChessGame::default()
+    };
+
+    let mut history = vec![start];
+    let mut result = "*";
+    for token in movetext_tokens(&movetext) {
+        if is_result(token) {
+            result = token;
+            break;
+        }
+
+        let index = game.deltas.len();
+        if let Some(nag) = token.strip_prefix('$') {
+            if let Ok(nag) = nag.parse() {
+                add_nag(game.annotations.entry(index).or_default(), nag);
+            }
+            continue;
+        }
+
+        if let Some(text) = token.strip_prefix('{').or_else(|| token.strip_prefix(';')) {
+            let text = text.strip_suffix('}').unwrap_or(text);
+            let annotation = game.annotations.entry(index).or_default();
+            let drawings = (annotation.arrows.len(), annotation.highlights.len());
+            let text = annotation.read_drawing_commands(text);
+
+            // a comment with only drawings is not kept as text.
+            if text.is_empty() && drawings != (annotation.arrows.len(), annotation.highlights.len()) {
+                continue;
+            }
+
+            let comment = annotation.comment.get_or_insert_with(String::new);
+            if !comment.is_empty() && !text.is_empty() {
+                comment.push(' ');
+            }
+            comment.push_str(&text);
+            continue;
+        }
+
+        // a game may go on after a draw that a player could have claimed.
+        let (src, dst, promote) = crate::san::parse_san(&game.cursor.state, token).ok_or(PgnError::Move(index))?;
+        game.end = None;
+        game.play(src, dst, promote).map_err(|_| PgnError::Move(index))?;
+        history.push(game.cursor.state);
+
+        let suffix = token.trim_start_matches(|c: char| c != '!' && c != '?');
+        if let Some(nag) = crate::annotation::suffix_nag(suffix) {
+            add_nag(game.annotations.entry(index + 1).or_default(), nag);
+        }
+    }
+
+    game.settings = settings;
+    if let Some(clock) = settings.clock {
+        game.cursor.white_time = clock.total_ms();
+        game.cursor.black_time = clock.total_ms();
+        game.cursor.clock_is_ticking = true;
+    }
+
+    let last = game.cursor.state;
+    game.end = match result {
+        "*" => None,
+        _ if last.is_checkmate() => Some(EndCondition::Checkmate),
+        _ if last.is_stalemate() => Some(EndCondition::Stalemate),
+        "1-0" => Some(EndCondition::BlackResign),
+        "0-1" => Some(EndCondition::WhiteResign),
+        _ if last.halfmoves >= crate::end::SEVENTY_FIVE_MOVES => Some(EndCondition::SeventyFiveMoveRule),
+        _ if last.halfmoves >= crate::end::FIFTY_MOVES => Some(EndCondition::FiftyMoveRule),
+        _ if history.iter().filter(|pos| pos.is_repetition_of(&last)).count() >= 3 => Some(EndCondition::Repetition),
+        _ if crate::end::insufficient_material(&last) => Some(EndCondition::InsufficientMaterial),
+        _ => Some(EndCondition::Agreement),
+    };
+
+    Ok(game)
+}
+
+/// The tags that `from_pgn` reads into the game.
+pub(crate) const GAME_TAGS: [&str; 21] = [
+    "Event", "Site", "Date", "Round", "White", "Black", "Result", "WhiteElo", "BlackElo",
+    "Variant", "Chess960", "WormholeSpawn", "WormholeMax", "WormholeStart", "WormholeWait",
+    "WormholeQueue", "Seed", "UTCTime", "TimeControl", "SetUp", "FEN",
+];
+
+/// Every tag pair of the first game in the PGN string, in order.
+pub(crate) fn tags(pgn: &str) -> Result<Vec<(String, String)>, PgnError> {
+    Ok(split(pgn)?.0)
+}
+
+/// Split the first game into its tag pairs and its movetext.
+fn split(pgn: &str) -> Result<(Vec<(String, String)>, String), PgnError> {
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+    for (i, line) in pgn.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') && movetext.trim().is_empty() {
+            tags.push(parse_tag(line).ok_or(PgnError::Tag(i))?);
+        } else {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    Ok((tags, movetext))
+}
+
+/// A move may have both a suffix and the same glyph as a NAG.
+fn add_nag(annotation: &mut Annotation, nag: u8) {
+    if !annotation.nags.contains(&nag) {
+        annotation.nags.push(nag);
+    }
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+/// Split a tag pair like `[Event "F/S Return Match"]` into its name and value.
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.push(chars.next()?);
+        } else {
+            unescaped.push(c);
+        }
+    }
+
+    Some((name.to_string(), unescaped))
+}
+
+fn parse_flag(value: &str) -> Result<bool, PgnError> {
+    match value {
+        "0" | "false" => Ok(false),
+        "1" | "true" => Ok(true),
+        _ => Err(PgnError::Setting),
+    }
+}
+
+/// A missing tag is read as zero, the default for every wormhole count.
+fn parse_number(value: Option<&str>) -> Result<u8, PgnError> {
+    value.map_or(Ok(0), |value| value.parse().map_err(|_| PgnError::Setting))
+}
+
+fn parse_clock(control: &str, date: Option<&str>, time: Option<&str>) -> Result<ClockSettings, PgnError> {
+    let (total, bonus) = control.split_once('+').unwrap_or((control, "0"));
+    let date = date.and_then(|date| NaiveDate::parse_from_str(date, "%Y.%m.%d").ok()).unwrap_or_default();
+    let time = time.and_then(|time| NaiveTime::parse_from_str(time, "%H:%M:%S").ok()).unwrap_or_default();
+
+    Ok(ClockSettings {
+        start: date.and_time(time).and_utc(),
+        total: total.parse().map_err(|_| PgnError::Setting)?,
+        bonus: bonus.parse().map_err(|_| PgnError::Setting)?,
+    })
+}
+
+/// The SAN moves, comments, NAGs, and result in the movetext, without
+/// move numbers or variations. Comments keep their "{" or ";".
+fn movetext_tokens(movetext: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+    let mut chars = movetext.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let skipped = match c {
+            '{' | ';' => {
+                let close = if c == '{' { '}' } else { '\n' };
+                let mut end = movetext.len();
+                while chars.next_if(|&(_, c)| c != close).is_some() {}
+                if let Some((j, c)) = chars.next() {
+                    end = j + c.len_utf8();
+                }
+
+                if depth == 0 {
+                    if let Some(s) = start.take() {
+                        tokens.push(&movetext[s..i]);
+                    }
+                    tokens.push(&movetext[i..end]);
+                }
+                true
+            },
+            '(' => {
+                depth += 1;
+                true
+            },
+            ')' => {
+                depth -= 1;
+                true
+            },
+            _ => depth > 0 || c.is_whitespace(),
+        };
+
+        if skipped {
+            if let Some(s) = start.take() {
+                tokens.push(&movetext[s..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+
+    if let Some(s) = start {
+        tokens.push(&movetext[s..]);
+    }
+
+    tokens.into_iter()
+        .map(|token| if is_result(token) || token.starts_with(['{', ';']) {
+            token
+        } else {
+            token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+        })
+        .filter(|token| !token.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate};
+
+    use super::PgnError;
+    use crate::{annotation::{Arrow, Brush, Highlight}, end::EndCondition, game::ChessGame, meta::GameMeta, settings::{ClockSettings, GameLimits, GameSettings, WormholeSettings, WormholeSpawnMode}, state::BoardState};
+
+    #[test]
+    fn fools_mate() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "f2f3 e7e5 g2g4 d8h4").unwrap();
+        game.end = Some(EndCondition::Checkmate);
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Result \"0-1\"]"));
+        assert!(pgn.ends_with("\n\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+    }
+
+    #[test]
+    fn variant_round_trip() {
+        let settings = GameSettings {
+            is_chess960: true,
+            clock: None,
+            wormhole: WormholeSettings {
+                spawn_mode: WormholeSpawnMode::Mirror,
+                max_count: 6,
+                start_count: 4,
+                hole_wait_time: 3,
+                hole_queue_time: 255,
+            },
+            move_filter: None,
+            limits: GameLimits::default(),
+        };
+
+        let mut game = crate::sim::random_game(settings, 21);
+        game.settings.clock = Some(ClockSettings {
+            start: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            bonus: 2,
+            total: 180,
+        });
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Variant \"Maulstrom\"]"));
+        assert!(pgn.contains("[WormholeSpawn \"Mirror\"]"));
+
+        let imported = ChessGame::from_pgn(&pgn).unwrap();
+        assert_eq!(imported.settings, game.settings);
+        assert_eq!(imported.seed, game.seed);
+        assert_eq!(imported.deltas, game.deltas);
+        assert!(imported.start.is_repetition_of(&game.start));
+        assert!(imported.audit().is_ok());
+    }
+
+    #[test]
+    fn metadata_tags() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4").unwrap();
+        game.meta = Some(GameMeta {
+            white: Some("Anna \"The Hole\" Berg".to_string()),
+            black: Some("Ben".to_string()),
+            white_elo: Some(2012),
+            event: Some("Club Night".to_string()),
+            date: NaiveDate::from_ymd_opt(2024, 3, 9),
+            ..GameMeta::default()
+        });
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[White \"Anna \\\"The Hole\\\" Berg\"]"));
+        assert!(pgn.contains("[Date \"2024.03.09\"]"));
+        assert!(pgn.contains("[Site \"?\"]"));
+        assert!(pgn.contains("[WhiteElo \"2012\"]"));
+        assert!(!pgn.contains("BlackElo"));
+        assert_eq!(ChessGame::from_pgn(&pgn).unwrap().meta, game.meta);
+    }
+
+    #[test]
+    fn stream_with_long_comments() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3").unwrap();
+        let words = "a long comment that wraps onto more than one line of movetext ".repeat(3);
+        game.annotate(1).unwrap().comment = Some(format!("{words}[%clk 0:05:00] {words}[%cal Ge2e4]"));
+        game.annotate(2).unwrap().comment = Some("[Event \"not a tag\"]".to_string());
+
+        let pgn = game.to_pgn();
+        assert!(pgn.lines().skip_while(|line| line.starts_with('[')).any(|line| line.starts_with('[')));
+
+        // a ";" comment can hold a "{" without starting a brace comment.
+        let second = "[Event \"B\"]\n\n1. d4 ; {\n[Event \"C\"]\n\n1. c4 *\n";
+        let text = format!("{pgn}\n{pgn}\n{second}");
+        let games: Vec<_> = super::GameStream::new(text.as_bytes()).map(Result::unwrap).collect();
+        assert_eq!(games.len(), 4);
+        let expected = ChessGame::from_pgn(&pgn).unwrap();
+        assert!(expected.annotations[&1].comment.as_deref().is_some_and(|comment| comment.ends_with("of movetext")));
+        for imported in &games[..2] {
+            assert_eq!(imported.deltas, game.deltas);
+            assert_eq!(imported.annotations, expected.annotations);
+        }
+
+        assert_eq!(games[2].moves_uci(), "d2d4");
+        assert_eq!(games[3].moves_uci(), "c2c4");
+    }
+
+    #[test]
+    fn stream_of_games() {
+        let pgn = "[Event \"A\"]\n\n1. e4 e5 *\n\n[Event \"B\"]\n\n1. e4 e6 2. Ke3 *\n\n[Event \"C\"]\n[Result \"0-1\"]\n\n1. f3 e5\n2. g4 Qh4# 0-1\n";
+        let games: Vec<_> = super::GameStream::new(pgn.as_bytes()).collect();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].as_ref().unwrap().deltas.len(), 2);
+        assert!(matches!(games[1], Err(super::StreamError::Pgn(PgnError::Move(2)))));
+
+        let mate = games[2].as_ref().unwrap();
+        assert_eq!(mate.end, Some(EndCondition::Checkmate));
+        assert_eq!(mate.meta.as_ref().and_then(|meta| meta.event.as_deref()), Some("C"));
+    }
+
+    #[test]
+    fn import_skips_variations() {
+        let game = ChessGame::from_pgn(
+            "[Event \"Casual\"]\n[Result \"0-1\"]\n\n1. f3 {weak} e5 2. g4?? (2. e4) $4 2... Qh4# 0-1\n"
+        ).unwrap();
+
+        assert_eq!(game.deltas.len(), 4);
+        assert_eq!(game.end, Some(EndCondition::Checkmate));
+        assert_eq!(game.annotations[&3].nags, vec![4]);
+        assert_eq!(ChessGame::from_pgn("1. e4 e6 2. Ke3").err(), Some(PgnError::Move(2)));
+    }
+
+    #[test]
+    fn castling_captures_and_promotion() {
+        let game = ChessGame::from_uci_moves(
+            BoardState::default(),
+            "e2e4 d7d5 e4d5 g8f6 g1f3 c7c6 f1e2 c6c5 e1g1 b7b5 d5d6 b5b4 d6e7 b4b3 e7f8q"
+        ).unwrap();
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("3. Nf3 c6 4. Be2 c5 5. O-O b5"));
+        assert!(pgn.contains("exd5"));
+        assert!(pgn.contains("7. dxe7 b3 8."));
+        assert!(pgn.ends_with("exf8=Q+ *\n"));
+    }
+
+    #[test]
+    fn annotations() {
+        let pgn = "[Event \"?\"]\n\n{Opening notes} 1. e4! {Best by test} e5 $2 2. Nf3 (2. f4 exf4) ; quiet\nNc6 *\n";
+        let game = ChessGame::from_pgn(pgn).unwrap();
+        assert_eq!(game.deltas.len(), 4);
+        assert_eq!(game.annotations[&0].comment.as_deref(), Some("Opening notes"));
+        assert_eq!(game.annotations[&1].nags, vec![1]);
+        assert_eq!(game.annotations[&1].comment.as_deref(), Some("Best by test"));
+        assert_eq!(game.annotations[&2].nags, vec![2]);
+        assert_eq!(game.annotations[&3].comment.as_deref(), Some("quiet"));
+
+        let exported = game.to_pgn();
+        assert!(exported.contains("{Opening notes} 1. e4 $1 {Best by test} 1... e5 $2 2. Nf3 {quiet} 2... Nc6 *"));
+        assert_eq!(ChessGame::from_pgn(&exported).unwrap().annotations, game.annotations);
+    }
+
+    #[test]
+    fn drawings() {
+        let pgn = "1. e4 {[%csl Gd5,Rf7][%cal Gg1f3,Bd2d4] Plans [%clk 0:05:00]} e5 {[%cal Yb8c6]} *\n";
+        let game = ChessGame::from_pgn(pgn).unwrap();
+        let after_e4 = &game.annotations[&1];
+        assert_eq!(after_e4.comment.as_deref(), Some("Plans [%clk 0:05:00]"));
+        assert_eq!(after_e4.highlights, vec![
+            Highlight { square: "d5".parse().unwrap(), color: Brush::Green },
+            Highlight { square: "f7".parse().unwrap(), color: Brush::Red },
+        ]);
+        assert_eq!(after_e4.arrows[1], Arrow { from: "d2".parse().unwrap(), to: "d4".parse().unwrap(), color: Brush::Blue });
+        assert_eq!(game.annotations[&2].comment, None);
+        assert_eq!(game.annotations[&2].arrows.len(), 1);
+
+        let exported = game.to_pgn();
+        assert!(exported.contains("1. e4 {[%csl Gd5,Rf7][%cal Gg1f3,Bd2d4] Plans [%clk 0:05:00]} 1... e5 {[%cal\nYb8c6]} *"));
+        assert_eq!(ChessGame::from_pgn(&exported).unwrap().annotations, game.annotations);
+    }
+}
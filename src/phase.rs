@@ -0,0 +1,88 @@
+//! Game phase detection from the material left on the board.
+
+use crate::{board::BitBoard, state::BoardState, team::Team};
+
+/// The phase material in the start position: 1 per knight and
+/// bishop, 2 per rook, and 4 per queen. Archbishops and chancellors
+/// count as queens.
+pub const TOTAL_PHASE: u32 = 24;
+
+/// The most phase material an endgame can have, such as a rook
+/// and a minor piece each, or a queen each.
+pub const ENDGAME_PHASE: u32 = 8;
+
+/// The least phase material the opening can have, so at most
+/// one pair of minor pieces has been traded.
+pub const OPENING_PHASE: u32 = 22;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Stage {
+    Opening,
+    Middlegame,
+    Endgame,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct GamePhase {
+    pub stage: Stage,
+
+    /// How far the material has tapered off, from 0 with all pieces
+    /// on the board to 256 with only kings and pawns. Evaluations
+    /// blend their middlegame and endgame terms by this value.
+    pub taper: u32,
+}
+
+impl GamePhase {
+    /// Blend a middlegame and an endgame score by the taper.
+    pub fn blend(&self, middlegame: i32, endgame: i32) -> i32 {
+        let taper = self.taper as i32;
+        (middlegame * (256 - taper) + endgame * taper) / 256
+    }
+}
+
+/// The phase of the position. Promotions can add material, so the
+/// phase material is capped at `TOTAL_PHASE`. The opening also ends
+/// once either team has developed all but three pieces off its back rank.
+pub fn phase(state: &BoardState) -> GamePhase {
+    let pieces = &state.pieces;
+    let material = (pieces.knights.count() + pieces.bishops.count()) as u32
+        + pieces.rooks.count() as u32 * 2
+        + (pieces.queens | pieces.archbishops | pieces.chancellors).count() as u32 * 4;
+
+    let material = material.min(TOTAL_PHASE);
+    let taper = ((TOTAL_PHASE - material) * 256 + TOTAL_PHASE / 2) / TOTAL_PHASE;
+
+    let undeveloped = |team: Team| (pieces.on_team(team) & BitBoard::new().with_rank(team.back_rank())).count();
+    let stage = if material <= ENDGAME_PHASE {
+        Stage::Endgame
+    } else if material >= OPENING_PHASE && undeveloped(Team::White) >= 4 && undeveloped(Team::Black) >= 4 {
+        Stage::Opening
+    } else {
+        Stage::Middlegame
+    };
+
+    GamePhase { stage, taper }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stage;
+    use crate::{game::ChessGame, state::BoardState};
+
+    #[test]
+    fn phases() {
+        let start = BoardState::default().phase();
+        assert_eq!(start.stage, Stage::Opening);
+        assert_eq!(start.taper, 0);
+        assert_eq!(start.blend(100, -50), 100);
+
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6 e1g1 f8c5 d2d3 d7d6 c1g5 c8g4 b1c3 d8d7").unwrap();
+        assert_eq!(game.cursor.state.phase().stage, Stage::Middlegame);
+
+        let rooks = BoardState::from_fen("4k3/pp3r2/8/8/8/8/PP6/R3K3 w - - 0 40").unwrap().phase();
+        assert_eq!(rooks.stage, Stage::Endgame);
+        assert_eq!(rooks.taper, 213);
+
+        assert_eq!(BoardState::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap().phase().taper, 256);
+    }
+}
@@ -1,57 +1,151 @@
 use crate::{board::{BitBoard, BitBoardIter}, square::Square, team::Team};
 
+/// A piece kind. The discriminant is the piece's code, which is
+/// what deltas and other compact encodings store, and the index of
+/// the piece's entry in `Piece::KINDS`.
+/// 
+/// Codes are 4 bits wide, and `Piece::NONE` is reserved to mean
+/// "no piece", leaving codes 8 to 14 free for variant pieces. Adding a
+/// piece takes a new variant with the next code and its `PieceKind`.
+/// Deltas kept with the 3-bit codes from before, where Rook was 3 and
+/// King was 4, are read with `migrate::from_v0`.
+///
+/// The archbishop (bishop and knight) and the chancellor (rook and knight)
+/// are the fairy pieces of Capablanca chess. They move as both of their
+/// parts, and enter a game through its start position, since pawns do
+/// not promote to them.
+#[repr(u8)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 pub enum Piece {
-    Bishop,
-    Knight,
-    Queen,
-    King,
-    Rook,
-    Pawn,
+    Bishop = 0,
+    Knight = 1,
+    Queen = 2,
+    King = 3,
+    Rook = 4,
+    Pawn = 5,
+    Archbishop = 6,
+    Chancellor = 7,
+}
+
+/// The properties of a piece that do not depend on how it moves.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PieceKind {
+    pub piece: Piece,
+
+    /// The English name of the piece, such as "knight".
+    pub name: &'static str,
+
+    /// The letter of the piece in lowercase, as written for black in FEN.
+    pub letter: char,
+
+    /// The chess figurines for white and black.
+    pub figurines: [char; 2],
+
+    /// The conventional material value of the piece, in centipawns.
+    pub value: i32,
+
+    /// Whether a pawn can promote to the piece.
+    pub promotable: bool,
 }
 
 impl Piece {
+    /// Every piece, ordered by code.
+    pub const ALL: [Self; 8] = [
+        Self::Bishop,
+        Self::Knight,
+        Self::Queen,
+        Self::King,
+        Self::Rook,
+        Self::Pawn,
+        Self::Archbishop,
+        Self::Chancellor,
+    ];
+
+    /// The number of piece kinds.
+    pub const COUNT: usize = Self::ALL.len();
+
+    /// The code that encodes the absence of a piece.
+    pub const NONE: u8 = 0b1111;
+
+    /// The kind of every piece, ordered by code.
+    pub const KINDS: [PieceKind; Self::COUNT] = [
+        PieceKind { piece: Self::Bishop, name: "bishop", letter: 'b', figurines: ['♗', '♝'], value: 330, promotable: true },
+        PieceKind { piece: Self::Knight, name: "knight", letter: 'n', figurines: ['♘', '♞'], value: 320, promotable: true },
+        PieceKind { piece: Self::Queen, name: "queen", letter: 'q', figurines: ['♕', '♛'], value: 900, promotable: true },
+        PieceKind { piece: Self::King, name: "king", letter: 'k', figurines: ['♔', '♚'], value: 0, promotable: false },
+        PieceKind { piece: Self::Rook, name: "rook", letter: 'r', figurines: ['♖', '♜'], value: 500, promotable: true },
+        PieceKind { piece: Self::Pawn, name: "pawn", letter: 'p', figurines: ['♙', '♟'], value: 100, promotable: false },
+        PieceKind { piece: Self::Archbishop, name: "archbishop", letter: 'a', figurines: ['\u{1FA50}', '\u{1FA53}'], value: 830, promotable: false },
+        PieceKind { piece: Self::Chancellor, name: "chancellor", letter: 'c', figurines: ['\u{1FA4F}', '\u{1FA52}'], value: 860, promotable: false },
+    ];
+
+    /// The entry of the piece in `Piece::KINDS`.
+    pub const fn kind(&self) -> &'static PieceKind {
+        &Self::KINDS[*self as usize]
+    }
+
     pub fn to_char_lower(&self) -> char {
-        match self {
-            Self::Bishop => 'b',
-            Self::Knight => 'n',
-            Self::Queen => 'q',
-            Self::King => 'k',
-            Self::Rook => 'r',
-            Self::Pawn => 'p'
+        self.kind().letter
+    }
+
+    /// The piece letter as used in SAN and for white in FEN.
+    pub fn to_char_upper(&self) -> char {
+        self.to_char_lower().to_ascii_uppercase()
+    }
+
+    /// Parse a piece letter in either case, such as 'N' or 'n'.
+    /// In FEN, the case is the team: uppercase for white.
+    pub const fn from_char(c: char) -> Option<Self> {
+        let c = c.to_ascii_lowercase();
+        let mut i = 0;
+        while i < Self::COUNT {
+            if Self::KINDS[i].letter == c {
+                return Some(Self::KINDS[i].piece)
+            }
+            i += 1;
         }
+        None
     }
 
-    pub fn to_u8(&self) -> u8 {
-        match self {
-            Self::Bishop => 0,
-            Self::Knight => 1,
-            Self::Queen => 2,
-            Self::Rook => 3,
-            Self::King => 4,
-            Self::Pawn => 5,
+    /// The chess figurine for the piece, such as '♘' for a white knight.
+    pub fn to_unicode(&self, team: Team) -> char {
+        match team {
+            Team::White => self.kind().figurines[0],
+            Team::Black => self.kind().figurines[1],
         }
     }
 
-    pub fn from_u8(u: u8) -> Option<Self> {
-        Some(
-            match u {
-                0 => Self::Bishop,
-                1 => Self::Knight,
-                2 => Self::Queen,
-                3 => Self::Rook,
-                4 => Self::King,
-                5 => Self::Pawn,
-                _ => return None
-            }
-        )
+    pub const fn to_u8(&self) -> u8 {
+        *self as u8
     }
 
-    fn next(&self) -> Option<Self> {
-        Self::from_u8(self.to_u8() + 1)
+    /// Decode a piece code, returning None for `Piece::NONE`
+    /// or any code that is not assigned to a piece.
+    pub const fn from_u8(u: u8) -> Option<Self> {
+        if (u as usize) < Self::COUNT {
+            Some(Self::ALL[u as usize])
+        } else {
+            None
+        }
+    }
+
+    /// The conventional material value of the piece, in centipawns.
+    /// The king has no material value.
+    pub const fn value(&self) -> i32 {
+        self.kind().value
+    }
+
+    /// Whether a pawn can promote to this piece.
+    pub const fn is_promotable(&self) -> bool {
+        self.kind().promotable
     }
 }
 
+/// The pieces on the board, as one bitboard per piece kind and per team.
+///
+/// With the `mailbox` feature, a 64-nibble array of piece codes is kept
+/// in sync with the bitboards so `piece_at` is a single lookup. Code that
+/// writes to the bitboards directly must call `sync_mailbox` afterwards.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct Pieces {
     pub bishops: BitBoard,
@@ -60,21 +154,55 @@ pub struct Pieces {
     pub kings: BitBoard,
     pub rooks: BitBoard,
     pub pawns: BitBoard,
+    pub archbishops: BitBoard,
+    pub chancellors: BitBoard,
     pub white: BitBoard,
     pub black: BitBoard,
+
+    #[cfg(feature = "mailbox")]
+    mailbox: Mailbox,
 }   
 
 impl Pieces {
-    pub fn just_pawns() -> Self {
+    /// A board with no pieces.
+    pub fn empty() -> Self {
         Self {
             bishops: BitBoard::new(),
             knights: BitBoard::new(),
             queens: BitBoard::new(),
             kings: BitBoard::new(),
             rooks: BitBoard::new(),
+            pawns: BitBoard::new(),
+            archbishops: BitBoard::new(),
+            chancellors: BitBoard::new(),
+            white: BitBoard::new(),
+            black: BitBoard::new(),
+            #[cfg(feature = "mailbox")]
+            mailbox: Mailbox::EMPTY,
+        }
+    }
+
+    pub fn just_pawns() -> Self {
+        let mut pieces = Self {
             pawns: BitBoard::new().with_rank_u8(1).with_rank_u8(6),
             white: BitBoard::new().with_rank_u8(1),
             black: BitBoard::new().with_rank_u8(6),
+            ..Self::empty()
+        };
+        pieces.sync_mailbox();
+        pieces
+    }
+
+    /// Rebuild the mailbox from the bitboards. This is only needed after
+    /// writing to the bitboards directly, and does nothing without the
+    /// `mailbox` feature.
+    pub fn sync_mailbox(&mut self) {
+        #[cfg(feature = "mailbox")]
+        {
+            self.mailbox = Mailbox::EMPTY;
+            for (_, pc, sq) in self.iter() {
+                self.mailbox.set(sq, pc.to_u8());
+            }
         }
     }
 
@@ -90,6 +218,21 @@ impl Pieces {
         self.white | self.black
     }
 
+    /// The pieces of both teams that slide diagonally.
+    pub fn diagonal_sliders(&self) -> BitBoard {
+        self.bishops | self.queens | self.archbishops
+    }
+
+    /// The pieces of both teams that slide along ranks and files.
+    pub fn orthogonal_sliders(&self) -> BitBoard {
+        self.rooks | self.queens | self.chancellors
+    }
+
+    /// The pieces of both teams that leap like a knight.
+    pub fn leapers(&self) -> BitBoard {
+        self.knights | self.archbishops | self.chancellors
+    }
+
     pub fn on_team(&self, team: Team) -> BitBoard {
         match team {
             Team::White => self.white,
@@ -97,6 +240,12 @@ impl Pieces {
         }
     }
 
+    #[cfg(feature = "mailbox")]
+    pub fn piece_at(&self, at: Square) -> Option<Piece> {
+        self.mailbox.get(at)
+    }
+
+    #[cfg(not(feature = "mailbox"))]
     pub fn piece_at(&self, at: Square) -> Option<Piece> {
         if !(self.white | self.black).has(at) { return None }
         if self.bishops.has(at) { return Some(Piece::Bishop) }
@@ -105,11 +254,18 @@ impl Pieces {
         if self.kings.has(at) { return Some(Piece::King) }
         if self.rooks.has(at) { return Some(Piece::Rook) }
         if self.pawns.has(at) { return Some(Piece::Pawn) }
+        if self.archbishops.has(at) { return Some(Piece::Archbishop) }
+        if self.chancellors.has(at) { return Some(Piece::Chancellor) }
         None
     }
 
     /// If holes has at, then every hole is searched for a piece.
     pub fn piece_at_or_on_hole(&self, at: Square, holes: BitBoard) -> Option<Piece> {
+        #[cfg(feature = "mailbox")]
+        if holes.has(at) {
+            return ((self.white | self.black) & holes).first().and_then(|sq| self.mailbox.get(sq))
+        }
+
         if holes.has(at) {
             if !(self.white | self.black).intersects(holes) { return None }
             if self.bishops.intersects(holes) { return Some(Piece::Bishop) }
@@ -118,6 +274,8 @@ impl Pieces {
             if self.kings.intersects(holes) { return Some(Piece::King) }
             if self.rooks.intersects(holes) { return Some(Piece::Rook) }
             if self.pawns.intersects(holes) { return Some(Piece::Pawn) }
+            if self.archbishops.intersects(holes) { return Some(Piece::Archbishop) }
+            if self.chancellors.intersects(holes) { return Some(Piece::Chancellor) }
             None
         } else {
             self.piece_at(at)
@@ -128,6 +286,8 @@ impl Pieces {
     /// all wormhole squares are checked.
     pub fn remove(&mut self, at: Square, holes: BitBoard) -> Option<Piece> {
         let sqs = if holes.has(at) { holes } else { BitBoard::from(at) };
+        #[cfg(feature = "mailbox")]
+        self.mailbox.clear_all(sqs & (self.white | self.black));
         if self.white.intersects(sqs) { self.white.clear_all(sqs); }
         if self.black.intersects(sqs) { self.black.clear_all(sqs); }
         if self.bishops.intersects(sqs) { return self.bishops.clear_all(sqs).then_some(Piece::Bishop); }
@@ -136,6 +296,8 @@ impl Pieces {
         if self.kings.intersects(sqs) { return self.kings.clear_all(sqs).then_some(Piece::King) }
         if self.rooks.intersects(sqs) { return self.rooks.clear_all(sqs).then_some(Piece::Rook) }
         if self.pawns.intersects(sqs) { return self.pawns.clear_all(sqs).then_some(Piece::Pawn) }
+        if self.archbishops.intersects(sqs) { return self.archbishops.clear_all(sqs).then_some(Piece::Archbishop) }
+        if self.chancellors.intersects(sqs) { return self.chancellors.clear_all(sqs).then_some(Piece::Chancellor) }
         None
     }
 
@@ -143,6 +305,8 @@ impl Pieces {
     pub fn insert(&mut self, at: Square, pc: Piece, team: Team, holes: BitBoard) {
         let i = if holes.has(at) { holes | at } else { at.into() };
         if (self.white | self.black).intersects(i) {
+            #[cfg(feature = "mailbox")]
+            self.mailbox.clear_all(i & (self.white | self.black));
             let j = !i;
             self.white &= j;
             self.black &= j;
@@ -152,6 +316,8 @@ impl Pieces {
             self.kings &= j;
             self.rooks &= j;
             self.pawns &= j;
+            self.archbishops &= j;
+            self.chancellors &= j;
         }
 
         match team {
@@ -165,7 +331,12 @@ impl Pieces {
             Piece::King => self.kings |= at,
             Piece::Rook => self.rooks |= at,
             Piece::Pawn => self.pawns |= at,
+            Piece::Archbishop => self.archbishops |= at,
+            Piece::Chancellor => self.chancellors |= at,
         }
+
+        #[cfg(feature = "mailbox")]
+        self.mailbox.set(at, pc.to_u8());
     }
 
     fn index(&self, piece: Piece) -> BitBoard {
@@ -176,6 +347,8 @@ impl Pieces {
             Piece::Rook => self.rooks,
             Piece::King => self.kings,
             Piece::Pawn => self.pawns,
+            Piece::Archbishop => self.archbishops,
+            Piece::Chancellor => self.chancellors,
         }
     }
 
@@ -190,10 +363,17 @@ impl Pieces {
             Piece::King => &mut self.kings,
             Piece::Rook => &mut self.rooks,
             Piece::Pawn => &mut self.pawns,
+            Piece::Archbishop => &mut self.archbishops,
+            Piece::Chancellor => &mut self.chancellors,
         } |= sqs;
+
+        #[cfg(feature = "mailbox")]
+        for sq in sqs {
+            self.mailbox.set(sq, piece.to_u8());
+        }
     }
 
-    pub fn as_array(&self) -> [(Team, Piece, BitBoard); 12] {
+    pub fn as_array(&self) -> [(Team, Piece, BitBoard); 16] {
         [
             (Team::White, Piece::Bishop, self.bishops & self.white),
             (Team::Black, Piece::Bishop, self.bishops & self.black),
@@ -206,10 +386,54 @@ impl Pieces {
             (Team::White, Piece::King, self.kings & self.white),
             (Team::Black, Piece::King, self.kings & self.black),
             (Team::White, Piece::Pawn, self.pawns & self.white),
-            (Team::Black, Piece::Pawn, self.pawns & self.black)
+            (Team::Black, Piece::Pawn, self.pawns & self.black),
+            (Team::White, Piece::Archbishop, self.archbishops & self.white),
+            (Team::Black, Piece::Archbishop, self.archbishops & self.black),
+            (Team::White, Piece::Chancellor, self.chancellors & self.white),
+            (Team::Black, Piece::Chancellor, self.chancellors & self.black),
         ]
     }
 
+    /// Swap the colors of every piece and mirror the board vertically.
+    pub fn flipped(&self) -> Self {
+        let mut pieces = Self {
+            bishops: self.bishops.flip_vertical(),
+            knights: self.knights.flip_vertical(),
+            queens: self.queens.flip_vertical(),
+            kings: self.kings.flip_vertical(),
+            rooks: self.rooks.flip_vertical(),
+            pawns: self.pawns.flip_vertical(),
+            archbishops: self.archbishops.flip_vertical(),
+            chancellors: self.chancellors.flip_vertical(),
+            white: self.black.flip_vertical(),
+            black: self.white.flip_vertical(),
+            #[cfg(feature = "mailbox")]
+            mailbox: Mailbox::EMPTY,
+        };
+        pieces.sync_mailbox();
+        pieces
+    }
+
+    /// Mirror every piece horizontally, keeping its color.
+    pub fn mirrored(&self) -> Self {
+        let mut pieces = Self {
+            bishops: self.bishops.flip_horizontal(),
+            knights: self.knights.flip_horizontal(),
+            queens: self.queens.flip_horizontal(),
+            kings: self.kings.flip_horizontal(),
+            rooks: self.rooks.flip_horizontal(),
+            pawns: self.pawns.flip_horizontal(),
+            archbishops: self.archbishops.flip_horizontal(),
+            chancellors: self.chancellors.flip_horizontal(),
+            white: self.white.flip_horizontal(),
+            black: self.black.flip_horizontal(),
+            #[cfg(feature = "mailbox")]
+            mailbox: Mailbox::EMPTY,
+        };
+        pieces.sync_mailbox();
+        pieces
+    }
+
     pub fn iter(&self) -> PiecesIter {
         PiecesIter {
             data: self.as_array(),
@@ -221,22 +445,57 @@ impl Pieces {
 
 impl Default for Pieces {
     fn default() -> Self {
-        Self {
+        let mut pieces = Self {
             knights: BitBoard(0x4200000000000042),
             bishops: BitBoard(0x2400000000000024),
             queens: BitBoard(0x800000000000008),
             kings: BitBoard(0x1000000000000010),
             pawns: BitBoard(0x00FF00000000FF00),
             rooks: BitBoard(0x8100000000000081),
+            archbishops: BitBoard::new(),
+            chancellors: BitBoard::new(),
             white: BitBoard(0x000000000000FFFF),
             black: BitBoard(0xFFFF000000000000),
+            #[cfg(feature = "mailbox")]
+            mailbox: Mailbox::EMPTY,
+        };
+        pieces.sync_mailbox();
+        pieces
+    }
+}
+
+/// Piece codes for every square, two squares per byte.
+#[cfg(feature = "mailbox")]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Mailbox([u8; 32]);
+
+#[cfg(feature = "mailbox")]
+impl Mailbox {
+    const EMPTY: Self = Self([(Piece::NONE << 4) | Piece::NONE; 32]);
+
+    #[inline(always)]
+    fn get(&self, sq: Square) -> Option<Piece> {
+        let i = sq.to_index();
+        Piece::from_u8((self.0[i >> 1] >> ((i & 1) * 4)) & 0xF)
+    }
+
+    #[inline(always)]
+    fn set(&mut self, sq: Square, code: u8) {
+        let i = sq.to_index();
+        let shift = (i & 1) * 4;
+        self.0[i >> 1] = (self.0[i >> 1] & !(0xF << shift)) | (code << shift);
+    }
+
+    fn clear_all(&mut self, sqs: BitBoard) {
+        for sq in sqs {
+            self.set(sq, Piece::NONE);
         }
     }
 }
 
 #[derive(Copy, Clone)]
 pub struct PiecesIter {
-    pub data: [(Team, Piece, BitBoard); 12],
+    pub data: [(Team, Piece, BitBoard); 16],
     pub iter: BitBoardIter,
     pub curr: usize, 
 }
@@ -258,4 +517,106 @@ impl Iterator for PiecesIter {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{Piece, Pieces};
+    use crate::{board::BitBoard, delta::BoardDelta, game::ChessGame, positions, square::Square, team::Team};
+
+    #[test]
+    fn char_conversions() {
+        for pc in Piece::ALL {
+            assert_eq!(Piece::from_char(pc.to_char_lower()), Some(pc));
+            assert_eq!(Piece::from_char(pc.to_char_upper()), Some(pc));
+            assert_ne!(pc.to_unicode(Team::White), pc.to_unicode(Team::Black));
+        }
+
+        assert_eq!(Piece::from_char('x'), None);
+        assert_eq!(Piece::Knight.to_unicode(Team::Black), '♞');
+    }
+
+    #[test]
+    fn codes() {
+        // stored deltas depend on these codes, so they must never change.
+        let codes: Vec<u8> = Piece::ALL.iter().map(Piece::to_u8).collect();
+        assert_eq!(codes, [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!((Piece::King.to_u8(), Piece::Rook.to_u8()), (3, 4));
+
+        for pc in Piece::ALL {
+            assert_eq!(Piece::from_u8(pc.to_u8()), Some(pc));
+            assert!(pc.to_u8() < Piece::NONE);
+        }
+
+        // codes 8..=14 are free, and NONE fits in the 4-bit fields of a delta.
+        assert_eq!(Piece::from_u8(8), None);
+        assert_eq!(Piece::from_u8(Piece::NONE), None);
+        assert_eq!(Piece::NONE, 0b1111);
+        assert_eq!(Piece::COUNT, 8);
+
+        for pc in Piece::ALL {
+            assert_eq!(pc.kind().piece, pc);
+            assert_eq!(Piece::from_char(pc.kind().letter), Some(pc));
+        }
+
+        let promotable: Vec<Piece> = Piece::ALL.into_iter().filter(Piece::is_promotable).collect();
+        assert_eq!(promotable, [Piece::Bishop, Piece::Knight, Piece::Queen, Piece::Rook]);
+
+        // a delta stores NONE in each piece field that has no piece.
+        let mut delta = BoardDelta::default();
+        assert_eq!(u16::from_le_bytes(delta.pack()[12..14].try_into().unwrap()), 0xFFF);
+        delta.set_promote_pc(Piece::King);
+        assert_eq!(delta.get_promote_pc(), None);
+        delta.set_capture_pc(Piece::Rook);
+        assert_eq!(delta.pack()[12] & 0xF, Piece::Rook.to_u8());
+    }
+
+    /// Check every square against the bitboards, which is what the
+    /// mailbox must agree with when the `mailbox` feature is enabled.
+    fn assert_in_sync(pieces: &Pieces, holes: BitBoard) {
+        for i in 0..64 {
+            let sq = Square::from_index(i);
+            let expected = pieces.as_array().into_iter().find(|(_, _, bb)| bb.has(sq)).map(|(_, pc, _)| pc);
+            assert_eq!(pieces.piece_at(sq), expected, "{sq}");
+            if !holes.has(sq) {
+                assert_eq!(pieces.piece_at_or_on_hole(sq, holes), expected, "{sq}");
+            }
+        }
+    }
+
+    #[test]
+    fn piece_at() {
+        let games = [
+            (positions::standard(), "e2e4 d7d5 e4d5 c7c5 d5c6 d8d2 b1d2 b7c6"),
+            (positions::kiwipete(), "e1g1 e8c8 d5e6 b4b3 e6f7 b3a2 f7f8q d8f8"),
+            (positions::wormhole_start(), "c2c3 e7e5 c3c4 f7f6 e2e4 f6f5 e4f5"),
+        ];
+
+        for (start, moves) in games {
+            let game = ChessGame::from_uci_moves(start, moves).unwrap();
+            for (_, state) in game.positions() {
+                assert_in_sync(&state.pieces, state.wormholes);
+                assert_in_sync(&state.pieces.flipped(), state.wormholes.flip_vertical());
+                assert_in_sync(&state.pieces.mirrored(), state.wormholes.flip_horizontal());
+            }
+        }
+
+        let holes = BitBoard::from(Square::from_index(18)) | Square::from_index(45);
+        let mut pieces = Pieces::empty();
+        assert_in_sync(&pieces, holes);
+        pieces.insert(Square::from_index(18), Piece::Knight, Team::White, holes);
+        assert_eq!(pieces.piece_at_or_on_hole(Square::from_index(45), holes), Some(Piece::Knight));
+        pieces.insert(Square::from_index(45), Piece::Queen, Team::Black, holes);
+        assert_in_sync(&pieces, holes);
+        assert_eq!(pieces.piece_at_or_on_hole(Square::from_index(18), holes), Some(Piece::Queen));
+        pieces.remove(Square::from_index(18), holes);
+        assert_in_sync(&pieces, holes);
+        assert!(pieces.occupied().is_empty());
+
+        // bitboards written directly are picked up after a sync.
+        pieces.rooks.set(Square::from_index(0));
+        pieces.white.set(Square::from_index(0));
+        pieces.sync_mailbox();
+        assert_in_sync(&pieces, holes);
+        assert_eq!(pieces.piece_at(Square::from_index(0)), Some(Piece::Rook));
+    }
+}
@@ -0,0 +1,104 @@
+//! Named positions, so tests and examples in this crate and downstream
+//! share the same fixtures.
+//!
+//! Each position is available as its FEN string, extended FEN for the
+//! wormhole setups, and as a constructor. The perft positions are from
+//! the Chess Programming Wiki.
+
+use crate::{castle::CastleRights, pieces::Pieces, state::BoardState};
+
+/// The standard starting position.
+pub const STANDARD: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Only the two kings, on their starting squares.
+pub const KINGS_ONLY: &str = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+
+/// The second perft position, with castling on both sides, pins, and
+/// en passant available after most pawn pushes.
+pub const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+/// The fourth perft position, where white starts in check and
+/// promotions and castling through attacked squares are close.
+pub const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+
+/// The fifth perft position, with a promotion by capture and a
+/// knight checking from f2.
+pub const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+/// The standard start with four wormholes mirrored across the middle of the board.
+pub const WORMHOLE_START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 c3,c6,f3,f6 - 0";
+
+/// Black is in check from the rook on a1, which enters the
+/// wormhole on a4 and leaves through the one on e4.
+pub const WORMHOLE_CHECK: &str = "4k3/8/8/8/8/8/8/R3K3 b - - 0 1 a4,e4 - 0";
+
+pub fn standard() -> BoardState {
+    BoardState::default()
+}
+
+/// A board with no pieces, no castling rights, and white to move,
+/// for setting up positions piece by piece. It has no kings, so it
+/// cannot be played from until they are placed.
+pub fn empty() -> BoardState {
+    BoardState {
+        pieces: Pieces::empty(),
+        castle: CastleRights { rights: 0, ..CastleRights::default() },
+        ..BoardState::default()
+    }
+}
+
+pub fn kings_only() -> BoardState {
+    named(KINGS_ONLY)
+}
+
+pub fn kiwipete() -> BoardState {
+    named(KIWIPETE)
+}
+
+pub fn position_4() -> BoardState {
+    named(POSITION_4)
+}
+
+pub fn position_5() -> BoardState {
+    named(POSITION_5)
+}
+
+pub fn wormhole_start() -> BoardState {
+    named(WORMHOLE_START)
+}
+
+pub fn wormhole_check() -> BoardState {
+    named(WORMHOLE_CHECK)
+}
+
+/// Parse one of the FEN constants above, which are all valid.
+fn named(fen: &str) -> BoardState {
+    BoardState::from_extended_fen(fen).expect("named positions are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::BoardState;
+
+    #[test]
+    fn named_positions() {
+        assert!(super::standard().is_repetition_of(&BoardState::from_fen(super::STANDARD).unwrap()));
+        assert!(super::empty().pieces.occupied().is_empty());
+        assert_eq!(super::kings_only().pieces.occupied().count(), 2);
+
+        let castles = super::kiwipete().legal_moves().into_iter().filter(|(_, _, trace)| trace.is_castle.is_some()).count();
+        assert_eq!(castles, 2);
+
+        // the first perft depth of each position.
+        assert_eq!(super::standard().legal_moves().len(), 20);
+        assert_eq!(super::kiwipete().legal_moves().len(), 48);
+        assert!(super::position_4().is_check());
+        assert_eq!(super::position_4().legal_moves().len(), 6);
+        let perft = |state: &BoardState| state.legal_moves().iter().map(|(_, _, trace)| if trace.requires_promotion { 4 } else { 1 }).sum::<usize>();
+        assert_eq!(perft(&super::position_5()), 44);
+        assert!(super::position_5().legal_moves().iter().any(|(_, _, trace)| trace.requires_promotion && trace.captures.is_some()));
+
+        assert_eq!(super::wormhole_start().wormholes.count(), 4);
+        assert!(super::wormhole_check().is_check());
+    }
+}
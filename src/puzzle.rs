@@ -0,0 +1,98 @@
+//! Checks that a puzzle has exactly one solution.
+//!
+//! A solution alternates between the solver's moves and the opponent's
+//! replies, starting with the solver. At each of the solver's moves,
+//! the solution move and the best of every other move are searched
+//! separately, and the solution must beat the alternative by `MARGIN`.
+
+use crate::{delta::BoardDelta, pieces::Piece, search::SearchOptions, square::Square, state::BoardState, uci::UciError};
+
+/// How many centipawns the solution move must score above every other
+/// move for it to be the only solution.
+pub const MARGIN: i32 = 150;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PuzzleError {
+    /// The solution could not be parsed or is not legal.
+    Uci(UciError),
+
+    /// Another move scores better than the solver's move at this halfmove index.
+    NotBest {
+        index: usize,
+        alternative: BoardDelta,
+    },
+
+    /// Another move scores within the margin of the solver's move at this
+    /// halfmove index, so the puzzle has more than one solution.
+    Ambiguous {
+        index: usize,
+        alternative: BoardDelta,
+    },
+}
+
+/// Verify that the solution, a whitespace-separated list of moves in
+/// coordinate notation, is the only winning line from the position,
+/// searching each of the solver's moves with the engine options.
+pub fn verify(state: &BoardState, solution: &str, engine: &SearchOptions) -> Result<(), PuzzleError> {
+    let game = crate::uci::from_uci_moves(*state, solution).map_err(PuzzleError::Uci)?;
+
+    for ((index, state), delta) in game.positions().zip(&game.deltas).step_by(2) {
+        let played = (delta.get_src_sq(), delta.get_dst_sq(), delta.get_promote_pc());
+        let others: Vec<_> = candidates(&state).into_iter().filter(|&mv| mv != played).collect();
+        if others.is_empty() {
+            continue;
+        }
+
+        let best = state.search(&SearchOptions { searchmoves: vec![played], ..engine.clone() });
+        let rest = state.search(&SearchOptions { searchmoves: others, ..engine.clone() });
+        let Some(alternative) = rest.best else {
+            continue;
+        };
+
+        if rest.score > best.score {
+            return Err(PuzzleError::NotBest { index, alternative })
+        }
+
+        if rest.score > best.score - MARGIN {
+            return Err(PuzzleError::Ambiguous { index, alternative })
+        }
+    }
+
+    Ok(())
+}
+
+/// Every legal move in the position, with each promotion listed separately.
+fn candidates(state: &BoardState) -> Vec<(Square, Square, Option<Piece>)> {
+    let mut moves = Vec::new();
+    for (src, dst, trace) in state.legal_moves() {
+        if trace.requires_promotion {
+            moves.extend(Piece::ALL.into_iter().filter(Piece::is_promotable).map(|pc| (src, dst, Some(pc))));
+        } else {
+            moves.push((src, dst, None));
+        }
+    }
+
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, PuzzleError};
+    use crate::{search::SearchOptions, state::BoardState, uci::UciError};
+
+    #[test]
+    fn unique_solutions() {
+        let engine = SearchOptions { depth: 2, ..SearchOptions::default() };
+        let back_rank = BoardState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(verify(&back_rank, "a1a8", &engine), Ok(()));
+        assert!(matches!(verify(&back_rank, "a1a2", &engine), Err(PuzzleError::NotBest { index: 0, .. })));
+        assert_eq!(verify(&back_rank, "a1a9", &engine), Err(PuzzleError::Uci(UciError::Malformed(0))));
+
+        // either rook mates.
+        let two_rooks = BoardState::from_fen("6k1/5ppp/8/8/8/8/8/RR4K1 w - - 0 1").unwrap();
+        let Err(PuzzleError::Ambiguous { index: 0, alternative }) = verify(&two_rooks, "a1a8", &engine) else {
+            panic!("expected the b1 rook to mate as well");
+        };
+        assert_eq!(alternative.to_uci(), "b1b8");
+    }
+}
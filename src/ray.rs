@@ -64,14 +64,14 @@ pub const fn neg_pos(sq: Square, occ: BitBoard) -> BitBoard {
 pub const fn truncate_lt_if_hit(ray: u64, occ: u64) -> Option<u64> {
     let o = ray & occ;
     if o == 0 { None } else {
-        Some(ray & (!0u64 >> 63 - o.trailing_zeros()))
+        Some(ray & (!0u64 >> (63 - o.trailing_zeros())))
     }
 }
 
 pub const fn truncate_gt_if_hit(ray: u64, occ: u64) -> Option<u64> {
     let o = ray & occ;
     if o == 0 { None } else {
-        Some(ray & (!0u64 << 63 - o.leading_zeros()))
+        Some(ray & (!0u64 << (63 - o.leading_zeros())))
     }
 }
 
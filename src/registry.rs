@@ -0,0 +1,140 @@
+//! A collection of games and branches, looked up by `game_id`.
+//!
+//! Branches only name their parent by id, so following a branch back
+//! to the game it came from needs every game along the way. The registry
+//! holds them, and reports parents that are missing or that loop.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::game::ChessGame;
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RegistryError {
+    /// The game is not a branch, so it has no parent.
+    NotABranch,
+
+    /// No game in the registry has this parent id.
+    Orphan(u64),
+
+    /// Following parents from the game returns to this id.
+    Cycle(u64),
+}
+
+#[derive(Clone, Default)]
+pub struct GameRegistry {
+    games: HashMap<u64, ChessGame>,
+}
+
+impl GameRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a game, returning the game it replaced if one had the same id.
+    pub fn insert(&mut self, game: ChessGame) -> Option<ChessGame> {
+        self.games.insert(game.game_id, game)
+    }
+
+    pub fn remove(&mut self, game_id: u64) -> Option<ChessGame> {
+        self.games.remove(&game_id)
+    }
+
+    pub fn get(&self, game_id: u64) -> Option<&ChessGame> {
+        self.games.get(&game_id)
+    }
+
+    pub fn get_mut(&mut self, game_id: u64) -> Option<&mut ChessGame> {
+        self.games.get_mut(&game_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Every game in the registry, in no particular order.
+    pub fn games(&self) -> impl Iterator<Item = &ChessGame> {
+        self.games.values()
+    }
+
+    /// The game the branch was branched from.
+    pub fn resolve(&self, branch: &ChessGame) -> Result<&ChessGame, RegistryError> {
+        let parent_id = branch.is_branch.ok_or(RegistryError::NotABranch)?.parent_id;
+        self.get(parent_id).ok_or(RegistryError::Orphan(parent_id))
+    }
+
+    /// The parents of the game, nearest first, ending with the root
+    /// game that is not a branch. Empty if the game is not a branch.
+    pub fn ancestors<'a>(&'a self, game: &'a ChessGame) -> Result<Vec<&'a ChessGame>, RegistryError> {
+        let mut seen = HashSet::from([game.game_id]);
+        let mut ancestors = Vec::new();
+        let mut current = game;
+
+        while current.is_branch.is_some() {
+            current = self.resolve(current)?;
+            if !seen.insert(current.game_id) {
+                return Err(RegistryError::Cycle(current.game_id))
+            }
+
+            ancestors.push(current);
+        }
+
+        Ok(ancestors)
+    }
+
+    /// The root game that the game was branched from, through any number
+    /// of branches. A game that is not a branch is its own root.
+    pub fn root<'a>(&'a self, game: &'a ChessGame) -> Result<&'a ChessGame, RegistryError> {
+        Ok(self.ancestors(game)?.pop().unwrap_or(game))
+    }
+
+    /// The ids of the branches whose parent is not in the registry.
+    pub fn orphans(&self) -> Vec<u64> {
+        self.games
+            .values()
+            .filter(|game| matches!(self.resolve(game), Err(RegistryError::Orphan(_))))
+            .map(|game| game.game_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameRegistry, RegistryError};
+    use crate::{game::ChessGame, state::BoardState};
+
+    #[test]
+    fn resolve_to_root() {
+        let mut root = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5").unwrap();
+        root.goto_start();
+        let mut branch = root.play_uci("d2d4").ok().and_then(|success| success.branch).unwrap();
+        assert!(branch.play_uci("d7d5").is_ok());
+        branch.goto_start();
+        let nested = branch.play_uci("g8f6").ok().and_then(|success| success.branch).unwrap();
+
+        let mut registry = GameRegistry::new();
+        for game in [&root, &branch, &nested] {
+            registry.insert(game.clone());
+        }
+
+        assert_eq!(registry.resolve(&nested).unwrap().game_id, branch.game_id);
+        assert_eq!(registry.root(&nested).unwrap().game_id, root.game_id);
+        assert_eq!(registry.root(&root).unwrap().game_id, root.game_id);
+        assert_eq!(registry.ancestors(&nested).unwrap().len(), 2);
+        assert_eq!(registry.resolve(&root).err(), Some(RegistryError::NotABranch));
+        assert!(registry.orphans().is_empty());
+
+        registry.remove(branch.game_id);
+        assert_eq!(registry.root(&nested).err(), Some(RegistryError::Orphan(branch.game_id)));
+        assert_eq!(registry.orphans(), vec![nested.game_id]);
+
+        // a branch whose parent is its own child.
+        let mut looped = branch.clone();
+        looped.is_branch.as_mut().unwrap().parent_id = nested.game_id;
+        registry.insert(looped);
+        assert_eq!(registry.root(&nested).err(), Some(RegistryError::Cycle(nested.game_id)));
+    }
+}
@@ -1,6 +1,7 @@
 use std::ops::Range;
 
-#[cfg(not(target_arch = "wasm32"))]
+/// A random seed from the system source. On wasm32 this is
+/// `crypto.getRandomValues`, through getrandom's `wasm_js` backend.
 pub fn entropy() -> u64 {
     match getrandom::u64() {
         Ok(v) => v,
@@ -11,12 +12,6 @@ pub fn entropy() -> u64 {
     }
 }
 
-#[cfg(target_arch = "wasm32")]
-pub fn entropy() -> u64 {
-    let now = web_time::Instant::now().elapsed().subsec_millis() as u64;
-    now.wrapping_mul(0xa076_1d64_78bd_642f)
-}
-
 /// Wasm-compatible Wyrand.
 #[derive(Copy, Clone)]
 pub struct WyRand {
@@ -36,7 +31,12 @@ impl WyRand {
         ((r >> 64) ^ r) as u64
     }
 
+    /// A random number in the range, or `range.start` if it is empty.
     pub fn range(&mut self, range: Range<usize>) -> u64 {
+        if range.is_empty() {
+            return range.start as u64
+        }
+
         (self.next() % (range.end as u64 - range.start as u64)) + range.start as u64
     }
 
@@ -46,3 +46,22 @@ impl WyRand {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn seeds_are_distinct_and_use_every_bit() {
+        let seeds: Vec<u64> = (0..64).map(|_| super::entropy()).collect();
+
+        let mut sorted = seeds.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), seeds.len());
+
+        // every bit should be set in some seed and clear in another.
+        let ones = seeds.iter().fold(0, |acc, seed| acc | seed);
+        let zeros = seeds.iter().fold(0, |acc, seed| acc | !seed);
+        assert_eq!(ones, !0);
+        assert_eq!(zeros, !0);
+    }
+}
@@ -0,0 +1,182 @@
+//! Standard Algebraic Notation.
+
+use std::fmt::Write;
+
+use crate::{castle::Castle, delta::BoardDelta, pieces::Piece, square::Square, state::BoardState};
+
+/// The Standard Algebraic Notation of a delta played in this position,
+/// with the piece letter, disambiguation, captures, promotion, and +/#.
+pub fn to_san(state: &BoardState, delta: BoardDelta) -> String {
+    let mut san = String::new();
+    let src = delta.get_src_sq();
+    let dst = delta.get_dst_sq();
+
+    if let Some(side) = delta.get_castle_side() {
+        san.push_str(match side {
+            Castle::Short => "O-O",
+            Castle::Long => "O-O-O",
+        });
+    } else {
+        let piece = state.pieces.piece_at_or_on_hole(src, state.wormholes).unwrap_or(Piece::Pawn);
+        let is_capture = delta.get_capture_pc().is_some() || delta.get_ep_capture_sq().is_some();
+
+        if piece != Piece::Pawn {
+            san.push(piece.to_char_upper());
+        }
+
+        // other pieces of the same kind that could also move to dst.
+        // Through wormholes, this includes pawns on other files.
+        let others: Vec<_> = state.legal_moves()
+            .into_iter()
+            .filter(|&(from, to, _)| {
+                to == dst && from != src &&
+                state.pieces.piece_at_or_on_hole(from, state.wormholes) == Some(piece)
+            })
+            .map(|(from, _, _)| from)
+            .collect();
+
+        if !others.is_empty() {
+            if others.iter().all(|sq| sq.file_u8() != src.file_u8()) {
+                san.push((b'a' + src.file_u8()) as char);
+            } else if piece != Piece::Pawn && others.iter().all(|sq| sq.rank_u8() != src.rank_u8()) {
+                san.push((b'1' + src.rank_u8()) as char);
+            } else {
+                let _ = write!(san, "{src}");
+            }
+        } else if piece == Piece::Pawn && is_capture {
+            san.push((b'a' + src.file_u8()) as char);
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+
+        let _ = write!(san, "{dst}");
+
+        if let Some(pc) = delta.get_promote_pc() {
+            san.push('=');
+            san.push(pc.to_char_upper());
+        }
+    }
+
+    if delta.is_check() {
+        san.push(if state.next(delta).has_legal_moves() { '+' } else { '#' });
+    }
+
+    san
+}
+
+/// Resolve a SAN move such as "Nxe5", "exd6", "O-O" or "e8=Q+" against
+/// the position, returning (src, dst, promote) for `ChessGame::play`.
+///
+/// Check and annotation suffixes are ignored, and "0-0" is accepted for
+/// castling. Returns None if no legal move, or more than one, matches.
+pub fn parse_san(state: &BoardState, san: &str) -> Option<(Square, Square, Option<Piece>)> {
+    let wanted = normalize(san);
+    if wanted.is_empty() {
+        return None
+    }
+
+    let mut found = None;
+    for (src, dst, trace) in state.legal_moves() {
+        let promotes: &[Option<Piece>] = if trace.requires_promotion {
+            &[Some(Piece::Queen), Some(Piece::Rook), Some(Piece::Bishop), Some(Piece::Knight)]
+        } else {
+            &[None]
+        };
+
+        for &promote in promotes {
+            let Some(delta) = state.delta(src, dst, &trace, promote) else {
+                continue
+            };
+
+            if normalize(&to_san(state, delta)) == wanted {
+                if found.is_some() {
+                    return None
+                }
+                found = Some((src, dst, promote));
+            }
+        }
+    }
+
+    found
+}
+
+/// Strip check and annotation suffixes and spell castling with letters.
+fn normalize(san: &str) -> String {
+    san.trim()
+        .trim_end_matches(['+', '#', '!', '?'])
+        .replace('0', "O")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::{ChessGame, PlayError}, state::BoardState};
+
+    fn san(fen: &str, mv: &str) -> String {
+        let state = BoardState::from_fen(fen).unwrap();
+        let (src, dst, promote) = crate::uci::parse_move(mv).unwrap();
+        let trace = state.trace(src, dst).unwrap();
+        let delta = state.delta(src, dst, &trace, promote).unwrap();
+        super::to_san(&state, delta)
+    }
+
+    #[test]
+    fn disambiguation() {
+        let knights = "rnbqkb1r/ppp1pppp/5n2/3p4/3P4/5N2/PPP1PPPP/RNBQKB1R w KQkq - 2 3";
+        assert_eq!(san(knights, "b1d2"), "Nbd2");
+        assert_eq!(san(knights, "f3d2"), "Nfd2");
+        assert_eq!(san(knights, "b1c3"), "Nc3");
+
+        let rooks = "4k3/8/8/8/8/R7/8/R3K3 w - - 0 1";
+        assert_eq!(san(rooks, "a1a2"), "R1a2");
+
+        let queens = "4k3/8/8/8/8/Q1Q5/8/Q3K3 w - - 0 1";
+        assert_eq!(san(queens, "a1b2"), "Q1b2");
+        assert_eq!(san(queens, "a3b2"), "Qa3b2");
+        assert_eq!(san(queens, "c3b2"), "Qcb2");
+
+        // the archbishops reach c3 by a slide and a leap.
+        let fairy = "4k3/8/8/8/8/8/8/A2A1CK1 w - - 0 1";
+        assert_eq!(san(fairy, "a1d4"), "Ad4");
+        assert_eq!(san(fairy, "a1c3"), "Aac3");
+        assert_eq!(san(fairy, "f1f6"), "Cf6+");
+    }
+
+    #[test]
+    fn castling_and_promotion() {
+        assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"), "O-O");
+        assert_eq!(san("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"), "O-O-O");
+        assert_eq!(san("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n"), "axb8=N");
+        assert_eq!(san("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"), "a8=Q+");
+    }
+
+    #[test]
+    fn play_san() {
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "").unwrap();
+        for mv in ["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6", "0-0", "Nxe4", "Re1", "d5", "Bxd5", "Qxd5", "Nc3"] {
+            assert!(game.play_san(mv).is_ok(), "{mv}");
+        }
+
+        assert!(game.play_san("Nd4?!").is_ok());
+        assert!(matches!(game.play_san("Nc3"), Err(PlayError::InvalidMove)));
+        assert!(matches!(game.play_san("Qe4"), Err(PlayError::InvalidMove)));
+        assert!(game.play_san("Nxe4").is_ok());
+    }
+
+    #[test]
+    fn pawns_through_wormholes() {
+        // the pawn on the d2 wormhole captures e5 from f4, like the pawn on d4.
+        let state = BoardState::from_extended_fen("4k3/8/8/4p3/3P4/8/3P4/4K3 w - - 0 1 d2,f4 - 0").unwrap();
+        let san = |mv: &str| {
+            let (src, dst, promote) = crate::uci::parse_move(mv).unwrap();
+            let trace = state.trace(src, dst).unwrap();
+            super::to_san(&state, state.delta(src, dst, &trace, promote).unwrap())
+        };
+
+        assert_eq!(san("d4e5"), "d4xe5");
+        assert_eq!(san("d2e5"), "d2xe5");
+        assert_eq!(super::parse_san(&state, "d2xe5"), crate::uci::parse_move("d2e5"));
+        assert_eq!(super::parse_san(&state, "dxe5"), None);
+    }
+}
@@ -0,0 +1,671 @@
+//! Alpha-beta search with quiescence, built on the tapered evaluator.
+
+use std::fmt;
+
+use crate::{delta::BoardDelta, eval::Accumulator, pieces::Piece, square::Square, state::BoardState, team::Team};
+
+/// The score of checkmate on the board. Mate in n halfmoves scores `MATE - n`.
+pub const MATE: i32 = 30_000;
+
+/// Scores further from zero than this are forced mates.
+pub const MATE_BOUND: i32 = MATE - 1_000;
+
+const INFINITY: i32 = MATE + 1;
+
+/// Lines are never searched deeper than this many halfmoves, which
+/// is also the longest principal variation a search can return.
+pub const MAX_PLY: usize = 64;
+
+/// The most moves a move list holds. Positions with more legal moves,
+/// which takes several promoted queens, only search the first this many.
+pub const MAX_MOVES: usize = 256;
+
+/// The least depth at which a move is tested for being singular.
+const SINGULAR_DEPTH: i32 = 4;
+
+/// How far every other move must fall below the best for it to be singular.
+const SINGULAR_MARGIN: i32 = 60;
+
+/// A score told apart as an evaluation or a forced mate.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum ScoreKind {
+    /// An evaluation in centipawns, from the view of the team to move.
+    Centipawns(i32),
+
+    /// Mate in this many moves, negative if the team to move is mated.
+    /// A position that is already checkmate is mate in 0.
+    MateIn(i16),
+}
+
+impl ScoreKind {
+    /// The kind of a score returned from the search.
+    pub fn from_score(score: i32) -> Self {
+        if score >= MATE_BOUND {
+            Self::mate(MATE - score)
+        } else if score <= -MATE_BOUND {
+            Self::mate(-(MATE + score))
+        } else {
+            Self::Centipawns(score)
+        }
+    }
+
+    /// Mate in this many halfmoves, negative if the team to move is mated.
+    pub fn mate(plies: i32) -> Self {
+        if plies > 0 {
+            Self::MateIn(((plies + 1) / 2) as i16)
+        } else {
+            Self::MateIn((plies / 2) as i16)
+        }
+    }
+
+    /// The score as written in a UCI info line, such as "cp 35" or "mate -2".
+    pub fn to_uci(&self) -> String {
+        match self {
+            Self::Centipawns(cp) => format!("cp {cp}"),
+            Self::MateIn(moves) => format!("mate {moves}"),
+        }
+    }
+
+    /// The score as a PGN `%eval` comment command, such as "[%eval 0.35]"
+    /// or "[%eval #-2]". PGN evaluations are from white's view, so the
+    /// team to move in the scored position is needed.
+    pub fn to_pgn_eval(&self, turn: Team) -> String {
+        let white = match turn {
+            Team::White => *self,
+            Team::Black => -*self,
+        };
+
+        match white {
+            Self::Centipawns(cp) => format!("[%eval {}{}.{:02}]", if cp < 0 { "-" } else { "" }, cp.abs() / 100, cp.abs() % 100),
+            Self::MateIn(moves) => format!("[%eval #{moves}]"),
+        }
+    }
+}
+
+impl std::ops::Neg for ScoreKind {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        match self {
+            Self::Centipawns(cp) => Self::Centipawns(-cp),
+            Self::MateIn(moves) => Self::MateIn(-moves),
+        }
+    }
+}
+
+/// Written as in SAN comments: pawns with a sign, such as "+0.35",
+/// or a mate such as "#3" or "#-2".
+impl fmt::Display for ScoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Centipawns(cp) => write!(f, "{}{}.{:02}", if *cp < 0 { '-' } else { '+' }, cp.abs() / 100, cp.abs() % 100),
+            Self::MateIn(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct SearchOptions {
+    /// The nominal depth of the search in halfmoves, before quiescence.
+    pub depth: u8,
+
+    /// Search one halfmove deeper after a move that gives check.
+    pub check_extensions: bool,
+
+    /// Search one halfmove deeper after the only legal move, or after a
+    /// move that a reduced search finds much better than every other.
+    pub singular_extensions: bool,
+
+    /// The half-width of the window around the previous iteration's
+    /// score that each iteration is first searched in. The window widens
+    /// whenever the score falls outside of it. Zero disables aspiration.
+    pub aspiration: i32,
+
+    /// Search the root in this window instead of a full one. A score
+    /// outside of the window is only a bound on the true score.
+    pub window: Option<(i32, i32)>,
+
+    /// Only search these moves at the root, like UCI `searchmoves`.
+    /// Moves that are not legal are ignored, and every move is
+    /// searched if the list is empty or none of them are legal.
+    pub searchmoves: Vec<(Square, Square, Option<Piece>)>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            depth: 4,
+            check_extensions: true,
+            singular_extensions: true,
+            aspiration: 30,
+            window: None,
+            searchmoves: Vec::new(),
+        }
+    }
+}
+
+/// How a fail-soft score relates to the true score of the position.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Bound {
+    /// The score is the true score.
+    Exact,
+
+    /// The search failed high, so the true score is at least the score.
+    Lower,
+
+    /// The search failed low, so the true score is at most the score.
+    Upper,
+}
+
+impl Bound {
+    /// The bound of a score returned from a search in the window.
+    pub const fn of(score: i32, alpha: i32, beta: i32) -> Self {
+        if score <= alpha {
+            Self::Upper
+        } else if score >= beta {
+            Self::Lower
+        } else {
+            Self::Exact
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    /// The best move, or None if the team to move has no legal moves.
+    pub best: Option<BoardDelta>,
+
+    /// The score in centipawns from the view of the team to move.
+    pub score: i32,
+
+    /// Whether the score is exact, or a bound because it fell
+    /// outside of the window given in the options.
+    pub bound: Bound,
+
+    /// The expected line of play, starting with the best move.
+    pub pv: Vec<BoardDelta>,
+
+    /// The number of positions visited.
+    pub nodes: u64,
+
+    /// The depth of the last completed iteration.
+    pub depth: u8,
+}
+
+impl SearchResult {
+    /// The score as an evaluation or a forced mate.
+    pub fn kind(&self) -> ScoreKind {
+        ScoreKind::from_score(self.score)
+    }
+
+    /// The result as a UCI info line, such as
+    /// "info depth 4 score cp 35 nodes 1024 pv e2e4 e7e5".
+    pub fn to_uci_info(&self) -> String {
+        let mut info = format!("info depth {} score {}", self.depth, self.kind().to_uci());
+        match self.bound {
+            Bound::Exact => {}
+            Bound::Lower => info.push_str(" lowerbound"),
+            Bound::Upper => info.push_str(" upperbound"),
+        }
+
+        info.push_str(&format!(" nodes {}", self.nodes));
+        if !self.pv.is_empty() {
+            info.push_str(" pv");
+            for delta in &self.pv {
+                info.push(' ');
+                info.push_str(&delta.to_uci());
+            }
+        }
+
+        info
+    }
+}
+
+/// Search the position with iterative deepening up to the option's depth.
+/// Each iteration after the first is searched in an aspiration window
+/// around the last score, and searched again with a wider window if
+/// the score falls outside of it.
+pub fn search(state: &BoardState, options: &SearchOptions) -> SearchResult {
+    let mut searcher = Searcher {
+        options: options.clone(),
+        nodes: 0,
+        history: [*state; MAX_PLY],
+        max_ply: (options.depth as usize * 2).clamp(1, MAX_PLY),
+        pv: [[BoardDelta::default(); MAX_PLY]; MAX_PLY + 1],
+        pv_len: [0; MAX_PLY + 1],
+        hint: [BoardDelta::default(); MAX_PLY],
+        hint_len: 0,
+    };
+
+    let acc = Accumulator::new(state);
+    let mut score: i32 = 0;
+    let mut bound = Bound::Exact;
+    let (lower, upper) = options.window.unwrap_or((-INFINITY, INFINITY));
+    for depth in 1..=options.depth.max(1) as i32 {
+        let mut width = options.aspiration;
+        let (mut alpha, mut beta) = if depth > 1 && width > 0 && score.abs() < MATE_BOUND {
+            ((score - width).max(lower), (score + width).min(upper))
+        } else {
+            (lower, upper)
+        };
+
+        loop {
+            let value = searcher.negamax(state, acc, depth, 0, alpha, beta, true);
+
+            // the line is searched first by the next attempt or iteration.
+            searcher.hint_len = searcher.pv_len[0];
+            searcher.hint[..searcher.hint_len].copy_from_slice(&searcher.pv[0][..searcher.hint_len]);
+
+            if value <= alpha && alpha > lower {
+                alpha = (value - width).max(lower);
+            } else if value >= beta && beta < upper {
+                beta = (value + width).min(upper);
+            } else {
+                score = value;
+                bound = Bound::of(value, alpha, beta);
+                break;
+            }
+
+            width *= 2;
+        }
+    }
+
+    let pv = searcher.hint[..searcher.hint_len].to_vec();
+    SearchResult {
+        best: pv.first().copied(),
+        score,
+        bound,
+        pv,
+        nodes: searcher.nodes,
+        depth: options.depth.max(1),
+    }
+}
+
+/// Moves in a fixed-size buffer, so generating them at a node does not allocate.
+struct MoveList {
+    moves: [BoardDelta; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    fn new() -> Self {
+        Self {
+            moves: [BoardDelta::default(); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Add a move, dropping it if the list is full.
+    fn push(&mut self, delta: BoardDelta) {
+        if self.len < MAX_MOVES {
+            self.moves[self.len] = delta;
+            self.len += 1;
+        }
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(&BoardDelta) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len {
+            if f(&self.moves[i]) {
+                self.moves[kept] = self.moves[i];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl std::ops::Deref for MoveList {
+    type Target = [BoardDelta];
+
+    fn deref(&self) -> &[BoardDelta] {
+        &self.moves[..self.len]
+    }
+}
+
+impl std::ops::DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [BoardDelta] {
+        &mut self.moves[..self.len]
+    }
+}
+
+struct Searcher {
+    options: SearchOptions,
+    nodes: u64,
+
+    /// The positions on the path from the root by ply, for repetition checks.
+    history: [BoardState; MAX_PLY],
+
+    /// Extensions stop once a line reaches this many halfmoves.
+    max_ply: usize,
+
+    /// The best line found from each ply, as a triangular table: the
+    /// line at a ply is its best move followed by the line of the ply
+    /// after it. Quiescence leaves its lines empty.
+    pv: [[BoardDelta; MAX_PLY]; MAX_PLY + 1],
+    pv_len: [usize; MAX_PLY + 1],
+
+    /// The line of the last search, which is searched first while the
+    /// path from the root follows it.
+    hint: [BoardDelta; MAX_PLY],
+    hint_len: usize,
+}
+
+impl Searcher {
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        state: &BoardState,
+        acc: Accumulator,
+        depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        on_hint: bool,
+    ) -> i32 {
+        self.nodes += 1;
+        self.pv_len[ply] = 0;
+
+        if ply > 0 && (state.halfmoves >= crate::end::FIFTY_MOVES || self.history[..ply].iter().any(|pos| pos.is_repetition_of(state))) {
+            return 0
+        }
+
+        if depth <= 0 || ply >= MAX_PLY {
+            return self.quiesce(state, acc, alpha, beta, ply)
+        }
+
+        let hint = (on_hint && ply < self.hint_len).then(|| self.hint[ply]);
+        let mut moves = ordered_moves(state, hint);
+        if ply == 0 && moves.iter().any(|delta| self.is_searchmove(delta)) {
+            moves.retain(|delta| self.is_searchmove(delta));
+        }
+
+        if moves.is_empty() {
+            return if state.king_attacked() { -MATE + ply as i32 } else { 0 }
+        }
+
+        let can_extend = ply < self.max_ply;
+        let singular = if self.options.singular_extensions && can_extend {
+            self.singular_move(state, acc, &moves, depth, ply)
+        } else {
+            None
+        };
+
+        let mut best = -INFINITY;
+        self.history[ply] = *state;
+        for &delta in moves.iter() {
+            let mut child_acc = acc;
+            let next = child_acc.make(state, delta);
+
+            let extend = can_extend && (
+                (self.options.check_extensions && next.king_attacked()) ||
+                singular.is_some_and(|singular| singular.is_same_move(&delta))
+            );
+
+            let child_on_hint = hint.is_some_and(|hint| hint.is_same_move(&delta));
+            let score = -self.negamax(&next, child_acc, depth - 1 + extend as i32, ply + 1, -beta, -alpha, child_on_hint);
+
+            // fail-soft: the best score and its line are kept even if
+            // they stay below alpha, so the root always has a best move.
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                self.update_pv(ply, delta);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best
+    }
+
+    /// Make the move followed by the line of the next ply the line of this ply.
+    fn update_pv(&mut self, ply: usize, delta: BoardDelta) {
+        let child_len = self.pv_len[ply + 1].min(MAX_PLY - 1);
+        let (rows, child_rows) = self.pv.split_at_mut(ply + 1);
+        let line = &mut rows[ply];
+        line[0] = delta;
+        line[1..=child_len].copy_from_slice(&child_rows[0][..child_len]);
+        self.pv_len[ply] = child_len + 1;
+    }
+
+    fn is_searchmove(&self, delta: &BoardDelta) -> bool {
+        self.options.searchmoves.iter().any(|&(src, dst, promote)| {
+            delta.get_src_sq() == src && delta.get_dst_sq() == dst && delta.get_promote_pc() == promote
+        })
+    }
+
+    /// The move to extend because it is the only legal move, or because a
+    /// reduced search scores every other move below it by the margin.
+    /// The first move is the candidate, since it is the best move from
+    /// the previous iteration whenever there is one.
+    fn singular_move(&mut self, state: &BoardState, acc: Accumulator, moves: &[BoardDelta], depth: i32, ply: usize) -> Option<BoardDelta> {
+        if moves.len() == 1 {
+            return Some(moves[0])
+        }
+
+        if depth < SINGULAR_DEPTH {
+            return None
+        }
+
+        let reduced = depth / 2 - 1;
+        let score = |searcher: &mut Self, delta: BoardDelta, alpha: i32, beta: i32| {
+            let mut child_acc = acc;
+            let next = child_acc.make(state, delta);
+            searcher.history[ply] = *state;
+            -searcher.negamax(&next, child_acc, reduced, ply + 1, -beta, -alpha, false)
+        };
+
+        let candidate = score(self, moves[0], -INFINITY, INFINITY);
+        if candidate.abs() >= MATE_BOUND {
+            return None
+        }
+
+        let bar = candidate - SINGULAR_MARGIN;
+        for &delta in &moves[1..] {
+            if score(self, delta, bar - 1, bar) >= bar {
+                return None
+            }
+        }
+
+        Some(moves[0])
+    }
+
+    fn quiesce(&mut self, state: &BoardState, acc: Accumulator, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        self.nodes += 1;
+        self.pv_len[ply] = 0;
+
+        if ply >= MAX_PLY {
+            return acc.evaluate(state)
+        }
+
+        // in check, every evasion is searched instead of standing pat.
+        let in_check = state.king_attacked();
+        let mut best = -INFINITY;
+        let moves = if in_check {
+            let evasions = ordered_moves(state, None);
+            if evasions.is_empty() {
+                return -MATE + ply as i32
+            }
+            evasions
+        } else {
+            best = acc.evaluate(state);
+            if best >= beta {
+                return best
+            }
+            alpha = alpha.max(best);
+            ordered_captures(state)
+        };
+
+        for &delta in moves.iter() {
+            let mut child_acc = acc;
+            let next = child_acc.make(state, delta);
+            let score = -self.quiesce(&next, child_acc, -beta, -alpha, ply + 1);
+
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+            }
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best
+    }
+}
+
+/// Every legal move as a delta, promoting to a queen or a knight, with
+/// the hint first and then the most valuable captures by the least
+/// valuable pieces.
+fn ordered_moves(state: &BoardState, hint: Option<BoardDelta>) -> MoveList {
+    let mut moves = MoveList::new();
+    state.for_each_legal_move(|src, dst, trace| {
+        let promotions: &[Option<Piece>] = if trace.requires_promotion {
+            &[Some(Piece::Queen), Some(Piece::Knight)]
+        } else {
+            &[None]
+        };
+
+        for &promote in promotions {
+            if let Some(delta) = state.delta(src, dst, &trace, promote) {
+                moves.push(delta);
+            }
+        }
+    });
+
+    sort_moves(state, &mut moves);
+    if let Some(i) = hint.and_then(|hint| moves.iter().position(|delta| delta.is_same_move(&hint))) {
+        moves[..=i].rotate_right(1);
+    }
+
+    moves
+}
+
+/// Every legal capture as a delta, promoting to a queen.
+fn ordered_captures(state: &BoardState) -> MoveList {
+    let mut moves = MoveList::new();
+    state.for_each_capture(|src, dst, trace| {
+        if let Some(delta) = state.delta(src, dst, &trace, Some(Piece::Queen)) {
+            moves.push(delta);
+        }
+    });
+
+    sort_moves(state, &mut moves);
+    moves
+}
+
+/// Sort by the most valuable victim and the least valuable attacker, in
+/// the order the moves were generated when the two are equal. Sorting
+/// in place keeps move ordering free of allocations.
+fn sort_moves(state: &BoardState, moves: &mut [BoardDelta]) {
+    moves.sort_unstable_by_key(|delta| {
+        let victim = delta.get_capture_pc().map_or(0, |pc| pc.value());
+        let promote = delta.get_promote_pc().map_or(0, |pc| pc.value());
+        let attacker = state.pieces.piece_at_or_on_hole(delta.get_src_sq(), state.wormholes).map_or(0, |pc| pc.value());
+        (-(victim * 16 + promote - attacker / 16), delta.get_src_sq().to_index(), delta.get_dst_sq().to_index())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, ScoreKind, SearchOptions, MATE, MAX_PLY};
+    use crate::team::Team;
+    use crate::state::BoardState;
+
+    #[test]
+    fn finds_mate_and_material() {
+        // back rank mate with the rook.
+        let mate = BoardState::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = mate.search(&SearchOptions { depth: 2, ..SearchOptions::default() });
+        assert_eq!(result.best.map(|delta| delta.to_uci()).as_deref(), Some("a1a8"));
+        assert_eq!(result.score, MATE - 1);
+        assert_eq!(result.kind(), ScoreKind::MateIn(1));
+        assert_eq!(result.to_uci_info(), format!("info depth 2 score mate 1 nodes {} pv a1a8", result.nodes));
+
+        // the knight on d5 is hanging to the queen.
+        let hanging = BoardState::from_fen("4k3/8/8/3n4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let result = hanging.search(&SearchOptions { depth: 2, ..SearchOptions::default() });
+        assert_eq!(result.best.map(|delta| delta.to_uci()).as_deref(), Some("d2d5"));
+        assert!(result.score > 800);
+    }
+
+    #[test]
+    fn pv_is_legal() {
+        let state = BoardState::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let result = state.search(&SearchOptions { depth: 3, ..SearchOptions::default() });
+        assert!(result.pv.len() >= 3 && result.pv.len() <= MAX_PLY);
+        assert_eq!(result.best, result.pv.first().copied());
+
+        let mut position = state;
+        for &delta in &result.pv {
+            assert!(position.verify_delta(&delta).is_ok(), "{}", delta.to_uci());
+            position = position.next(delta);
+        }
+    }
+
+    #[test]
+    fn extensions_can_be_disabled() {
+        let state = BoardState::from_fen("6k1/5ppp/8/8/8/8/1q3PPP/R5K1 w - - 0 1").unwrap();
+        let options = SearchOptions { depth: 2, ..SearchOptions::default() };
+        let plain = state.search(&SearchOptions { check_extensions: false, singular_extensions: false, ..options.clone() });
+        let extended = state.search(&options);
+        assert!(extended.nodes > plain.nodes);
+        assert_eq!(extended.best.map(|delta| delta.to_uci()).as_deref(), Some("a1a8"));
+    }
+
+    #[test]
+    fn windows_and_bounds() {
+        let hanging = BoardState::from_fen("4k3/8/8/3n4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let options = SearchOptions { depth: 3, ..SearchOptions::default() };
+        let full = hanging.search(&SearchOptions { aspiration: 0, ..options.clone() });
+        let aspirated = hanging.search(&options);
+        assert_eq!((aspirated.score, aspirated.bound), (full.score, Bound::Exact));
+        assert_eq!(aspirated.best.map(|delta| delta.to_uci()), full.best.map(|delta| delta.to_uci()));
+
+        let high = hanging.search(&SearchOptions { window: Some((-50, 50)), ..options.clone() });
+        assert_eq!(high.bound, Bound::Lower);
+        assert!(high.score >= 50);
+
+        let low = hanging.search(&SearchOptions { window: Some((2000, 3000)), ..options });
+        assert_eq!(low.bound, Bound::Upper);
+        assert!(low.score <= 2000 && low.best.is_some());
+    }
+
+    #[test]
+    fn searchmoves() {
+        let hanging = BoardState::from_fen("4k3/8/8/3n4/8/8/3Q4/4K3 w - - 0 1").unwrap();
+        let searchmoves = ["d2d1", "e1f1", "a1a2"].map(|uci| crate::uci::parse_move(uci).unwrap()).to_vec();
+        let result = hanging.search(&SearchOptions { depth: 2, searchmoves, ..SearchOptions::default() });
+        let best = result.best.map(|delta| delta.to_uci());
+        assert!(best.as_deref() == Some("d2d1") || best.as_deref() == Some("e1f1"));
+
+        // with no legal searchmoves, every move is searched.
+        let searchmoves = vec![crate::uci::parse_move("a1a2").unwrap()];
+        let full = hanging.search(&SearchOptions { depth: 2, searchmoves, ..SearchOptions::default() });
+        assert_eq!(full.best.map(|delta| delta.to_uci()).as_deref(), Some("d2d5"));
+        assert!(full.score > result.score + 200);
+    }
+
+    #[test]
+    fn score_kinds() {
+        assert_eq!(ScoreKind::from_score(35), ScoreKind::Centipawns(35));
+        assert_eq!(ScoreKind::from_score(MATE - 3), ScoreKind::MateIn(2));
+        assert_eq!(ScoreKind::from_score(-MATE + 4), ScoreKind::MateIn(-2));
+        assert_eq!(ScoreKind::from_score(-MATE), ScoreKind::MateIn(0));
+
+        assert_eq!(ScoreKind::Centipawns(-120).to_uci(), "cp -120");
+        assert_eq!(ScoreKind::MateIn(-2).to_uci(), "mate -2");
+        assert_eq!(ScoreKind::Centipawns(35).to_string(), "+0.35");
+        assert_eq!(ScoreKind::Centipawns(-5).to_string(), "-0.05");
+        assert_eq!(ScoreKind::MateIn(3).to_string(), "#3");
+
+        // PGN evaluations are from white's view.
+        assert_eq!(ScoreKind::Centipawns(35).to_pgn_eval(Team::Black), "[%eval -0.35]");
+        assert_eq!(ScoreKind::MateIn(2).to_pgn_eval(Team::Black), "[%eval #-2]");
+        assert_eq!(ScoreKind::Centipawns(1250).to_pgn_eval(Team::White), "[%eval 12.50]");
+    }
+}
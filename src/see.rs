@@ -0,0 +1,83 @@
+//! Static exchange evaluation.
+
+use crate::{pieces::Piece, square::Square, state::BoardState};
+
+/// The expected material gain, in centipawns, of playing the capture
+/// from src to dst and then letting both sides keep recapturing on dst
+/// with their least valuable piece for as long as it is profitable.
+///
+/// Returns None if the move is not legal. Non-captures start at zero,
+/// so a negative value means the moved piece can be won.
+pub fn see(state: &BoardState, src: Square, dst: Square) -> Option<i32> {
+    let trace = state.trace(src, dst)?;
+    let delta = state.delta(src, dst, &trace, Some(Piece::Queen))?;
+    let captured = if trace.is_capture_en_passant.is_some() {
+        Piece::Pawn.value()
+    } else {
+        trace.captures.map_or(0, |pc| pc.value())
+    };
+
+    let next = state.next(delta);
+    let recapture = next.legal_moves()
+        .into_iter()
+        .filter(|&(_, to, trace)| to == dst && trace.captures.is_some())
+        .min_by_key(|&(from, _, _)| next.pieces.piece_at_or_on_hole(from, next.wormholes).map_or(0, |pc| pc.value()))
+        .and_then(|(from, to, _)| see(&next, from, to))
+        .unwrap_or(0);
+
+    Some(captured - recapture.max(0))
+}
+
+/// Whether the position is quiet: the team to move is not in check,
+/// and neither team has a capture that wins material.
+pub fn is_quiet(state: &BoardState) -> bool {
+    if state.king_attacked() {
+        return false
+    }
+
+    let mut null = *state;
+    null.turn = !state.turn;
+    null.en_passant = None;
+
+    [state, &null].into_iter().all(|pos| {
+        pos.legal_moves()
+            .into_iter()
+            .filter(|(_, _, trace)| trace.captures.is_some() || trace.is_capture_en_passant.is_some())
+            .all(|(src, dst, _)| see(pos, src, dst).is_none_or(|gain| gain <= 0))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::see;
+    use crate::state::BoardState;
+
+    fn sq(s: &str) -> crate::square::Square {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn exchanges() {
+        let start = BoardState::default();
+        assert_eq!(see(&start, sq("e2"), sq("e4")), Some(0));
+        assert_eq!(see(&start, sq("e2"), sq("e5")), None);
+        assert!(start.is_quiet());
+
+        // the rook wins a loose pawn, but loses itself to a defended one.
+        let loose = BoardState::from_fen("4k3/8/8/4p3/8/8/8/4RK2 w - - 0 1").unwrap();
+        assert_eq!(see(&loose, sq("e1"), sq("e5")), Some(100));
+        assert!(!loose.is_quiet());
+        let defended = BoardState::from_fen("4k3/8/3p4/4p3/8/8/8/4RK2 w - - 0 1").unwrap();
+        assert_eq!(see(&defended, sq("e1"), sq("e5")), Some(-400));
+        assert!(defended.is_quiet());
+
+        let knight = BoardState::from_fen("4k3/8/3p4/4n3/3P4/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(see(&knight, sq("d4"), sq("e5")), Some(220));
+        let en_passant = BoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert_eq!(see(&en_passant, sq("e5"), sq("d6")), Some(100));
+
+        // a winning capture for the team not to move, or being in check, is not quiet.
+        assert!(!BoardState::from_fen("4k3/8/8/8/8/8/1p6/R3K3 w - - 0 1").unwrap().is_quiet());
+        assert!(!BoardState::from_fen("4k3/8/8/8/8/8/8/4RK2 b - - 0 1").unwrap().is_quiet());
+    }
+}
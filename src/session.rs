@@ -0,0 +1,170 @@
+//! A variation tree with an engine and annotations, for analysis boards.
+//!
+//! `AnalysisSession` wraps a `GameTree` so a GUI can move through the
+//! lines, play moves, ask the engine for the best line, annotate
+//! positions, and export PGN through one object.
+
+use crate::{annotation::Annotation, game::{AnnotateError, ChessGame, Cursor, PlayError, PlaySuccess}, pieces::Piece, search::{SearchOptions, SearchResult}, square::Square, tree::GameTree};
+
+#[derive(Clone)]
+pub struct AnalysisSession {
+    tree: GameTree,
+
+    /// The options the engine searches with.
+    pub options: SearchOptions,
+}
+
+impl AnalysisSession {
+    /// Start a session on the game, with the cursor where the game has it.
+    pub fn new(game: ChessGame) -> Self {
+        Self::from_tree(GameTree::new(game))
+    }
+
+    pub fn from_tree(tree: GameTree) -> Self {
+        Self {
+            tree,
+            options: SearchOptions::default(),
+        }
+    }
+
+    pub fn tree(&self) -> &GameTree {
+        &self.tree
+    }
+
+    pub fn into_tree(self) -> GameTree {
+        self.tree
+    }
+
+    pub fn cursor(&self) -> &Cursor {
+        self.tree.cursor()
+    }
+
+    /// Move the cursor forward along the current line.
+    pub fn forward(&mut self) -> Option<&Cursor> {
+        self.tree.next()
+    }
+
+    /// Move the cursor back, leaving a variation from its first move.
+    pub fn back(&mut self) -> Option<&Cursor> {
+        self.tree.prev()
+    }
+
+    /// Play a move at the cursor, entering or creating a variation if
+    /// it differs from the move already played there. See `GameTree::play`.
+    pub fn play(&mut self, src: Square, dst: Square, promote: Option<Piece>) -> Result<PlaySuccess, PlayError> {
+        self.tree.play(src, dst, promote)
+    }
+
+    /// Play a move in Standard Algebraic Notation at the cursor.
+    pub fn play_san(&mut self, san: &str) -> Result<PlaySuccess, PlayError> {
+        let (src, dst, promote) = crate::san::parse_san(&self.cursor().state, san).ok_or(PlayError::InvalidMove)?;
+        self.play(src, dst, promote)
+    }
+
+    /// Search the cursor position with the session's options.
+    pub fn analyse(&self) -> SearchResult {
+        self.cursor().state.search(&self.options)
+    }
+
+    /// Search the cursor position and add the engine's line to the tree,
+    /// with the score as a `%eval` comment on its first move. Moves the
+    /// line shares with the tree are followed instead of added, and at
+    /// the end of a line the engine's moves extend it. The cursor stays
+    /// where it was. Returns the search, and the index of the line that
+    /// holds the first move, or None if there are no legal moves.
+    pub fn add_engine_line(&mut self) -> Result<(SearchResult, Option<usize>), PlayError> {
+        let result = self.analyse();
+        if result.pv.is_empty() {
+            return Ok((result, None))
+        }
+
+        let address = self.tree.address();
+        let line = self.play_line(&result);
+        self.tree.goto_address(&address);
+        line.map(|line| (result, Some(line)))
+    }
+
+    /// Play the engine's line from the cursor, and annotate the position
+    /// after its first move with the score. Returns the line of that move.
+    fn play_line(&mut self, result: &SearchResult) -> Result<usize, PlayError> {
+        let eval = result.kind().to_pgn_eval(self.cursor().state.turn);
+        let mut line = self.tree.current();
+        for (i, delta) in result.pv.iter().enumerate() {
+            let (src, dst) = self.cursor().state.move_squares(delta);
+            self.play(src, dst, delta.get_promote_pc())?;
+
+            if i == 0 {
+                line = self.tree.current();
+                let annotation = self.annotate().map_err(|err| match err {
+                    AnnotateError::Limit(limit) => PlayError::Limit(limit),
+                    // the cursor is always on a position of its line.
+                    AnnotateError::Index => PlayError::InvalidMove,
+                })?;
+                set_eval(annotation, &eval);
+            }
+        }
+
+        Ok(line)
+    }
+
+    /// The annotation of the cursor position, created empty if there
+    /// is none. Fails once the line has as many annotations as its
+    /// limits allow.
+    pub fn annotate(&mut self) -> Result<&mut Annotation, AnnotateError> {
+        let game = self.tree.current_game_mut();
+        let index = game.cursor.index;
+        game.annotate(index)
+    }
+
+    /// The tree as PGN, with the mainline's moves and every other line
+    /// as a variation. See `GameTree::to_pgn`.
+    pub fn to_pgn(&self) -> String {
+        self.tree.to_pgn()
+    }
+}
+
+/// Add the `%eval` command to the comment, replacing an earlier one.
+fn set_eval(annotation: &mut Annotation, eval: &str) {
+    let mut comment = annotation.comment.take().unwrap_or_default();
+    if let Some(start) = comment.find("[%eval ") && let Some(len) = comment[start..].find(']') {
+        comment.replace_range(start..=start + len, "");
+    }
+
+    let comment = comment.split_whitespace().chain([eval]).collect::<Vec<_>>().join(" ");
+    annotation.comment = Some(comment);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnalysisSession;
+    use crate::{game::ChessGame, search::SearchOptions, state::BoardState};
+
+    #[test]
+    fn session() {
+        // white misses mate with Qxf7# on the last move.
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 a2a3").unwrap();
+        let mut session = AnalysisSession::new(game);
+        session.options = SearchOptions { depth: 2, ..SearchOptions::default() };
+        assert!(session.forward().is_none());
+        assert_eq!(session.back().map(|cursor| cursor.index), Some(6));
+
+        let (result, line) = session.add_engine_line().ok().unwrap();
+        assert_eq!(result.best.map(|delta| delta.to_uci()).as_deref(), Some("h5f7"));
+        assert_eq!(line, Some(1));
+        assert_eq!(session.tree().games().len(), 2);
+        assert_eq!((session.tree().current(), session.cursor().index), (0, 6));
+
+        // adding the same line again follows it, and replaces the score.
+        assert_eq!(session.add_engine_line().ok().unwrap().1, Some(1));
+        assert_eq!(session.tree().games().len(), 2);
+
+        session.annotate().unwrap().comment = Some("Nf6 allows mate".to_string());
+        session.play_san("Qxe5+").ok().unwrap();
+        assert_eq!(session.tree().games().len(), 3);
+
+        let pgn = session.to_pgn();
+        assert!(pgn.contains("{Nf6 allows mate}"), "{pgn}");
+        assert!(pgn.contains("(4. Qxf7# {[%eval #1]})"), "{pgn}");
+        assert!(pgn.contains("(4. Qxe5+)"), "{pgn}");
+    }
+}
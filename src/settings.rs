@@ -1,6 +1,10 @@
 
 use chrono::{DateTime, Utc};
 
+use crate::{clock::TimeSource, square::Square, state::BoardState, trace::MoveTrace};
+
+// move filters compare by address, which is only used to tell settings apart.
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct GameSettings {
     /// Whether or not the game is Chess960. 
@@ -15,14 +19,65 @@ pub struct GameSettings {
 
     /// How Wormholes are spawned or placed on the board.
     pub wormhole: WormholeSettings,
+
+    /// A house rule that can forbid moves the rules of chess allow,
+    /// such as "no queen moves before move 10". Respected by
+    /// `ChessGame::play`, `ChessGame::valid_moves`, and `ChessGame::audit`.
+    /// Filters are not saved by the binary or PGN formats.
+    pub move_filter: Option<MoveFilter>,
+
+    /// Caps on how large the game can grow. Like the move filter,
+    /// limits are not saved by the binary or PGN formats.
+    pub limits: GameLimits,
 }
 
+/// Receives the position, the source and destination squares as passed
+/// to `ChessGame::play`, and the trace of a legal move. Returns false to
+/// forbid the move.
+pub type MoveFilter = fn(&BoardState, Square, Square, &MoveTrace) -> bool;
+
 impl Default for GameSettings {
     fn default() -> Self {
         Self {
             is_chess960: false,
             clock: None,
             wormhole: WormholeSettings::default(),
+            move_filter: None,
+            limits: GameLimits::default(),
+        }
+    }
+}
+
+/// Caps on how large a game can grow, for servers that take moves and
+/// annotations from untrusted clients. None is unlimited.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct GameLimits {
+    /// The most halfmoves a game or branch can hold, enforced by `ChessGame::play`.
+    pub max_plies: Option<u32>,
+
+    /// The most branches a tree can hold besides its mainline, enforced
+    /// by `GameTree::play` and `GameTree::insert` with the mainline's limits.
+    pub max_branches: Option<u32>,
+
+    /// The most annotated positions a game can hold, enforced by
+    /// `ChessGame::annotate`.
+    pub max_annotations: Option<u32>,
+}
+
+/// The limit that stopped a game from growing.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LimitError {
+    Plies,
+    Branches,
+    Annotations,
+}
+
+impl GameLimits {
+    /// Check that a collection of `len` items can grow by one under `max`.
+    pub(crate) fn check(max: Option<u32>, len: usize, error: LimitError) -> Result<(), LimitError> {
+        match max {
+            Some(max) if len >= max as usize => Err(error),
+            _ => Ok(()),
         }
     }
 }
@@ -39,6 +94,27 @@ pub struct ClockSettings {
     pub total: u32,
 }
 
+impl ClockSettings {
+    /// A clock for a game starting at the current time of the source.
+    pub fn starting_now(time: &impl TimeSource, total: u32, bonus: u32) -> Self {
+        Self {
+            start: time.now(),
+            bonus,
+            total,
+        }
+    }
+
+    /// The total time available per-side, in milliseconds.
+    pub const fn total_ms(&self) -> u32 {
+        self.total.saturating_mul(1000)
+    }
+
+    /// The bonus time per-move, in milliseconds.
+    pub const fn bonus_ms(&self) -> u32 {
+        self.bonus.saturating_mul(1000)
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct WormholeSettings {
     /// The way that Wormholes are spawned.
@@ -48,7 +124,8 @@ pub struct WormholeSettings {
     pub max_count: u8, 
 
     /// The number of wormholes that are placed at the start
-    /// of the game (according to the spawn mode). 
+    /// of the game (according to the spawn mode). Mirror mode
+    /// places pairs, so an odd count is rounded down.
     pub start_count: u8,
 
     /// The number of halfmoves until a queued wormhole is added.
@@ -0,0 +1,304 @@
+//! Batch simulation of random games, for stress testing and variant analysis.
+
+use std::thread;
+
+use crate::{end::EndCondition, game::{ChessGame, PlayError}, pieces::Piece, rng::WyRand, settings::GameSettings, square::Square, state::BoardState, team::Team, trace::MoveTrace};
+
+/// Games still running after this many halfmoves are stopped and counted as unfinished.
+pub const MAX_PLIES: usize = 1000;
+
+/// How evenly a variant's settings split the results between the teams.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct BalanceReport {
+    /// The outcomes the scores are derived from.
+    pub sim: SimReport,
+
+    /// White's points per game, counting a draw or unfinished game as half.
+    pub white_score: f64,
+
+    /// Black's points per game, counting a draw or unfinished game as half.
+    pub black_score: f64,
+
+    /// The mean number of halfmoves per game.
+    pub average_plies: f64,
+
+    /// The fraction of finished games that ended in a win for either team.
+    pub decisiveness: f64,
+}
+
+/// Outcomes of a batch of simulated games.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct SimReport {
+    /// The number of games played.
+    pub games: usize,
+
+    pub white_wins: usize,
+    pub black_wins: usize,
+    pub draws: usize,
+
+    /// Games stopped at `MAX_PLIES` or at the ply limit of the settings
+    /// without an end condition.
+    pub unfinished: usize,
+
+    pub checkmates: usize,
+    pub stalemates: usize,
+    pub fifty_move: usize,
+    pub repetitions: usize,
+
+    /// The number of halfmoves played across all games.
+    pub total_plies: usize,
+
+    /// The number of halfmoves in the longest game.
+    pub longest: usize,
+}
+
+impl SimReport {
+    /// The mean number of halfmoves per game.
+    pub fn average_plies(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.total_plies as f64 / self.games as f64
+        }
+    }
+
+    fn record(&mut self, game: &ChessGame) {
+        let plies = game.deltas.len();
+        self.games += 1;
+        self.total_plies += plies;
+        self.longest = self.longest.max(plies);
+
+        match game.end {
+            Some(EndCondition::Checkmate) => {
+                self.checkmates += 1;
+                match game.cursor.state.turn {
+                    Team::White => self.black_wins += 1,
+                    Team::Black => self.white_wins += 1,
+                }
+            },
+            Some(EndCondition::Stalemate) => {
+                self.stalemates += 1;
+                self.draws += 1;
+            },
+            Some(EndCondition::FiftyMoveRule | EndCondition::SeventyFiveMoveRule) => {
+                self.fifty_move += 1;
+                self.draws += 1;
+            },
+            Some(EndCondition::Repetition) => {
+                self.repetitions += 1;
+                self.draws += 1;
+            },
+            Some(_) => self.draws += 1,
+            None => self.unfinished += 1,
+        }
+    }
+
+    fn merge(&mut self, other: &SimReport) {
+        self.games += other.games;
+        self.white_wins += other.white_wins;
+        self.black_wins += other.black_wins;
+        self.draws += other.draws;
+        self.unfinished += other.unfinished;
+        self.checkmates += other.checkmates;
+        self.stalemates += other.stalemates;
+        self.fifty_move += other.fifty_move;
+        self.repetitions += other.repetitions;
+        self.total_plies += other.total_plies;
+        self.longest = self.longest.max(other.longest);
+    }
+}
+
+/// Play `n` games of uniformly random legal moves across all available
+/// threads, and collect how they ended. The results only depend on the
+/// seed, not on the number of threads. A panic in any game is propagated.
+pub fn run_random_games(n: usize, settings: GameSettings, seed: u64) -> SimReport {
+    run_games(n, settings, seed, 0)
+}
+
+/// Measure how balanced the settings are by simulating `n_games` games
+/// between two players of the same engine level (see `play_game`).
+pub fn balance_report(settings: GameSettings, n_games: usize, engine_level: u8) -> BalanceReport {
+    let sim = run_games(n_games, settings, 0x6d61_756c_7374_726d, engine_level);
+    let games = sim.games.max(1) as f64;
+    let finished = (sim.games - sim.unfinished).max(1) as f64;
+    let halves = (sim.draws + sim.unfinished) as f64 / 2.0;
+
+    BalanceReport {
+        sim,
+        white_score: (sim.white_wins as f64 + halves) / games,
+        black_score: (sim.black_wins as f64 + halves) / games,
+        average_plies: sim.average_plies(),
+        decisiveness: (sim.white_wins + sim.black_wins) as f64 / finished,
+    }
+}
+
+/// Compare variants by running `balance_report` for each of the settings,
+/// such as every start count of wormholes, returning them with their reports.
+pub fn balance_sweep(settings: impl IntoIterator<Item = GameSettings>, n_games: usize, engine_level: u8) -> Vec<(GameSettings, BalanceReport)> {
+    settings.into_iter().map(|settings| (settings, balance_report(settings, n_games, engine_level))).collect()
+}
+
+fn run_games(n: usize, settings: GameSettings, seed: u64, level: u8) -> SimReport {
+    let mut rng = WyRand { seed };
+    let seeds: Vec<u64> = (0..n).map(|_| rng.next()).collect();
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = seeds.len().div_ceil(threads).max(1);
+
+    let mut report = SimReport::default();
+    thread::scope(|scope| {
+        let handles: Vec<_> = seeds.chunks(chunk).map(|seeds| {
+            scope.spawn(move || {
+                let mut report = SimReport::default();
+                for &seed in seeds {
+                    report.record(&play_game(settings, seed, level));
+                }
+                report
+            })
+        }).collect();
+
+        for handle in handles {
+            match handle.join() {
+                Ok(part) => report.merge(&part),
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+    });
+
+    report
+}
+
+/// Play one game of random legal moves until it ends, or reaches
+/// `MAX_PLIES` or the ply limit of the settings.
+pub fn random_game(settings: GameSettings, seed: u64) -> ChessGame {
+    play_game(settings, seed, 0)
+}
+
+/// Play one game until it ends, or reaches `MAX_PLIES` or the ply
+/// limit of the settings, with both sides
+/// choosing moves at the engine level:
+///  - 0: uniformly random legal moves.
+///  - 1: the move with the best static exchange, ties broken randomly.
+///  - 2 and above: a mate in one if there is one, otherwise as level 1.
+pub fn play_game(settings: GameSettings, seed: u64, level: u8) -> ChessGame {
+    let mut game = ChessGame::init_seeded(settings, seed);
+    let mut rng = WyRand { seed };
+
+    while game.deltas.len() < MAX_PLIES {
+        let state = game.cursor.state;
+        let mut moves = state.legal_moves();
+        moves.retain(|(src, dst, trace)| game.allows(&state, *src, *dst, trace));
+        if moves.is_empty() {
+            game.end = Some(if state.king_attacked() {
+                EndCondition::Checkmate
+            } else {
+                EndCondition::Stalemate
+            });
+            break;
+        }
+
+        let (src, dst, trace) = moves[choose(&state, &moves, level, &mut rng)];
+        let promote = trace.requires_promotion.then(|| match level {
+            0 => [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight][rng.range(0..4) as usize],
+            _ => Piece::Queen,
+        });
+
+        match game.play(src, dst, promote) {
+            Ok(_) => {},
+            // the ply limit was reached, or a timeout ended the game.
+            Err(PlayError::Limit(_) | PlayError::GameEnded(_)) => break,
+            Err(_) => panic!("[E445] 'sim::play_game' legal move {src:?} -> {dst:?} was rejected (seed {seed:016x})"),
+        }
+
+        if game.end.is_some() {
+            break;
+        }
+    }
+
+    game
+}
+
+fn choose(state: &BoardState, moves: &[(Square, Square, MoveTrace)], level: u8, rng: &mut WyRand) -> usize {
+    if level >= 2 {
+        let mate = moves.iter().position(|&(src, dst, trace)| {
+            state.delta(src, dst, &trace, Some(Piece::Queen)).is_some_and(|delta| {
+                let next = state.next(delta);
+                next.king_attacked() && !next.has_legal_moves()
+            })
+        });
+
+        if let Some(i) = mate {
+            return i
+        }
+    }
+
+    if level >= 1 {
+        let gains: Vec<i32> = moves.iter()
+            .map(|&(src, dst, _)| crate::see::see(state, src, dst).unwrap_or(i32::MIN))
+            .collect();
+        let best = gains.iter().copied().max().unwrap_or(0);
+        let candidates: Vec<usize> = (0..moves.len()).filter(|&i| gains[i] == best).collect();
+        return candidates[rng.range(0..candidates.len()) as usize]
+    }
+
+    rng.range(0..moves.len()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::settings::{GameLimits, GameSettings, WormholeSettings, WormholeSpawnMode};
+
+    #[test]
+    fn random_games_are_legal_and_deterministic() {
+        let report = super::run_random_games(6, GameSettings::default(), 7);
+        assert_eq!(report.games, 6);
+        assert_eq!(report.white_wins + report.black_wins + report.draws + report.unfinished, 6);
+        assert_eq!(report, super::run_random_games(6, GameSettings::default(), 7));
+
+        let game = super::random_game(GameSettings { is_chess960: true, ..GameSettings::default() }, 11);
+        assert!(game.audit().is_ok());
+    }
+
+    #[test]
+    fn balance_report_with_wormholes() {
+        let settings = GameSettings {
+            wormhole: WormholeSettings {
+                spawn_mode: WormholeSpawnMode::Mirror,
+                max_count: 4,
+                start_count: 4,
+                ..WormholeSettings::default()
+            },
+            ..GameSettings::default()
+        };
+
+        let game = super::play_game(settings, 3, 2);
+        assert_eq!(game.start.wormholes.count(), 4);
+        assert_eq!(game.start.wormholes, game.start.wormholes.flip_vertical());
+        assert!(game.audit().is_ok());
+
+        let report = super::balance_report(settings, 4, 2);
+        assert_eq!(report.sim.games, 4);
+        assert!((report.white_score + report.black_score - 1.0).abs() < 1e-9);
+        assert!((0.0..=1.0).contains(&report.decisiveness));
+
+        let sweep = super::balance_sweep([0, 2, 4].map(|start_count| GameSettings {
+            wormhole: WormholeSettings { start_count, ..settings.wormhole },
+            ..settings
+        }), 4, 2);
+        assert_eq!(sweep.len(), 3);
+        assert_eq!(sweep[2], (settings, report));
+        assert!(sweep.iter().all(|(_, report)| report.sim.games == 4));
+    }
+
+    #[test]
+    fn ply_limit_stops_games() {
+        let limits = GameLimits { max_plies: Some(20), ..GameLimits::default() };
+        let game = super::random_game(GameSettings { limits, ..GameSettings::default() }, 5);
+        assert_eq!(game.deltas.len(), 20);
+        assert!(game.audit().is_ok());
+
+        let report = super::run_random_games(3, GameSettings { limits, ..GameSettings::default() }, 9);
+        assert_eq!(report.games, 3);
+        assert_eq!(report.longest, 20);
+    }
+}
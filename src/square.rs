@@ -1,8 +1,8 @@
 
 //! Struct for representing squares on a chess board.
 
-use std::{cmp::Ordering, fmt, mem, ops::{Add, BitOr}};
-use crate::{board::BitBoard, cached::*, ray::Ray, team::Team};
+use std::{fmt, ops::BitOr, str::FromStr};
+use crate::{board::BitBoard, cached::*, geometry::{FILES, RANKS, SQUARES}, ray::Ray, team::Team};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Ord, PartialOrd)]
 pub enum Rank {
@@ -29,8 +29,17 @@ impl Rank {
             Self::Eighth => 7,
         }
     }
+
+    pub fn from_i8(n: i8) -> Option<Self> {
+        if n >= 0 && n < RANKS as i8 {
+            Some(Self::from(n as u8))
+        } else {
+            None
+        }
+    }
 }
 
+/// Values above 7 are clamped to the eighth rank, use `Rank::from_i8` to check.
 impl From<u8> for Rank {
     fn from(value: u8) -> Self {
         match value {
@@ -66,7 +75,7 @@ impl File {
     }
 
     pub fn from_i8(n: i8) -> Option<Self> {
-        if n >= 0 && n < 8 {
+        if n >= 0 && n < FILES as i8 {
             Some(Self::from(n as u8))
         } else {
             None
@@ -74,6 +83,7 @@ impl File {
     }
 }
 
+/// Values above 7 are clamped to the h-file, use `File::from_i8` to check.
 impl From<u8> for File {
     fn from(value: u8) -> Self {
         match value {
@@ -99,6 +109,16 @@ impl Square {
         Self((rank.to_u8() << 3) | file.to_u8())
     }
 
+    /// Create a square from a rank and file index,
+    /// or None if either is off the board.
+    pub const fn try_new(rank: u8, file: u8) -> Option<Self> {
+        if rank < RANKS && file < FILES {
+            Some(Self((rank << 3) | file))
+        } else {
+            None
+        }
+    }
+
     pub fn rank(&self) -> Rank {
         (self.0 >> 3).into()
     }
@@ -127,11 +147,30 @@ impl Square {
         Self(i as u8)
     }
 
+    /// Create a square from its index, or None if it is not on the board.
+    pub const fn try_from_index(i: usize) -> Option<Self> {
+        if i < SQUARES {
+            Some(Self(i as u8))
+        } else {
+            None
+        }
+    }
+
     /// Squares between self and rhs, including rhs and excluding self.
     pub const fn between(&self, rhs: Self) -> BitBoard {
         BitBoard(crate::cached::BETWEEN_EXCLUSIVE[self.to_index()][rhs.to_index()])
     }
 
+    /// Mirror the square vertically, so the first rank becomes the eighth.
+    pub const fn flip_vertical(&self) -> Self {
+        Self(self.0 ^ 0b111000)
+    }
+
+    /// Mirror the square horizontally, so the a-file becomes the h-file.
+    pub const fn flip_horizontal(&self) -> Self {
+        Self(self.0 ^ 0b000111)
+    }
+
     pub fn pawn_captures(&self, team: Team) -> BitBoard {
         match team {
             Team::White => BitBoard(crate::cached::WHITE_PAWN_ATTACKS[self.to_index()]),
@@ -142,7 +181,7 @@ impl Square {
     pub const fn next(&self, delta: (i8, i8)) -> Option<Self> {
         let rank = self.rank_u8() as i8 + delta.0;
         let file = self.file_u8() as i8 + delta.1;
-        if rank >= 0 && rank < 8 && file >= 0 && file < 8 {
+        if rank >= 0 && rank < RANKS as i8 && file >= 0 && file < FILES as i8 {
             Some(Self(((rank as u8) << 3) | file as u8))
         } else {
             None
@@ -157,6 +196,17 @@ impl Square {
         BitBoard(crate::cached::KNIGHT_MOVES[self.0 as usize])
     }
 
+    /// The fewest knight moves from self to rhs.
+    pub fn knight_distance(&self, rhs: Self) -> u8 {
+        // a knight reaches every square of an empty board.
+        crate::path::distance(*self, rhs, BitBoard::new(), |sq| sq.knight_moves()).unwrap()
+    }
+
+    /// A shortest knight path from self to rhs, including rhs and excluding self.
+    pub fn knight_path(&self, rhs: Self) -> Vec<Self> {
+        crate::path::shortest_path(*self, rhs, BitBoard::new(), |sq| sq.knight_moves()).unwrap()
+    }
+
     pub fn rook_moves(&self, occupied: BitBoard) -> BitBoard {
         crate::magic::get_rook_moves(*self, occupied)
     }
@@ -229,9 +279,56 @@ impl fmt::Debug for Square {
     }
 }
 
+/// Coordinate notation, such as "e4".
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file_u8()) as char, (b'1' + self.rank_u8()) as char)
+    }
+}
+
+/// The string is not a square in coordinate notation.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ParseSquareError;
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("expected a square such as \"e4\"")
+    }
+}
+
+impl std::error::Error for ParseSquareError {}
+
+/// Parse coordinate notation, such as "e4". The file must be lowercase.
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let &[file, rank] = s.as_bytes() else {
+            return Err(ParseSquareError)
+        };
+
+        Square::try_new(
+            rank.checked_sub(b'1').ok_or(ParseSquareError)?,
+            file.checked_sub(b'a').ok_or(ParseSquareError)?,
+        ).ok_or(ParseSquareError)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::BitBoard;
+    use super::{BitBoard, ParseSquareError, Square};
+
+    #[test]
+    fn coordinate_notation() {
+        for square in BitBoard(!0) {
+            assert_eq!(square.to_string().parse(), Ok(square));
+        }
+
+        assert_eq!(Square::from((3, 4)).to_string(), "e4");
+        for bad in ["", "e", "e9", "i4", "E4", "e40", "`4", "e0"] {
+            assert_eq!(bad.parse::<Square>(), Err(ParseSquareError), "{bad}");
+        }
+    }
 
     #[test]
     fn king_moves() {
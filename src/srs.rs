@@ -0,0 +1,268 @@
+//! Spaced-repetition scheduling for puzzle sets.
+//!
+//! Puzzles are scheduled by id with the SM-2 algorithm: each review is
+//! graded from 0 to 5, and a puzzle graded 3 or better is seen again
+//! after a growing interval, while a failed puzzle starts over the next
+//! day. Days are calendar dates, so the caller decides the time zone.
+//!
+//! # Layout
+//! The review state encodes to bytes, all integers little-endian:
+//!
+//! - 4 bytes: the magic "MLSR".
+//! - 1 byte: the format version, currently 1.
+//! - The number of cards (4 bytes), then for each card the puzzle id
+//!   (8 bytes), the ease in hundredths (2 bytes), the interval in days
+//!   (4 bytes), the repetitions and lapses (2 bytes each), and the due
+//!   date as days since 0001-01-01 (4 bytes).
+//! - A CRC-32 of everything before it (4 bytes).
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Days, NaiveDate};
+
+use crate::binary::BinaryError;
+
+const MAGIC: &[u8; 4] = b"MLSR";
+const VERSION: u8 = 1;
+const CARD_SIZE: usize = 22;
+
+/// The ease of a new card, in hundredths.
+pub const START_EASE: u16 = 250;
+
+/// The lowest the ease can fall, in hundredths.
+pub const MIN_EASE: u16 = 130;
+
+/// The review state of one puzzle.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Card {
+    /// How quickly the interval grows, in hundredths.
+    pub ease: u16,
+
+    /// The days between the last review and the next.
+    pub interval: u32,
+
+    /// The number of passing reviews in a row.
+    pub repetitions: u16,
+
+    /// The number of failed reviews.
+    pub lapses: u16,
+
+    /// The day the puzzle should next be reviewed.
+    pub due: NaiveDate,
+}
+
+impl Card {
+    /// A card that has never been reviewed, due on the day.
+    pub fn new(due: NaiveDate) -> Self {
+        Self {
+            ease: START_EASE,
+            interval: 0,
+            repetitions: 0,
+            lapses: 0,
+            due,
+        }
+    }
+
+    /// Apply a review graded from 0 (no idea) to 5 (perfect), on the day.
+    /// Grades above 5 count as 5.
+    pub fn review(&mut self, grade: u8, today: NaiveDate) {
+        let grade = grade.min(5);
+        if grade < 3 {
+            self.repetitions = 0;
+            self.lapses = self.lapses.saturating_add(1);
+            self.interval = 1;
+        } else {
+            self.interval = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval as u64 * self.ease as u64).div_ceil(100).min(u32::MAX as u64) as u32,
+            };
+
+            let miss = (5 - grade) as i32;
+            let ease = self.ease as i32 + 10 - miss * (8 + miss * 2);
+            self.ease = ease.max(MIN_EASE as i32) as u16;
+            self.repetitions = self.repetitions.saturating_add(1);
+        }
+
+        self.due = today.checked_add_days(Days::new(self.interval as u64)).unwrap_or(NaiveDate::MAX);
+    }
+}
+
+/// The cards of a puzzle set, by puzzle id.
+#[derive(Clone, Default, Eq, PartialEq, Debug)]
+pub struct Scheduler {
+    cards: BTreeMap<u64, Card>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a new card for the puzzle, due on the day. Returns false and
+    /// keeps the existing card if the puzzle already has one.
+    pub fn add(&mut self, id: u64, today: NaiveDate) -> bool {
+        if self.cards.contains_key(&id) {
+            return false
+        }
+
+        self.cards.insert(id, Card::new(today));
+        true
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Card> {
+        self.cards.remove(&id)
+    }
+
+    pub fn card(&self, id: u64) -> Option<&Card> {
+        self.cards.get(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Grade a review of the puzzle on the day, adding a card for it if it
+    /// has none, and return the updated card.
+    pub fn review(&mut self, id: u64, grade: u8, today: NaiveDate) -> Card {
+        let card = self.cards.entry(id).or_insert_with(|| Card::new(today));
+        card.review(grade, today);
+        *card
+    }
+
+    /// The puzzles due on or before the day, the most overdue first.
+    pub fn due(&self, today: NaiveDate) -> Vec<u64> {
+        let mut due: Vec<_> = self.cards.iter().filter(|(_, card)| card.due <= today).collect();
+        due.sort_by_key(|&(&id, card)| (card.due, id));
+        due.into_iter().map(|(&id, _)| id).collect()
+    }
+
+    /// The earliest day any puzzle is due, None if there are no cards.
+    pub fn next_due(&self) -> Option<NaiveDate> {
+        self.cards.values().map(|card| card.due).min()
+    }
+
+    /// Encode every card, in the layout described in the module docs.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(MAGIC.len() + 9 + self.cards.len() * CARD_SIZE);
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&(self.cards.len() as u32).to_le_bytes());
+        for (&id, card) in &self.cards {
+            out.extend_from_slice(&id.to_le_bytes());
+            out.extend_from_slice(&card.ease.to_le_bytes());
+            out.extend_from_slice(&card.interval.to_le_bytes());
+            out.extend_from_slice(&card.repetitions.to_le_bytes());
+            out.extend_from_slice(&card.lapses.to_le_bytes());
+            out.extend_from_slice(&card.due.num_days_from_ce().to_le_bytes());
+        }
+
+        let crc = crate::binary::crc32(&out);
+        out.extend_from_slice(&crc.to_le_bytes());
+        out
+    }
+
+    /// Decode cards written by `to_bytes`, verifying the checksum.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+        if bytes.len() < MAGIC.len() + 1 || &bytes[..4] != MAGIC {
+            return Err(BinaryError::Magic)
+        }
+
+        if bytes[4] != VERSION {
+            return Err(BinaryError::Version(bytes[4]))
+        }
+
+        let Some((body, checksum)) = bytes.split_last_chunk::<4>() else {
+            return Err(BinaryError::Truncated)
+        };
+
+        if crate::binary::crc32(body) != u32::from_le_bytes(*checksum) {
+            return Err(BinaryError::Checksum)
+        }
+
+        let Some((count, records)) = body[5..].split_first_chunk::<4>() else {
+            return Err(BinaryError::Truncated)
+        };
+
+        let count = u32::from_le_bytes(*count) as usize;
+        if records.len() != count * CARD_SIZE {
+            return Err(BinaryError::Truncated)
+        }
+
+        let mut cards = BTreeMap::new();
+        for record in records.chunks_exact(CARD_SIZE) {
+            let field = |at: usize, len: usize| &record[at..at + len];
+            let id = u64::from_le_bytes(field(0, 8).try_into().unwrap());
+            let days = i32::from_le_bytes(field(18, 4).try_into().unwrap());
+            let card = Card {
+                ease: u16::from_le_bytes(field(8, 2).try_into().unwrap()),
+                interval: u32::from_le_bytes(field(10, 4).try_into().unwrap()),
+                repetitions: u16::from_le_bytes(field(14, 2).try_into().unwrap()),
+                lapses: u16::from_le_bytes(field(16, 2).try_into().unwrap()),
+                due: NaiveDate::from_num_days_from_ce_opt(days).ok_or(BinaryError::InvalidField)?,
+            };
+
+            if card.ease < MIN_EASE {
+                return Err(BinaryError::InvalidField)
+            }
+
+            cards.insert(id, card);
+        }
+
+        Ok(Self { cards })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Scheduler, MIN_EASE};
+    use crate::binary::BinaryError;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap() + chrono::Days::new(d as u64 - 1)
+    }
+
+    #[test]
+    fn schedule() {
+        let mut srs = Scheduler::new();
+        assert!(srs.add(7, day(1)));
+        assert!(srs.add(3, day(1)));
+        assert!(!srs.add(7, day(2)));
+        assert_eq!(srs.due(day(1)), vec![3, 7]);
+
+        // 1 day, 6 days, then the interval grows by the ease.
+        assert_eq!(srs.review(7, 4, day(1)).due, day(2));
+        assert_eq!(srs.review(7, 4, day(2)).due, day(8));
+        let card = srs.review(7, 5, day(8));
+        assert_eq!((card.interval, card.ease), (15, 260));
+        assert_eq!(srs.due(day(8)), vec![3]);
+        assert_eq!(srs.next_due(), Some(day(1)));
+
+        // a lapse starts over without changing the ease.
+        let card = srs.review(7, 1, day(23));
+        assert_eq!((card.interval, card.repetitions, card.lapses, card.ease), (1, 0, 1, 260));
+
+        for _ in 0..10 {
+            srs.review(3, 3, day(1));
+        }
+        assert_eq!(srs.card(3).unwrap().ease, MIN_EASE);
+    }
+
+    #[test]
+    fn serialize() {
+        let mut srs = Scheduler::new();
+        srs.review(u64::MAX, 5, day(1));
+        srs.review(42, 2, day(3));
+
+        let bytes = srs.to_bytes();
+        assert_eq!(Scheduler::from_bytes(&bytes), Ok(srs));
+        assert_eq!(Scheduler::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(BinaryError::Checksum));
+        assert_eq!(Scheduler::from_bytes(b"MLST\x01").err(), Some(BinaryError::Magic));
+    }
+}
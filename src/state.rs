@@ -1,4 +1,4 @@
-use crate::{board::BitBoard, castle::CastleRights, pieces::{Piece, Pieces}, square::Square, team::Team, trace::MoveTrace, delta::BoardDelta};
+use crate::{board::BitBoard, code::CodeError, castle::{Castle, CastleRights}, fen::FenError, holes::HoleExits, pieces::{Piece, Pieces}, square::Square, team::Team, trace::MoveTrace, delta::BoardDelta};
 
 #[derive(Copy, Clone)]
 pub struct BoardState {
@@ -12,6 +12,9 @@ pub struct BoardState {
     pub pieces: Pieces,
     pub castle: CastleRights,
     pub turn: Team,
+
+    /// Cached attacks out of the wormholes, see `BoardState::hole_exits`.
+    pub exits: HoleExits,
 }
 
 impl BoardState {
@@ -23,9 +26,101 @@ impl BoardState {
         crate::trace::trace(self, src, dst, None)
     }
 
+    /// The squares to pass to `trace` or `play` to replay the move of a
+    /// delta played in this position. Castling deltas store the rook's
+    /// target square, so castling is given as the king moving to its
+    /// target square, as in `legal_moves`.
+    pub fn move_squares(&self, delta: &BoardDelta) -> (Square, Square) {
+        let src = delta.get_src_sq();
+        match delta.get_castle_side() {
+            Some(side) => (src, self.castle.king_target(side, self.turn)),
+            None => (src, delta.get_dst_sq()),
+        }
+    }
+
+    /// Build the delta for a move that was traced in this position.
+    /// Returns None if the move requires a promotion and `promote`
+    /// is not a piece a pawn can promote to.
+    pub fn delta(&self, src: Square, dst: Square, trace: &MoveTrace, promote: Option<Piece>) -> Option<BoardDelta> {
+        let mut delta = BoardDelta::default();
+        if trace.requires_promotion {
+            match promote {
+                Some(pc) if pc.is_promotable() => delta.set_promote_pc(pc),
+                _ => return None,
+            }
+        }
+
+        let mut castle = self.castle;
+
+        if let Some(side) = trace.is_castle {
+            castle.lose(Castle::Short, self.turn);
+            castle.lose(Castle::Long, self.turn);
+            delta.set_src_sq(castle.king_start(self.turn));
+            delta.set_dst_sq(castle.rook_target(side, self.turn));
+            delta.set_is_castle(side);
+        } else {
+            delta.set_src_sq(src);
+            delta.set_dst_sq(dst);
+
+            if !trace.is_king_move {
+                if let Some(side) = trace.loses_castle {
+                    castle.lose(side, self.turn);
+                }
+            }
+
+            if let Some(side) = trace.takes_castle {
+                castle.lose(side, !self.turn);
+            }
+
+            if let Some(capture) = trace.captures {
+                delta.set_capture_pc(capture);
+            }
+
+            if trace.captures.is_some() || self.pieces.piece_at_or_on_hole(src, self.wormholes) == Some(Piece::Pawn) {
+                delta.set_resets_halfmoves();
+            }
+
+            if trace.allows_en_passant.is_some() {
+                delta.set_is_double_push();
+            } else if let Some(ep_sq) = trace.is_capture_en_passant {
+                delta.set_ep_capture_sq(self.en_passant_victim(ep_sq));
+            }
+        }
+
+        if trace.is_king_move {
+            castle.lose(Castle::Long, self.turn);
+            castle.lose(Castle::Short, self.turn);
+        }
+
+        if let Some(ep_sq) = self.en_passant {
+            delta.set_prev_ep_sq(ep_sq);
+        }
+
+        delta.set_castle_deltas(self.castle.rights, castle.rights);
+        delta.set_prev_halfmoves(self.halfmoves);
+
+        if self.king_attacked() {
+            delta.set_was_check();
+        }
+
+        if self.next(delta).king_attacked() {
+            delta.set_is_check();
+        }
+
+        Some(delta)
+    }
+
+    /// Check a delta from an untrusted source against the delta its move
+    /// produces in this position, see `audit::verify_delta`.
+    pub fn verify_delta(&self, delta: &BoardDelta) -> Result<MoveTrace, crate::delta::DeltaError> {
+        crate::audit::verify_delta(self, delta)
+    }
+
     /// Execute changes.
     pub fn next(&self, delta: BoardDelta) -> Self {
         let mut next = *self;
+        next.turn = !self.turn;
+        next.en_passant = None;
         let src = delta.get_src_sq();
         let dst = delta.get_dst_sq();
 
@@ -40,7 +135,7 @@ impl BoardState {
             next.pieces.remove(src, self.wormholes);
 
             // remove captured piece
-            if let Some(_) = delta.get_capture_pc() {
+            if delta.get_capture_pc().is_some() {
                 if let Some(capture_sq) = delta.get_ep_capture_sq() {
                     next.pieces.remove(capture_sq, self.wormholes);
                 } else {
@@ -53,7 +148,7 @@ impl BoardState {
                 next.pieces.insert(dst, promote_pc, self.turn, self.wormholes);
             } else {
                 if let Some(moved_pc) = moved_piece {
-                    next.pieces.insert(dst, moved_pc, next.turn, self.wormholes);
+                    next.pieces.insert(dst, moved_pc, self.turn, self.wormholes);
                 }
             }
 
@@ -73,12 +168,20 @@ impl BoardState {
 
         if delta.is_pushed_wormhole() {
             next.next_hole = Some(delta.get_wormhole_sq());
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(square = delta.get_wormhole_sq().to_index(), in_1 = next.hole_in_1, "wormhole pending");
         }
 
         if delta.is_popped_wormhole() {
             let hole_sq = delta.get_wormhole_sq();
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(square = hole_sq.to_index(), "wormhole spawned");
+
             debug_assert_eq!(self.next_hole, Some(hole_sq), "[E998 (invalid hole state)]");
             next.wormholes.set(hole_sq);
+            next.exits = HoleExits::new(next.wormholes);
             next.next_hole = None;
         }
 
@@ -88,12 +191,12 @@ impl BoardState {
         if delta.is_resets_halfmoves() {
             next.halfmoves = 0;
         } else {
-            next.halfmoves += 1;
+            next.halfmoves = next.halfmoves.saturating_add(1);
         }
 
         // Fullmoves increments when black moves.
         if self.turn == Team::Black {
-            next.fullmoves += 1;
+            next.fullmoves = next.fullmoves.saturating_add(1);
         }
 
         next.is_check = delta.is_check();
@@ -127,8 +230,8 @@ impl BoardState {
                 }
             }
 
-            if let Some(promote_pc) = delta.get_promote_pc() {
-                prev.pieces.insert(src, promote_pc, prev.turn, prev.wormholes);
+            if delta.get_promote_pc().is_some() {
+                prev.pieces.insert(src, Piece::Pawn, prev.turn, prev.wormholes);
             } else {
                 if let Some(moved_pc) = moved_piece {
                     prev.pieces.insert(src, moved_pc, prev.turn, prev.wormholes);
@@ -143,6 +246,7 @@ impl BoardState {
         } else if delta.is_popped_wormhole() {
             let hole_sq = delta.get_wormhole_sq();
             prev.wormholes.clear(hole_sq);
+            prev.exits = HoleExits::new(prev.wormholes);
             prev.next_hole = Some(hole_sq);
             prev.hole_in_1 = true;
         }
@@ -151,7 +255,7 @@ impl BoardState {
         prev.halfmoves = delta.get_prev_halfmoves();
 
         if self.turn == Team::White {
-            prev.fullmoves -= 1;
+            prev.fullmoves = prev.fullmoves.saturating_sub(1).max(1);
         }
 
         prev.is_check = delta.was_check();
@@ -159,6 +263,77 @@ impl BoardState {
         prev
     }
 
+    /// The same position with the colors swapped: pieces change team and
+    /// are mirrored vertically, along with the wormholes, the en-passant
+    /// square, castling rights, and the side to move. 
+    pub fn flipped(&self) -> Self {
+        Self {
+            en_passant: self.en_passant.map(|sq| sq.flip_vertical()),
+            next_hole: self.next_hole.map(|sq| sq.flip_vertical()),
+            wormholes: self.wormholes.flip_vertical(),
+            exits: HoleExits::new(self.wormholes.flip_vertical()),
+            pieces: self.pieces.flipped(),
+            castle: self.castle.flipped(),
+            turn: !self.turn,
+            ..*self
+        }
+    }
+
+    /// The same position mirrored horizontally, so the a-file becomes
+    /// the h-file. Castling always moves the king to the c or g file,
+    /// so it is not symmetric across files and all rights are removed.
+    pub fn mirrored(&self) -> Self {
+        let mut castle = self.castle;
+        castle.rights = 0;
+        Self {
+            en_passant: self.en_passant.map(|sq| sq.flip_horizontal()),
+            next_hole: self.next_hole.map(|sq| sq.flip_horizontal()),
+            wormholes: self.wormholes.flip_horizontal(),
+            exits: HoleExits::new(self.wormholes.flip_horizontal()),
+            pieces: self.pieces.mirrored(),
+            castle,
+            ..*self
+        }
+    }
+
+    /// Apply a symmetry transform to the position.
+    pub fn transformed(&self, transform: Transform) -> Self {
+        match transform {
+            Transform::FlipColors => self.flipped(),
+            Transform::MirrorFiles => self.mirrored(),
+            Transform::Rotate => self.flipped().mirrored(),
+        }
+    }
+
+    /// The same position with `team` to move, for switching sides on an
+    /// analysis board. The en-passant square is cleared, since it only
+    /// applies to the move right after a double push. Returns None if
+    /// `team` would already be attacking the enemy king.
+    pub fn with_turn(&self, team: Team) -> Option<Self> {
+        let mut state = Self {
+            turn: !team,
+            en_passant: None,
+            ..*self
+        };
+
+        if state.king_attacked() {
+            return None
+        }
+
+        state.turn = team;
+        state.is_check = state.king_attacked();
+        Some(state)
+    }
+
+    /// The position as seen by the team to move: unchanged if white is
+    /// to move, otherwise color-flipped so that white is to move.
+    pub fn relative(&self) -> Self {
+        match self.turn {
+            Team::White => *self,
+            Team::Black => self.flipped(),
+        }
+    }
+
     pub fn checkable_king(&self) -> Option<Square> {
         let rel = self.pieces.kings & self.pieces.on_team(self.turn);
         (rel.count() == 1).then(|| {
@@ -166,6 +341,236 @@ impl BoardState {
         })
     }
 
+    /// The square of the pawn that is captured by moving to the en-passant square.
+    /// After a double push through a wormhole, the pawn may stand on a hole.
+    fn en_passant_victim(&self, ep_sq: Square) -> Square {
+        let theirs = self.pieces.get(Piece::Pawn, !self.turn);
+        match ep_sq.next((-self.turn.pawn_dir(), 0)) {
+            Some(sq) if theirs.has(sq) => sq,
+            _ => (theirs & self.wormholes).first().unwrap_or(ep_sq),
+        }
+    }
+
+    /// Every legal capture for the team to move, including en passant
+    /// and captures through wormholes, as `(src, dst, trace)`.
+    /// Quiet moves are never traced, which makes this much cheaper than
+    /// filtering `legal_moves`. Promoting captures are listed once.
+    pub fn captures(&self) -> Vec<(Square, Square, MoveTrace)> {
+        let mut captures = Vec::new();
+        self.for_each_capture(|src, dst, trace| captures.push((src, dst, trace)));
+        captures
+    }
+
+    /// Call `f` with every legal capture, as listed by `captures`,
+    /// without collecting them.
+    pub(crate) fn for_each_capture(&self, mut f: impl FnMut(Square, Square, MoveTrace)) {
+        let defense = crate::defense::defense(self);
+        let mut targets = self.pieces.on_team(!self.turn).transmit(self.wormholes);
+        if let Some(ep_sq) = self.en_passant {
+            targets |= BitBoard::from(ep_sq).transmit(self.wormholes);
+        }
+
+        for src in self.pieces.on_team(self.turn) {
+            for dst in crate::compute::compute(self, src, Some(defense)) & targets {
+                if let Some(trace) = crate::trace::trace(self, src, dst, Some(defense)) {
+                    if trace.captures.is_some() {
+                        f(src, dst, trace);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Every legal move for the team to move that gives check, captures,
+    /// or creates a threat, see `forcing::forcing_moves`.
+    pub fn forcing_moves(&self) -> Vec<crate::forcing::ForcingMove> {
+        crate::forcing::forcing_moves(self)
+    }
+
+    /// Whether the team to move is in check, found from the attacks on
+    /// its king rather than read from the `is_check` field, which is only
+    /// as reliable as the delta or notation the position came from.
+    pub fn is_check(&self) -> bool {
+        self.king_attacked()
+    }
+
+    /// The same as `is_check`, under the name engines usually pair with `checkers`.
+    pub fn in_check(&self) -> bool {
+        self.king_attacked()
+    }
+
+    /// The opponent's pieces that give check, including through wormholes.
+    /// Two checkers is a double check, which only a king move escapes.
+    pub fn checkers(&self) -> BitBoard {
+        crate::defense::checkers(self)
+    }
+
+    /// Whether the team to move is in check and has no legal move.
+    pub fn is_checkmate(&self) -> bool {
+        self.king_attacked() && !self.has_legal_moves()
+    }
+
+    /// Whether the team to move is not in check but has no legal move.
+    pub fn is_stalemate(&self) -> bool {
+        !self.king_attacked() && !self.has_legal_moves()
+    }
+
+    /// Whether the king of the team to move is attacked.
+    pub(crate) fn king_attacked(&self) -> bool {
+        let kings = self.pieces.get(Piece::King, self.turn);
+        crate::defense::defense(self).intersects(kings)
+    }
+
+    /// Whether the team to move has at least one legal move. Stops at the
+    /// first legal move found rather than generating every move, trying
+    /// the king first, since it is often the only piece that can move when
+    /// in check, so it is cheap enough to call after every move.
+    pub fn has_legal_moves(&self) -> bool {
+        let defense = crate::defense::defense(self);
+        let kings = self.pieces.get(Piece::King, self.turn);
+        let others = self.pieces.on_team(self.turn) & !kings;
+        kings.into_iter().chain(others).any(|src| {
+            crate::compute::compute(self, src, Some(defense))
+                .into_iter()
+                .any(|dst| crate::trace::trace(self, src, dst, Some(defense)).is_some())
+        })
+    }
+
+    /// Every legal move for the team to move, as `(src, dst, trace)`.
+    /// Castling is listed once, as the king moving to its target square.
+    /// Moves that require promotion are listed once per destination.
+    pub(crate) fn legal_moves(&self) -> Vec<(Square, Square, MoveTrace)> {
+        let mut moves = Vec::new();
+        self.for_each_legal_move(|src, dst, trace| moves.push((src, dst, trace)));
+        moves
+    }
+
+    /// Call `f` with every legal move, as listed by `legal_moves`,
+    /// without collecting them.
+    pub(crate) fn for_each_legal_move(&self, mut f: impl FnMut(Square, Square, MoveTrace)) {
+        let defense = crate::defense::defense(self);
+        for src in self.pieces.on_team(self.turn) {
+            for dst in crate::compute::compute(self, src, Some(defense)) {
+                if let Some(trace) = crate::trace::trace(self, src, dst, Some(defense)) {
+                    if let Some(side) = trace.is_castle {
+                        if src != self.castle.king_start(self.turn) || dst != self.castle.king_target(side, self.turn) {
+                            continue;
+                        }
+                    }
+
+                    f(src, dst, trace);
+                }
+            }
+        }
+    }
+
+    /// Threats the opponent would be able to carry out
+    /// if it were their turn to move.
+    pub fn threats(&self) -> Vec<crate::threat::Threat> {
+        crate::threat::threats(self)
+    }
+
+    /// The static evaluation in centipawns, from the view of the team to move.
+    pub fn evaluate(&self) -> i32 {
+        crate::eval::evaluate(self)
+    }
+
+    /// The phase of the game, from the material left on the board.
+    pub fn phase(&self) -> crate::phase::GamePhase {
+        crate::phase::phase(self)
+    }
+
+    /// Search for the best move with alpha-beta to the option's depth.
+    pub fn search(&self, options: &crate::search::SearchOptions) -> crate::search::SearchResult {
+        crate::search::search(self, options)
+    }
+
+    /// The Standard Algebraic Notation of a delta played in this position.
+    pub fn to_san(&self, delta: BoardDelta) -> String {
+        crate::san::to_san(self, delta)
+    }
+
+    /// Write the position in Forsyth-Edwards Notation.
+    pub fn to_fen(&self) -> String {
+        crate::fen::to_fen(self)
+    }
+
+    /// Write the position in Shredder-FEN, which names castling rights by rook file.
+    pub fn to_shredder_fen(&self) -> String {
+        crate::fen::to_shredder_fen(self)
+    }
+
+    /// Parse a position from Forsyth-Edwards Notation.
+    /// X-FEN and Shredder-FEN castling rights are also accepted.
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        crate::fen::from_fen(fen)
+    }
+
+    /// Write the position in extended FEN, which also records the wormholes.
+    pub fn to_extended_fen(&self) -> String {
+        crate::fen::to_extended_fen(self)
+    }
+
+    /// Parse a position from extended FEN or standard FEN.
+    pub fn from_extended_fen(fen: &str) -> Result<Self, FenError> {
+        crate::fen::from_extended_fen(fen)
+    }
+
+    /// Draw the position as an SVG diagram from the side of the team.
+    pub fn to_svg(&self, annotation: Option<&crate::annotation::Annotation>, perspective: Team) -> String {
+        crate::svg::to_svg(self, annotation, perspective)
+    }
+
+    /// A short URL-safe code for sharing the position, see `code`.
+    pub fn to_code(&self) -> String {
+        crate::code::to_code(self)
+    }
+
+    pub fn from_code(code: &str) -> Result<Self, CodeError> {
+        crate::code::from_code(code)
+    }
+
+    /// Whether the team to move is out of check and neither
+    /// team has a capture that wins material.
+    pub fn is_quiet(&self) -> bool {
+        crate::see::is_quiet(self)
+    }
+
+    /// Whether the two states are the same position for the purposes
+    /// of repetition: same pieces, side to move, castling rights,
+    /// en-passant square, and wormholes.
+    pub fn is_repetition_of(&self, other: &BoardState) -> bool {
+        self.pieces == other.pieces &&
+        self.turn == other.turn &&
+        self.castle == other.castle &&
+        self.en_passant == other.en_passant &&
+        self.wormholes == other.wormholes &&
+        self.next_hole == other.next_hole &&
+        self.hole_in_1 == other.hole_in_1
+    }
+
+    /// The exits for the given wormholes, from the cache if it was built
+    /// for them. A stale cache, such as after assigning `wormholes`
+    /// directly, is never used.
+    /// With the `cache-stats` feature, lookups are counted in
+    /// `holes::cache_stats`.
+    pub fn hole_exits(&self, wormholes: BitBoard) -> HoleExits {
+        let hit = self.exits.wormholes == wormholes;
+        #[cfg(feature = "cache-stats")]
+        crate::holes::record(hit);
+        if hit {
+            self.exits
+        } else {
+            HoleExits::new(wormholes)
+        }
+    }
+
+    /// Rebuild the cached wormhole exits for the current wormholes, such
+    /// as after assigning `wormholes` directly, so lookups hit again.
+    pub fn invalidate_cache(&mut self) {
+        self.exits = HoleExits::new(self.wormholes);
+    }
+
     pub fn next_wormholes(&self) -> BitBoard {
         if let Some(hole_sq) = self.next_hole && self.hole_in_1 {
             self.wormholes.with(hole_sq)
@@ -175,6 +580,20 @@ impl BoardState {
     }
 }
 
+/// Symmetries of the board that preserve the rules of the game,
+/// apart from castling in the case of mirroring files.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Transform {
+    /// Swap colors and mirror vertically (see `BoardState::flipped`).
+    FlipColors,
+
+    /// Mirror horizontally (see `BoardState::mirrored`).
+    MirrorFiles,
+
+    /// Swap colors and rotate the board by 180 degrees.
+    Rotate,
+}
+
 impl Default for BoardState {
     fn default() -> Self {
         Self {
@@ -188,7 +607,127 @@ impl Default for BoardState {
             pieces: Pieces::default(),
             castle: CastleRights::default(),
             turn: Team::White,
+            exits: HoleExits::default(),
         }
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::{BoardState, Transform};
+    use crate::{board::BitBoard, square::Square, team::Team};
+
+    #[test]
+    fn with_turn() {
+        let state = BoardState::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let black = state.with_turn(Team::Black).unwrap();
+        assert_eq!(black.turn, Team::Black);
+        assert!(!black.is_check);
+        assert!(black.with_turn(Team::White).unwrap().is_repetition_of(&state));
+
+        // black is in check, so the move cannot be handed to white.
+        let check = BoardState::from_fen("R3k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert!(check.with_turn(Team::White).is_none());
+        assert!(check.with_turn(Team::Black).unwrap().is_check);
+    }
+
+    #[test]
+    fn mates() {
+        let mate = BoardState::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert!(mate.is_check() && mate.is_checkmate() && !mate.is_stalemate());
+
+        let stalemate = BoardState::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(!stalemate.is_check() && !stalemate.is_checkmate() && stalemate.is_stalemate());
+
+        let check = BoardState::from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        assert!(check.is_check() && !check.is_checkmate());
+
+        assert!(BoardState::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap().is_checkmate());
+        assert!(!BoardState::default().is_check());
+
+        // only the rook can move, to block the check.
+        let block = BoardState::from_fen("R5k1/5ppp/2r5/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(block.has_legal_moves() && !block.is_checkmate());
+        assert!(!mate.has_legal_moves() && !stalemate.has_legal_moves());
+        assert!(BoardState::default().has_legal_moves());
+    }
+
+    #[test]
+    fn wormhole_exits() {
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+
+        // stalemate, unless the bishop can leave its wormhole on h1 through d4.
+        let stalemate = BoardState::from_fen("k7/8/1Q6/8/8/8/6p1/6Kb b - - 0 1").unwrap();
+        assert!(!stalemate.has_legal_moves() && stalemate.is_stalemate());
+        let exit = BoardState::from_extended_fen("k7/8/1Q6/8/8/8/6p1/6Kb b - - 0 1 d4,h1 - 0").unwrap();
+        assert!(exit.has_legal_moves() && !exit.is_stalemate());
+        let moves = exit.legal_moves();
+        assert!(!moves.is_empty() && moves.iter().all(|&(src, _, _)| src == sq("h1")));
+
+        // the rook reaches h8 along the back rank through d1 and a8.
+        let mate = BoardState::from_extended_fen("7k/6pp/8/8/8/2K5/8/R7 b - - 0 1 a8,d1 - 0").unwrap();
+        assert!(mate.is_check() && !mate.has_legal_moves() && mate.is_checkmate());
+        assert!(!BoardState::from_fen("7k/6pp/8/8/8/2K5/8/R7 b - - 0 1").unwrap().is_check());
+
+        // check through a wormhole still has a way out.
+        let check = crate::positions::wormhole_check();
+        assert!(check.is_check() && check.has_legal_moves() && !check.is_checkmate());
+    }
+
+    #[test]
+    fn checkers() {
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+        assert!(BoardState::default().checkers().is_empty());
+
+        let check = BoardState::from_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        assert_eq!(check.checkers(), BitBoard::from(sq("e1")));
+        assert!(check.in_check());
+
+        let double = BoardState::from_fen("4k3/8/3N4/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        assert_eq!(double.checkers(), BitBoard::from(sq("d6")) | sq("e1"));
+
+        // the rook enters the wormhole on a4 and leaves through e4.
+        let hole = BoardState::from_extended_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1 a4,e4 - 0").unwrap();
+        assert_eq!(hole.checkers(), BitBoard::from(sq("a1")));
+        assert!(hole.in_check() && hole.is_check());
+    }
+
+    #[test]
+    fn transforms() {
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+        assert_eq!(sq("a1").flip_horizontal(), sq("h1"));
+        assert_eq!(sq("d4").flip_horizontal(), sq("e4"));
+        assert_eq!(BitBoard(0x0101010101010101).flip_horizontal(), BitBoard(0x8080808080808080));
+        assert_eq!(BitBoard::from(sq("b2")).flip_horizontal(), BitBoard::from(sq("g2")));
+
+        let en_passant = BoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let mirrored = BoardState::from_fen("3k4/8/8/3Pp3/8/8/8/3K4 w - e6 0 1").unwrap();
+        assert!(en_passant.mirrored().is_repetition_of(&mirrored));
+        assert!(en_passant.mirrored().mirrored().is_repetition_of(&en_passant));
+        assert!(en_passant.transformed(Transform::MirrorFiles).is_repetition_of(&mirrored));
+        assert!(mirrored.trace(sq("d5"), sq("e6")).is_some_and(|trace| trace.is_capture_en_passant.is_some()));
+
+        // mirroring removes castling rights, but keeps every other move.
+        let start = BoardState::default().mirrored();
+        assert_eq!(start.castle.rights, 0);
+        assert_eq!(start.pieces.piece_at(sq("d1")), Some(crate::pieces::Piece::King));
+        assert_eq!(start.legal_moves().len(), 20);
+
+        let hole = BoardState::from_extended_fen("4k3/8/8/8/8/8/8/R3K3 b - - 0 1 a4,e4 - 0").unwrap();
+        let hole_mirrored = hole.mirrored();
+        assert_eq!(hole_mirrored.wormholes, BitBoard::from(sq("h4")) | sq("d4"));
+        assert_eq!(hole_mirrored.checkers(), BitBoard::from(sq("h1")));
+
+        let rotated = hole.transformed(Transform::Rotate);
+        assert!(rotated.is_repetition_of(&hole.flipped().mirrored()));
+        assert_eq!(rotated.turn, Team::White);
+        assert_eq!(rotated.checkers(), BitBoard::from(sq("h8")));
+        assert!(hole.transformed(Transform::FlipColors).is_repetition_of(&hole.flipped()));
+
+        // relative positions always have white to move.
+        assert!(hole.relative().is_repetition_of(&hole.flipped()));
+        assert!(mirrored.relative().is_repetition_of(&mirrored));
+        assert_eq!(hole.relative().legal_moves().len(), hole.legal_moves().len());
+    }
+}
@@ -0,0 +1,144 @@
+//! Post-game statistics computed from the move times stored in the deltas.
+
+use crate::{game::ChessGame, team::Team};
+
+/// Moves played at or below this many milliseconds are counted as premoves.
+pub const PREMOVE_MS: u32 = 100;
+
+/// A player is in time trouble once their clock falls below
+/// this fraction (1/n) of the total time.
+pub const SCRAMBLE_DIVISOR: i64 = 10;
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimeReport {
+    pub white: TeamTimes,
+    pub black: TeamTimes,
+}
+
+impl TimeReport {
+    pub fn get(&self, team: Team) -> &TeamTimes {
+        match team {
+            Team::White => &self.white,
+            Team::Black => &self.black,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TeamTimes {
+    /// The number of moves played by this team.
+    pub moves: usize,
+
+    /// The mean time per move, in milliseconds.
+    pub average_ms: u32,
+
+    /// The median time per move, in milliseconds.
+    pub median_ms: u32,
+
+    /// The longest think as `(index, ms)`, where index is the
+    /// halfmove index of the position the move was played in.
+    pub longest: Option<(usize, u32)>,
+
+    /// Halfmove indices of moves played faster than `PREMOVE_MS`.
+    pub premoves: Vec<usize>,
+
+    /// The halfmove index after which this team's clock first fell
+    /// into time trouble, if the game is timed and it ever did.
+    pub scramble_start: Option<usize>,
+}
+
+/// Build a move-time report for both teams.
+pub fn time_report(game: &ChessGame) -> TimeReport {
+    let mut times: [Vec<(usize, u32)>; 2] = [Vec::new(), Vec::new()];
+    let mut turn = game.start.turn;
+    for (i, delta) in game.deltas.iter().enumerate() {
+        times[turn as usize].push((i, delta.get_time()));
+        turn = !turn;
+    }
+
+    let mut report = TimeReport {
+        white: team_times(&times[Team::White as usize]),
+        black: team_times(&times[Team::Black as usize]),
+    };
+
+    if let Some(clock) = game.settings.clock {
+        let threshold = clock.total_ms() as i64 / SCRAMBLE_DIVISOR;
+        for (index, white, black) in game.clock_history() {
+            if report.white.scramble_start.is_none() && white < threshold {
+                report.white.scramble_start = Some(index);
+            }
+
+            if report.black.scramble_start.is_none() && black < threshold {
+                report.black.scramble_start = Some(index);
+            }
+        }
+    }
+
+    report
+}
+
+fn team_times(times: &[(usize, u32)]) -> TeamTimes {
+    if times.is_empty() {
+        return TeamTimes::default()
+    }
+
+    let total: u64 = times.iter().map(|&(_, ms)| ms as u64).sum();
+    let mut sorted: Vec<u32> = times.iter().map(|&(_, ms)| ms).collect();
+    sorted.sort_unstable();
+
+    let mid = sorted.len() / 2;
+    let median_ms = if sorted.len().is_multiple_of(2) {
+        ((sorted[mid - 1] as u64 + sorted[mid] as u64) / 2) as u32
+    } else {
+        sorted[mid]
+    };
+
+    TeamTimes {
+        moves: times.len(),
+        average_ms: (total / times.len() as u64) as u32,
+        median_ms,
+        longest: times.iter().copied().max_by_key(|&(_, ms)| ms),
+        premoves: times.iter().filter(|&&(_, ms)| ms <= PREMOVE_MS).map(|&(i, _)| i).collect(),
+        scramble_start: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{time_report, TeamTimes};
+    use crate::{clock::MockClock, game::ChessGame, settings::{ClockSettings, GameSettings}, state::BoardState, team::Team};
+
+    #[test]
+    fn report() {
+        let time = MockClock::new(0);
+        let settings = GameSettings {
+            clock: Some(ClockSettings::starting_now(&time, 10, 0)),
+            ..GameSettings::default()
+        };
+
+        let mut game = ChessGame::init(settings);
+        for (ms, uci) in [(50, "e2e4"), (1_000, "e7e5"), (4_000, "g1f3"), (2_000, "b8c6"), (5_200, "f1c4")] {
+            time.advance(ms);
+            let (src, dst, promote) = crate::uci::parse_move(uci).unwrap();
+            game.play_timed(src, dst, promote, &time).ok().unwrap();
+        }
+
+        // white's clock falls below a second after the fifth move.
+        let report = time_report(&game);
+        assert_eq!(report.white, TeamTimes {
+            moves: 3,
+            average_ms: 3_083,
+            median_ms: 4_000,
+            longest: Some((4, 5_200)),
+            premoves: vec![0],
+            scramble_start: Some(5),
+        });
+        assert_eq!(report.get(Team::Black).median_ms, 1_500);
+        assert_eq!(report.black.longest, Some((3, 2_000)));
+        assert_eq!(report.black.scramble_start, None);
+
+        let untimed = ChessGame::from_uci_moves(BoardState::default(), "e2e4").unwrap();
+        assert_eq!(time_report(&untimed).white.scramble_start, None);
+        assert_eq!(time_report(&untimed).black, TeamTimes::default());
+    }
+}
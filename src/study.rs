@@ -0,0 +1,200 @@
+//! Studies, an ordered set of chapters that share a name and tags.
+//!
+//! Each chapter is a titled variation tree, like a chapter of a lichess
+//! study. As PGN, every chapter is a game of its own, tagged with the
+//! study name, the chapter title, and the shared tags. Variations are
+//! written but skipped by `from_pgn`, so only the binary format keeps
+//! every line of every chapter.
+
+use crate::{binary::BinaryError, pgn::{GameStream, PgnError}, tree::GameTree};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum StudyError {
+    /// The chapter at this index could not be imported.
+    Pgn(usize, PgnError),
+}
+
+#[derive(Clone)]
+pub struct Chapter {
+    pub title: String,
+    pub tree: GameTree,
+}
+
+#[derive(Clone, Default)]
+pub struct Study {
+    pub name: String,
+
+    /// Tags written into every chapter, after the tags of the game.
+    /// Tags that the game already has, like "Event", are not shared.
+    pub tags: Vec<(String, String)>,
+
+    /// The chapters in the order they are shown.
+    pub chapters: Vec<Chapter>,
+}
+
+impl Study {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Add a chapter after the others, returning its index.
+    pub fn add_chapter(&mut self, title: impl Into<String>, tree: GameTree) -> usize {
+        self.chapters.push(Chapter { title: title.into(), tree });
+        self.chapters.len() - 1
+    }
+
+    pub fn remove_chapter(&mut self, index: usize) -> Option<Chapter> {
+        (index < self.chapters.len()).then(|| self.chapters.remove(index))
+    }
+
+    /// Move the chapter at `from` so that it is at `to`, shifting the
+    /// chapters between them. Returns false if either index is out of range.
+    pub fn move_chapter(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.chapters.len() || to >= self.chapters.len() {
+            return false
+        }
+
+        let chapter = self.chapters.remove(from);
+        self.chapters.insert(to, chapter);
+        true
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Set a shared tag, replacing its value if it is already set.
+    pub fn set_tag(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let (name, value) = (name.into(), value.into());
+        match self.tags.iter_mut().find(|(key, _)| *key == name) {
+            Some((_, old)) => *old = value,
+            None => self.tags.push((name, value)),
+        }
+    }
+
+    /// Write every chapter as a game in a multi-game PGN, with the
+    /// "StudyName" and "ChapterName" tags.
+    pub fn to_pgn(&self) -> String {
+        let mut tags = vec![
+            ("StudyName".to_string(), self.name.clone()),
+            ("ChapterName".to_string(), String::new()),
+        ];
+        tags.extend(self.tags.iter().filter(|(name, _)| is_shared(name)).cloned());
+
+        let mut pgn = String::new();
+        for (i, chapter) in self.chapters.iter().enumerate() {
+            if i > 0 {
+                pgn.push('\n');
+            }
+
+            tags[1].1.clone_from(&chapter.title);
+            pgn.push_str(&crate::pgn::write_pgn(chapter.tree.mainline(), Some(&chapter.tree), &tags));
+        }
+
+        pgn
+    }
+
+    /// Read a multi-game PGN, with each game as a chapter. The name comes
+    /// from the first game's "StudyName" tag, and the shared tags are the
+    /// tags that every game has with the same value.
+    pub fn from_pgn(pgn: &str) -> Result<Self, StudyError> {
+        let mut study = Self::default();
+        let mut stream = GameStream::new(pgn.as_bytes());
+        let mut index = 0;
+
+        // a str is valid UTF-8, so reading it never fails.
+        while let Some(Ok(text)) = stream.next_text() {
+            let error = |error| StudyError::Pgn(index, error);
+            let game = crate::pgn::from_pgn(&text).map_err(error)?;
+            let tags = crate::pgn::tags(&text).map_err(error)?;
+            let tag = |name: &str| tags.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone());
+
+            if index == 0 {
+                study.name = tag("StudyName").unwrap_or_default();
+                study.tags = tags.iter().filter(|(name, _)| is_shared(name)).cloned().collect();
+            } else {
+                study.tags.retain(|pair| tags.contains(pair));
+            }
+
+            study.add_chapter(tag("ChapterName").unwrap_or_default(), GameTree::new(game));
+            index += 1;
+        }
+
+        Ok(study)
+    }
+
+    /// Encode the study and every line of its chapters, see `binary::study_to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        crate::binary::study_to_bytes(self)
+    }
+
+    /// Decode a study written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+        crate::binary::study_from_bytes(bytes)
+    }
+}
+
+fn is_shared(name: &str) -> bool {
+    !crate::pgn::GAME_TAGS.contains(&name) && name != "StudyName" && name != "ChapterName"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Study, StudyError};
+    use crate::{binary::BinaryError, game::ChessGame, pgn::PgnError, state::BoardState, tree::GameTree};
+
+    fn study() -> Study {
+        let mut italian = GameTree::new(ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 f1c4").unwrap());
+        italian.goto_address(&crate::address::NodeAddress { root: italian.mainline().game_id, path: vec![(0, 4)] });
+        let (src, dst, promote) = crate::uci::parse_move("f1b5").unwrap();
+        assert!(italian.play(src, dst, promote).is_ok());
+
+        let mut study = Study::new("Open games");
+        study.set_tag("Annotator", "Coach");
+        study.set_tag("Annotator", "Trainer");
+        study.add_chapter("Scotch", GameTree::new(ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6 d2d4").unwrap()));
+        study.add_chapter("Italian", italian);
+        assert!(study.move_chapter(1, 0));
+        study
+    }
+
+    #[test]
+    fn pgn() {
+        let study = study();
+        let pgn = study.to_pgn();
+        assert_eq!(pgn.matches("[StudyName \"Open games\"]").count(), 2);
+        assert_eq!(pgn.matches("[Annotator \"Trainer\"]").count(), 2);
+        assert!(pgn.find("[ChapterName \"Italian\"]") < pgn.find("[ChapterName \"Scotch\"]"));
+        assert!(pgn.contains("3. Bc4 (3. Bb5) *"));
+
+        let read = Study::from_pgn(&pgn).unwrap();
+        assert_eq!(read.name, "Open games");
+        assert_eq!(read.tags, study.tags);
+        assert_eq!(read.chapters.len(), 2);
+        assert_eq!(read.chapters[0].title, "Italian");
+        assert_eq!(read.chapters[0].tree.mainline().moves_uci(), "e2e4 e7e5 g1f3 b8c6 f1c4");
+        assert_eq!(read.chapters[1].tree.mainline().moves_uci(), "e2e4 e7e5 g1f3 b8c6 d2d4");
+
+        let broken = pgn.replace("d4 *", "d5 *");
+        assert_eq!(Study::from_pgn(&broken).err(), Some(StudyError::Pgn(1, PgnError::Move(4))));
+    }
+
+    #[test]
+    fn binary() {
+        let study = study();
+        let read = Study::from_bytes(&study.to_bytes()).unwrap();
+        assert_eq!(read.name, study.name);
+        assert_eq!(read.tags, study.tags);
+        assert_eq!(read.chapters[0].title, "Italian");
+        assert_eq!(read.chapters[0].tree.games().len(), 2);
+        assert_eq!(read.chapters[0].tree.games()[1].moves_uci(), "");
+        assert_eq!(read.to_pgn(), study.to_pgn());
+
+        let mut bytes = study.to_bytes();
+        bytes[12] ^= 1;
+        assert_eq!(Study::from_bytes(&bytes).err(), Some(BinaryError::Checksum));
+    }
+}
@@ -0,0 +1,141 @@
+//! Board diagrams as SVG, with the arrows and highlights of an annotation.
+//!
+//! Squares are 45 units wide. Pieces are drawn as Unicode glyphs so the
+//! diagram needs no images, and each wormhole is a dark disc under the
+//! piece on it. A piece on a wormhole is drawn on the hole it is stored
+//! on. The colors of the drawings follow lichess.
+
+use std::fmt::Write;
+
+use crate::{annotation::{Annotation, Brush}, square::Square, state::BoardState, team::Team};
+
+const SQUARE: f32 = 45.0;
+const LIGHT: &str = "#f0d9b5";
+const DARK: &str = "#b58863";
+const WORMHOLE: &str = "#3b2357";
+
+/// Draw the position from the side of the team, with the drawings of
+/// the annotation on top of the pieces.
+pub fn to_svg(state: &BoardState, annotation: Option<&Annotation>, perspective: Team) -> String {
+    let size = SQUARE * 8.0;
+    let mut svg = String::new();
+    let _ = writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {size} {size}\" width=\"{size}\" height=\"{size}\">");
+
+    svg.push_str("<defs>\n");
+    for brush in [Brush::Green, Brush::Red, Brush::Yellow, Brush::Blue] {
+        let _ = writeln!(
+            svg,
+            "<marker id=\"head-{}\" viewBox=\"0 0 4 4\" refX=\"2\" refY=\"2\" markerWidth=\"4\" markerHeight=\"4\" orient=\"auto\"><path d=\"M0,0 L4,2 L0,4 z\" fill=\"{}\"/></marker>",
+            brush.to_char(),
+            rgb(brush),
+        );
+    }
+    svg.push_str("</defs>\n");
+
+    for i in 0..64 {
+        let sq = Square::from_index(i);
+        let (x, y) = corner(sq, perspective);
+        let fill = if (sq.rank_u8() + sq.file_u8()).is_multiple_of(2) { DARK } else { LIGHT };
+        let _ = writeln!(svg, "<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE}\" height=\"{SQUARE}\" fill=\"{fill}\"/>");
+    }
+
+    for sq in state.wormholes {
+        let (x, y) = center(sq, perspective);
+        let _ = writeln!(svg, "<circle cx=\"{x}\" cy=\"{y}\" r=\"{}\" fill=\"{WORMHOLE}\" opacity=\"0.7\"/>", SQUARE * 0.42);
+    }
+
+    for (team, piece, board) in state.pieces.as_array() {
+        for sq in board {
+            let (x, y) = center(sq, perspective);
+            let _ = writeln!(
+                svg,
+                "<text x=\"{x}\" y=\"{y}\" font-size=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                SQUARE * 0.85,
+                piece.to_unicode(team),
+            );
+        }
+    }
+
+    if let Some(annotation) = annotation {
+        for highlight in &annotation.highlights {
+            let (x, y) = center(highlight.square, perspective);
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{x}\" cy=\"{y}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"4\" opacity=\"0.8\"/>",
+                SQUARE * 0.45,
+                rgb(highlight.color),
+            );
+        }
+
+        for arrow in &annotation.arrows {
+            let (x1, y1) = center(arrow.from, perspective);
+            let (x2, y2) = center(arrow.to, perspective);
+
+            // stop short of the center so the tip of the head lands on it.
+            let len = (x2 - x1).hypot(y2 - y1).max(1.0);
+            let short = SQUARE * 0.3 / len;
+            let (x2, y2) = (x2 - (x2 - x1) * short, y2 - (y2 - y1) * short);
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" opacity=\"0.8\" marker-end=\"url(#head-{})\"/>",
+                rgb(arrow.color),
+                SQUARE * 0.2,
+                arrow.color.to_char(),
+            );
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn rgb(brush: Brush) -> &'static str {
+    match brush {
+        Brush::Green => "#15781b",
+        Brush::Red => "#882020",
+        Brush::Yellow => "#e68f00",
+        Brush::Blue => "#003088",
+    }
+}
+
+/// The top left corner of the square in the diagram.
+fn corner(sq: Square, perspective: Team) -> (f32, f32) {
+    let (file, rank) = (sq.file_u8() as f32, sq.rank_u8() as f32);
+    match perspective {
+        Team::White => (file * SQUARE, (7.0 - rank) * SQUARE),
+        Team::Black => ((7.0 - file) * SQUARE, rank * SQUARE),
+    }
+}
+
+fn center(sq: Square, perspective: Team) -> (f32, f32) {
+    let (x, y) = corner(sq, perspective);
+    (x + SQUARE / 2.0, y + SQUARE / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_svg;
+    use crate::{annotation::{Annotation, Arrow, Brush, Highlight}, state::BoardState, team::Team};
+
+    #[test]
+    fn drawings() {
+        let state = BoardState::from_extended_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1 a2,c4 - 0").unwrap();
+        let annotation = Annotation {
+            arrows: vec![Arrow { from: "a1".parse().unwrap(), to: "a8".parse().unwrap(), color: Brush::Red }],
+            highlights: vec![Highlight { square: "e8".parse().unwrap(), color: Brush::Blue }],
+            ..Annotation::default()
+        };
+
+        let svg = to_svg(&state, Some(&annotation), Team::White);
+        assert!(svg.starts_with("<svg "));
+        assert_eq!(svg.matches("<rect ").count(), 64);
+        assert_eq!(svg.matches("opacity=\"0.7\"").count(), 2);
+        assert_eq!(svg.matches("<text ").count(), 3);
+        assert!(svg.contains("<circle cx=\"202.5\" cy=\"22.5\" r=\"20.25\" fill=\"none\" stroke=\"#003088\""));
+        assert!(svg.contains("<line x1=\"22.5\" y1=\"337.5\" x2=\"22.5\" y2=\"36\" stroke=\"#882020\""));
+
+        // from black's side, a1 is in the top right corner.
+        let flipped = to_svg(&state, Some(&annotation), Team::Black);
+        assert!(flipped.contains("<line x1=\"337.5\" y1=\"22.5\""));
+    }
+}
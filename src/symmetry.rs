@@ -0,0 +1,76 @@
+//! Consistency checks for evaluation functions.
+//!
+//! An evaluation scored from white's perspective should give the
+//! color-flipped position the same score with the opposite sign.
+//! Asymmetries usually point to a bug in a piece-square table or a
+//! term that only handles one side.
+
+use crate::{game::ChessGame, state::BoardState};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Asymmetry {
+    /// The halfmove index of the position, if it was part of a game.
+    pub index: Option<usize>,
+
+    /// The score of the position.
+    pub eval: i32,
+
+    /// The score of the color-flipped position.
+    pub flipped_eval: i32,
+}
+
+/// Check that `eval(state) == -eval(state.flipped())`,
+/// where `eval` scores positions from white's perspective.
+pub fn check<F: Fn(&BoardState) -> i32>(state: &BoardState, eval: F) -> Result<(), Asymmetry> {
+    let score = eval(state);
+    let flipped_score = eval(&state.flipped());
+    if score == -flipped_score {
+        Ok(())
+    } else {
+        Err(Asymmetry {
+            index: None,
+            eval: score,
+            flipped_eval: flipped_score,
+        })
+    }
+}
+
+/// Check every position in the game, returning the first asymmetry.
+pub fn check_game<F: Fn(&BoardState) -> i32>(game: &ChessGame, eval: F) -> Result<(), Asymmetry> {
+    let mut state = game.start;
+    for index in 0..=game.deltas.len() {
+        check(&state, &eval).map_err(|err| Asymmetry { index: Some(index), ..err })?;
+        if let Some(delta) = game.deltas.get(index) {
+            state = state.next(*delta);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::ChessGame, pieces::Piece, state::BoardState, team::Team};
+
+    fn material(state: &BoardState) -> i32 {
+        state.pieces.iter()
+            .map(|(team, pc, _)| if team == Team::White { pc.value() } else { -pc.value() })
+            .sum()
+    }
+
+    #[test]
+    fn material_is_symmetric() {
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 d7d5 e4d5 d8d5 b1c3").unwrap();
+        assert_eq!(super::check_game(&game, material), Ok(()));
+
+        let state = game.cursor.state;
+        assert_eq!(state.flipped().turn, Team::White);
+        assert!(state.is_repetition_of(&state.flipped().flipped()));
+    }
+
+    #[test]
+    fn one_sided_term_is_reported() {
+        let white_queen_bonus = |state: &BoardState| material(state) + state.pieces.get(Piece::Queen, Team::White).count() as i32;
+        assert!(super::check(&BoardState::default(), white_queen_bonus).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Win/draw/loss tablebases for the smallest wormhole endgames.
+//!
+//! No external tablebase knows about wormholes, and a piece that can
+//! jump between holes changes which of these endings are won, so
+//! tables are generated here by retrograde analysis for a king and one
+//! piece against a lone king with a fixed set of wormholes.
+//!
+//! Every position is indexed by the side to move and the squares of the
+//! three pieces. A piece on a wormhole occupies every hole, so it is
+//! always indexed on the first hole. The fifty-move rule is ignored.
+//!
+//! Positions are resolved outward from the checkmates, nearest first,
+//! so the table also knows how many halfmoves each won or lost position
+//! is from mate.
+
+use std::collections::VecDeque;
+
+use crate::{board::BitBoard, holes::HoleExits, pieces::{Piece, Pieces}, search::ScoreKind, square::Square, state::BoardState, team::Team};
+
+const SIZE: usize = 2 * 64 * 64 * 64;
+
+/// The outcome of a position with best play, from the view of the team to move.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum Wdl {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The outcome of every position where white has a king and one piece
+/// against the black king, for one arrangement of wormholes.
+#[derive(Clone)]
+pub struct Tablebase {
+    /// The piece white has besides the king.
+    pub piece: Piece,
+
+    /// The wormholes every position in the table has.
+    pub wormholes: BitBoard,
+
+    /// The outcome at each index, None if the index is not a legal position.
+    table: Vec<Option<Wdl>>,
+
+    /// The halfmoves to mate at each index with best play, 0 for draws.
+    mate: Vec<u16>,
+}
+
+impl Tablebase {
+    /// Generate the table for a king and the piece against a king.
+    /// Returns None for pawns and kings, which are not supported.
+    pub fn generate(piece: Piece, wormholes: BitBoard) -> Option<Self> {
+        if matches!(piece, Piece::Pawn | Piece::King) {
+            return None
+        }
+
+        let mut table = vec![None; SIZE];
+
+        // the number of moves from each position that are not yet known
+        // to lose for the mover. A position whose moves all win for the
+        // opponent is lost. Captures of the piece draw, so they are never
+        // counted down.
+        let mut remaining = vec![0u8; SIZE];
+        let mut offsets = vec![0u32; SIZE + 1];
+        let mut successors = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for index in 0..SIZE {
+            if let Some(state) = position(index, piece, wormholes) {
+                let moves = state.legal_moves();
+                remaining[index] = moves.len() as u8;
+                table[index] = Some(Wdl::Draw);
+
+                if moves.is_empty() && state.king_attacked() {
+                    table[index] = Some(Wdl::Loss);
+                    queue.push_back(index);
+                }
+
+                for (src, dst, trace) in moves {
+                    let next = state.delta(src, dst, &trace, None).map(|delta| state.next(delta));
+                    if let Some(next) = next.and_then(|next| index_of(&next, piece)) {
+                        successors.push(next as u32);
+                    }
+                }
+            }
+
+            offsets[index + 1] = successors.len() as u32;
+        }
+
+        // invert the successors into the positions each one is reached from.
+        let mut starts = vec![0u32; SIZE + 1];
+        for &next in &successors {
+            starts[next as usize + 1] += 1;
+        }
+        for i in 0..SIZE {
+            starts[i + 1] += starts[i];
+        }
+
+        let mut fill = starts.clone();
+        let mut predecessors = vec![0u32; successors.len()];
+        for index in 0..SIZE {
+            for &next in &successors[offsets[index] as usize..offsets[index + 1] as usize] {
+                predecessors[fill[next as usize] as usize] = index as u32;
+                fill[next as usize] += 1;
+            }
+        }
+
+        drop(successors);
+
+        // a position is won if any move reaches a lost position, and lost
+        // once every move reaches a won position. Resolving nearest first
+        // means a win takes the quickest mate, and a loss the slowest.
+        let mut mate = vec![0u16; SIZE];
+        let mut resolved = vec![false; SIZE];
+        for &index in &queue {
+            resolved[index] = true;
+        }
+
+        while let Some(index) = queue.pop_front() {
+            let lost = table[index] == Some(Wdl::Loss);
+            for &prev in &predecessors[starts[index] as usize..starts[index + 1] as usize] {
+                let prev = prev as usize;
+                if resolved[prev] {
+                    continue;
+                }
+
+                if lost {
+                    table[prev] = Some(Wdl::Win);
+                } else {
+                    remaining[prev] -= 1;
+                    if remaining[prev] != 0 {
+                        continue;
+                    }
+                    table[prev] = Some(Wdl::Loss);
+                }
+
+                resolved[prev] = true;
+                mate[prev] = mate[index] + 1;
+                queue.push_back(prev);
+            }
+        }
+
+        Some(Self { piece, wormholes, table, mate })
+    }
+
+    /// The outcome of the position, from the view of the team to move.
+    /// Either team may have the piece. Returns None if the position has
+    /// different material or wormholes than the table, a pending wormhole,
+    /// or castling rights.
+    pub fn probe(&self, state: &BoardState) -> Option<Wdl> {
+        self.table[self.index(state)?]
+    }
+
+    /// The outcome of the position as a score, a mate in some number of
+    /// moves if it is won or lost, and zero centipawns if it is drawn.
+    pub fn probe_score(&self, state: &BoardState) -> Option<ScoreKind> {
+        let index = self.index(state)?;
+        let plies = self.mate[index] as i32;
+        Some(match self.table[index]? {
+            Wdl::Win => ScoreKind::mate(plies),
+            Wdl::Loss => ScoreKind::mate(-plies),
+            Wdl::Draw => ScoreKind::Centipawns(0),
+        })
+    }
+
+    fn index(&self, state: &BoardState) -> Option<usize> {
+        if state.next_hole.is_some() || state.castle.rights != 0 {
+            return None
+        }
+
+        let state = if state.pieces.get(self.piece, Team::White).is_empty() {
+            state.flipped()
+        } else {
+            *state
+        };
+
+        if state.wormholes != self.wormholes {
+            return None
+        }
+
+        index_of(&state, self.piece)
+    }
+}
+
+/// The index of a position with white's king and piece against the black king,
+/// or None if the position has any other material.
+fn index_of(state: &BoardState, piece: Piece) -> Option<usize> {
+    let pieces = &state.pieces;
+    let expected = pieces.get(Piece::King, Team::White).count() == 1
+        && pieces.get(Piece::King, Team::Black).count() == 1
+        && pieces.get(piece, Team::White).count() == 1
+        && pieces.occupied().count() == 3;
+
+    if !expected {
+        return None
+    }
+
+    let square = |board: BitBoard| {
+        let sq = board.first()?;
+        if state.wormholes.has(sq) { state.wormholes.first() } else { Some(sq) }
+    };
+
+    let turn = match state.turn {
+        Team::White => 0,
+        Team::Black => 1,
+    };
+
+    let white = square(pieces.get(Piece::King, Team::White))?.to_index();
+    let black = square(pieces.get(Piece::King, Team::Black))?.to_index();
+    let piece = square(pieces.get(piece, Team::White))?.to_index();
+    Some(((turn * 64 + white) * 64 + black) * 64 + piece)
+}
+
+/// The position at the index, or None if it is not a legal position: pieces
+/// share a square, more than one piece is on the wormholes, a piece is on a
+/// hole other than the first, or the team not to move is in check.
+fn position(index: usize, piece: Piece, wormholes: BitBoard) -> Option<BoardState> {
+    let squares = [
+        (Square::from_index(index / 64 / 64 % 64), Piece::King, Team::White),
+        (Square::from_index(index / 64 % 64), Piece::King, Team::Black),
+        (Square::from_index(index % 64), piece, Team::White),
+    ];
+
+    let mut occupied = BitBoard::new();
+    for (sq, _, _) in squares {
+        if occupied.has(sq) || (wormholes.has(sq) && (occupied.intersects(wormholes) || wormholes.first() != Some(sq))) {
+            return None
+        }
+        occupied.set(sq);
+    }
+
+    let mut state = BoardState {
+        wormholes,
+        exits: HoleExits::new(wormholes),
+        pieces: Pieces::empty(),
+        turn: if index < SIZE / 2 { Team::White } else { Team::Black },
+        ..BoardState::default()
+    };
+
+    state.castle.rights = 0;
+    for (sq, pc, team) in squares {
+        state.pieces.insert(sq, pc, team, BitBoard::new());
+    }
+
+    state.with_turn(state.turn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tablebase, Wdl};
+    use crate::{board::BitBoard, pieces::Piece, search::ScoreKind, square::Square, state::BoardState};
+
+    #[test]
+    fn queen_wins() {
+        let tb = Tablebase::generate(Piece::Queen, BitBoard::new()).unwrap();
+        let probe = |fen: &str| tb.probe(&BoardState::from_fen(fen).unwrap());
+
+        assert_eq!(probe("8/8/8/4k3/8/8/8/3QK3 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/8/4k3/8/8/8/3QK3 b - - 0 1"), Some(Wdl::Loss));
+
+        // the king takes the undefended queen.
+        assert_eq!(probe("8/8/8/8/8/8/3q4/4K2k w - - 0 1"), Some(Wdl::Draw));
+
+        // black has the queen, and the white king is stalemated or mated.
+        assert_eq!(probe("8/8/8/8/8/5k2/5q2/7K w - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("8/8/8/8/8/5k2/6q1/7K w - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(probe("8/8/8/8/8/5k2/8/R6K w - - 0 1"), None);
+
+        let score = |fen: &str| tb.probe_score(&BoardState::from_fen(fen).unwrap());
+        assert_eq!(score("6k1/8/6K1/8/8/8/8/3Q4 w - - 0 1"), Some(ScoreKind::MateIn(1)));
+        assert_eq!(score("8/8/8/8/8/5k2/6q1/7K w - - 0 1"), Some(ScoreKind::MateIn(0)));
+        assert_eq!(score("8/8/8/8/8/5k2/5q2/7K w - - 0 1"), Some(ScoreKind::Centipawns(0)));
+        let Some(ScoreKind::MateIn(moves)) = score("8/8/8/4k3/8/8/8/3QK3 b - - 0 1") else {
+            panic!("expected a forced mate");
+        };
+        assert!((-10..-1).contains(&moves));
+
+        let holes = BitBoard::from(Square::from_index(27)) | Square::from_index(36);
+        assert!(Tablebase::generate(Piece::Pawn, holes).is_none());
+    }
+
+    #[test]
+    fn rook_with_wormholes() {
+        let holes = BitBoard::from(Square::from_index(27)) | Square::from_index(36);
+        let tb = Tablebase::generate(Piece::Rook, holes).unwrap();
+        let probe = |fen: &str| tb.probe(&BoardState::from_extended_fen(fen).unwrap());
+
+        assert_eq!(probe("8/8/1k6/8/8/8/8/R3K3 w - - 0 1 d4,e5 - 0"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/1k6/8/8/8/8/R3K3 w - - 0 1"), None);
+
+        // a rook on either hole is the same position.
+        let on_d4 = probe("8/8/1k6/8/3R4/8/8/4K3 b - - 0 1 d4,e5 - 0");
+        assert!(on_d4.is_some());
+        assert_eq!(probe("8/8/1k6/4R3/8/8/8/4K3 b - - 0 1 d4,e5 - 0"), on_d4);
+    }
+}
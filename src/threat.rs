@@ -0,0 +1,70 @@
+//! Detection of what the opponent is threatening.
+
+use crate::{pieces::Piece, square::Square, state::BoardState};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Threat {
+    /// An enemy piece attacks a friendly piece that is
+    /// undefended or worth more than the attacker.
+    Attack {
+        attacker: Square,
+        target: Square,
+        piece: Piece,
+    },
+
+    /// The enemy could checkmate by playing this move.
+    MateIn1 {
+        src: Square,
+        dst: Square,
+    },
+}
+
+/// Find the threats against the team to move, by passing the
+/// turn to the opponent and looking at the moves they could make.
+pub fn threats(state: &BoardState) -> Vec<Threat> {
+    let mut null = *state;
+    null.turn = !state.turn;
+    null.en_passant = None;
+
+    // squares the team to move defends.
+    let defended = crate::defense::defense(&null);
+    let mut threats = Vec::new();
+
+    for (src, dst, trace) in null.legal_moves() {
+        let Some(delta) = null.delta(src, dst, &trace, Some(Piece::Queen)) else {
+            continue
+        };
+
+        let next = null.next(delta);
+        if next.king_attacked() && !next.has_legal_moves() {
+            threats.push(Threat::MateIn1 { src, dst });
+        }
+
+        if let Some(piece) = trace.captures && piece != Piece::King {
+            let attacker = null.pieces.piece_at_or_on_hole(src, null.wormholes).unwrap_or(Piece::Pawn);
+            if !defended.has(dst) || piece.value() > attacker.value() {
+                threats.push(Threat::Attack { attacker: src, target: dst, piece });
+            }
+        }
+    }
+
+    threats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Threat;
+    use crate::{game::ChessGame, square::Square, state::BoardState};
+
+    #[test]
+    fn scholars_mate_threat() {
+        let game = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 d1h5 b8c6 f1c4").unwrap();
+        let threats = game.cursor.state.threats();
+        let h5 = Square::from((4, 7));
+        let f7 = Square::from((6, 5));
+        assert!(threats.contains(&Threat::MateIn1 { src: h5, dst: f7 }));
+
+        // the pawns on e5, f7, and h7 are all defended.
+        assert!(!threats.iter().any(|threat| matches!(threat, Threat::Attack { .. })));
+    }
+}
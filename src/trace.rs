@@ -1,5 +1,5 @@
 
-use crate::{board::BitBoard, cached::*, ray::*, castle::{can_castle, Castle}, pieces::Piece, square::Square, state::BoardState, team::Team};
+use crate::{board::BitBoard, castle::{can_castle, Castle}, pieces::Piece, square::Square, state::BoardState};
 
 #[derive(Copy, Clone)]
 pub struct MoveTrace {
@@ -9,7 +9,8 @@ pub struct MoveTrace {
     /// Whether the move is a capture of a piece.
     pub captures: Option<Piece>,
 
-    /// Whether the move is a capture en-passant.
+    /// If the move is a capture en-passant, the en-passant square
+    /// the capturing pawn moves to.
     pub is_capture_en_passant: Option<Square>,
 
     /// Whether the move allows en passant
@@ -32,6 +33,13 @@ pub struct MoveTrace {
     /// Whether the move takes castle from the opponent.
     /// Occurs when you capture an opponents' rook that had castle rights.
     pub takes_castle: Option<Castle>,
+
+    /// Whether the move puts the opponent in check. Only set by
+    /// `ChessGame::play`, since tracing does not make the move.
+    pub gives_check: bool,
+
+    /// Whether the move checkmates the opponent. Only set by `ChessGame::play`.
+    pub gives_checkmate: bool,
 }
 
 impl Default for MoveTrace {
@@ -46,6 +54,8 @@ impl Default for MoveTrace {
             is_castle: None,
             loses_castle: None,
             takes_castle: None,
+            gives_check: false,
+            gives_checkmate: false,
         }
     }
 }
@@ -64,7 +74,6 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
         let srcs = BitBoard::from(src).transmit(wormholes);
         let turn = state.turn;
         let occupied = state.pieces.occupied().transmit(wormholes);
-        let holes_are_occ = occupied.intersects(wormholes);
         let captures = state.pieces.piece_at_or_on_hole(dst, wormholes);
         let loses_castle = state.castle.move_loses_castle(srcs, turn);
         let takes_castle = state.castle.capture_takes_castle(dsts, !turn);
@@ -76,9 +85,11 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                 if dst.rank() == turn.back_rank() && src.rank() == turn.back_rank() {
                     for side in [Castle::Long, Castle::Short] {
                         if src == state.castle.king_start(turn) {
+                            // the king moves to its target square, or onto its rook. The rook's
+                            // target square is a normal king move, like Kf1 or Kd1.
                             if can_castle(side, turn, state.castle, defense, occupied, src) && (
-                                dst == state.castle.rook_target(side, turn) || 
-                                dst == state.castle.king_target(side, turn)
+                                dst == state.castle.king_target(side, turn) ||
+                                dst == state.castle.rook_start(side, turn)
                             ) {
                                 return Some(MoveTrace {
                                     is_king_move: true,
@@ -91,14 +102,18 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                 }
 
                 if wormholes.has(src) {
-                    for out_sq in wormholes {
-                        let mv = out_sq.king_moves() & !(friendly | defense | wormholes);
-                        if mv.has(dst) {
-                            return Some(MoveTrace {
-                                route: (out_sq != src).then(|| (src, out_sq)),
-                                is_king_move: true,
-                                ..MoveTrace::default()
-                            })
+                    // only search for the exit if some hole reaches dst.
+                    if (state.hole_exits(wormholes).king & !(friendly | defense)).has(dst) {
+                        for out_sq in wormholes {
+                            if out_sq.king_moves().has(dst) {
+                                return Some(MoveTrace {
+                                    route: (out_sq != src).then(|| (src, out_sq)),
+                                    is_king_move: true,
+                                    takes_castle,
+                                    captures,
+                                    ..MoveTrace::default()
+                                })
+                            }
                         }
                     }
                 } else {
@@ -106,6 +121,8 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                     if mv.intersects(dsts) {
                         return Some(MoveTrace {
                             is_king_move: true,
+                            takes_castle,
+                            captures,
                             ..MoveTrace::default()
                         })
                     }
@@ -113,69 +130,22 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
             },
             Piece::Knight => {
                 let blockable = crate::blockable::blockable(src, state);
-                if wormholes.has(src) {
-                    for out_sq in wormholes {
-                        if ((out_sq.knight_moves() & !friendly) & blockable).intersects(dsts) {
-                            return Some(MoveTrace {
-                                route: (out_sq != src).then(|| (src, out_sq)),
-                                takes_castle,
-                                captures,
-                                ..MoveTrace::default()
-                            })
-                        }
-                    }
-                } else {
-                    if ((src.knight_moves() & !friendly) & blockable).intersects(dsts) {
-                        return Some(MoveTrace {
-                            takes_castle,
-                            captures,
-                            ..MoveTrace::default()
-                        })
-                    }
-                }
+                return leap(state, src, dst, blockable, MoveTrace { captures, takes_castle, ..MoveTrace::default() })
             },
             Piece::Bishop => {
                 let blockable = crate::blockable::blockable(src, state);
-                if wormholes.has(src) {
-                    for out_sq in wormholes {
-                        let diag = (out_sq.bishop_moves(occupied) & blockable) & !friendly;
-                        if diag.has(dst) {
-                            return Some(MoveTrace {
-                                route: (src != out_sq).then(|| (src, out_sq)),
-                                captures,
-                                takes_castle,
-                                ..Default::default()
-                            })
-                        }
-                    }
-                } else {
-                    let moves = src.bishop_moves(occupied);
-                    if ((moves & !friendly) & blockable).intersects(dsts) {
-                        return Some(MoveTrace {
-                            takes_castle,
-                            captures,
-                            ..MoveTrace::default()
-                        })
-                    }
-
-                    if !holes_are_occ {
-                        for in_sq in moves & wormholes {
-                            if let Some(ray) = src.diag_ray(in_sq) {
-                                for out_sq in wormholes {
-                                    if ((ray.cast(out_sq, occupied) & !friendly) & blockable).intersects(dsts) {
-                                        return Some(MoveTrace {
-                                            route: Some((in_sq, out_sq)),
-                                            takes_castle,
-                                            captures,
-                                            ..MoveTrace::default()
-                                        })
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+                return slide_diagonal(state, src, dst, blockable, MoveTrace { captures, takes_castle, ..MoveTrace::default() })
+            },
+            Piece::Archbishop => {
+                let blockable = crate::blockable::blockable(src, state);
+                let mv = MoveTrace { captures, takes_castle, ..MoveTrace::default() };
+                return leap(state, src, dst, blockable, mv).or_else(|| slide_diagonal(state, src, dst, blockable, mv))
+            },
+            Piece::Chancellor => {
+                let blockable = crate::blockable::blockable(src, state);
+                let mv = MoveTrace { captures, takes_castle, ..MoveTrace::default() };
+                return leap(state, src, dst, blockable, mv).or_else(|| slide_orthogonal(state, src, dst, blockable, mv))
+            },
             Piece::Rook => {
                 let blockable = crate::blockable::blockable(src, state);
 
@@ -196,94 +166,14 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                         }
                     }
                 } else {
-                    if wormholes.has(src) {
-                        for out_sq in wormholes {
-                            let ortho = (out_sq.rook_moves(occupied) & !friendly) & blockable;
-                            if ortho.has(dst) {
-                                return Some(MoveTrace {
-                                    route: (src != out_sq).then(|| (src, out_sq)),
-                                    captures,
-                                    takes_castle,
-                                    loses_castle,
-                                    ..Default::default()
-                                })
-                            }
-                        }
-                    } else {
-                        let moves = src.rook_moves(occupied);
-                        if ((moves & !friendly) & blockable).intersects(dsts) {
-                            return Some(MoveTrace {
-                                captures,
-                                takes_castle,
-                                loses_castle,
-                                ..MoveTrace::default()
-                            })
-                        }
-
-                        if !holes_are_occ {
-                            for in_sq in moves & wormholes {
-                                if let Some(ray) = src.ortho_ray(in_sq) {
-                                    for out_sq in wormholes {
-                                        if ((ray.cast(out_sq, occupied) & !friendly) & blockable).has(dst) {
-                                            return Some(MoveTrace {
-                                                route: Some((in_sq, out_sq)),
-                                                captures,
-                                                takes_castle,
-                                                loses_castle,
-                                                ..Default::default()
-                                            })
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+                    return slide_orthogonal(state, src, dst, blockable, MoveTrace { captures, takes_castle, loses_castle, ..MoveTrace::default() })
                 }
             }
             Piece::Queen => {
                 let blockable = crate::blockable::blockable(src, state);
-                let takeable = (!friendly) | blockable;
-
-                if wormholes.has(src) {
-                    for out_sq in wormholes {
-                        if ((out_sq.rook_moves(occupied) | out_sq.bishop_moves(occupied)) & takeable).has(dst) {
-                            return Some(MoveTrace {
-                                route: (out_sq != src).then(|| (src, out_sq)),
-                                captures,
-                                takes_castle,
-                                ..Default::default()
-                            })
-                        }
-                    }
-                } else {
-                    let moves = src.rook_moves(occupied) | src.bishop_moves(occupied);
-
-                    if (moves & takeable).intersects(dsts) {
-                        return Some(MoveTrace {
-                            captures,
-                            takes_castle,
-                            ..Default::default()
-                        })
-                    }
-
-                    if !holes_are_occ {
-                        for in_sq in moves & wormholes {
-                            if let Some(ray) = src.ray(in_sq) {
-                                for out_sq in wormholes {
-                                    if (ray.cast(out_sq, occupied) & takeable).has(dst) {
-                                        return Some(MoveTrace {
-                                            route: Some((in_sq, out_sq)),
-                                            captures,
-                                            takes_castle,
-                                            ..Default::default()
-                                        })
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+                let mv = MoveTrace { captures, takes_castle, ..MoveTrace::default() };
+                return slide_diagonal(state, src, dst, blockable, mv).or_else(|| slide_orthogonal(state, src, dst, blockable, mv))
+            },
             Piece::Pawn => {
                 let delta = (turn.pawn_dir(), 0);
                 let pawn_rank = turn.pawn_rank();
@@ -300,7 +190,7 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                             if let Some(ep_sq) = state.en_passant && dst == ep_sq {
                                 return Some(MoveTrace {
                                     route: (src != out_sq).then(|| (src, out_sq)),
-                                    captures,
+                                    captures: Some(Piece::Pawn),
                                     takes_castle,
                                     is_capture_en_passant: Some(ep_sq),
                                     requires_promotion,
@@ -326,7 +216,7 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                                 })
                             }
 
-                            if let Some(two) = out_sq.next(delta) && is_pawn_rank && !occupied.has(two) {
+                            if let Some(two) = one.next(delta) && is_pawn_rank && !occupied.has(two) {
                                 if blockable.has(two) && two == dst {
                                     return Some(MoveTrace {
                                         route: (src != out_sq).then(|| (src, out_sq)),
@@ -342,7 +232,7 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                     if (src.pawn_captures(turn) & takeable).intersects(dsts) {
                         if let Some(ep_sq) = state.en_passant && dst == ep_sq {
                             return Some(MoveTrace {
-                                captures,
+                                captures: Some(Piece::Pawn),
                                 is_capture_en_passant: Some(ep_sq),
                                 takes_castle,
                                 requires_promotion,
@@ -371,7 +261,7 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                         if is_pawn_rank {
                             if wormholes.has(one) {
                                 for out_sq in wormholes {
-                                    if let Some(two) = out_sq.next(delta) && !occupied.has(two) && blockable.has(two) {
+                                    if let Some(two) = out_sq.next(delta) && two == dst && !occupied.has(two) && blockable.has(two) {
                                         return Some(MoveTrace {
                                             route: (one != out_sq).then(|| (one, out_sq)),
                                             allows_en_passant: Some(one),
@@ -381,7 +271,7 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
                                     }
                                 }
                             } else {
-                                if let Some(two) = one.next(delta) && !occupied.has(two) && blockable.has(two) {
+                                if let Some(two) = one.next(delta) && two == dst && !occupied.has(two) && blockable.has(two) {
                                     return Some(MoveTrace {
                                         allows_en_passant: Some(one),
                                         requires_promotion,
@@ -397,4 +287,310 @@ pub fn trace(state: &BoardState, src: Square, dst: Square, defense: Option<BitBo
     }
 
     None
-}
\ No newline at end of file
+}
+
+/// Trace a knight leap from `src` to `dst`, returning `mv` with the
+/// route through the wormholes if the leap starts on one.
+fn leap(state: &BoardState, src: Square, dst: Square, blockable: BitBoard, mv: MoveTrace) -> Option<MoveTrace> {
+    let wormholes = state.wormholes;
+    let friendly = state.pieces.on_team(state.turn);
+    let dsts = BitBoard::from(dst).transmit(wormholes);
+
+    if wormholes.has(src) {
+        // only search for the exit if some hole reaches dst.
+        if ((state.hole_exits(wormholes).knight & !friendly) & blockable).intersects(dsts) {
+            for out_sq in wormholes {
+                if out_sq.knight_moves().intersects(dsts) {
+                    return Some(MoveTrace { route: (out_sq != src).then(|| (src, out_sq)), ..mv })
+                }
+            }
+        }
+    } else if ((src.knight_moves() & !friendly) & blockable).intersects(dsts) {
+        return Some(mv)
+    }
+
+    None
+}
+
+/// Trace a diagonal slide from `src` to `dst`, returning `mv` with the
+/// route through the wormholes if the slide passes through one.
+fn slide_diagonal(state: &BoardState, src: Square, dst: Square, blockable: BitBoard, mv: MoveTrace) -> Option<MoveTrace> {
+    let wormholes = state.wormholes;
+    let friendly = state.pieces.on_team(state.turn);
+    let dsts = BitBoard::from(dst).transmit(wormholes);
+    let occupied = state.pieces.occupied().transmit(wormholes);
+
+    if wormholes.has(src) {
+        for out_sq in wormholes {
+            let diag = (out_sq.bishop_moves(occupied) & blockable) & !friendly;
+            if diag.has(dst) {
+                return Some(MoveTrace { route: (src != out_sq).then(|| (src, out_sq)), ..mv })
+            }
+        }
+    } else {
+        let moves = src.bishop_moves(occupied);
+        if ((moves & !friendly) & blockable).intersects(dsts) {
+            return Some(mv)
+        }
+
+        if !occupied.intersects(wormholes) {
+            for in_sq in moves & wormholes {
+                if let Some(ray) = src.diag_ray(in_sq) {
+                    for out_sq in wormholes {
+                        if ((ray.cast(out_sq, occupied) & !friendly) & blockable).intersects(dsts) {
+                            return Some(MoveTrace { route: Some((in_sq, out_sq)), ..mv })
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Trace a slide along a rank or file from `src` to `dst`, returning `mv`
+/// with the route through the wormholes if the slide passes through one.
+fn slide_orthogonal(state: &BoardState, src: Square, dst: Square, blockable: BitBoard, mv: MoveTrace) -> Option<MoveTrace> {
+    let wormholes = state.wormholes;
+    let friendly = state.pieces.on_team(state.turn);
+    let dsts = BitBoard::from(dst).transmit(wormholes);
+    let occupied = state.pieces.occupied().transmit(wormholes);
+
+    if wormholes.has(src) {
+        for out_sq in wormholes {
+            let ortho = (out_sq.rook_moves(occupied) & !friendly) & blockable;
+            if ortho.has(dst) {
+                return Some(MoveTrace { route: (src != out_sq).then(|| (src, out_sq)), ..mv })
+            }
+        }
+    } else {
+        let moves = src.rook_moves(occupied);
+        if ((moves & !friendly) & blockable).intersects(dsts) {
+            return Some(mv)
+        }
+
+        if !occupied.intersects(wormholes) {
+            for in_sq in moves & wormholes {
+                if let Some(ray) = src.ortho_ray(in_sq) {
+                    for out_sq in wormholes {
+                        if ((ray.cast(out_sq, occupied) & !friendly) & blockable).has(dst) {
+                            return Some(MoveTrace { route: Some((in_sq, out_sq)), ..mv })
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::BitBoard, pieces::{Piece, Pieces}, square::Square, state::BoardState, team::Team};
+
+    fn sq(rank: u8, file: u8) -> Square {
+        Square::from((rank, file))
+    }
+
+    fn state(pieces: &[(Square, Piece, Team)], wormholes: BitBoard) -> BoardState {
+        let mut state = BoardState {
+            wormholes,
+            pieces: Pieces::empty(),
+            ..BoardState::default()
+        };
+
+        for &(at, pc, team) in pieces {
+            state.pieces.insert(at, pc, team, BitBoard::new());
+        }
+
+        state.castle.rights = 0;
+        state
+    }
+
+    #[test]
+    fn fairy_pieces_move_as_their_parts() {
+        let d4 = sq(3, 3);
+        for (pc, slides) in [
+            (Piece::Archbishop, d4.bishop_moves(BitBoard::new())),
+            (Piece::Chancellor, d4.rook_moves(BitBoard::new())),
+        ] {
+            let state = state(&[
+                (sq(0, 7), Piece::King, Team::White),
+                (sq(7, 0), Piece::King, Team::Black),
+                (d4, pc, Team::White),
+            ], BitBoard::new());
+
+            assert_eq!(state.valid_moves(d4), slides | d4.knight_moves());
+        }
+
+        // the chancellor on f6 checks like a knight and guards the f-file like a rook.
+        let state = BoardState::from_fen("4k3/8/5C2/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert!(state.king_attacked());
+        let escapes: Vec<Square> = state.legal_moves().into_iter().map(|(_, dst, _)| dst).collect();
+        assert_eq!(escapes, [sq(6, 4), sq(7, 3)]);
+    }
+
+    #[test]
+    fn wormhole_exit_onto_back_rank_promotes() {
+        // white pawn on a c3 wormhole, the other wormhole is on f7.
+        let state = state(&[
+            (sq(0, 0), Piece::King, Team::White),
+            (sq(5, 7), Piece::King, Team::Black),
+            (sq(2, 2), Piece::Pawn, Team::White),
+            (sq(7, 4), Piece::Rook, Team::Black),
+        ], sq(2, 2) | sq(6, 5));
+
+        let push = state.trace(sq(2, 2), sq(7, 5)).expect("push through wormhole");
+        assert!(push.requires_promotion);
+        assert_eq!(push.route, Some((sq(2, 2), sq(6, 5))));
+
+        let capture = state.trace(sq(2, 2), sq(7, 4)).expect("capture through wormhole");
+        assert!(capture.requires_promotion);
+        assert_eq!(capture.captures, Some(Piece::Rook));
+
+        assert!(state.delta(sq(2, 2), sq(7, 4), &capture, None).is_none());
+        let delta = state.delta(sq(2, 2), sq(7, 4), &capture, Some(Piece::Knight)).unwrap();
+        let next = state.next(delta);
+        assert_eq!(next.pieces.piece_at(sq(7, 4)), Some(Piece::Knight));
+        assert_eq!(next.prev(delta).pieces, state.pieces);
+    }
+
+    #[test]
+    fn double_push_from_wormhole() {
+        // white pawn on a b2 wormhole, the other wormhole is on g5.
+        let state = state(&[
+            (sq(0, 7), Piece::King, Team::White),
+            (sq(7, 7), Piece::King, Team::Black),
+            (sq(1, 1), Piece::Pawn, Team::White),
+            (sq(6, 6), Piece::Knight, Team::Black),
+        ], sq(1, 1) | sq(4, 6));
+
+        let trace = state.trace(sq(1, 1), sq(3, 1)).expect("double push from the wormhole");
+        assert_eq!(trace.allows_en_passant, Some(sq(2, 1)));
+
+        // the double push out of g5 is blocked by the knight on g7.
+        assert!(state.trace(sq(1, 1), sq(6, 6)).is_none());
+        assert!(!state.valid_moves(sq(1, 1)).has(sq(6, 6)));
+    }
+
+    #[test]
+    fn en_passant_removes_the_pawn() {
+        let state = BoardState::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let trace = state.trace(sq(4, 4), sq(5, 3)).expect("en passant");
+        assert_eq!(trace.captures, Some(Piece::Pawn));
+
+        let delta = state.delta(sq(4, 4), sq(5, 3), &trace, None).unwrap();
+        assert_eq!(delta.get_ep_capture_sq(), Some(sq(4, 3)));
+
+        let next = state.next(delta);
+        assert_eq!(next.pieces.piece_at(sq(4, 3)), None);
+        assert_eq!(next.pieces.piece_at(sq(5, 3)), Some(Piece::Pawn));
+        assert_eq!(next.prev(delta).pieces, state.pieces);
+    }
+
+    #[test]
+    fn captures_only() {
+        let board = BoardState::from_fen("4k3/8/2n5/3pP3/8/8/8/R3K3 w - d6 0 1").unwrap();
+        let mut captures: Vec<_> = board.captures().into_iter().map(|(src, dst, _)| (src, dst)).collect();
+        captures.sort();
+        assert_eq!(captures, vec![(sq(4, 4), sq(5, 3))]);
+
+        // the rook enters the a2 wormhole and leaves through c4 to take on c7.
+        let holes = state(&[
+            (sq(0, 4), Piece::King, Team::White),
+            (sq(7, 7), Piece::King, Team::Black),
+            (sq(0, 0), Piece::Rook, Team::White),
+            (sq(6, 2), Piece::Knight, Team::Black),
+        ], sq(1, 0) | sq(3, 2));
+
+        let captures = holes.captures();
+        assert_eq!(captures.len(), 1);
+        assert_eq!((captures[0].0, captures[0].1), (sq(0, 0), sq(6, 2)));
+        assert!(captures[0].2.route.is_some());
+    }
+
+    #[test]
+    fn no_friendly_captures() {
+        for state in [crate::positions::standard(), crate::positions::kiwipete(), crate::positions::position_4()] {
+            let friendly = state.pieces.on_team(state.turn);
+            // the king and rook can be moved onto each other to castle.
+            for src in friendly {
+                for dst in state.valid_moves(src) & friendly {
+                    assert!(state.trace(src, dst).is_some_and(|trace| trace.is_castle.is_some()), "{src:?} {dst:?}");
+                }
+
+                for dst in friendly {
+                    assert!(state.trace(src, dst).is_none_or(|trace| trace.is_castle.is_some()), "{src:?} {dst:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pins() {
+        // the bishop is pinned along the e-file, so it cannot move at all.
+        let pinned = state(&[
+            (sq(0, 4), Piece::King, Team::White),
+            (sq(1, 4), Piece::Bishop, Team::White),
+            (sq(7, 4), Piece::Rook, Team::Black),
+            (sq(7, 0), Piece::King, Team::Black),
+        ], BitBoard::new());
+
+        assert!(pinned.valid_moves(sq(1, 4)).is_empty());
+        assert!(pinned.trace(sq(1, 4), sq(2, 3)).is_none());
+
+        // a queen pinned on the file can only move along it.
+        let mut queen = pinned;
+        queen.pieces.remove(sq(1, 4), BitBoard::new());
+        queen.pieces.insert(sq(1, 4), Piece::Queen, Team::White, BitBoard::new());
+        let moves = queen.valid_moves(sq(1, 4));
+        assert_eq!(moves, BitBoard::from(sq(2, 4)) | sq(3, 4) | sq(4, 4) | sq(5, 4) | sq(6, 4) | sq(7, 4));
+        assert!(queen.trace(sq(1, 4), sq(2, 5)).is_none());
+        assert!(queen.trace(sq(1, 4), sq(7, 4)).is_some());
+    }
+
+    #[test]
+    fn rook_attacks_stay_orthogonal_through_wormholes() {
+        // the rook on a8 enters the a4 wormhole and leaves through d4,
+        // continuing down the d-file and not along its diagonals.
+        let state = state(&[
+            (sq(0, 7), Piece::King, Team::White),
+            (sq(7, 0), Piece::Rook, Team::Black),
+            (sq(7, 7), Piece::King, Team::Black),
+        ], sq(3, 0) | sq(3, 3));
+
+        let defense = crate::defense::defense(&state);
+        assert!(defense.has(sq(2, 3)) && defense.has(sq(0, 3)));
+        assert!(!defense.has(sq(2, 2)) && !defense.has(sq(2, 4)));
+    }
+
+    #[test]
+    fn king_steps_beside_castling() {
+        let start = BoardState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+
+        // the rook's target squares are normal king moves while castling is legal.
+        for (uci, san, king) in [("e1f1", "Kf1", sq(0, 5)), ("e1d1", "Kd1", sq(0, 3))] {
+            let game = crate::game::ChessGame::from_uci_moves(start, uci).unwrap();
+            assert_eq!(game.deltas[0].get_castle_side(), None);
+            assert_eq!(game.cursor.state.pieces.piece_at(king), Some(Piece::King));
+            assert_eq!(game.cursor.state.castle.rights & 0b11, 0);
+            assert_eq!(start.to_san(game.deltas[0]), san);
+            assert_eq!(crate::san::parse_san(&start, san), Some((sq(0, 4), king, None)));
+        }
+
+        // the king's target square and the rook's square still castle.
+        for uci in ["e1g1", "e1h1"] {
+            let game = crate::game::ChessGame::from_uci_moves(start, uci).unwrap();
+            assert!(game.deltas[0].get_castle_side().is_some());
+            assert_eq!(game.cursor.state.pieces.piece_at(sq(0, 5)), Some(Piece::Rook));
+            assert_eq!(game.moves_uci(), "e1g1");
+            assert_eq!(game.audit(), Ok(()));
+        }
+
+        let legal = start.legal_moves();
+        assert_eq!(legal.iter().filter(|(src, _, _)| *src == sq(0, 4)).count(), 7);
+        assert_eq!(legal.iter().filter(|(_, _, trace)| trace.is_castle.is_some()).count(), 2);
+    }
+}
@@ -0,0 +1,3 @@
+//! Drills and checks for chess training apps.
+
+pub mod blindfold;
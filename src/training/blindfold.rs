@@ -0,0 +1,173 @@
+//! Blindfold training: recalling a position without seeing the board,
+//! naming square colors, and finding knight paths.
+
+use crate::{board::BitBoard, pieces::Piece, rng::WyRand, square::Square, state::BoardState, team::Team};
+
+/// How well a reconstruction of a position matches the real one.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RecallScore {
+    /// The number of pieces placed on the right square.
+    pub correct: usize,
+
+    /// Claimed pieces that are not in the position, including claims
+    /// for a square that was already claimed.
+    pub wrong: Vec<(Square, Piece, Team)>,
+
+    /// Pieces in the position that were not claimed.
+    pub missing: Vec<(Square, Piece, Team)>,
+}
+
+impl RecallScore {
+    /// The correct pieces as a fraction of every piece in the position and
+    /// every wrong claim, from 0 to 1. An empty claim of an empty board is 1.
+    pub fn accuracy(&self) -> f32 {
+        let total = self.correct + self.wrong.len() + self.missing.len();
+        if total == 0 {
+            return 1.0
+        }
+
+        self.correct as f32 / total as f32
+    }
+
+    pub fn is_perfect(&self) -> bool {
+        self.wrong.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// Score a reconstruction of the position. A piece on a wormhole occupies
+/// every hole, so a claim for it on any of the holes is correct.
+pub fn verify_recall(claimed: &[(Square, Piece, Team)], actual: &BoardState) -> RecallScore {
+    let mut pieces: Vec<(Square, Piece, Team, bool)> = actual.pieces
+        .as_array()
+        .into_iter()
+        .flat_map(|(team, piece, board)| board.into_iter().map(move |sq| (sq, piece, team, false)))
+        .collect();
+
+    let holes = actual.wormholes;
+    let same_square = |a: Square, b: Square| a == b || (holes.has(a) && holes.has(b));
+
+    let mut score = RecallScore::default();
+    let mut seen = BitBoard::new();
+    for &(sq, piece, team) in claimed {
+        let found = pieces.iter_mut().find(|(at, pc, tm, matched)| {
+            !*matched && *pc == piece && *tm == team && same_square(*at, sq)
+        });
+
+        match found {
+            Some(entry) if !seen.has(sq) => {
+                entry.3 = true;
+                score.correct += 1;
+            },
+            _ => score.wrong.push((sq, piece, team)),
+        }
+
+        seen.set(sq);
+    }
+
+    score.missing = pieces
+        .into_iter()
+        .filter(|&(.., matched)| !matched)
+        .map(|(sq, piece, team, _)| (sq, piece, team))
+        .collect();
+
+    score
+}
+
+/// Whether the square is light, like h1.
+pub fn is_light(sq: Square) -> bool {
+    (sq.rank_u8() + sq.file_u8()) % 2 == 1
+}
+
+/// Random squares to name the color of, each with whether it is light.
+pub fn square_color_drills(seed: u64, count: usize) -> Vec<(Square, bool)> {
+    let mut rng = WyRand { seed };
+    (0..count)
+        .map(|_| Square::from_index(rng.range(0..64) as usize))
+        .map(|sq| (sq, is_light(sq)))
+        .collect()
+}
+
+/// A knight journey to find without a board.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct KnightPathDrill {
+    pub from: Square,
+    pub to: Square,
+
+    /// The fewest knight moves from one square to the other.
+    pub moves: u8,
+}
+
+impl KnightPathDrill {
+    /// Whether the squares, not including `from`, are a shortest knight path
+    /// ending on `to`.
+    pub fn check(&self, path: &[Square]) -> bool {
+        let mut at = self.from;
+        for &sq in path {
+            if !at.knight_moves().has(sq) {
+                return false
+            }
+            at = sq;
+        }
+
+        at == self.to && path.len() == self.moves as usize
+    }
+}
+
+/// Random knight journeys between different squares.
+pub fn knight_path_drills(seed: u64, count: usize) -> Vec<KnightPathDrill> {
+    let mut rng = WyRand { seed };
+    let mut drills = Vec::with_capacity(count);
+    while drills.len() < count {
+        let from = Square::from_index(rng.range(0..64) as usize);
+        let to = Square::from_index(rng.range(0..64) as usize);
+        if from != to {
+            drills.push(KnightPathDrill { from, to, moves: from.knight_distance(to) });
+        }
+    }
+
+    drills
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_light, knight_path_drills, square_color_drills, verify_recall, KnightPathDrill};
+    use crate::{pieces::Piece, square::Square, state::BoardState, team::Team};
+
+    #[test]
+    fn recall() {
+        let state = BoardState::from_extended_fen("4k3/8/8/8/2N5/8/8/4K3 w - - 0 1 a2,c4 - 0").unwrap();
+        let sq = |s: &str| s.parse::<Square>().unwrap();
+
+        // the knight on c4 is also on the a2 hole.
+        let claimed = [
+            (sq("e1"), Piece::King, Team::White),
+            (sq("a2"), Piece::Knight, Team::White),
+            (sq("e8"), Piece::Queen, Team::Black),
+            (sq("e1"), Piece::King, Team::White),
+        ];
+
+        let score = verify_recall(&claimed, &state);
+        assert_eq!(score.correct, 2);
+        assert_eq!(score.wrong, vec![claimed[2], claimed[3]]);
+        assert_eq!(score.missing, vec![(sq("e8"), Piece::King, Team::Black)]);
+        assert_eq!(score.accuracy(), 0.4);
+        assert!(verify_recall(&claimed[..2], &state).missing.len() == 1);
+    }
+
+    #[test]
+    fn drills() {
+        assert!(is_light("h1".parse().unwrap()));
+        assert!(!is_light("a1".parse().unwrap()));
+        assert!(square_color_drills(7, 20).iter().all(|&(sq, light)| light == is_light(sq)));
+
+        let corner = KnightPathDrill { from: "a1".parse().unwrap(), to: "b2".parse().unwrap(), moves: 4 };
+        assert_eq!(corner.from.knight_distance(corner.to), 4);
+        let path = ["c2", "e3", "d1", "b2"].map(|s| s.parse().unwrap());
+        assert!(corner.check(&path));
+        assert!(!corner.check(&path[..3]));
+
+        let drills = knight_path_drills(3, 50);
+        assert_eq!(drills.len(), 50);
+        assert!(drills.iter().all(|drill| drill.from != drill.to && (1..=6).contains(&drill.moves)));
+    }
+}
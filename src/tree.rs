@@ -0,0 +1,489 @@
+//! A game together with all of its variations.
+//!
+//! Each line in the tree is a `ChessGame`. The mainline comes first, and
+//! every other line is a branch whose `Branch::parent_id` names another
+//! line in the tree. The cursor can move freely across branch boundaries.
+
+use crate::{address::NodeAddress, game::{ChessGame, Cursor, PlayError, PlaySuccess}, memory::MemoryFootprint, pieces::Piece, settings::{GameLimits, LimitError}, square::Square, state::BoardState};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TreeError {
+    /// The game is not a branch, so it has no place in the tree.
+    NotABranch,
+
+    /// No line in the tree has the branch's parent id.
+    UnknownParent,
+
+    /// The branch does not start from a position in its parent.
+    StartMismatch,
+
+    /// The parent has no move at the branch point to swap the branch with.
+    NoContinuation,
+
+    /// The tree already has as many branches as the mainline's limits allow.
+    Limit(LimitError),
+}
+
+/// A line in the variation tree, as listed by `GameTree::lines`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Line {
+    /// The index of the line in `GameTree::games`.
+    pub game: usize,
+
+    /// The line this one branches from, None for the mainline.
+    pub parent: Option<usize>,
+
+    /// The halfmove index in the parent that this line branches from.
+    pub src_index: usize,
+
+    /// The number of branches between this line and the mainline.
+    pub depth: usize,
+}
+
+#[derive(Clone)]
+pub struct GameTree {
+    /// The mainline, then the branches in the order they were added.
+    games: Vec<ChessGame>,
+
+    /// The index of the line holding the cursor.
+    current: usize,
+}
+
+impl GameTree {
+    pub fn new(mainline: ChessGame) -> Self {
+        Self {
+            games: vec![mainline],
+            current: 0,
+        }
+    }
+
+    pub fn mainline(&self) -> &ChessGame {
+        &self.games[0]
+    }
+
+    /// Every line in the tree, with the mainline first.
+    pub fn games(&self) -> &[ChessGame] {
+        &self.games
+    }
+
+    /// The index of the line holding the cursor.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The line holding the cursor.
+    pub fn current_game(&self) -> &ChessGame {
+        &self.games[self.current]
+    }
+
+    /// The line holding the cursor, for changes that keep its moves,
+    /// such as annotations.
+    pub(crate) fn current_game_mut(&mut self) -> &mut ChessGame {
+        &mut self.games[self.current]
+    }
+
+    pub fn cursor(&self) -> &Cursor {
+        &self.games[self.current].cursor
+    }
+
+    /// Add a branch created by `ChessGame::branch` whose parent is in the tree.
+    /// Returns the index of the new line.
+    pub fn insert(&mut self, branch: ChessGame) -> Result<usize, TreeError> {
+        self.check_branch(&branch)?;
+        self.check_branch_limit().map_err(TreeError::Limit)?;
+        self.games.push(branch);
+        Ok(self.games.len() - 1)
+    }
+
+    /// Rebuild a tree from the lines listed by `GameTree::games`, keeping
+    /// their order. Unlike `insert`, a branch may come before its parent.
+    pub fn from_lines(mainline: ChessGame, branches: Vec<ChessGame>) -> Result<Self, TreeError> {
+        let mut tree = Self::new(mainline);
+        tree.games.extend(branches);
+        for line in &tree.games[1..] {
+            tree.check_branch(line)?;
+        }
+
+        Ok(tree)
+    }
+
+    /// Check that the mainline's limits allow another branch.
+    fn check_branch_limit(&self) -> Result<(), LimitError> {
+        GameLimits::check(self.games[0].settings.limits.max_branches, self.games.len() - 1, LimitError::Branches)
+    }
+
+    /// Check that the branch starts from a position in its parent.
+    fn check_branch(&self, branch: &ChessGame) -> Result<(), TreeError> {
+        let info = branch.is_branch.ok_or(TreeError::NotABranch)?;
+        let parent = self.find(info.parent_id).ok_or(TreeError::UnknownParent)?;
+
+        let expected = position(&self.games[parent], info.src_index)
+            .map(|state| state.next(info.delta))
+            .ok_or(TreeError::StartMismatch)?;
+
+        if !expected.is_repetition_of(&branch.start) {
+            return Err(TreeError::StartMismatch)
+        }
+
+        Ok(())
+    }
+
+    /// The line that `line` branches from, None for the mainline.
+    pub fn parent(&self, line: usize) -> Option<usize> {
+        if line == 0 {
+            return None
+        }
+
+        self.find(self.games.get(line)?.is_branch?.parent_id)
+    }
+
+    /// The lines that branch from the position at `index` in `line`,
+    /// in the order they were added.
+    pub fn variations(&self, line: usize, index: usize) -> Vec<usize> {
+        let Some(game) = self.games.get(line) else {
+            return Vec::new()
+        };
+
+        (1..self.games.len())
+            .filter(|&i| self.games[i].is_branch.is_some_and(|branch| {
+                branch.parent_id == game.game_id && branch.src_index == index
+            }))
+            .collect()
+    }
+
+    /// Every line in the tree, depth-first: each line is followed by
+    /// the lines branching from it, ordered by where they branch.
+    pub fn lines(&self) -> Vec<Line> {
+        let mut lines = Vec::with_capacity(self.games.len());
+        self.push_lines(0, None, 0, 0, &mut lines);
+        lines
+    }
+
+    fn push_lines(&self, game: usize, parent: Option<usize>, src_index: usize, depth: usize, lines: &mut Vec<Line>) {
+        lines.push(Line { game, parent, src_index, depth });
+
+        for index in 0..=self.games[game].deltas.len() {
+            for child in self.variations(game, index) {
+                self.push_lines(child, Some(game), index, depth + 1, lines);
+            }
+        }
+    }
+
+    /// Play a move at the cursor. If the move differs from the one already
+    /// played in the position, the cursor enters the variation starting with
+    /// it, which is created if there is none. Branches are added to the
+    /// tree instead of being returned in `PlaySuccess`.
+    pub fn play(&mut self, src: Square, dst: Square, promote: Option<Piece>) -> Result<PlaySuccess, PlayError> {
+        let mut success = self.games[self.current].play(src, dst, promote)?;
+        if let Some(branch) = success.branch.take() {
+            let index = self.cursor().index;
+            let existing = self.variations(self.current, index).into_iter().find(|&line| {
+                self.games[line].is_branch.is_some_and(|info| info.delta.is_same_move(&success.delta))
+            });
+
+            self.current = match existing {
+                Some(line) => line,
+                None => {
+                    self.check_branch_limit().map_err(PlayError::Limit)?;
+                    self.games.push(branch);
+                    self.games.len() - 1
+                }
+            };
+
+            self.games[self.current].goto_start();
+        }
+
+        Ok(success)
+    }
+
+    /// Make a branch the continuation of its parent, see
+    /// `ChessGame::promote_branch`. The parent's old continuation becomes
+    /// the branch, and lines branching from either continuation move with
+    /// it. Promoting each line on the way up makes a line the mainline.
+    /// The cursor stays on the same position.
+    pub fn promote(&mut self, line: usize) -> Result<(), TreeError> {
+        let info = self.games.get(line).and_then(|game| game.is_branch).ok_or(TreeError::NotABranch)?;
+        let parent = self.parent(line).ok_or(TreeError::UnknownParent)?;
+        let fork = info.src_index;
+
+        let index = self.cursor().index;
+        let cursor = if self.current == line {
+            (parent, fork + 1 + index)
+        } else if self.current == parent && index > fork {
+            (line, index - fork - 1)
+        } else {
+            (self.current, index)
+        };
+
+        let (low, high) = self.games.split_at_mut(line.max(parent));
+        let (parent_game, branch) = if parent < line {
+            (&mut low[parent], &mut high[0])
+        } else {
+            (&mut high[0], &mut low[line])
+        };
+        parent_game.promote_branch(branch)?;
+
+        let (parent_id, line_id) = (self.games[parent].game_id, self.games[line].game_id);
+        for (i, game) in self.games.iter_mut().enumerate() {
+            let Some(branch) = game.is_branch.as_mut().filter(|_| i != line) else {
+                continue;
+            };
+
+            if branch.parent_id == parent_id && branch.src_index > fork {
+                branch.parent_id = line_id;
+                branch.src_index -= fork + 1;
+            } else if branch.parent_id == line_id {
+                branch.parent_id = parent_id;
+                branch.src_index += fork + 1;
+            }
+        }
+
+        self.current = cursor.0;
+        self.games[cursor.0].goto(cursor.1);
+        Ok(())
+    }
+
+    /// Advance the cursor along the current line.
+    pub fn next(&mut self) -> Option<&Cursor> {
+        self.games[self.current].next()
+    }
+
+    /// Move the cursor back one halfmove. From the start of a branch,
+    /// this returns to the position in the parent it branched from.
+    pub fn prev(&mut self) -> Option<&Cursor> {
+        if self.cursor().index == 0 {
+            let parent = self.parent(self.current)?;
+            let src_index = self.games[self.current].is_branch?.src_index;
+            self.current = parent;
+            self.games[parent].goto(src_index);
+            return Some(self.cursor())
+        }
+
+        self.games[self.current].prev()
+    }
+
+    /// Move the cursor into the nth variation (from 0) that branches
+    /// from the cursor position, onto the position after its first move.
+    pub fn enter(&mut self, n: usize) -> Option<&Cursor> {
+        let line = *self.variations(self.current, self.cursor().index).get(n)?;
+        self.current = line;
+        self.games[line].goto_start();
+        Some(self.cursor())
+    }
+
+    /// The address of the cursor position.
+    pub fn address(&self) -> NodeAddress {
+        let mut steps = Vec::new();
+        let mut line = self.current;
+        let mut index = self.cursor().index;
+
+        while let Some(parent) = self.parent(line) {
+            let src_index = self.games[line].is_branch.map_or(0, |info| info.src_index);
+            let n = self.variations(parent, src_index).iter().position(|&v| v == line).unwrap_or(0);
+            steps.push((n + 1, index + 1));
+            line = parent;
+            index = src_index;
+        }
+
+        steps.push((0, index));
+        steps.reverse();
+        NodeAddress { root: self.games[0].game_id, path: steps }
+    }
+
+    /// Move the cursor to an address from `address`. Returns None
+    /// if the address does not lead to a position in the tree.
+    pub fn goto_address(&mut self, address: &NodeAddress) -> Option<&Cursor> {
+        if address.root != self.games[0].game_id {
+            return None
+        }
+
+        let (mut line, mut index) = (0, 0);
+        for &(branch, ply) in &address.path {
+            if branch == 0 {
+                index += ply;
+            } else {
+                line = *self.variations(line, index).get(branch - 1)?;
+                index = ply.checked_sub(1)?;
+            }
+
+            if index > self.games[line].deltas.len() {
+                return None
+            }
+        }
+
+        self.current = line;
+        self.games[line].goto(index);
+        Some(self.cursor())
+    }
+
+    /// The bytes held by the tree and all of its lines, see `memory`.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        crate::memory::tree(self)
+    }
+
+    /// The tree in the Graphviz DOT language, see `graph::to_dot`.
+    pub fn to_dot(&self) -> String {
+        crate::graph::to_dot(self)
+    }
+
+    /// The mainline as PGN with the other lines as variations, see `pgn::tree_to_pgn`.
+    pub fn to_pgn(&self) -> String {
+        crate::pgn::tree_to_pgn(self)
+    }
+
+    /// The tree as nested JSON objects, see `graph::to_json_tree`.
+    pub fn to_json_tree(&self) -> String {
+        crate::graph::to_json_tree(self)
+    }
+
+    fn find(&self, game_id: u64) -> Option<usize> {
+        self.games.iter().position(|game| game.game_id == game_id)
+    }
+}
+
+/// The position at `index` in the game, if the game is that long.
+fn position(game: &ChessGame, index: usize) -> Option<BoardState> {
+    game.positions().nth(index).map(|(_, state)| state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameTree, TreeError};
+    use crate::{game::ChessGame, state::BoardState};
+
+    fn play(tree: &mut GameTree, uci: &str) {
+        let (src, dst, promote) = crate::uci::parse_move(uci).unwrap();
+        assert!(tree.play(src, dst, promote).is_ok(), "{uci}");
+    }
+
+    #[test]
+    fn variations() {
+        let mainline = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6").unwrap();
+        let mut tree = GameTree::new(mainline);
+        while tree.prev().is_some() {}
+        assert_eq!(tree.cursor().index, 0);
+
+        // 1. e4 c5 2. Nf3, then 2... d6 and 2... Nc6.
+        play(&mut tree, "e2e4");
+        play(&mut tree, "c7c5");
+        play(&mut tree, "g1f3");
+        play(&mut tree, "d7d6");
+        let sicilian = tree.current();
+        assert_eq!(sicilian, 1);
+        assert!(tree.prev().is_some());
+        play(&mut tree, "b8c6");
+        assert_eq!(tree.current(), 2);
+
+        // replaying a known first move enters the existing variation.
+        while tree.current() != 0 {
+            tree.prev();
+        }
+        assert_eq!(tree.cursor().index, 1);
+        play(&mut tree, "c7c5");
+        assert_eq!(tree.current(), sicilian);
+
+        let lines = tree.lines();
+        assert_eq!(lines.len(), 3);
+        assert_eq!((lines[2].parent, lines[2].depth), (Some(1), 2));
+
+        // d6 is the second halfmove of the Sicilian line.
+        play(&mut tree, "g1f3");
+        play(&mut tree, "d7d6");
+        let address = tree.address();
+        assert_eq!(address.path, vec![(0, 1), (1, 3)]);
+        assert_eq!(address.ply(), 4);
+
+        while tree.prev().is_some() {}
+        assert_eq!((tree.current(), tree.cursor().index), (0, 0));
+        assert!(tree.goto_address(&address).is_some());
+        assert_eq!(tree.current(), sicilian);
+        assert_eq!(tree.cursor().state.fullmoves, 3);
+
+        // 2... Nc6 branches from the Sicilian line after 2. Nf3.
+        let nc6 = address.parent().unwrap().with(1, 2).with(1, 1);
+        assert!(tree.goto_address(&nc6).is_some());
+        assert_eq!(tree.current(), 2);
+    }
+
+    #[test]
+    fn promote() {
+        let mainline = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 g1f3 b8c6").unwrap();
+        let mut tree = GameTree::new(mainline);
+        tree.goto_address(&crate::address::NodeAddress { root: tree.mainline().game_id, path: vec![(0, 1)] });
+
+        // 1... c5 2. Nf3 d6, with 2... Nc6 branching from the Sicilian.
+        for uci in ["c7c5", "g1f3", "d7d6"] {
+            play(&mut tree, uci);
+        }
+        assert!(tree.prev().is_some());
+        play(&mut tree, "b8c6");
+        assert!(tree.prev().is_some());
+        assert!(tree.prev().is_some());
+        assert_eq!(tree.current(), 1);
+
+        // the cursor after 2. Nf3 in the e5 line moves along with that line.
+        tree.goto_address(&crate::address::NodeAddress { root: tree.mainline().game_id, path: vec![(0, 3)] });
+        tree.games[0].annotate(3).unwrap().comment = Some("Nf3".to_string());
+        let before = tree.cursor().state;
+        assert!(tree.promote(1).is_ok());
+        assert_eq!(tree.current(), 1);
+        assert!(tree.cursor().state.is_repetition_of(&before));
+        assert_eq!(tree.mainline().moves_uci(), "e2e4 c7c5 g1f3 d7d6");
+        assert_eq!(tree.games()[1].moves_uci(), "g1f3 b8c6");
+        assert!(tree.games()[1].is_branch.is_some_and(|info| info.delta.to_uci() == "e7e5"));
+        assert_eq!(tree.games()[1].annotations[&1].comment.as_deref(), Some("Nf3"));
+
+        // Nc6 now branches from the mainline after 2. Nf3.
+        assert_eq!(tree.variations(0, 3), vec![2]);
+        assert_eq!(tree.promote(0), Err(TreeError::NotABranch));
+        assert!(tree.promote(2).is_ok());
+        assert_eq!(tree.mainline().moves_uci(), "e2e4 c7c5 g1f3 b8c6");
+        assert_eq!(tree.variations(0, 3), vec![2]);
+    }
+
+    #[test]
+    fn export() {
+        let mainline = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5").unwrap();
+        let mut tree = GameTree::new(mainline);
+        tree.prev();
+        play(&mut tree, "c7c5");
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph tree {"));
+        assert!(dot.contains("\"0:1\" [label=\"1. e4\\n"));
+        assert!(dot.contains("\"0:1\" -> \"0:2\";"));
+        assert!(dot.contains("\"0:1\" -> \"1:0\" [style=dashed];"));
+        assert!(dot.contains("\"1:0\" [label=\"1... c5\\n"));
+
+        let json = tree.to_json_tree();
+        assert!(json.starts_with("{\"line\":0,\"index\":0,\"san\":null,\"eval\":\"+0.00\""));
+        assert!(json.contains("\"san\":\"1... e5\""));
+        assert!(json.contains("\"san\":\"1... c5\""));
+        assert_eq!(json.matches('{').count(), 4);
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+
+        play(&mut tree, "g1f3");
+        assert!(tree.to_pgn().ends_with("\n\n1. e4 e5 (1... c5 2. Nf3) *\n"));
+    }
+
+    #[test]
+    fn branch_limit() {
+        use crate::{game::PlayError, settings::{GameLimits, LimitError}};
+
+        let mut mainline = ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5").unwrap();
+        mainline.settings.limits = GameLimits { max_branches: Some(1), ..GameLimits::default() };
+        let mut tree = GameTree::new(mainline);
+        tree.prev();
+        play(&mut tree, "c7c5");
+
+        tree.prev();
+        let (src, dst, _) = crate::uci::parse_move("d7d5").unwrap();
+        assert!(matches!(tree.play(src, dst, None), Err(PlayError::Limit(LimitError::Branches))));
+        assert_eq!(tree.games().len(), 2);
+
+        // an existing branch can still be entered.
+        play(&mut tree, "c7c5");
+        let branch = tree.games()[1].clone();
+        assert_eq!(tree.insert(branch).err(), Some(TreeError::Limit(LimitError::Branches)));
+    }
+}
@@ -0,0 +1,162 @@
+//! Coordinate (long algebraic) move notation, as used by UCI engines.
+
+use crate::{fen::FenError, game::{ChessGame, Cursor, PlayError}, pieces::Piece, settings::GameSettings, square::Square, state::BoardState};
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum UciError {
+    /// The move at this index is not valid coordinate notation.
+    Malformed(usize),
+
+    /// The move at this index cannot be played in its position.
+    Illegal(usize),
+
+    /// The position command has neither "startpos" nor "fen".
+    Position,
+
+    /// The FEN in the position command could not be parsed.
+    Fen(FenError),
+}
+
+/// Parse a move such as "e2e4" or "e7e8q" into (src, dst, promote).
+pub fn parse_move(s: &str) -> Option<(Square, Square, Option<Piece>)> {
+    if !s.is_ascii() || !(4..=5).contains(&s.len()) {
+        return None
+    }
+
+    let src = s[0..2].parse().ok()?;
+    let dst = s[2..4].parse().ok()?;
+    let promote = match s.as_bytes().get(4) {
+        None => None,
+        Some(&c) if c.is_ascii_lowercase() => Some(Piece::from_char(c as char).filter(Piece::is_promotable)?),
+        Some(_) => return None,
+    };
+
+    Some((src, dst, promote))
+}
+
+/// Build a game from a start position and a whitespace-separated list
+/// of moves in coordinate notation, validating every move. Only an end
+/// condition reached by the last move is kept, since a game may go on
+/// after a draw that a player could have claimed.
+pub fn from_uci_moves(start: BoardState, moves: &str) -> Result<ChessGame, UciError> {
+    let mut game = ChessGame {
+        start,
+        cursor: Cursor::new(start),
+        settings: GameSettings::default(),
+        game_id: crate::rng::entropy(),
+        ..ChessGame::default()
+    };
+
+    for (i, mv) in moves.split_whitespace().enumerate() {
+        let (src, dst, promote) = parse_move(mv).ok_or(UciError::Malformed(i))?;
+        game.end = None;
+        game.play(src, dst, promote).map_err(|err| match err {
+            PlayError::GameEnded(_) |
+            PlayError::InvalidMove |
+            PlayError::Forbidden |
+            PlayError::RequiresPromotion |
+            PlayError::InconsistentDelta |
+            PlayError::Limit(_) => UciError::Illegal(i),
+        })?;
+    }
+
+    Ok(game)
+}
+
+/// Build a game from a UCI position command, such as
+/// "position startpos moves e2e4 e7e5" or "position fen <fen> moves ...".
+/// The "position" keyword is optional, and extended FEN is accepted.
+/// Move indices in errors count from the first move after "moves".
+pub fn from_uci_position(command: &str) -> Result<ChessGame, UciError> {
+    let command = command.trim();
+    let command = command.strip_prefix("position").unwrap_or(command).trim_start();
+    let (position, moves) = match command.split_once("moves") {
+        Some((position, moves)) => (position.trim(), moves),
+        None => (command, ""),
+    };
+
+    let start = if position == "startpos" {
+        BoardState::default()
+    } else if let Some(fen) = position.strip_prefix("fen") {
+        BoardState::from_extended_fen(fen).map_err(UciError::Fen)?
+    } else {
+        return Err(UciError::Position)
+    };
+
+    from_uci_moves(start, moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UciError;
+    use crate::{end::EndCondition, game::ChessGame, state::BoardState};
+
+    #[test]
+    fn from_uci_moves() {
+        let game = ChessGame::from_uci_moves(BoardState::default(), "  e2e4\te7e5\n g1f3 ").unwrap();
+        assert_eq!(game.deltas.len(), 3);
+        assert!(game.start.is_repetition_of(&BoardState::default()));
+        assert!(game.cursor_is_last());
+
+        assert_eq!(ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 e1").err(), Some(UciError::Malformed(2)));
+        assert_eq!(ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e8x").err(), Some(UciError::Malformed(1)));
+        assert_eq!(ChessGame::from_uci_moves(BoardState::default(), "e2e4 e7e5 e4e5").err(), Some(UciError::Illegal(2)));
+
+        let mate = ChessGame::from_uci_moves(BoardState::default(), "f2f3 e7e5 g2g4 d8h4").unwrap();
+        assert_eq!(mate.end, Some(EndCondition::Checkmate));
+        assert_eq!(ChessGame::from_uci_moves(BoardState::default(), "f2f3 e7e5 g2g4 d8h4 e1f2").err(), Some(UciError::Illegal(4)));
+
+        // a repetition that was not claimed does not end the game.
+        let shuffle = "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8";
+        let repeated = ChessGame::from_uci_moves(BoardState::default(), shuffle).unwrap();
+        assert_eq!(repeated.end, Some(EndCondition::Repetition));
+        let played_on = ChessGame::from_uci_moves(BoardState::default(), &format!("{shuffle} e2e4")).unwrap();
+        assert_eq!(played_on.end, None);
+        assert_eq!(played_on.deltas.len(), 9);
+    }
+
+    #[test]
+    fn round_trip() {
+        let moves = ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1", "f6e4", "d2d4", "e4f2"];
+        let mut game = ChessGame::from_uci_moves(BoardState::default(), "").unwrap();
+        for mv in moves {
+            assert_eq!(game.play_uci(mv).ok().map(|ok| ok.delta.to_uci()).as_deref(), Some(mv));
+        }
+
+        assert_eq!(game.moves_uci(), moves.join(" "));
+        assert!(game.play_uci("e9e4").is_err());
+        assert!(game.play_uci("a1a2").is_err());
+
+        let position = ChessGame::from_uci_position("position startpos moves e2e4 e7e5 g1f3").unwrap();
+        assert_eq!(position.moves_uci(), "e2e4 e7e5 g1f3");
+        assert_eq!(ChessGame::from_uci_position("startpos moves e2e5").err(), Some(UciError::Illegal(0)));
+        assert_eq!(ChessGame::from_uci_position("position kiwipete").err(), Some(UciError::Position));
+
+        let promote = ChessGame::from_uci_position("position fen 4k3/P7/8/8/8/8/8/4K3 w - - 0 1 moves a7a8n e8d7").unwrap();
+        assert_eq!(promote.cursor.state.pieces.piece_at("a8".parse().unwrap()), Some(crate::pieces::Piece::Knight));
+
+        let promote = ChessGame::from_uci_moves(BoardState::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap(), "a7a8n").unwrap();
+        assert_eq!(promote.deltas[0].to_uci(), "a7a8n");
+    }
+
+    #[test]
+    fn from_uci_position() {
+        let start = ChessGame::from_uci_position("position startpos").unwrap();
+        assert!(start.deltas.is_empty() && start.start.is_repetition_of(&BoardState::default()));
+        let bare = ChessGame::from_uci_position("  startpos moves e2e4  ").unwrap();
+        assert_eq!(bare.moves_uci(), "e2e4");
+
+        let fen = ChessGame::from_uci_position(&format!("position fen {}", crate::positions::KIWIPETE)).unwrap();
+        assert!(fen.start.is_repetition_of(&crate::positions::kiwipete()));
+        let holes = ChessGame::from_uci_position(&format!("position fen {} moves e1f1", crate::positions::WORMHOLE_CHECK)).err();
+        assert_eq!(holes, Some(UciError::Illegal(0)));
+        let holes = ChessGame::from_uci_position(&format!("position fen {} moves e8d7", crate::positions::WORMHOLE_CHECK)).unwrap();
+        assert_eq!(holes.start.wormholes.count(), 2);
+        assert_eq!(holes.moves_uci(), "e8d7");
+
+        assert!(matches!(ChessGame::from_uci_position("position fen 8/8 w - - 0 1"), Err(UciError::Fen(_))));
+        assert_eq!(ChessGame::from_uci_position("position startpos moves e2e4 e7").err(), Some(UciError::Malformed(1)));
+        assert_eq!(ChessGame::from_uci_position("position moves e2e4").err(), Some(UciError::Position));
+        assert_eq!(ChessGame::from_uci_position("").err(), Some(UciError::Position));
+    }
+}
@@ -0,0 +1,124 @@
+//! Feeds random and mutated inputs through the public API.
+//! Any panic is a bug: malformed input must surface as None or Err.
+
+use maulstrom::{positions, prelude::*, rng::WyRand, uci};
+
+const FENS: [&str; 5] = [
+    positions::STANDARD,
+    positions::KIWIPETE,
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "4k3/8/8/8/8/8/8/R3K3 b - - 255 65535",
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 d4,e5 c3 1",
+];
+
+const ALPHABET: &[u8] = b"pnbrqkPNBRQK12345678/ -wabcdefgh,KQkq09";
+
+fn mutate(rng: &mut WyRand, s: &str) -> String {
+    let mut bytes = s.as_bytes().to_vec();
+    for _ in 0..rng.range(1..4) {
+        let c = ALPHABET[rng.range(0..ALPHABET.len()) as usize];
+        match rng.range(0..3) {
+            0 if !bytes.is_empty() => {
+                let i = rng.range(0..bytes.len()) as usize;
+                bytes[i] = c;
+            },
+            1 if !bytes.is_empty() => {
+                bytes.remove(rng.range(0..bytes.len()) as usize);
+            },
+            _ => bytes.insert(rng.range(0..bytes.len() + 1) as usize, c),
+        }
+    }
+
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+fn exercise(rng: &mut WyRand, mut state: maulstrom::state::BoardState) {
+    for _ in 0..40 {
+        // the mailbox, if enabled, must agree with the bitboards.
+        for sq in BitBoard(!0) {
+            let expected = Piece::ALL.into_iter().find(|&pc| {
+                (state.pieces.get(pc, Team::White) | state.pieces.get(pc, Team::Black)).has(sq)
+            });
+            assert_eq!(state.pieces.piece_at(sq), expected);
+        }
+
+        let _ = state.to_fen();
+        let _ = state.threats();
+        let _ = state.is_quiet();
+
+        let mut moves = Vec::new();
+        for src in BitBoard(!0) {
+            for dst in state.valid_moves(src) {
+                moves.push((src, dst));
+            }
+        }
+
+        let Some(&(src, dst)) = moves.get(rng.range(0..moves.len().max(1)) as usize) else {
+            return
+        };
+
+        let Some(trace) = state.trace(src, dst) else {
+            continue
+        };
+
+        let promote = Piece::ALL[rng.range(0..Piece::COUNT) as usize];
+        if let Some(delta) = state.delta(src, dst, &trace, Some(promote)) {
+            let next = state.next(delta);
+            let _ = next.prev(delta);
+            state = next;
+        }
+    }
+}
+
+#[test]
+fn fen_parsing_never_panics() {
+    let mut rng = WyRand { seed: 0x5eed };
+    for _ in 0..2000 {
+        let base = FENS[rng.range(0..FENS.len()) as usize];
+        let fen = mutate(&mut rng, base);
+        let _ = maulstrom::state::BoardState::from_fen(&fen);
+        if let Ok(state) = maulstrom::state::BoardState::from_extended_fen(&fen) {
+            exercise(&mut rng, state);
+        }
+    }
+}
+
+#[test]
+fn move_parsing_never_panics() {
+    let mut rng = WyRand { seed: 0xface };
+    for _ in 0..2000 {
+        let moves = mutate(&mut rng, "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 e1g1 g8f6 b5c6 d7c6");
+        let _ = uci::parse_move(&moves);
+        let _ = uci::from_uci_moves(Default::default(), &moves);
+    }
+}
+
+#[test]
+fn square_arithmetic_never_panics() {
+    for i in 0..=u8::MAX {
+        for j in 0..=u8::MAX {
+            let _ = Square::try_new(i, j);
+        }
+        let _ = Square::try_from_index(i as usize);
+    }
+
+    for sq in BitBoard(!0) {
+        for dr in -8..=8 {
+            for df in -8..=8 {
+                let _ = sq.next((dr, df));
+            }
+        }
+    }
+}
+
+#[test]
+fn share_code_parsing_never_panics() {
+    let mut rng = WyRand { seed: 0xc0de };
+    for _ in 0..2000 {
+        let base = maulstrom::state::BoardState::from_extended_fen(FENS[rng.range(0..FENS.len()) as usize]).unwrap();
+        let code = mutate(&mut rng, &base.to_code());
+        if let Ok(state) = maulstrom::state::BoardState::from_code(&code) {
+            exercise(&mut rng, state);
+        }
+    }
+}